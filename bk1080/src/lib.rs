@@ -0,0 +1,506 @@
+#![no_std]
+
+use core::ops::{Bound, RangeBounds};
+
+mod doc_table;
+
+pub mod registers;
+pub use registers::Register;
+
+/// The I2C device id of the BK1080, including the read/write bit.
+pub const DEVICE_ID: u8 = 0x80;
+
+/// Size of the addressable register space, in u16s.
+///
+/// Registers after 0x0f are internal.
+pub const REG_MAX: u8 = 0x22;
+
+/// How many times to poll for a seek or tune to complete before
+/// giving up with [Error::Timeout].
+///
+/// A full-band seek takes a few seconds. Each poll is one register
+/// read, so even on a fast bus this is plenty.
+pub const STC_POLLS: u32 = 100_000;
+
+/// A bus the BK1080 can be connected to.
+///
+/// The BK1080 is almost, but not quite, I2C. The device id is always
+/// sent with the write bit clear, and the read/write bit is instead
+/// sent at the bottom of the register address. Reads are done without
+/// a repeated start, so this can't sit on top of a generic I2C trait.
+pub trait Interface {
+    /// The Error type this bus produces.
+    type Error;
+
+    /// Perform a start, write `output`, and stop.
+    fn write(&mut self, output: &[u8]) -> Result<(), Self::Error>;
+
+    /// Perform a start, write `output`, read into `input`, and stop,
+    /// with no repeated start in between.
+    fn write_then_read(&mut self, output: &[u8], input: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+/// An interface to the Beken BK1080 FM radio chip.
+///
+/// This keeps a copy of every register, updated on each read and
+/// write, available through [Bk1080::get()].
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Bk1080<I> {
+    interface: I,
+    registers: [u16; REG_MAX as usize],
+}
+
+/// An error produced by the BK1080 interface.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error<E> {
+    /// Bus error.
+    Bus(E),
+    /// Register address or frequency out of range.
+    OutOfRange,
+    /// The device is configured with an invalid value.
+    InvalidData,
+    /// A seek or tune did not complete in [STC_POLLS] polls.
+    Timeout,
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(other: E) -> Self {
+        Self::Bus(other)
+    }
+}
+
+/// Seek directions, for [Bk1080::seek()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SeekDirection {
+    /// Seek upwards in frequency.
+    Up,
+    /// Seek downwards in frequency.
+    Down,
+}
+
+impl<I> Bk1080<I>
+where
+    I: Interface,
+{
+    /// Create the interface, and read all registers from the device.
+    pub fn new(interface: I) -> Result<Self, Error<I::Error>> {
+        let mut this = Self {
+            interface,
+            registers: [0; REG_MAX as usize],
+        };
+
+        this.update(..)?;
+        Ok(this)
+    }
+
+    /// Release the bus used by this interface.
+    pub fn release(self) -> I {
+        self.interface
+    }
+
+    /// Read a range of raw registers from the device.
+    pub fn update<R>(&mut self, range: R) -> Result<&[u16], Error<I::Error>>
+    where
+        R: RangeBounds<u8>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(i) => *i as usize,
+            Bound::Excluded(i) => *i as usize + 1,
+            Bound::Unbounded => 0,
+        };
+
+        let end = match range.end_bound() {
+            Bound::Included(i) => *i as usize + 1,
+            Bound::Excluded(i) => *i as usize,
+            Bound::Unbounded => self.registers.len(),
+        };
+
+        if start > end || end > self.registers.len() {
+            return Err(Error::OutOfRange);
+        }
+
+        let mut data = [0; REG_MAX as usize * 2];
+        let data = &mut data[..(end - start) * 2];
+        if !data.is_empty() {
+            self.interface
+                .write_then_read(&[DEVICE_ID, ((start as u8) << 1) | 1], data)?;
+        }
+
+        for (r, d) in self.registers[start..end]
+            .iter_mut()
+            .zip(data.chunks_exact(2))
+        {
+            *r = u16::from_be_bytes([d[0], d[1]]);
+        }
+
+        Ok(&self.registers[start..end])
+    }
+
+    /// Get the last known value of a raw register, without talking to
+    /// the device.
+    pub fn get(&self, address: u8) -> Option<u16> {
+        self.registers.get(address as usize).copied()
+    }
+
+    /// Read a raw register on the device.
+    pub fn read_raw(&mut self, address: u8) -> Result<u16, Error<I::Error>> {
+        Ok(self.update(address..=address)?[0])
+    }
+
+    /// Write a raw register to the device.
+    ///
+    /// This reads the register back afterwards, and returns the value
+    /// the device now reports.
+    pub fn write_raw(&mut self, address: u8, value: u16) -> Result<u16, Error<I::Error>> {
+        if address >= REG_MAX {
+            return Err(Error::OutOfRange);
+        }
+
+        let [hi, lo] = value.to_be_bytes();
+        self.interface.write(&[DEVICE_ID, address << 1, hi, lo])?;
+
+        self.read_raw(address)
+    }
+
+    /// Modify a raw register on the device.
+    pub fn modify_raw(
+        &mut self,
+        address: u8,
+        f: impl FnOnce(u16) -> u16,
+    ) -> Result<u16, Error<I::Error>> {
+        let value = self.read_raw(address)?;
+        self.write_raw(address, f(value))
+    }
+
+    /// Read a register on the device.
+    pub fn read<R>(&mut self) -> Result<R, Error<I::Error>>
+    where
+        R: Register,
+    {
+        Ok(self.read_raw(R::ADDRESS)?.into())
+    }
+
+    /// Write a register to the device.
+    pub fn write<R>(&mut self, value: R) -> Result<R, Error<I::Error>>
+    where
+        R: Register,
+    {
+        Ok(self.write_raw(R::ADDRESS, value.into())?.into())
+    }
+
+    /// Modify a register on the device.
+    pub fn modify<R>(&mut self, f: impl FnOnce(R) -> R) -> Result<R, Error<I::Error>>
+    where
+        R: Register,
+    {
+        let value = self.read()?;
+        self.write(f(value))
+    }
+
+    /// Initialize every register and power on the device.
+    pub fn enable(&mut self) -> Result<(), Error<I::Error>> {
+        // register values written by the stock firmware, see registers
+        // for the meaning of the documented ones
+        const INITREGS: &[u16] = &[
+            0x0008, // 0x00 (r) : unknown 0
+            0x1080, // 0x01 (r) : chip id
+            0x0201, // 0x02 (rw): power
+            0x0000, // 0x03 (rw): channel
+            0x40C0, // 0x04 (rw): system 1
+            0x0A1F, // 0x05 (rw): system 2
+            0x002E, // 0x06 (rw): system 3
+            0x02FF, // 0x07 (rw): test 1, 148Hz "on bit"
+            0x5B11, // 0x08 (rw): test 2, should be same as startup
+            0x0000, // 0x09 (rw): boot, should be same as startup
+            0x411E, // 0x0a (r) : rssi
+            0x0000, // 0x0b (r) : read channel
+            //
+            // the rest are reserved
+            //
+            0xCE00, // 0x0c (r) : reserved 0, always 0
+            0x0000, // 0x0d (r) : reserved 1, always 0
+            0x0000, // 0x0e (r) : reserved 2, always 0
+            0x1000, // 0x0f (r) : reserved 3, always 0
+            //
+            // the rest are internal registers
+            //
+            0x3197, 0x0000, 0x13FF, 0x9852, 0x0000, 0x0000, 0x0008, 0x0000, 0x51E1, 0xA8BC, 0x2645,
+            0x00E4, 0x1CD8, 0x3A50, 0xEAE0, 0x3000, 0x0200, 0x0000,
+        ];
+        for (addr, val) in INITREGS.iter().enumerate() {
+            self.write_raw(addr as u8, *val)?;
+        }
+        self.write(registers::Power::new())?;
+        self.write(registers::System2::new().with_space(Ok(registers::Space::Khz200)))?;
+        Ok(())
+    }
+
+    /// Wait for the seek/tune complete flag to become `complete`.
+    fn wait_stc(&mut self, complete: bool) -> Result<registers::Rssi, Error<I::Error>> {
+        for _ in 0..STC_POLLS {
+            let rssi: registers::Rssi = self.read()?;
+            if rssi.seek_tune_complete() == complete {
+                return Ok(rssi);
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    /// Tune to a raw channel, and wait for the tune to complete.
+    ///
+    /// What frequency this is depends on the band and spacing
+    /// configured in [registers::System2].
+    pub fn tune(&mut self, channel: u16) -> Result<(), Error<I::Error>> {
+        let channel = registers::Channel::new().with_channel(channel & 0x3ff);
+        self.write(channel)?;
+        self.wait_stc(false)?;
+        self.write(channel.with_tune(true))?;
+        self.wait_stc(true)?;
+        Ok(())
+    }
+
+    /// The band and channel spacing currently configured.
+    fn band_space(&self) -> Result<(registers::Band, u32), Error<I::Error>> {
+        let system2 =
+            registers::System2::from(self.registers[registers::System2::ADDRESS as usize]);
+        let space = system2.space().map_err(|_| Error::InvalidData)?;
+        Ok((system2.band(), space.khz()))
+    }
+
+    /// Tune to a frequency, in kHz, and wait for the tune to complete.
+    ///
+    /// The channel is computed from the band and spacing configured
+    /// in [registers::System2], and the frequency is rounded to the
+    /// nearest channel.
+    pub fn tune_frequency(&mut self, khz: u32) -> Result<(), Error<I::Error>> {
+        let (band, space) = self.band_space()?;
+        if khz < band.bottom_khz() || khz > band.top_khz() {
+            return Err(Error::OutOfRange);
+        }
+
+        let channel = (khz - band.bottom_khz() + space / 2) / space;
+        if channel > 0x3ff {
+            return Err(Error::OutOfRange);
+        }
+
+        self.tune(channel as u16)
+    }
+
+    /// The frequency currently tuned, in kHz.
+    pub fn frequency(&mut self) -> Result<u32, Error<I::Error>> {
+        let channel = self.read::<registers::ReadChannel>()?.channel();
+        let (band, space) = self.band_space()?;
+        Ok(band.bottom_khz() + channel as u32 * space)
+    }
+
+    /// Read the current RSSI, in dBuV. See [registers::Rssi].
+    pub fn rssi(&mut self) -> Result<u8, Error<I::Error>> {
+        Ok(self.read::<registers::Rssi>()?.rssi())
+    }
+
+    /// Seek to the next station in the given direction, and wait for
+    /// the seek to complete.
+    ///
+    /// Returns the frequency found, in kHz, or `None` if the seek
+    /// failed. How the seek behaves at the band limit is configured by
+    /// [registers::Power::seek_stop_at_limit()].
+    pub fn seek(&mut self, direction: SeekDirection) -> Result<Option<u32>, Error<I::Error>> {
+        let power = self.modify(|r: registers::Power| {
+            r.with_seek(false)
+                .with_seek_up(direction == SeekDirection::Up)
+        })?;
+        self.wait_stc(false)?;
+        self.write(power.with_seek(true))?;
+        let rssi = self.wait_stc(true)?;
+        self.write(power)?;
+
+        if rssi.seek_failed() {
+            Ok(None)
+        } else {
+            self.frequency().map(Some)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use core::convert::Infallible;
+    use std::vec::Vec;
+
+    use super::*;
+
+    // a fake device on the other end of the bus
+    struct MockInterface {
+        registers: [u16; REG_MAX as usize],
+        writes: Vec<(u8, u16)>,
+        seek_result: Option<u16>,
+        // never set STC, and count reads of the RSSI register
+        stuck: bool,
+        rssi_reads: u32,
+    }
+
+    impl MockInterface {
+        fn new() -> Self {
+            let mut registers = [0; REG_MAX as usize];
+            registers[0x01] = 0x1080;
+            registers[0x02] = 0x0201;
+            registers[0x05] = 0x0a1f;
+            Self {
+                registers,
+                writes: Vec::new(),
+                seek_result: None,
+                stuck: false,
+                rssi_reads: 0,
+            }
+        }
+    }
+
+    impl Interface for MockInterface {
+        type Error = Infallible;
+
+        fn write(&mut self, output: &[u8]) -> Result<(), Self::Error> {
+            assert_eq!(output.len(), 4);
+            assert_eq!(output[0], DEVICE_ID);
+            assert_eq!(output[1] & 1, 0);
+
+            let address = output[1] >> 1;
+            let value = u16::from_be_bytes([output[2], output[3]]);
+            self.registers[address as usize] = value;
+            self.writes.push((address, value));
+
+            // emulate tune and seek, setting STC and READCHAN
+            let stc = match address {
+                0x03 => {
+                    if value & 0x8000 != 0 {
+                        self.registers[0x0b] = value & 0x3ff;
+                    }
+                    Some(value & 0x8000 != 0)
+                }
+                0x02 => {
+                    if value & 0x0100 != 0 {
+                        match self.seek_result {
+                            Some(c) => self.registers[0x0b] = c,
+                            None => self.registers[0x0a] |= 0x2000,
+                        }
+                    }
+                    Some(value & 0x0100 != 0)
+                }
+                _ => None,
+            };
+            if let Some(stc) = stc.filter(|_| !self.stuck) {
+                self.registers[0x0a] &= !0x4000;
+                if stc {
+                    self.registers[0x0a] |= 0x4000;
+                }
+            }
+
+            Ok(())
+        }
+
+        fn write_then_read(&mut self, output: &[u8], input: &mut [u8]) -> Result<(), Self::Error> {
+            assert_eq!(output.len(), 2);
+            assert_eq!(output[0], DEVICE_ID);
+            assert_eq!(output[1] & 1, 1);
+
+            let start = (output[1] >> 1) as usize;
+            if start == 0x0a {
+                self.rssi_reads += 1;
+            }
+            for (i, d) in input.chunks_exact_mut(2).enumerate() {
+                d.copy_from_slice(&self.registers[start + i].to_be_bytes());
+            }
+
+            Ok(())
+        }
+    }
+
+    fn mock() -> Bk1080<MockInterface> {
+        Bk1080::new(MockInterface::new()).unwrap()
+    }
+
+    #[test]
+    fn read_write() {
+        let mut bk = mock();
+        assert_eq!(bk.get(0x01), Some(0x1080));
+        assert_eq!(bk.read::<registers::ChipId>().unwrap().id(), 0x1080);
+
+        bk.interface.registers[0x0a] = 0x411e;
+        assert_eq!(bk.rssi(), Ok(0x1e));
+        assert_eq!(bk.get(0x0a), Some(0x411e));
+
+        assert_eq!(bk.write_raw(0x07, 0x02ff), Ok(0x02ff));
+        assert_eq!(bk.interface.writes, [(0x07, 0x02ff)]);
+
+        assert_eq!(bk.read_raw(REG_MAX), Err(Error::OutOfRange));
+        assert_eq!(bk.write_raw(REG_MAX, 0), Err(Error::OutOfRange));
+    }
+
+    #[test]
+    fn tune_frequency() {
+        let mut bk = mock();
+
+        // 87.5 - 108 MHz, 100 kHz spacing
+        bk.tune_frequency(101_100).unwrap();
+        assert_eq!(bk.interface.writes, [(0x03, 136), (0x03, 0x8000 | 136)]);
+        assert_eq!(bk.frequency(), Ok(101_100));
+
+        // rounds to the nearest channel
+        bk.interface.writes.clear();
+        bk.tune_frequency(87_549).unwrap();
+        assert_eq!(bk.interface.writes, [(0x03, 0), (0x03, 0x8000)]);
+
+        assert_eq!(bk.tune_frequency(87_000), Err(Error::OutOfRange));
+        assert_eq!(bk.tune_frequency(108_100), Err(Error::OutOfRange));
+
+        // 76 - 90 MHz, 200 kHz spacing
+        let system2 = registers::System2::new()
+            .with_band(registers::Band::Fm76_90)
+            .with_space(Ok(registers::Space::Khz200));
+        bk.write(system2).unwrap();
+        bk.interface.writes.clear();
+        bk.tune_frequency(80_000).unwrap();
+        assert_eq!(bk.interface.writes, [(0x03, 20), (0x03, 0x8000 | 20)]);
+        assert_eq!(bk.frequency(), Ok(80_000));
+
+        bk.write(system2.with_space(Err(3))).unwrap();
+        assert_eq!(bk.tune_frequency(80_000), Err(Error::InvalidData));
+    }
+
+    #[test]
+    fn seek() {
+        let mut bk = mock();
+
+        bk.interface.seek_result = Some(42);
+        assert_eq!(bk.seek(SeekDirection::Down), Ok(Some(91_700)));
+        assert_eq!(
+            bk.interface.writes,
+            [(0x02, 0x0001), (0x02, 0x0101), (0x02, 0x0001)]
+        );
+
+        bk.interface.writes.clear();
+        bk.interface.seek_result = None;
+        assert_eq!(bk.seek(SeekDirection::Up), Ok(None));
+        assert_eq!(
+            bk.interface.writes,
+            [(0x02, 0x0201), (0x02, 0x0301), (0x02, 0x0201)]
+        );
+    }
+
+    #[test]
+    fn timeout() {
+        let mut bk = mock();
+        bk.interface.stuck = true;
+
+        assert_eq!(bk.tune(136), Err(Error::Timeout));
+        assert_eq!(bk.interface.rssi_reads, 1 + STC_POLLS);
+
+        bk.interface.rssi_reads = 0;
+        assert_eq!(bk.seek(SeekDirection::Up), Err(Error::Timeout));
+        assert_eq!(bk.interface.rssi_reads, 1 + STC_POLLS);
+    }
+}
@@ -0,0 +1,481 @@
+//! Interfaces for the internal registers of the BK1080.
+//!
+//! Register defaults are the values the K5 firmware writes at startup.
+//!
+#![doc = crate::doc_table::doc_table! {
+    "0x00" => {
+        /* 0x00 */, /* 0x01 */ ChipId, /* 0x02 */ Power, /* 0x03 */ Channel,
+        /* 0x04 */ System1, /* 0x05 */ System2, /* 0x06 */ System3, /* 0x07 */,
+        /* 0x08 */, /* 0x09 */, /* 0x0a */ Rssi, /* 0x0b */ ReadChannel,
+        /* 0x0c */, /* 0x0d */, /* 0x0e */, /* 0x0f */,
+    },
+}]
+
+use bitfield_struct::bitfield;
+
+/// A trait describing a register generically.
+pub trait Register: Clone + From<u16> + Into<u16> {
+    /// The address of this register, 7 bits.
+    const ADDRESS: u8;
+}
+
+/// 0x01 Chip ID.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChipId {
+    /// Chip ID, always 0x1080.
+    #[bits(16, access = RO, default = 0x1080)]
+    pub id: u16,
+}
+
+impl Register for ChipId {
+    const ADDRESS: u8 = 0x01;
+}
+
+/// 0x02 Power configuration.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Power {
+    /// Power up the device.
+    #[bits(1, default = true)]
+    pub enable: bool,
+
+    #[bits(5)]
+    __: u8,
+
+    /// Power down the device.
+    pub disable: bool,
+
+    __: bool,
+
+    /// Start a seek. Clear this once [Rssi::seek_tune_complete()] is set.
+    pub seek: bool,
+    /// Seek upwards in frequency, otherwise downwards.
+    #[bits(1, default = true)]
+    pub seek_up: bool,
+    /// Stop seeking at the band limit, otherwise wrap around.
+    pub seek_stop_at_limit: bool,
+
+    __: bool,
+
+    /// Clock select, false for external clock.
+    pub clock_select: bool,
+    /// Force mono.
+    pub mono: bool,
+    /// Mute audio output.
+    pub mute: bool,
+    /// Disable soft mute.
+    pub soft_mute_disabled: bool,
+}
+
+impl Register for Power {
+    const ADDRESS: u8 = 0x02;
+}
+
+/// 0x03 Channel.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Channel {
+    /// Channel to tune to, see [Band] and [Space].
+    #[bits(10)]
+    pub channel: u16,
+
+    #[bits(5)]
+    __: u8,
+
+    /// Start tuning. Clear this once [Rssi::seek_tune_complete()] is set.
+    pub tune: bool,
+}
+
+impl Register for Channel {
+    const ADDRESS: u8 = 0x03;
+}
+
+/// 0x04 System configuration 1.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct System1 {
+    /// GPIO1 mode, 0b00 is high impedance.
+    #[bits(2)]
+    pub gpio1: u8,
+    /// GPIO2 mode, 0b00 is high impedance.
+    #[bits(2)]
+    pub gpio2: u8,
+    /// GPIO3 mode, 0b00 is high impedance.
+    #[bits(2)]
+    pub gpio3: u8,
+    /// Stereo blend RSSI adjustment. 0b11 blends at 25 - 43 dBuV.
+    #[bits(2, default = 0b11)]
+    pub blend_adjust: u8,
+
+    #[bits(2)]
+    __: u8,
+
+    /// Disable AGC.
+    pub agc_disabled: bool,
+    /// Use 50us de-emphasis (Europe), otherwise 75us (USA).
+    pub deemphasis_50us: bool,
+
+    __: bool,
+
+    /// Bypass the de-emphasis filter.
+    pub deemphasis_bypass: bool,
+    /// Enable the seek/tune complete interrupt.
+    #[bits(1, default = true)]
+    pub stc_interrupt_enabled: bool,
+
+    __: bool,
+}
+
+impl Register for System1 {
+    const ADDRESS: u8 = 0x04;
+}
+
+/// 0x05 System configuration 2.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct System2 {
+    /// Volume, 0xf is 0dB full scale, in 2dB steps down.
+    #[bits(4, default = 0xf)]
+    pub volume: u8,
+    /// Channel spacing.
+    #[bits(2, default = Ok(Space::Khz100), from = Space::from_bits, into = Space::into_bits)]
+    pub space: Result<Space, u8>,
+    /// Band.
+    #[bits(2, default = Band::Fm87_108)]
+    pub band: Band,
+    /// RSSI threshold for seek.
+    #[bits(8, default = 0x0a)]
+    pub seek_threshold: u8,
+}
+
+impl Register for System2 {
+    const ADDRESS: u8 = 0x05;
+}
+
+/// Channel spacing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Space {
+    /// 200 kHz (USA, Europe).
+    Khz200 = 0,
+    /// 100 kHz (Europe, Japan).
+    Khz100 = 1,
+    /// 50 kHz.
+    Khz50 = 2,
+}
+
+impl Space {
+    pub const fn into_bits(this: Result<Self, u8>) -> u8 {
+        match this {
+            Ok(v) => v as u8,
+            Err(v) => v,
+        }
+    }
+
+    pub const fn from_bits(v: u8) -> Result<Self, u8> {
+        match v {
+            0 => Ok(Self::Khz200),
+            1 => Ok(Self::Khz100),
+            2 => Ok(Self::Khz50),
+            _ => Err(v),
+        }
+    }
+
+    /// Channel spacing, in kHz.
+    pub const fn khz(&self) -> u32 {
+        match self {
+            Self::Khz200 => 200,
+            Self::Khz100 => 100,
+            Self::Khz50 => 50,
+        }
+    }
+}
+
+/// Band.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Band {
+    /// 87.5 - 108 MHz (USA, Europe).
+    Fm87_108 = 0,
+    /// 76 - 108 MHz (Japan wide band).
+    Fm76_108 = 1,
+    /// 76 - 90 MHz (Japan).
+    Fm76_90 = 2,
+    /// 64 - 76 MHz.
+    Fm64_76 = 3,
+}
+
+impl Band {
+    pub const fn into_bits(self) -> u8 {
+        self as u8
+    }
+
+    pub const fn from_bits(v: u8) -> Self {
+        match v & 0b11 {
+            0 => Self::Fm87_108,
+            1 => Self::Fm76_108,
+            2 => Self::Fm76_90,
+            _ => Self::Fm64_76,
+        }
+    }
+
+    /// Bottom of the band, in kHz. This is channel 0.
+    pub const fn bottom_khz(&self) -> u32 {
+        match self {
+            Self::Fm87_108 => 87_500,
+            Self::Fm76_108 | Self::Fm76_90 => 76_000,
+            Self::Fm64_76 => 64_000,
+        }
+    }
+
+    /// Top of the band, in kHz, inclusive.
+    pub const fn top_khz(&self) -> u32 {
+        match self {
+            Self::Fm87_108 | Self::Fm76_108 => 108_000,
+            Self::Fm76_90 => 90_000,
+            Self::Fm64_76 => 76_000,
+        }
+    }
+}
+
+/// 0x06 System configuration 3.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct System3 {
+    /// Seek FM impulse detection threshold.
+    #[bits(4, default = 0b1110)]
+    pub seek_count: u8,
+    /// Seek SNR threshold.
+    #[bits(4, default = 0b0010)]
+    pub seek_snr: u8,
+
+    #[bits(4)]
+    __: u8,
+
+    /// Soft mute attenuation, 0b00 is 16dB.
+    #[bits(2)]
+    pub soft_mute_attenuation: u8,
+    /// Soft mute attack and recover rate, 0b00 is fastest.
+    #[bits(2)]
+    pub soft_mute_rate: u8,
+}
+
+impl Register for System3 {
+    const ADDRESS: u8 = 0x06;
+}
+
+/// 0x0a RSSI and status.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Rssi {
+    /// Received signal strength, in dBuV.
+    #[bits(8, access = RO)]
+    pub rssi: u8,
+    /// Stereo is being received.
+    #[bits(1, access = RO)]
+    pub stereo: bool,
+    /// Stereo indicator.
+    #[bits(1, access = RO)]
+    pub stereo_indicator: bool,
+
+    #[bits(2)]
+    __: u8,
+
+    /// AFC railed, the channel is invalid.
+    #[bits(1, access = RO)]
+    pub afc_railed: bool,
+    /// Seek failed, or hit the band limit.
+    #[bits(1, access = RO)]
+    pub seek_failed: bool,
+    /// Seek or tune is complete.
+    #[bits(1, access = RO)]
+    pub seek_tune_complete: bool,
+
+    __: bool,
+}
+
+impl Register for Rssi {
+    const ADDRESS: u8 = 0x0a;
+}
+
+/// 0x0b Current channel.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReadChannel {
+    /// The channel currently tuned, see [Band] and [Space].
+    #[bits(10, access = RO)]
+    pub channel: u16,
+    /// FM impulse count.
+    #[bits(4, access = RO)]
+    pub impulse_count: u8,
+
+    #[bits(2)]
+    __: u8,
+}
+
+impl Register for ReadChannel {
+    const ADDRESS: u8 = 0x0b;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // helper to read off bit slices from the datasheet and assert them
+    macro_rules! check_bits {
+        ($name:ty { $($field:ident[$($spec:tt)*] $(= $default:expr)?),*$(,)? }) => {
+            $(
+                {
+                    paste::paste! {
+                        let found = ($name::[<$field:upper _OFFSET>], $name::[<$field:upper _BITS>]);
+                    }
+                    let expected = check_bits!(@spechelper, [$($spec)*]);
+                    assert_eq!(expected, found, "on {}::{}, expected (len, size) of {:?}, found {:?}", stringify!($name), stringify!($field), expected, found);
+                    $(
+                        let expected = $default;
+                        let found = <$name>::new().$field();
+                        assert_eq!(expected, found, "on {}::{}, expected default {:?}, found {:?}", stringify!($name), stringify!($field), expected, found);
+                    )?
+                }
+            )*
+        };
+        (@spechelper, [$bit:literal]) => {
+            // start, len
+            ($bit, 1)
+        };
+        (@spechelper, [$end:literal : $start:literal]) => {
+            // start, len
+            ($start, 1 + $end - $start)
+        };
+    }
+
+    #[test]
+    fn r01_chip_id() {
+        assert_eq!(ChipId::ADDRESS, 0x01);
+        check_bits!(ChipId { id[15:0] = 0x1080 });
+    }
+
+    #[test]
+    fn r02_power() {
+        assert_eq!(Power::ADDRESS, 0x02);
+        assert_eq!(Power::new().into_bits(), 0x0201);
+        check_bits!(Power {
+            soft_mute_disabled[15] = false,
+            mute[14] = false,
+            mono[13] = false,
+            clock_select[12] = false,
+            seek_stop_at_limit[10] = false,
+            seek_up[9] = true,
+            seek[8] = false,
+            disable[6] = false,
+            enable[0] = true,
+        });
+    }
+
+    #[test]
+    fn r03_channel() {
+        assert_eq!(Channel::ADDRESS, 0x03);
+        check_bits!(Channel {
+            tune[15] = false,
+            channel[9:0] = 0,
+        });
+        assert_eq!(
+            0x8123,
+            Channel::new()
+                .with_channel(0x123)
+                .with_tune(true)
+                .into_bits()
+        );
+    }
+
+    #[test]
+    fn r04_system1() {
+        assert_eq!(System1::ADDRESS, 0x04);
+        assert_eq!(System1::new().into_bits(), 0x40c0);
+        check_bits!(System1 {
+            stc_interrupt_enabled[14] = true,
+            deemphasis_bypass[13] = false,
+            deemphasis_50us[11] = false,
+            agc_disabled[10] = false,
+            blend_adjust[7:6] = 0b11,
+            gpio3[5:4] = 0,
+            gpio2[3:2] = 0,
+            gpio1[1:0] = 0,
+        });
+    }
+
+    #[test]
+    fn r05_system2() {
+        assert_eq!(System2::ADDRESS, 0x05);
+        assert_eq!(System2::new().into_bits(), 0x0a1f);
+        check_bits!(System2 {
+            seek_threshold[15:8] = 0x0a,
+            band[7:6] = Band::Fm87_108,
+            space[5:4] = Ok(Space::Khz100),
+            volume[3:0] = 0xf,
+        });
+
+        assert_eq!(Err(3), System2::from_bits(0x0030).space());
+        assert_eq!(Band::Fm64_76, System2::from_bits(0x00c0).band());
+    }
+
+    #[test]
+    fn r06_system3() {
+        assert_eq!(System3::ADDRESS, 0x06);
+        assert_eq!(System3::new().into_bits(), 0x002e);
+        check_bits!(System3 {
+            soft_mute_rate[15:14] = 0,
+            soft_mute_attenuation[13:12] = 0,
+            seek_snr[7:4] = 0b0010,
+            seek_count[3:0] = 0b1110,
+        });
+    }
+
+    #[test]
+    fn r0a_rssi() {
+        assert_eq!(Rssi::ADDRESS, 0x0a);
+        check_bits!(Rssi {
+            seek_tune_complete[14] = false,
+            seek_failed[13] = false,
+            afc_railed[12] = false,
+            stereo_indicator[9] = false,
+            stereo[8] = false,
+            rssi[7:0] = 0,
+        });
+
+        let r = Rssi::from_bits(0x411e);
+        assert!(r.seek_tune_complete());
+        assert!(r.stereo());
+        assert!(!r.stereo_indicator());
+        assert_eq!(0x1e, r.rssi());
+    }
+
+    #[test]
+    fn r0b_read_channel() {
+        assert_eq!(ReadChannel::ADDRESS, 0x0b);
+        check_bits!(ReadChannel {
+            impulse_count[13:10] = 0,
+            channel[9:0] = 0,
+        });
+    }
+
+    #[test]
+    fn bands() {
+        assert_eq!(Band::Fm87_108.bottom_khz(), 87_500);
+        assert_eq!(Band::Fm76_90.top_khz(), 90_000);
+        for v in 0..4 {
+            assert_eq!(v, Band::from_bits(v).into_bits());
+        }
+    }
+}
@@ -8,6 +8,13 @@ mod doc_table;
 pub mod registers;
 pub use registers::Register;
 
+#[cfg(any(test, feature = "test-support"))]
+pub mod mock;
+
+pub mod scan;
+
+pub mod tone_squelch;
+
 /// An interface to the Beken BK4819 chip.
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -16,8 +23,34 @@ pub struct Bk4819<Scn, Scl, Sda, Delay> {
     scl: Scl,
     sda: Sda,
     delay: Delay,
+    clock_delay_ns: u32,
+    frequency_hz: u32,
+    frequency_offset_hz: i16,
 }
 
+/// The default half-cycle delay for the bus, in nanoseconds.
+///
+/// This gives a clock of around 500 kHz, the same as the stock
+/// firmware. See [Bk4819::with_clock_delay_ns()].
+pub const DEFAULT_CLOCK_DELAY_NS: u32 = 1_000;
+
+/// Microseconds to wait for the PLL to lock after tuning, used by
+/// [Bk4819::set_frequency_blocking()], [Bk4819::set_frequency_settled()]
+/// and [Bk4819::sweep()].
+///
+/// Beken documents neither a lock time nor a lock status bit, so this
+/// is a fixed wait, chosen to cover a typical retune within a band
+/// with some margin. Switching between Rx and Tx takes longer than a
+/// retune, so wait longer there.
+pub const PLL_SETTLE_US: u32 = 1_000;
+
+/// The delay type of a [Bk4819] created with [Bk4819::new_borrowed()],
+/// that borrows a delay implementation for each access instead of
+/// owning one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BorrowedDelay;
+
 /// An error produced by the BK4819 interface.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -32,13 +65,356 @@ impl<E> From<E> for Error<E> {
     }
 }
 
+/// Demodulation modes, for [Bk4819::set_modulation()].
+///
+/// FM and AM are used by the stock firmware. USB and LSB come from
+/// community firmware mods, and are not documented by Beken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Modulation {
+    /// Frequency modulation.
+    Fm,
+    /// Amplitude modulation.
+    Am,
+    /// Upper sideband.
+    Usb,
+    /// Lower sideband.
+    ///
+    /// The device has no sideband selection, so this is identical to
+    /// [Modulation::Usb]. The sideband is picked by tuning above or
+    /// below the carrier.
+    Lsb,
+}
+
+/// Channel bandwidths, for [Bk4819::set_bandwidth()].
+///
+/// Wide and narrow are the settings used by the stock firmware.
+/// Narrower comes from community firmware mods, and is not documented
+/// by Beken. See [registers::FilterBandwidth].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Bandwidth {
+    /// 25kHz channels, 0x3028.
+    Wide,
+    /// 12.5kHz channels, 0x4048.
+    Narrow,
+    /// 6.25kHz channels, 0x4058.
+    Narrower,
+}
+
+impl Bandwidth {
+    /// The filter register value used for this bandwidth.
+    pub fn register(self) -> registers::FilterBandwidth {
+        let (rf, af_tx_lpf, mode) = match self {
+            Self::Wide => (3, 0, registers::FilterMode::Wide),
+            Self::Narrow => (4, 1, registers::FilterMode::Narrow),
+            Self::Narrower => (4, 1, registers::FilterMode::Narrower),
+        };
+        registers::FilterBandwidth::new()
+            .with_rf(rf)
+            .with_rf_weak(0)
+            .with_af_tx_lpf(af_tx_lpf)
+            .with_mode(Ok(mode))
+    }
+}
+
+/// Power amplifier settings, for [Bk4819::set_pa()].
+///
+/// These set [registers::PaControl]. `bias` drives the PA bias pin
+/// from 0V (0x00) to 3.2V (0xff), or about 12.5mV per step. This is
+/// what mostly sets the RF output, but not linearly: the output rises
+/// steeply once the external PA starts to conduct, and then flattens
+/// out, and where that happens differs by band and from radio to
+/// radio. This is why radios carry a per-band calibration table.
+/// `gain1` and `gain2` are the internal PA stages, 0 (min) to 7 (max).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PaSettings {
+    /// PA bias output, 0x00 (0V) to 0xff (3.2V).
+    pub bias: u8,
+    /// PA gain 1, 0 through 7.
+    pub gain1: u8,
+    /// PA gain 2, 0 through 7.
+    pub gain2: u8,
+}
+
+/// Transmit power levels, for [Bk4819::set_tx_power()].
+///
+/// Staying within legal power limits, and within the limits of the
+/// hardware, is the caller's responsibility. None of these presets
+/// are measured, and actual output varies with band and radio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TxPower {
+    /// Low power.
+    Low,
+    /// Medium power.
+    Mid,
+    /// High power.
+    High,
+}
+
+impl From<TxPower> for PaSettings {
+    fn from(level: TxPower) -> Self {
+        level.pa()
+    }
+}
+
+impl TxPower {
+    /// The uncalibrated PA settings used for this level.
+    ///
+    /// The gains are the ones the stock firmware uses above 280MHz.
+    /// Below that, it uses `gain1 = 1` and `gain2 = 0`.
+    pub const fn pa(self) -> PaSettings {
+        let bias = match self {
+            Self::Low => 0x1e,
+            Self::Mid => 0x50,
+            Self::High => 0x8c,
+        };
+        PaSettings {
+            bias,
+            gain1: 4,
+            gain2: 2,
+        }
+    }
+
+    /// Index of this level in a `[low, mid, high]` calibration table.
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// VOX settings, for [Bk4819::set_vox()].
+///
+/// The thresholds are compared against [registers::VoiceAmplitude],
+/// which measures the mic audio after [registers::MicSensitivity] and
+/// mic AGC. There is no fixed relation to sound level, so changing
+/// the mic gain also changes how loud you must speak to trigger VOX.
+/// Read [Bk4819::voice_amplitude()] while talking and while quiet to
+/// choose thresholds. The thresholds are 11 bits, up to 0x7ff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct VoxConfig {
+    /// VOX turns on when the voice amplitude rises above this.
+    pub threshold: u16,
+    /// VOX turns off when the voice amplitude falls below this.
+    /// Setting this below `threshold` adds hysteresis.
+    pub release: u16,
+    /// Time to wait before VOX turns off, in 128ms steps, 0 through 15.
+    pub delay: u8,
+}
+
+/// AGC settings, for [Bk4819::set_agc()].
+///
+/// [Self::default()] matches the register defaults, and leaves the
+/// gain table alone.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AgcConfig {
+    /// Automatic gain, or locked at `index`.
+    pub mode: registers::AgcMode,
+    /// The AGC index to hold when locked, 3 (max) down to -1 (min).
+    pub index: i8,
+    /// AGC attack speed.
+    pub speed: registers::AgcSpeed,
+    /// RSSI threshold to raise the gain below, 7 bits.
+    pub low_threshold: u8,
+    /// RSSI threshold to lower the gain above, 7 bits.
+    pub high_threshold: u8,
+    /// Gain table entries for 0x10 through 0x14, if they should be
+    /// written. See [Self::default_table()].
+    pub table: Option<[registers::AgcGainTable; 5]>,
+}
+
+impl Default for AgcConfig {
+    fn default() -> Self {
+        let control = registers::AgcControl::new();
+        let filters = registers::AgcFilters::new();
+        Self {
+            mode: filters.agc_mode(),
+            index: filters.agc_index(),
+            speed: control.speed(),
+            low_threshold: control.low_threshold(),
+            high_threshold: control.high_threshold(),
+            table: None,
+        }
+    }
+}
+
+impl AgcConfig {
+    /// The gain table the device resets to.
+    pub fn default_table() -> [registers::AgcGainTable; 5] {
+        [
+            registers::AgcGainTable0::new().into(),
+            registers::AgcGainTable1::new().into(),
+            registers::AgcGainTable2::new().into(),
+            registers::AgcGainTable3::new().into(),
+            registers::AgcGainTable4::new().into(),
+        ]
+    }
+}
+
+/// Compander modes, for [Bk4819::set_compander()].
+///
+/// The compander compresses transmitted audio and expands received
+/// audio, for less noise on weak signals. It only works when both
+/// ends agree: compressed audio received without expansion sounds
+/// flat and loud, and expanding audio that was never compressed
+/// sounds choppy. Match this to the radio on the other end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CompanderMode {
+    /// No compander.
+    Off,
+    /// Compress transmitted audio only.
+    Tx,
+    /// Expand received audio only.
+    Rx,
+    /// Compress transmitted audio, and expand received audio.
+    TxRx,
+}
+
+/// Transmit audio settings, for [Bk4819::set_af_config()].
+///
+/// [Self::default()] matches the register defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AfConfig {
+    /// Mic gain, 0 (min) to 31 (max), in 0.5dB steps. See
+    /// [Bk4819::set_mic_gain()].
+    pub mic_gain: u8,
+    /// Compander mode.
+    pub compander: CompanderMode,
+    /// Transmit pre-emphasis. This should match the receiver's
+    /// de-emphasis.
+    pub preemphasis: bool,
+}
+
+impl Default for AfConfig {
+    fn default() -> Self {
+        let filters = registers::AfFilters::new();
+        Self {
+            mic_gain: registers::MicSensitivity::new().sensitivity(),
+            compander: CompanderMode::Off,
+            preemphasis: !filters.tx_preemphasis_disabled(),
+        }
+    }
+}
+
+/// A single register write, for [Bk4819::apply_registers()].
+///
+/// Any typed [Register] converts into this with [From].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RegWrite {
+    /// The register address, 7 bits.
+    pub address: u8,
+    /// The value to write.
+    pub value: u16,
+}
+
+impl<R> From<R> for RegWrite
+where
+    R: Register,
+{
+    fn from(other: R) -> Self {
+        Self {
+            address: R::ADDRESS,
+            value: other.into(),
+        }
+    }
+}
+
+/// A fixed-size collection of up to `N` typed register writes.
+///
+/// ```
+/// # use bk4819::{registers, Batch};
+/// let batch = Batch::<2>::new()
+///     .with(registers::PaControl::new())
+///     .with(registers::Enable::rx());
+/// assert_eq!(batch.len(), 2);
+/// ```
+///
+/// Pass this to [Bk4819::apply_registers()]. Writes happen in the
+/// order they were added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Batch<const N: usize> {
+    writes: [RegWrite; N],
+    len: usize,
+}
+
+impl<const N: usize> Default for Batch<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Batch<N> {
+    /// Create an empty batch.
+    pub const fn new() -> Self {
+        Self {
+            writes: [RegWrite {
+                address: 0,
+                value: 0,
+            }; N],
+            len: 0,
+        }
+    }
+
+    /// Add a register write to the end of the batch.
+    ///
+    /// Panics if the batch already holds `N` writes.
+    pub fn with(mut self, value: impl Into<RegWrite>) -> Self {
+        self.push(value);
+        self
+    }
+
+    /// Add a register write to the end of the batch.
+    ///
+    /// Panics if the batch already holds `N` writes.
+    pub fn push(&mut self, value: impl Into<RegWrite>) {
+        assert!(self.len < N, "register batch is full");
+        self.writes[self.len] = value.into();
+        self.len += 1;
+    }
+
+    /// The number of writes in the batch.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Is the batch empty?
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The writes in the batch, in order.
+    pub fn as_slice(&self) -> &[RegWrite] {
+        &self.writes[..self.len]
+    }
+}
+
+impl<const N: usize> IntoIterator for Batch<N> {
+    type Item = RegWrite;
+    type IntoIter = core::iter::Take<core::array::IntoIter<RegWrite, N>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.writes.into_iter().take(self.len)
+    }
+}
+
 /// A handle to do raw communication with a BK4819.
 ///
-/// Created by [Bk4819::transaction()].
+/// Created by [Bk4819::raw()] with the owned delay, or by
+/// [Bk4819::raw_with()] with a borrowed one.
 #[derive(Debug)]
-#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 struct Raw<'a, Scn, Scl, Sda, Delay> {
-    device: &'a mut Bk4819<Scn, Scl, Sda, Delay>,
+    scn: &'a mut Scn,
+    scl: &'a mut Scl,
+    sda: &'a mut Sda,
+    delay: &'a mut Delay,
+    clock_delay_ns: u32,
 }
 
 impl<'a, Scn, Scl, Sda, Delay, E> Raw<'a, Scn, Scl, Sda, Delay>
@@ -51,20 +427,20 @@ where
     /// Wait half a clock cycle.
     #[inline(always)]
     fn wait_clk(&mut self) {
-        self.device.wait_clk();
+        self.delay.delay_ns(self.clock_delay_ns);
     }
 
     /// Write a single bit to the device.
     #[inline(always)]
     fn write_bit(&mut self, bit: bool) -> Result<(), Error<E>> {
         // set data on falling edge
-        self.device.sda.set_state(bit.into())?;
-        self.device.scl.set_low()?;
+        self.sda.set_state(bit.into())?;
+        self.scl.set_low()?;
 
         self.wait_clk();
 
         // sample data on rising edge
-        self.device.scl.set_high()?;
+        self.scl.set_high()?;
 
         self.wait_clk();
 
@@ -76,16 +452,16 @@ where
     fn read_bit(&mut self) -> Result<bool, Error<E>> {
         // weird thing: try to read from sda now and discard it
         // to flip it to input for implementations that flip on demand
-        self.device.sda.is_high()?;
+        self.sda.is_high()?;
 
         // set data on falling edge
-        self.device.scl.set_low()?;
+        self.scl.set_low()?;
 
         self.wait_clk();
 
         // sample data on rising edge
-        let bit = self.device.sda.is_high()?;
-        self.device.scl.set_high()?;
+        let bit = self.sda.is_high()?;
+        self.scl.set_high()?;
 
         self.wait_clk();
 
@@ -122,85 +498,309 @@ where
 
         Ok(value)
     }
+
+    /// Perform a transaction, reading and writing from the device.
+    fn transaction<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, Error<E>>,
+    ) -> Result<T, Error<E>> {
+        // bring scn and scl low to activate device
+        self.scn.set_low()?;
+        self.scl.set_low()?;
+
+        // do the transaction
+        let res = f(self);
+
+        // bring scl low once more at the end
+        self.scl.set_low()?;
+
+        self.wait_clk();
+
+        // bring scn and scl high to deactivate device
+        self.scn.set_high()?;
+        self.scl.set_high()?;
+
+        self.wait_clk();
+
+        // set sda to high at the very end so its in a known state
+        self.sda.set_high()?;
+
+        res
+    }
+
+    /// Read a register.
+    fn read_register(&mut self, address: u8) -> Result<u16, Error<E>> {
+        self.transaction(|raw| {
+            raw.write_u8(address | 0x80)?;
+            raw.read_u16()
+        })
+    }
+
+    /// Write a register.
+    fn write_register(&mut self, address: u8, value: u16) -> Result<(), Error<E>> {
+        self.transaction(|raw| {
+            raw.write_u8(address & 0x7f)?;
+            raw.write_u16(value)
+        })
+    }
+
+    /// Tune to a frequency register value, in units of 10Hz.
+    fn tune(&mut self, word: u32) -> Result<(), Error<E>> {
+        self.write_register(registers::FrequencyLow::ADDRESS, word as u16)?;
+        self.write_register(registers::FrequencyHigh::ADDRESS, (word >> 16) as u16)?;
+        let enable = self.read_register(registers::Enable::ADDRESS)?;
+        self.relock(enable)
+    }
+
+    /// Turn [registers::Enable] off and back on to `enable`.
+    ///
+    /// The PLL only picks up a new frequency when this is toggled, so
+    /// the stock firmware does this after every retune.
+    fn relock(&mut self, enable: u16) -> Result<(), Error<E>> {
+        self.write_register(registers::Enable::ADDRESS, 0)?;
+        self.write_register(registers::Enable::ADDRESS, enable)
+    }
+
+    /// Reset the device.
+    fn reset(&mut self) -> Result<(), Error<E>> {
+        // set everything to the default state
+        self.scn.set_high()?;
+        self.scl.set_high()?;
+        self.sda.set_high()?;
+
+        // let it all settle
+        self.wait_clk();
+
+        let reset = registers::Reset::new();
+        self.write_register(registers::Reset::ADDRESS, reset.with_reset(true).into())?;
+        self.write_register(registers::Reset::ADDRESS, reset.into())
+    }
 }
 
-impl<Scn, Scl, Sda, Delay, E> Bk4819<Scn, Scl, Sda, Delay>
+impl<Scn, Scl, Sda, E> Bk4819<Scn, Scl, Sda, BorrowedDelay>
 where
     Scn: OutputPin<Error = E>,
     Scl: OutputPin<Error = E>,
     Sda: OutputPin<Error = E> + InputPin<Error = E>,
-    Delay: DelayNs,
 {
-    /// Create the interface with the given pins and delay implementation.
+    /// Create the interface with the given pins, but no delay
+    /// implementation of its own.
     ///
-    /// The delay implementation will be asked to delay by single
-    /// microseconds.
-    pub fn new(scn: Scn, scl: Scl, sda: Sda, delay: Delay) -> Result<Self, Error<E>> {
+    /// Every access then borrows a delay for the length of the call,
+    /// through the `*_with()` methods like [Self::read_raw_with()].
+    /// This lets one delay serve this device and other drivers,
+    /// instead of dedicating a timer to each. The tradeoff is that
+    /// only raw register access is available: the higher-level
+    /// methods like [Bk4819::set_frequency()] need an owned delay, from
+    /// [Bk4819::new()]. Either way, the bus timing is the same.
+    ///
+    /// The device is reset using `delay`.
+    pub fn new_borrowed(
+        scn: Scn,
+        scl: Scl,
+        sda: Sda,
+        delay: &mut impl DelayNs,
+    ) -> Result<Self, Error<E>> {
         let mut this = Self {
             scn,
             scl,
             sda,
-            delay,
+            delay: BorrowedDelay,
+            clock_delay_ns: DEFAULT_CLOCK_DELAY_NS,
+            frequency_hz: 0,
+            frequency_offset_hz: 0,
         };
 
-        this.reset()?;
+        this.reset_with(delay)?;
         Ok(this)
     }
+}
 
+impl<Scn, Scl, Sda, Delay, E> Bk4819<Scn, Scl, Sda, Delay>
+where
+    Scn: OutputPin<Error = E>,
+    Scl: OutputPin<Error = E>,
+    Sda: OutputPin<Error = E> + InputPin<Error = E>,
+{
     /// Release the pins and delay used by this interface.
     pub fn release(self) -> (Scn, Scl, Sda, Delay) {
         (self.scn, self.scl, self.sda, self.delay)
     }
 
-    /// Wait half a clock cycle.
-    #[inline(always)]
-    fn wait_clk(&mut self) {
-        self.delay.delay_us(1);
+    /// Set the half-cycle delay for the bus, in nanoseconds.
+    ///
+    /// The datasheet gives no maximum clock for this three-wire bus,
+    /// and the stock firmware never runs it faster than about
+    /// 500 kHz, the default. A shorter delay speeds up every register
+    /// access, but is untested territory. A longer delay can help on
+    /// long or noisy wiring.
+    pub fn with_clock_delay_ns(mut self, ns: u32) -> Self {
+        self.clock_delay_ns = ns;
+        self
     }
 
-    /// Perform a transaction, reading and writing from the device.
-    fn transaction<T>(
+    /// The half-cycle delay for the bus, in nanoseconds.
+    pub fn clock_delay_ns(&self) -> u32 {
+        self.clock_delay_ns
+    }
+
+    /// Raw communication, using a borrowed delay.
+    fn raw_with<'a, D>(&'a mut self, delay: &'a mut D) -> Raw<'a, Scn, Scl, Sda, D>
+    where
+        D: DelayNs,
+    {
+        Raw {
+            scn: &mut self.scn,
+            scl: &mut self.scl,
+            sda: &mut self.sda,
+            delay,
+            clock_delay_ns: self.clock_delay_ns,
+        }
+    }
+
+    /// Read a raw register on the device, using a borrowed delay.
+    pub fn read_raw_with(
         &mut self,
-        f: impl FnOnce(&mut Raw<'_, Scn, Scl, Sda, Delay>) -> Result<T, Error<E>>,
-    ) -> Result<T, Error<E>> {
-        // bring scn and scl low to activate device
-        self.scn.set_low()?;
-        self.scl.set_low()?;
+        delay: &mut impl DelayNs,
+        address: u8,
+    ) -> Result<u16, Error<E>> {
+        self.raw_with(delay).read_register(address)
+    }
 
-        // do the transaction
-        let res = f(&mut Raw { device: self });
+    /// Write a raw register to the device, using a borrowed delay.
+    pub fn write_raw_with(
+        &mut self,
+        delay: &mut impl DelayNs,
+        address: u8,
+        value: u16,
+    ) -> Result<(), Error<E>> {
+        self.raw_with(delay).write_register(address, value)
+    }
 
-        // bring scl low once more at the end
-        self.scl.set_low()?;
+    /// Modify a raw register on the device, using a borrowed delay.
+    pub fn modify_raw_with(
+        &mut self,
+        delay: &mut impl DelayNs,
+        address: u8,
+        f: impl FnOnce(u16) -> u16,
+    ) -> Result<(), Error<E>> {
+        let value = self.read_raw_with(delay, address)?;
+        self.write_raw_with(delay, address, f(value))
+    }
 
-        self.wait_clk();
+    /// Read a register on the device, using a borrowed delay.
+    pub fn read_with<R>(&mut self, delay: &mut impl DelayNs) -> Result<R, Error<E>>
+    where
+        R: Register,
+    {
+        Ok(self.read_raw_with(delay, R::ADDRESS)?.into())
+    }
 
-        // bring scn and scl high to deactivate device
-        self.scn.set_high()?;
-        self.scl.set_high()?;
+    /// Write a register to the device, using a borrowed delay.
+    pub fn write_with<R>(&mut self, delay: &mut impl DelayNs, value: R) -> Result<(), Error<E>>
+    where
+        R: Register,
+    {
+        self.write_raw_with(delay, R::ADDRESS, value.into())
+    }
 
-        self.wait_clk();
+    /// Modify a register on the device, using a borrowed delay.
+    pub fn modify_with<R>(
+        &mut self,
+        delay: &mut impl DelayNs,
+        f: impl FnOnce(R) -> R,
+    ) -> Result<(), Error<E>>
+    where
+        R: Register,
+    {
+        let value = self.read_with(delay)?;
+        self.write_with(delay, f(value))
+    }
 
-        // set sda to high at the very end so its in a known state
-        self.sda.set_high()?;
+    /// Reset the device, using a borrowed delay.
+    pub fn reset_with(&mut self, delay: &mut impl DelayNs) -> Result<(), Error<E>> {
+        self.raw_with(delay).reset()
+    }
 
-        res
+    /// Tune to a frequency, in Hz, and wait for the PLL to lock.
+    ///
+    /// This is [Self::set_frequency()], using a borrowed delay for the
+    /// bus, followed by a wait of [PLL_SETTLE_US]. Use this before
+    /// anything that needs the new frequency, like reading the RSSI,
+    /// or readings can come from the old one.
+    pub fn set_frequency_blocking(
+        &mut self,
+        freq_hz: u32,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Error<E>> {
+        self.frequency_hz = freq_hz;
+        let word = self.frequency_word();
+        self.raw_with(delay).tune(word)?;
+        delay.delay_us(PLL_SETTLE_US);
+        Ok(())
+    }
+
+    // the frequency register value, with the offset applied
+    fn frequency_word(&self) -> u32 {
+        let freq_hz = self
+            .frequency_hz
+            .saturating_add_signed(self.frequency_offset_hz as i32);
+        freq_hz / 10
+    }
+}
+
+impl<Scn, Scl, Sda, Delay, E> Bk4819<Scn, Scl, Sda, Delay>
+where
+    Scn: OutputPin<Error = E>,
+    Scl: OutputPin<Error = E>,
+    Sda: OutputPin<Error = E> + InputPin<Error = E>,
+    Delay: DelayNs,
+{
+    /// Create the interface with the given pins and delay implementation.
+    ///
+    /// The delay implementation will be asked to delay by half a bus
+    /// clock cycle at a time, [DEFAULT_CLOCK_DELAY_NS] unless changed
+    /// with [Self::with_clock_delay_ns()]. To share one delay with
+    /// other drivers instead, see [Bk4819::new_borrowed()].
+    pub fn new(scn: Scn, scl: Scl, sda: Sda, delay: Delay) -> Result<Self, Error<E>> {
+        let mut this = Self {
+            scn,
+            scl,
+            sda,
+            delay,
+            clock_delay_ns: DEFAULT_CLOCK_DELAY_NS,
+            frequency_hz: 0,
+            frequency_offset_hz: 0,
+        };
+
+        this.reset()?;
+        Ok(this)
+    }
+
+    /// Borrow the delay implementation, for waiting between operations.
+    pub fn delay(&mut self) -> &mut Delay {
+        &mut self.delay
+    }
+
+    /// Raw communication, using the owned delay.
+    fn raw(&mut self) -> Raw<'_, Scn, Scl, Sda, Delay> {
+        Raw {
+            scn: &mut self.scn,
+            scl: &mut self.scl,
+            sda: &mut self.sda,
+            delay: &mut self.delay,
+            clock_delay_ns: self.clock_delay_ns,
+        }
     }
 
     /// Read a raw register on the device.
     pub fn read_raw(&mut self, address: u8) -> Result<u16, Error<E>> {
-        self.transaction(|raw| {
-            raw.write_u8(address | 0x80)?;
-            raw.read_u16()
-        })
+        self.raw().read_register(address)
     }
 
     /// Write a raw register to the device.
     pub fn write_raw(&mut self, address: u8, value: u16) -> Result<(), Error<E>> {
-        self.transaction(|raw| {
-            raw.write_u8(address & 0x7f)?;
-            raw.write_u16(value)
-        })
+        self.raw().write_register(address, value)
     }
 
     /// Modify a raw register on the device.
@@ -225,6 +825,33 @@ where
         self.write_raw(R::ADDRESS, value.into())
     }
 
+    /// Write a sequence of raw registers, in order.
+    ///
+    /// The BK4819 latches a write when SCN rises, so each register is
+    /// still its own transaction. Writes happen strictly in the order
+    /// given, and stop at the first error, so a failed batch may be
+    /// partly applied.
+    pub fn apply(&mut self, regs: &[(u8, u16)]) -> Result<(), Error<E>> {
+        for &(address, value) in regs {
+            self.write_raw(address, value)?;
+        }
+        Ok(())
+    }
+
+    /// Write a sequence of typed registers, in order.
+    ///
+    /// This accepts a [Batch], or anything else that produces
+    /// [RegWrite]s. The ordering is the same as [Self::apply()].
+    pub fn apply_registers(
+        &mut self,
+        regs: impl IntoIterator<Item = RegWrite>,
+    ) -> Result<(), Error<E>> {
+        for reg in regs {
+            self.write_raw(reg.address, reg.value)?;
+        }
+        Ok(())
+    }
+
     /// Modify a register on the device.
     pub fn modify<R>(&mut self, f: impl FnOnce(R) -> R) -> Result<(), Error<E>>
     where
@@ -234,22 +861,413 @@ where
         self.write(f(value))
     }
 
+    /// Read every register in [registers::KNOWN] and log it with
+    /// [defmt], decoded field-by-field.
+    ///
+    /// See [registers::log()] to decode a single register.
+    #[cfg(feature = "defmt")]
+    pub fn log_registers(&mut self) -> Result<(), Error<E>> {
+        for &address in registers::KNOWN {
+            registers::log(address, self.read_raw(address)?);
+        }
+        Ok(())
+    }
+
     /// Reset the device.
     pub fn reset(&mut self) -> Result<(), Error<E>> {
-        // set everything to the default state
-        self.scn.set_high()?;
-        self.scl.set_high()?;
-        self.sda.set_high()?;
+        self.raw().reset()
+    }
 
-        // let it all settle
-        self.wait_clk();
+    /// Reset the device, and bring it to a known, safe idle state.
+    ///
+    /// This does not depend on what state the device was left in, so
+    /// it is safe to call on a device left transmitting. After
+    /// [Self::reset()], it writes:
+    ///
+    ///  1. [registers::PowerControl], with the band gap, crystal and
+    ///     DSP on, and the RF LDO at 2.4V, as the stock firmware does.
+    ///  2. [registers::Enable], with everything off, so the transmitter
+    ///     is off. Receiving needs [registers::Enable::rx()].
+    ///  3. [registers::PaControl], with PA CTL off, zero bias, and low
+    ///     gains, so the PA stays off until [Self::set_pa()].
+    ///  4. [registers::GpioOutput], with GPIO5 and GPIO6 enabled (the
+    ///     UV-K5's red and green LEDs), and every output low.
+    ///  5. [registers::InterruptEnable], with every interrupt off.
+    pub fn init_defaults(&mut self) -> Result<(), Error<E>> {
+        self.reset()?;
+
+        let batch = Batch::<5>::new()
+            .with(
+                registers::PowerControl::new()
+                    .with_band_gap_enabled(true)
+                    .with_xtal_enabled(true)
+                    .with_dsp_enabled(true)
+                    .with_unknown_b3(true)
+                    .with_rf_ldo_select(registers::LdoVoltage::V2_4),
+            )
+            .with(registers::Enable::new())
+            .with(
+                registers::PaControl::new()
+                    .with_gain2(0b010)
+                    .with_gain1(0b100),
+            )
+            .with(
+                registers::GpioOutput::new()
+                    .with_enabled(5, true)
+                    .with_enabled(6, true),
+            )
+            .with(registers::InterruptEnable::new());
+        self.apply_registers(batch)
+    }
 
-        self.write(registers::Reset::new().with_reset(true))?;
-        self.write(registers::Reset::new())?;
+    /// Set the AF Rx de-emphasis.
+    pub fn set_deemphasis(&mut self, deemphasis: registers::Deemphasis) -> Result<(), Error<E>> {
+        self.modify(|r: registers::AfFilters| r.with_rx_deemphasis(deemphasis))
+    }
 
+    /// Set the demodulation mode.
+    ///
+    /// This sets the AF output, the IF setting used by SSB mods, and
+    /// disables AFC for anything but FM. Other settings, like filter
+    /// bandwidth and AGC, are left alone. Use the raw register access
+    /// to experiment further.
+    pub fn set_modulation(&mut self, modulation: Modulation) -> Result<(), Error<E>> {
+        let output = match modulation {
+            Modulation::Fm => registers::AfOutput::Fm,
+            Modulation::Am => registers::AfOutput::Am,
+            Modulation::Usb | Modulation::Lsb => registers::AfOutput::Baseband2,
+        };
+        let ssb = matches!(modulation, Modulation::Usb | Modulation::Lsb);
+
+        self.write(registers::AfControl::new().with_output(Ok(output)))?;
+        self.write(registers::Unknown3d::new().with_data(if ssb { 0x0000 } else { 0x2aab }))?;
+        self.modify(|r: registers::AfcControl| r.with_afc_disabled(modulation != Modulation::Fm))
+    }
+
+    /// Set the channel bandwidth.
+    ///
+    /// This sets the Rx filter bandwidth and Tx AF low-pass together,
+    /// to the values in [Bandwidth::register()]. Use the raw register
+    /// access to set them separately.
+    pub fn set_bandwidth(&mut self, bandwidth: Bandwidth) -> Result<(), Error<E>> {
+        self.write(bandwidth.register())
+    }
+
+    /// Tune to a frequency, in Hz.
+    ///
+    /// The device only tunes in steps of 10Hz, so this is rounded down.
+    /// Any offset from [Self::set_frequency_offset()] is added first.
+    ///
+    /// This returns as soon as the registers are written, before the
+    /// PLL has locked. Use [Self::set_frequency_settled()] or
+    /// [Self::set_frequency_blocking()] to also wait for it.
+    pub fn set_frequency(&mut self, freq_hz: u32) -> Result<(), Error<E>> {
+        self.frequency_hz = freq_hz;
+        self.write_frequency()
+    }
+
+    /// Tune to a frequency, in Hz, and wait [PLL_SETTLE_US] for the
+    /// PLL to lock, using the owned delay.
+    ///
+    /// This is [Self::set_frequency_blocking()] for a device created
+    /// with [Bk4819::new()].
+    pub fn set_frequency_settled(&mut self, freq_hz: u32) -> Result<(), Error<E>> {
+        self.set_frequency(freq_hz)?;
+        self.delay.delay_us(PLL_SETTLE_US);
         Ok(())
     }
 
+    /// Fine tune away from the frequency given to
+    /// [Self::set_frequency()], in Hz.
+    ///
+    /// The offset can be anywhere from -32768Hz to 32767Hz, and is
+    /// kept across later calls to [Self::set_frequency()]. It has the
+    /// same 10Hz resolution as the frequency registers, since there is
+    /// no separate offset register: the offset frequency is rounded
+    /// down to a multiple of 10Hz. [Self::sweep()] ignores the offset.
+    pub fn set_frequency_offset(&mut self, hz: i16) -> Result<(), Error<E>> {
+        self.frequency_offset_hz = hz;
+        self.write_frequency()
+    }
+
+    /// The current offset set by [Self::set_frequency_offset()], in Hz.
+    pub fn frequency_offset(&self) -> i16 {
+        self.frequency_offset_hz
+    }
+
+    fn write_frequency(&mut self) -> Result<(), Error<E>> {
+        let word = self.frequency_word();
+        self.raw().tune(word)
+    }
+
+    /// Enable or disable automatic frequency control, for FM.
+    ///
+    /// [Self::set_modulation()] also sets this, enabling AFC only for
+    /// FM.
+    pub fn enable_afc(&mut self, enable: bool) -> Result<(), Error<E>> {
+        self.modify(|r: registers::AfcControl| r.with_afc_disabled(!enable))
+    }
+
+    /// Read the frequency error measured by AFC, in Hz.
+    ///
+    /// This has a resolution of about 3.33Hz. See
+    /// [registers::AfcError].
+    pub fn afc_error(&mut self) -> Result<i32, Error<E>> {
+        Ok(self.read::<registers::AfcError>()?.hz())
+    }
+
+    /// Read the current RSSI, in 0.5dB steps. See [registers::Rssi].
+    pub fn rssi(&mut self) -> Result<u16, Error<E>> {
+        Ok(self.read::<registers::Rssi>()?.rssi())
+    }
+
+    /// Read an approximate transmit power indication, while
+    /// transmitting.
+    ///
+    /// The BK4819 has no documented forward power detector, so this
+    /// reads the RSSI detector in [registers::Rssi] with the transmitter
+    /// on. This is at best a relative indication of output, in
+    /// uncalibrated units: it may not track output linearly, or at all
+    /// on some radios, so check it against a power meter before relying
+    /// on it. Relating it to actual output power needs a per-radio
+    /// calibration table, like the factory calibration in EEPROM.
+    ///
+    /// There is no reverse power detector either, so SWR can't be
+    /// measured.
+    pub fn tx_power_reading(&mut self) -> Result<u16, Error<E>> {
+        self.rssi()
+    }
+
+    /// Is the device's squelch open? See [registers::Status].
+    pub fn squelch_open(&mut self) -> Result<bool, Error<E>> {
+        Ok(self.read::<registers::Status>()?.squelch_open())
+    }
+
+    /// Check that a device is answering on the bus.
+    ///
+    /// The BK4819 has no documented ID or revision register, so this
+    /// writes two test patterns to [registers::FrequencyLow], reads
+    /// each back, and then restores the old value. A missing device or
+    /// a dead bus reads as all zeros or all ones, and fails.
+    ///
+    /// This briefly changes the frequency, so call it before tuning or
+    /// while the device is idle.
+    pub fn is_present(&mut self) -> Result<bool, Error<E>> {
+        let old = self.read_raw(registers::FrequencyLow::ADDRESS)?;
+
+        let mut present = true;
+        for pattern in [0xa55a, 0x5aa5] {
+            self.write_raw(registers::FrequencyLow::ADDRESS, pattern)?;
+            present &= self.read_raw(registers::FrequencyLow::ADDRESS)? == pattern;
+        }
+
+        self.write_raw(registers::FrequencyLow::ADDRESS, old)?;
+        Ok(present)
+    }
+
+    /// Acknowledge and return the pending interrupt flags, if any.
+    ///
+    /// This follows the stock firmware: if [registers::Status] shows
+    /// an interrupt pending, write [registers::Interrupts] to
+    /// acknowledge it, and then read the flags. Only interrupts
+    /// enabled in [registers::InterruptEnable] are ever flagged.
+    pub fn take_interrupts(&mut self) -> Result<Option<registers::Interrupts>, Error<E>> {
+        if !self.read::<registers::Status>()?.interrupt_pending() {
+            return Ok(None);
+        }
+
+        self.write(registers::Interrupts::new())?;
+        Ok(Some(self.read()?))
+    }
+
+    /// Sweep across `count` frequencies spaced by `step_hz`, starting
+    /// at `start_hz`, recording the RSSI at each into `out`.
+    ///
+    /// This only writes the frequency registers that change between
+    /// steps, so small steps usually cost a single register write,
+    /// plus toggling [registers::Enable] to retrigger the PLL.
+    ///
+    /// After each retune, this waits [PLL_SETTLE_US] for the PLL to
+    /// lock, like [Self::set_frequency_blocking()], and then another
+    /// `settle_us` microseconds before sampling the RSSI. Shorter
+    /// settling makes for a faster sweep, but the RSSI needs time to
+    /// catch up, and too short a delay will smear readings across
+    /// neighboring steps.
+    ///
+    /// The device is left tuned to the last frequency in the sweep.
+    ///
+    /// If `out` is shorter than `count`, only `out.len()` frequencies
+    /// are swept.
+    pub fn sweep(
+        &mut self,
+        start_hz: u32,
+        step_hz: u32,
+        count: usize,
+        settle_us: u32,
+        out: &mut [u16],
+    ) -> Result<(), Error<E>> {
+        let enable = self.read_raw(registers::Enable::ADDRESS)?;
+        let mut last: Option<u32> = None;
+        let mut freq_hz = start_hz;
+        for rssi in out.iter_mut().take(count) {
+            let word = freq_hz / 10;
+            if last.map(|l| l as u16) != Some(word as u16) {
+                self.write(registers::FrequencyLow::new().with_data(word as u16))?;
+            }
+            if last.map(|l| l >> 16) != Some(word >> 16) {
+                self.write(registers::FrequencyHigh::new().with_data((word >> 16) as u16))?;
+            }
+            if last != Some(word) {
+                self.raw().relock(enable)?;
+                self.delay.delay_us(PLL_SETTLE_US);
+            }
+            last = Some(word);
+
+            self.delay.delay_us(settle_us);
+            *rssi = self.rssi()?;
+
+            freq_hz = freq_hz.wrapping_add(step_hz);
+        }
+
+        Ok(())
+    }
+
+    /// Set up the power amplifier, and enable PA CTL output.
+    pub fn set_pa(&mut self, pa: PaSettings) -> Result<(), Error<E>> {
+        self.write(
+            registers::PaControl::new()
+                .with_bias(pa.bias)
+                .with_pactl_enabled(true)
+                .with_gain1(pa.gain1 & 0b111)
+                .with_gain2(pa.gain2 & 0b111),
+        )
+    }
+
+    /// Set the transmit power to one of the uncalibrated presets in
+    /// [TxPower::pa()].
+    ///
+    /// Exceeding legal power limits is the caller's responsibility.
+    pub fn set_tx_power(&mut self, level: TxPower) -> Result<(), Error<E>> {
+        self.set_pa(level.pa())
+    }
+
+    /// Set the transmit power using a calibrated `[low, mid, high]`
+    /// table of PA biases, usually read from the radio's factory
+    /// calibration for the current band.
+    ///
+    /// Exceeding legal power limits is the caller's responsibility.
+    pub fn set_tx_power_calibrated(
+        &mut self,
+        level: TxPower,
+        biases: &[u8; 3],
+    ) -> Result<(), Error<E>> {
+        self.set_pa(PaSettings {
+            bias: biases[level.index()],
+            ..level.pa()
+        })
+    }
+
+    /// Configure VOX, or disable it with [None].
+    ///
+    /// VOX events are reported in [registers::Interrupts], and the
+    /// current state by [Self::vox_active()].
+    pub fn set_vox(&mut self, config: Option<VoxConfig>) -> Result<(), Error<E>> {
+        if let Some(config) = config {
+            self.modify(|r: registers::VoxOnThreshold| r.with_threshold(config.threshold & 0x7ff))?;
+            self.modify(|r: registers::VoxOffThreshold| r.with_threshold(config.release & 0x7ff))?;
+            self.modify(|r: registers::VoxDelay| r.with_delay(config.delay & 0xf))?;
+        }
+
+        self.modify(|r: registers::FeatureEnable| r.with_vox_enabled(config.is_some()))
+    }
+
+    /// Configure the AGC.
+    ///
+    /// This writes the gain table first, if given, and then
+    /// the AGC control and mode, in one [Batch].
+    pub fn set_agc(&mut self, config: AgcConfig) -> Result<(), Error<E>> {
+        // keep the DC filter settings that share the mode register
+        let filters = self.read::<registers::AgcFilters>()?;
+
+        let mut batch = Batch::<7>::new();
+        if let Some(table) = config.table {
+            batch.push(registers::AgcGainTable0(table[0]));
+            batch.push(registers::AgcGainTable1(table[1]));
+            batch.push(registers::AgcGainTable2(table[2]));
+            batch.push(registers::AgcGainTable3(table[3]));
+            batch.push(registers::AgcGainTable4(table[4]));
+        }
+        batch.push(
+            registers::AgcControl::new()
+                .with_low_threshold(config.low_threshold & 0x7f)
+                .with_high_threshold(config.high_threshold & 0x7f)
+                .with_speed(config.speed),
+        );
+        batch.push(
+            filters
+                .with_agc_mode(config.mode)
+                .with_agc_index(config.index),
+        );
+        self.apply_registers(batch)
+    }
+
+    /// Set the mic gain, 0 (min) to 31 (max), in 0.5dB steps.
+    ///
+    /// Larger values are clamped to 31. The device resets to 16. See
+    /// [registers::MicSensitivity].
+    pub fn set_mic_gain(&mut self, gain: u8) -> Result<(), Error<E>> {
+        self.modify(|r: registers::MicSensitivity| r.with_sensitivity(gain.min(0x1f)))
+    }
+
+    /// Set the compander mode.
+    ///
+    /// This uses the same compressor and expander settings as the
+    /// stock firmware, a 2:1 compressor and a 1:2 expander. The other
+    /// end must use a matching mode, see [CompanderMode].
+    pub fn set_compander(&mut self, mode: CompanderMode) -> Result<(), Error<E>> {
+        if mode != CompanderMode::Off {
+            let (compress, expand) = match mode {
+                CompanderMode::Tx => (true, false),
+                CompanderMode::Rx => (false, true),
+                _ => (true, true),
+            };
+
+            let compressor = registers::Compressor::new();
+            let expander = registers::Expander::new();
+            self.write(if compress {
+                compressor
+            } else {
+                compressor.with_ratio(registers::CompressorRatio::Disabled)
+            })?;
+            self.write(if expand {
+                expander
+            } else {
+                expander.with_ratio(registers::ExpanderRatio::Disabled)
+            })?;
+        }
+
+        self.modify(|r: registers::FeatureEnable| {
+            r.with_compander_enabled(mode != CompanderMode::Off)
+        })
+    }
+
+    /// Configure the transmit audio path.
+    pub fn set_af_config(&mut self, config: AfConfig) -> Result<(), Error<E>> {
+        self.set_mic_gain(config.mic_gain)?;
+        self.set_compander(config.compander)?;
+        self.modify(|r: registers::AfFilters| r.with_tx_preemphasis_disabled(!config.preemphasis))
+    }
+
+    /// Is VOX currently active?
+    pub fn vox_active(&mut self) -> Result<bool, Error<E>> {
+        Ok(self.read::<registers::Status>()?.vox_active())
+    }
+
+    /// Read the current voice amplitude. See [VoxConfig].
+    pub fn voice_amplitude(&mut self) -> Result<u16, Error<E>> {
+        Ok(self.read::<registers::VoiceAmplitude>()?.amplitude())
+    }
+
     /// Is a given GPIO output enabled?
     pub fn gpio_is_output_enabled(&mut self, pin: u8) -> Result<bool, Error<E>> {
         Ok(self.read::<registers::GpioOutput>()?.enabled(pin))
@@ -290,3 +1308,626 @@ where
         self.modify(|r: registers::GpioOutput| r.with_state(pin, !r.state(pin)))
     }
 }
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use core::cell::RefCell;
+    use core::convert::Infallible;
+    use std::vec::Vec;
+
+    use embedded_hal::digital::ErrorType;
+
+    use super::*;
+    use crate::mock::{mock, MockBus, NoDelay, Pin};
+
+    // a pin on a dead bus, always reading the same level
+    struct StuckPin(bool);
+
+    impl ErrorType for StuckPin {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for StuckPin {
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    impl InputPin for StuckPin {
+        fn is_high(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.0)
+        }
+
+        fn is_low(&mut self) -> Result<bool, Self::Error> {
+            Ok(!self.0)
+        }
+    }
+
+    // a delay that records every request, in nanoseconds
+    struct RecordDelay<'a>(&'a RefCell<Vec<u32>>);
+
+    impl DelayNs for RecordDelay<'_> {
+        fn delay_ns(&mut self, ns: u32) {
+            self.0.borrow_mut().push(ns);
+        }
+    }
+
+    #[test]
+    fn clock_delay() {
+        let bus = RefCell::new(MockBus::new());
+        let delays = RefCell::new(Vec::new());
+        let pin = |set| Pin::new(&bus, set);
+        let mut bk = Bk4819::new(
+            pin(MockBus::set_scn),
+            pin(MockBus::set_scl),
+            pin(MockBus::set_sda),
+            RecordDelay(&delays),
+        )
+        .unwrap();
+        assert_eq!(bk.clock_delay_ns(), DEFAULT_CLOCK_DELAY_NS);
+
+        delays.borrow_mut().clear();
+        bk.write_raw(0x38, 0xbeef).unwrap();
+        assert!(!delays.borrow().is_empty());
+        assert!(delays.borrow().iter().all(|&ns| ns == 1_000));
+
+        let mut bk = bk.with_clock_delay_ns(250);
+        delays.borrow_mut().clear();
+        assert_eq!(bk.read_raw(0x38), Ok(0xbeef));
+        assert!(!delays.borrow().is_empty());
+        assert!(delays.borrow().iter().all(|&ns| ns == 250));
+    }
+
+    #[test]
+    fn borrowed_delay() {
+        let owned_bus = RefCell::new(MockBus::new());
+        let owned_delays = RefCell::new(Vec::new());
+        let pin = |set| Pin::new(&owned_bus, set);
+        let mut owned = Bk4819::new(
+            pin(MockBus::set_scn),
+            pin(MockBus::set_scl),
+            pin(MockBus::set_sda),
+            RecordDelay(&owned_delays),
+        )
+        .unwrap();
+        owned.write_raw(0x38, 0xbeef).unwrap();
+        assert_eq!(owned.read_raw(0x38), Ok(0xbeef));
+        owned
+            .modify(|r: registers::PaControl| r.with_gain1(0b101))
+            .unwrap();
+
+        let bus = RefCell::new(MockBus::new());
+        let delays = RefCell::new(Vec::new());
+        let mut delay = RecordDelay(&delays);
+        let pin = |set| Pin::new(&bus, set);
+        let mut borrowed = Bk4819::new_borrowed(
+            pin(MockBus::set_scn),
+            pin(MockBus::set_scl),
+            pin(MockBus::set_sda),
+            &mut delay,
+        )
+        .unwrap();
+        borrowed.write_raw_with(&mut delay, 0x38, 0xbeef).unwrap();
+        assert_eq!(borrowed.read_raw_with(&mut delay, 0x38), Ok(0xbeef));
+        borrowed
+            .modify_with(&mut delay, |r: registers::PaControl| r.with_gain1(0b101))
+            .unwrap();
+
+        // the same writes, with the same clock delays between them
+        assert_eq!(bus.borrow().writes, owned_bus.borrow().writes);
+        assert_eq!(bus.borrow().registers, owned_bus.borrow().registers);
+        assert!(!delays.borrow().is_empty());
+        assert_eq!(delays, owned_delays);
+    }
+
+    #[test]
+    fn apply() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+
+        bk.apply(&[(0x38, 0x1234), (0x39, 0x5678), (0x38, 0xbeef)])
+            .unwrap();
+        assert_eq!(
+            bus.borrow().writes,
+            [(0x38, 0x1234), (0x39, 0x5678), (0x38, 0xbeef)]
+        );
+
+        bus.borrow_mut().writes.clear();
+        let batch = Batch::<3>::new()
+            .with(registers::PaControl::new())
+            .with(registers::Enable::new())
+            .with(registers::Enable::rx());
+        bk.apply_registers(batch).unwrap();
+        assert_eq!(
+            bus.borrow().writes,
+            [(0x36, 0x003f), (0x30, 0x0000), (0x30, 0xbff1)]
+        );
+    }
+
+    #[test]
+    fn init_defaults() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+
+        // left mid-transmit, with the PA up
+        bus.borrow_mut().registers[0x30] = 0xc1fe;
+        bus.borrow_mut().registers[0x36] = 0x8ca2;
+
+        bk.init_defaults().unwrap();
+        assert_eq!(
+            bus.borrow().writes,
+            [
+                (0x00, 0x8000),
+                (0x00, 0x0000),
+                (0x37, 0x1d0f),
+                (0x30, 0x0000),
+                (0x36, 0x0022),
+                (0x33, 0xfc00),
+                (0x3f, 0x0000),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_write() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+
+        bus.borrow_mut().registers[0x67] = 0xfe42;
+        assert_eq!(bk.rssi(), Ok(0x42));
+
+        bk.write_raw(0x38, 0xbeef).unwrap();
+        assert_eq!(bk.read_raw(0x38), Ok(0xbeef));
+        assert_eq!(bus.borrow().writes, [(0x38, 0xbeef)]);
+    }
+
+    #[test]
+    fn set_modulation() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+        bus.borrow_mut().registers[0x73] = 0x0a02;
+
+        bk.set_modulation(Modulation::Usb).unwrap();
+        bk.set_modulation(Modulation::Fm).unwrap();
+        assert_eq!(
+            bus.borrow().writes,
+            [
+                (0x47, 0x6540),
+                (0x3d, 0x0000),
+                (0x73, 0x0a12),
+                (0x47, 0x6140),
+                (0x3d, 0x2aab),
+                (0x73, 0x0a02),
+            ]
+        );
+    }
+
+    #[test]
+    fn set_bandwidth() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+
+        bk.set_bandwidth(Bandwidth::Wide).unwrap();
+        bk.set_bandwidth(Bandwidth::Narrow).unwrap();
+        bk.set_bandwidth(Bandwidth::Narrower).unwrap();
+        assert_eq!(
+            bus.borrow().writes,
+            [(0x43, 0x3028), (0x43, 0x4048), (0x43, 0x4058)]
+        );
+    }
+
+    #[test]
+    fn set_tx_power() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+
+        bk.set_tx_power(TxPower::Low).unwrap();
+        bk.set_tx_power(TxPower::Mid).unwrap();
+        bk.set_tx_power(TxPower::High).unwrap();
+        bk.set_tx_power_calibrated(TxPower::Mid, &[0x32, 0x64, 0x96])
+            .unwrap();
+        bk.set_pa(PaSettings {
+            bias: 0xff,
+            gain1: 1,
+            gain2: 0,
+        })
+        .unwrap();
+
+        assert_eq!(
+            bus.borrow().writes,
+            [
+                (0x36, 0x1ea2),
+                (0x36, 0x50a2),
+                (0x36, 0x8ca2),
+                (0x36, 0x64a2),
+                (0x36, 0xff88),
+            ]
+        );
+    }
+
+    #[test]
+    fn tx_power_reading() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+        bus.borrow_mut().registers[0x67] = 0xfe80;
+
+        assert_eq!(bk.tx_power_reading(), Ok(0x80));
+        assert!(bus.borrow().writes.is_empty());
+    }
+
+    #[test]
+    fn is_present() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+        bus.borrow_mut().registers[0x38] = 0x1234;
+
+        assert_eq!(bk.is_present(), Ok(true));
+        assert_eq!(
+            bus.borrow().writes,
+            [(0x38, 0xa55a), (0x38, 0x5aa5), (0x38, 0x1234)]
+        );
+
+        for level in [false, true] {
+            let mut bk =
+                Bk4819::new(StuckPin(level), StuckPin(level), StuckPin(level), NoDelay).unwrap();
+            assert_eq!(bk.is_present(), Ok(false));
+        }
+    }
+
+    #[test]
+    fn set_agc() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+        bus.borrow_mut().registers[0x7e] = 0x302e;
+
+        bk.set_agc(AgcConfig {
+            table: Some(AgcConfig::default_table()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            bus.borrow().writes,
+            [
+                (0x10, 0x0038),
+                (0x11, 0x025a),
+                (0x12, 0x037b),
+                (0x13, 0x03de),
+                (0x14, 0x0000),
+                (0x49, 0x2830),
+                (0x7e, 0x302e),
+            ]
+        );
+
+        bus.borrow_mut().writes.clear();
+        bk.set_agc(AgcConfig {
+            mode: registers::AgcMode::Locked,
+            index: -1,
+            speed: registers::AgcSpeed::Fast,
+            low_threshold: 56,
+            high_threshold: 84,
+            table: None,
+        })
+        .unwrap();
+        assert_eq!(bus.borrow().writes, [(0x49, 0x6a38), (0x7e, 0xf02e)]);
+    }
+
+    #[test]
+    fn set_vox() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+        {
+            let mut bus = bus.borrow_mut();
+            bus.registers[0x31] = 0x0008;
+            bus.registers[0x46] = 0xa000;
+            bus.registers[0x79] = 0x1800;
+            bus.registers[0x7a] = 0x289a;
+        }
+
+        bk.set_vox(Some(VoxConfig {
+            threshold: 0x50,
+            release: 0x40,
+            delay: 5,
+        }))
+        .unwrap();
+        bk.set_vox(None).unwrap();
+        assert_eq!(
+            bus.borrow().writes,
+            [
+                (0x46, 0xa050),
+                (0x79, 0x1840),
+                (0x7a, 0x589a),
+                (0x31, 0x000c),
+                (0x31, 0x0008),
+            ]
+        );
+
+        bus.borrow_mut().registers[0x0c] = 0x0004;
+        assert_eq!(bk.vox_active(), Ok(true));
+        bus.borrow_mut().registers[0x0c] = 0x0002;
+        assert_eq!(bk.vox_active(), Ok(false));
+    }
+
+    #[test]
+    fn sweep() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+        bus.borrow_mut().registers[0x67] = 0x0080;
+        bus.borrow_mut().registers[0x30] = 0xbff1;
+
+        // 0x0001_fffe * 10Hz, stepping across the high word boundary
+        let mut out = [0; 4];
+        bk.sweep(0x1fffe * 10, 10, 4, 100, &mut out).unwrap();
+
+        assert_eq!(out, [0x80; 4]);
+        assert_eq!(
+            bus.borrow().writes,
+            [
+                (0x38, 0xfffe),
+                (0x39, 0x0001),
+                (0x30, 0x0000),
+                (0x30, 0xbff1),
+                (0x38, 0xffff),
+                (0x30, 0x0000),
+                (0x30, 0xbff1),
+                (0x38, 0x0000),
+                (0x39, 0x0002),
+                (0x30, 0x0000),
+                (0x30, 0xbff1),
+                (0x38, 0x0001),
+                (0x30, 0x0000),
+                (0x30, 0xbff1),
+            ]
+        );
+    }
+
+    #[test]
+    fn sweep_short_output() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+        bus.borrow_mut().registers[0x67] = 0x0080;
+
+        // only as many steps as fit
+        let mut out = [0; 2];
+        bk.sweep(1000, 10, 4, 0, &mut out).unwrap();
+        assert_eq!(out, [0x80; 2]);
+        assert_eq!(bus.borrow().registers[0x38], 101);
+    }
+
+    #[test]
+    fn sweep_small_steps() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+
+        // steps under 10Hz only retune when the frequency word changes
+        let mut out = [0; 4];
+        bk.sweep(1000, 5, 4, 0, &mut out).unwrap();
+        assert_eq!(
+            bus.borrow().writes,
+            [
+                (0x38, 100),
+                (0x39, 0),
+                (0x30, 0),
+                (0x30, 0),
+                (0x38, 101),
+                (0x30, 0),
+                (0x30, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn frequency_offset() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+
+        // 0x0001_0000 * 10Hz, so offsets cross the high word boundary
+        bus.borrow_mut().registers[0x30] = 0xbff1;
+        bk.set_frequency(655_360).unwrap();
+        bk.set_frequency_offset(1_250).unwrap();
+        bk.set_frequency_offset(-1_250).unwrap();
+        assert_eq!(
+            bus.borrow().writes,
+            [
+                (0x38, 0x0000),
+                (0x39, 0x0001),
+                (0x30, 0x0000),
+                (0x30, 0xbff1),
+                (0x38, 0x007d),
+                (0x39, 0x0001),
+                (0x30, 0x0000),
+                (0x30, 0xbff1),
+                (0x38, 0xff83),
+                (0x39, 0x0000),
+                (0x30, 0x0000),
+                (0x30, 0xbff1),
+            ]
+        );
+
+        // kept across retuning
+        bus.borrow_mut().writes.clear();
+        bk.set_frequency(1_000).unwrap();
+        assert_eq!(bk.frequency_offset(), -1_250);
+        assert_eq!(
+            bus.borrow().writes,
+            [
+                (0x38, 0x0000),
+                (0x39, 0x0000),
+                (0x30, 0x0000),
+                (0x30, 0xbff1)
+            ]
+        );
+    }
+
+    #[test]
+    fn set_frequency_blocking() {
+        let bus = RefCell::new(MockBus::new());
+        let delays = RefCell::new(Vec::new());
+        let pin = |set| Pin::new(&bus, set);
+        let mut bk = Bk4819::new(
+            pin(MockBus::set_scn),
+            pin(MockBus::set_scl),
+            pin(MockBus::set_sda),
+            RecordDelay(&delays),
+        )
+        .unwrap();
+
+        // only clock delays without blocking
+        bus.borrow_mut().writes.clear();
+        delays.borrow_mut().clear();
+        bk.set_frequency(145_500_000).unwrap();
+        assert!(delays
+            .borrow()
+            .iter()
+            .all(|&ns| ns == DEFAULT_CLOCK_DELAY_NS));
+
+        // the same writes with blocking, then a wait for the PLL
+        bus.borrow_mut().registers[0x30] = 0xbff1;
+        let blocking_delays = RefCell::new(Vec::new());
+        bk.set_frequency_blocking(145_500_000, &mut RecordDelay(&blocking_delays))
+            .unwrap();
+        assert_eq!(
+            blocking_delays.borrow().last(),
+            Some(&(PLL_SETTLE_US * 1_000))
+        );
+        let count = blocking_delays.borrow().len();
+        assert!(blocking_delays.borrow()[..count - 1]
+            .iter()
+            .all(|&ns| ns == DEFAULT_CLOCK_DELAY_NS));
+        assert_eq!(
+            bus.borrow().writes,
+            [
+                (0x38, 0x03f0),
+                (0x39, 0x00de),
+                (0x30, 0x0000),
+                (0x30, 0x0000),
+                (0x38, 0x03f0),
+                (0x39, 0x00de),
+                (0x30, 0x0000),
+                (0x30, 0xbff1),
+            ]
+        );
+
+        // and the same again with the owned delay
+        bus.borrow_mut().writes.clear();
+        delays.borrow_mut().clear();
+        bk.set_frequency_settled(145_500_000).unwrap();
+        assert_eq!(delays.borrow().last(), Some(&(PLL_SETTLE_US * 1_000)));
+        assert_eq!(
+            bus.borrow().writes,
+            [
+                (0x38, 0x03f0),
+                (0x39, 0x00de),
+                (0x30, 0x0000),
+                (0x30, 0xbff1),
+            ]
+        );
+    }
+
+    #[test]
+    fn sweep_settle() {
+        let bus = RefCell::new(MockBus::new());
+        let delays = RefCell::new(Vec::new());
+        let pin = |set| Pin::new(&bus, set);
+        let mut bk = Bk4819::new(
+            pin(MockBus::set_scn),
+            pin(MockBus::set_scl),
+            pin(MockBus::set_sda),
+            RecordDelay(&delays),
+        )
+        .unwrap();
+
+        // the PLL wait only follows a retune, the settle time every step
+        delays.borrow_mut().clear();
+        let mut out = [0; 3];
+        bk.sweep(1000, 5, 3, 100, &mut out).unwrap();
+        let waits: Vec<u32> = delays
+            .borrow()
+            .iter()
+            .copied()
+            .filter(|&ns| ns != DEFAULT_CLOCK_DELAY_NS)
+            .collect();
+        assert_eq!(
+            waits,
+            [
+                PLL_SETTLE_US * 1_000,
+                100_000,
+                100_000,
+                PLL_SETTLE_US * 1_000,
+                100_000,
+            ]
+        );
+    }
+
+    #[test]
+    fn afc() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+        bus.borrow_mut().registers[0x6d] = -30i16 as u16;
+
+        assert_eq!(bk.afc_error(), Ok(-100));
+        bk.enable_afc(false).unwrap();
+        bk.enable_afc(true).unwrap();
+        assert_eq!(bus.borrow().writes, [(0x73, 0x0010), (0x73, 0x0000)]);
+    }
+
+    #[test]
+    fn set_mic_gain() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+        bus.borrow_mut().registers[0x7d] = 0xe550;
+
+        bk.set_mic_gain(0).unwrap();
+        bk.set_mic_gain(0x1f).unwrap();
+        bk.set_mic_gain(0x40).unwrap();
+        assert_eq!(
+            bus.borrow().writes,
+            [(0x7d, 0xe540), (0x7d, 0xe55f), (0x7d, 0xe55f)]
+        );
+    }
+
+    #[test]
+    fn set_compander() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+
+        bk.set_compander(CompanderMode::TxRx).unwrap();
+        bk.set_compander(CompanderMode::Tx).unwrap();
+        bk.set_compander(CompanderMode::Off).unwrap();
+        assert_eq!(
+            bus.borrow().writes,
+            [
+                (0x29, 0xab40),
+                (0x28, 0x6b38),
+                (0x31, 0x0008),
+                (0x29, 0xab40),
+                (0x28, 0x2b38),
+                (0x31, 0x0008),
+                (0x31, 0x0000),
+            ]
+        );
+    }
+
+    #[test]
+    fn set_af_config() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+        bus.borrow_mut().registers[0x7d] = 0xe550;
+
+        bk.set_af_config(AfConfig {
+            mic_gain: 0x08,
+            preemphasis: false,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            bus.borrow().writes,
+            [(0x7d, 0xe548), (0x31, 0x0000), (0x2b, 0x0004)]
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! A fake BK4819 for tests, here and in crates built on this one.
+//!
+//! This is only available with the `test-support` feature, and needs
+//! `std`.
+
+extern crate std;
+
+use core::cell::RefCell;
+use core::convert::Infallible;
+use std::vec::Vec;
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{ErrorType, InputPin, OutputPin};
+
+use crate::Bk4819;
+
+/// A fake BK4819 on the other end of a bit-banged bus.
+pub struct MockBus {
+    scn: bool,
+    scl: bool,
+    sda: bool,
+    bits: u32,
+    bit_count: u32,
+    /// The device registers, read and written over the bus.
+    pub registers: [u16; 0x80],
+    /// Every register write, in order, as (address, value).
+    pub writes: Vec<(u8, u16)>,
+}
+
+impl Default for MockBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockBus {
+    /// A new bus, with every register zero.
+    pub fn new() -> Self {
+        Self {
+            scn: true,
+            scl: true,
+            sda: true,
+            bits: 0,
+            bit_count: 0,
+            registers: [0; 0x80],
+            writes: Vec::new(),
+        }
+    }
+
+    fn address(&self) -> u8 {
+        (self.bits >> (self.bit_count - 8)) as u8
+    }
+
+    /// Drive the SCN (chip select) line.
+    pub fn set_scn(&mut self, high: bool) {
+        if high && !self.scn && self.bit_count == 24 {
+            let address = (self.bits >> 16) as u8;
+            if address == 0x02 {
+                // writing interrupts acknowledges them, see take_interrupts
+                self.registers[0x0c] &= !1;
+                self.writes.push((address, self.bits as u16));
+            } else if address & 0x80 == 0 {
+                self.registers[address as usize] = self.bits as u16;
+                self.writes.push((address, self.bits as u16));
+            }
+        }
+        if !high {
+            self.bits = 0;
+            self.bit_count = 0;
+        }
+        self.scn = high;
+    }
+
+    /// Drive the SCL (clock) line.
+    pub fn set_scl(&mut self, high: bool) {
+        if high && !self.scl && !self.scn {
+            // sample on rising edge
+            self.bits = (self.bits << 1) | self.sda as u32;
+            self.bit_count += 1;
+        }
+        self.scl = high;
+    }
+
+    /// Drive the SDA (data) line.
+    pub fn set_sda(&mut self, high: bool) {
+        self.sda = high;
+    }
+
+    /// Read the SDA line, driven by the device during a read.
+    pub fn read_sda(&self) -> bool {
+        if self.bit_count >= 8 && self.address() & 0x80 != 0 {
+            let value = self.registers[(self.address() & 0x7f) as usize];
+            value & (0x8000 >> (self.bit_count - 8)) != 0
+        } else {
+            self.sda
+        }
+    }
+}
+
+/// One line of a [MockBus], that reads as SDA.
+pub struct Pin<'a> {
+    bus: &'a RefCell<MockBus>,
+    set: fn(&mut MockBus, bool),
+}
+
+impl<'a> Pin<'a> {
+    /// A pin driving `bus` with `set`, such as [MockBus::set_scn].
+    pub fn new(bus: &'a RefCell<MockBus>, set: fn(&mut MockBus, bool)) -> Self {
+        Self { bus, set }
+    }
+}
+
+impl ErrorType for Pin<'_> {
+    type Error = Infallible;
+}
+
+impl OutputPin for Pin<'_> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        (self.set)(&mut self.bus.borrow_mut(), false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        (self.set)(&mut self.bus.borrow_mut(), true);
+        Ok(())
+    }
+}
+
+impl InputPin for Pin<'_> {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.bus.borrow().read_sda())
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.is_high()?)
+    }
+}
+
+/// A delay that returns immediately.
+pub struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+/// A [Bk4819] talking to `bus`, with the writes made by
+/// [Bk4819::new()] already cleared.
+pub fn mock(bus: &RefCell<MockBus>) -> Bk4819<Pin<'_>, Pin<'_>, Pin<'_>, NoDelay> {
+    let pin = |set| Pin::new(bus, set);
+    let bk = Bk4819::new(
+        pin(MockBus::set_scn),
+        pin(MockBus::set_scl),
+        pin(MockBus::set_sda),
+        NoDelay,
+    )
+    .unwrap();
+    bus.borrow_mut().writes.clear();
+    bk
+}
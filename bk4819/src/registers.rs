@@ -4,8 +4,8 @@
     "0x00" => {
         /* 0x00 */ Reset, /* 0x01 */, /* 0x02 */ Interrupts, /* 0x03 */,
         /* 0x04 */, /* 0x05 */, /* 0x06 */, /* 0x07 */ CtcControl,
-        /* 0x08 */, /* 0x09 */, /* 0x0a */, /* 0x0b */,
-        /* 0x0c */, /* 0x0d */, /* 0x0e */, /* 0x0f */,
+        /* 0x08 */ CdcssCode, /* 0x09 */, /* 0x0a */, /* 0x0b */,
+        /* 0x0c */ Status, /* 0x0d */, /* 0x0e */, /* 0x0f */,
     },
     "0x10" => {
         /* 0x10 */ AgcGainTable0, /* 0x11 */ AgcGainTable1, /* 0x12 */ AgcGainTable2, /* 0x13 */ AgcGainTable3,
@@ -16,37 +16,37 @@
     "0x20" => {
         /* 0x20 */, /* 0x21 */, /* 0x22 */, /* 0x23 */,
         /* 0x24 */, /* 0x25 */, /* 0x26 */, /* 0x27 */,
-        /* 0x28 */, /* 0x29 */, /* 0x2a */, /* 0x2b */,
+        /* 0x28 */ Expander, /* 0x29 */ Compressor, /* 0x2a */, /* 0x2b */ AfFilters,
         /* 0x2c */, /* 0x2d */, /* 0x2e */, /* 0x2f */,
     },
     "0x30" => {
-        /* 0x30 */, /* 0x31 */, /* 0x32 */, /* 0x33 */ GpioOutput,
+        /* 0x30 */ Enable, /* 0x31 */ FeatureEnable, /* 0x32 */, /* 0x33 */ GpioOutput,
         /* 0x34 */, /* 0x35 */, /* 0x36 */ PaControl, /* 0x37 */ PowerControl,
-        /* 0x38 */, /* 0x39 */, /* 0x3a */, /* 0x3b */,
-        /* 0x3c */, /* 0x3d */, /* 0x3e */, /* 0x3f */,
+        /* 0x38 */ FrequencyLow, /* 0x39 */ FrequencyHigh, /* 0x3a */, /* 0x3b */,
+        /* 0x3c */, /* 0x3d */ Unknown3d, /* 0x3e */, /* 0x3f */ InterruptEnable,
     },
     "0x40" => {
-        /* 0x40 */, /* 0x41 */, /* 0x42 */, /* 0x43 */,
-        /* 0x44 */, /* 0x45 */, /* 0x46 */, /* 0x47 */,
-        /* 0x48 */ AfGain, /* 0x49 */ Unknown49, /* 0x4a */, /* 0x4b */,
+        /* 0x40 */, /* 0x41 */, /* 0x42 */, /* 0x43 */ FilterBandwidth,
+        /* 0x44 */, /* 0x45 */, /* 0x46 */ VoxOnThreshold, /* 0x47 */ AfControl,
+        /* 0x48 */ AfGain, /* 0x49 */ AgcControl, /* 0x4a */, /* 0x4b */,
         /* 0x4c */, /* 0x4d */, /* 0x4e */, /* 0x4f */,
     },
     "0x50" => {
-        /* 0x50 */, /* 0x51 */, /* 0x52 */, /* 0x53 */,
+        /* 0x50 */, /* 0x51 */ CxcssControl, /* 0x52 */, /* 0x53 */,
         /* 0x54 */, /* 0x55 */, /* 0x56 */, /* 0x57 */,
         /* 0x58 */, /* 0x59 */, /* 0x5a */, /* 0x5b */,
         /* 0x5c */, /* 0x5d */, /* 0x5e */, /* 0x5f */,
     },
     "0x60" => {
         /* 0x60 */, /* 0x61 */, /* 0x62 */, /* 0x63 */,
-        /* 0x64 */, /* 0x65 */, /* 0x66 */, /* 0x67 */,
+        /* 0x64 */ VoiceAmplitude, /* 0x65 */, /* 0x66 */, /* 0x67 */ Rssi,
         /* 0x68 */, /* 0x69 */, /* 0x6a */, /* 0x6b */,
-        /* 0x6c */, /* 0x6d */, /* 0x6e */, /* 0x6f */,
+        /* 0x6c */, /* 0x6d */ AfcError, /* 0x6e */, /* 0x6f */,
     },
     "0x70" => {
-        /* 0x70 */, /* 0x71 */, /* 0x72 */, /* 0x73 */,
+        /* 0x70 */, /* 0x71 */, /* 0x72 */, /* 0x73 */ AfcControl,
         /* 0x74 */, /* 0x75 */, /* 0x76 */, /* 0x77 */,
-        /* 0x78 */, /* 0x79 */, /* 0x7a */, /* 0x7b */ Unknown7b,
+        /* 0x78 */, /* 0x79 */ VoxOffThreshold, /* 0x7a */ VoxDelay, /* 0x7b */ Unknown7b,
         /* 0x7c */, /* 0x7d */ MicSensitivity, /* 0x7e */ AgcFilters, /* 0x7f */,
     },
 }]
@@ -164,7 +164,10 @@ impl Register for Reset {
 
 /// 0x02 Interrupt status flags.
 ///
-/// Writing any value to this register clears these flags.
+/// Writing any value to this register acknowledges the pending
+/// interrupt, see [Status]. The stock firmware does this before
+/// reading the flags. Interrupts must be enabled in
+/// [InterruptEnable].
 #[cfg_attr(not(feature = "defmt"), bitfield(u16))]
 #[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -278,6 +281,54 @@ impl CtcMode {
     }
 }
 
+/// 0x08 CDCSS code word.
+///
+/// The 24-bit code word is written in two halves, one write each,
+/// selected by [Self::high()]. See [crate::tone_squelch].
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CdcssCode {
+    /// 12 bits of the code word.
+    #[bits(12)]
+    pub data: u16,
+
+    #[bits(3)]
+    __: u8,
+
+    /// Whether [Self::data()] is the high 12 bits, or the low 12 bits.
+    pub high: bool,
+}
+
+impl Register for CdcssCode {
+    const ADDRESS: u8 = 0x08;
+}
+
+/// 0x0c Status.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Status {
+    /// An interrupt is pending, see [Interrupts].
+    #[bits(1, access = RO)]
+    pub interrupt_pending: bool,
+
+    /// Squelch is open.
+    #[bits(1, access = RO)]
+    pub squelch_open: bool,
+
+    /// VOX is active.
+    #[bits(1, access = RO)]
+    pub vox_active: bool,
+
+    #[bits(13)]
+    __: u16,
+}
+
+impl Register for Status {
+    const ADDRESS: u8 = 0x0c;
+}
+
 /// 0x10 - 0x14 AGC gain table entry.
 ///
 /// Index Max->Min is 3, 2, 1, 0, -1.
@@ -399,6 +450,272 @@ impl Register for MicAgc {
     const ADDRESS: u8 = 0x19;
 }
 
+/// 0x28 AF Rx expander.
+///
+/// The receive half of the compander, which undoes the other end's
+/// [Compressor]. Only active with [FeatureEnable::compander_enabled()].
+/// The defaults are what the stock firmware writes.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Expander {
+    /// Expander noise point, in dB.
+    #[bits(7, default = 56)]
+    pub noise_point: u8,
+
+    /// Expander 0dB point, in dB.
+    #[bits(7, default = 86)]
+    pub zero_point: u8,
+
+    /// Expander ratio.
+    #[bits(2, default = ExpanderRatio::Ratio1To2)]
+    pub ratio: ExpanderRatio,
+}
+
+/// AF Rx expander ratio.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum ExpanderRatio {
+    Disabled = 0b00,
+    Ratio1To2 = 0b01,
+    Ratio1To3 = 0b10,
+    Ratio1To4 = 0b11,
+}
+
+impl ExpanderRatio {
+    pub const fn into_bits(self) -> u8 {
+        self as u8
+    }
+
+    pub const fn from_bits(v: u8) -> Self {
+        match v {
+            0b00 => Self::Disabled,
+            0b01 => Self::Ratio1To2,
+            0b10 => Self::Ratio1To3,
+            _ => Self::Ratio1To4,
+        }
+    }
+}
+
+impl Register for Expander {
+    const ADDRESS: u8 = 0x28;
+}
+
+/// 0x29 AF Tx compressor.
+///
+/// The transmit half of the compander. Only active with
+/// [FeatureEnable::compander_enabled()]. The defaults are what the
+/// stock firmware writes.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Compressor {
+    /// Compressor noise point, in dB.
+    #[bits(7, default = 64)]
+    pub noise_point: u8,
+
+    /// Compressor 0dB point, in dB.
+    #[bits(7, default = 86)]
+    pub zero_point: u8,
+
+    /// Compressor ratio.
+    #[bits(2, default = CompressorRatio::Ratio2To1)]
+    pub ratio: CompressorRatio,
+}
+
+/// AF Tx compressor ratio.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum CompressorRatio {
+    Disabled = 0b00,
+    Ratio4To3 = 0b01,
+    Ratio2To1 = 0b10,
+    Ratio4To1 = 0b11,
+}
+
+impl CompressorRatio {
+    pub const fn into_bits(self) -> u8 {
+        self as u8
+    }
+
+    pub const fn from_bits(v: u8) -> Self {
+        match v {
+            0b00 => Self::Disabled,
+            0b01 => Self::Ratio4To3,
+            0b10 => Self::Ratio2To1,
+            _ => Self::Ratio4To1,
+        }
+    }
+}
+
+impl Register for Compressor {
+    const ADDRESS: u8 = 0x29;
+}
+
+/// 0x2b AF Rx/Tx filters and de-emphasis.
+///
+/// The datasheet only documents enable bits here. There is no
+/// 50/75µs time constant selection, and no noise blanker, so the
+/// de-emphasis is either on or off.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AfFilters {
+    /// AF Tx 300Hz high-pass filter disable.
+    pub tx_hpf_disabled: bool,
+
+    /// AF Tx 3kHz low-pass filter disable.
+    pub tx_lpf_disabled: bool,
+
+    /// AF Tx pre-emphasis disable.
+    pub tx_preemphasis_disabled: bool,
+
+    #[bits(5)]
+    __: u8,
+
+    /// AF Rx 300Hz high-pass filter disable.
+    pub rx_hpf_disabled: bool,
+
+    /// AF Rx 3kHz low-pass filter disable.
+    pub rx_lpf_disabled: bool,
+
+    /// AF Rx de-emphasis.
+    ///
+    /// The stock firmware leaves this enabled.
+    #[bits(1, default = Deemphasis::Enabled)]
+    pub rx_deemphasis: Deemphasis,
+
+    #[bits(5)]
+    __: u8,
+}
+
+/// AF Rx de-emphasis setting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Deemphasis {
+    /// De-emphasis enabled.
+    Enabled = 0,
+    /// De-emphasis disabled, for flat audio response.
+    Disabled = 1,
+}
+
+impl Deemphasis {
+    pub const fn into_bits(self) -> u8 {
+        self as u8
+    }
+
+    pub const fn from_bits(v: u8) -> Self {
+        match v {
+            0 => Self::Enabled,
+            _ => Self::Disabled,
+        }
+    }
+}
+
+impl Register for AfFilters {
+    const ADDRESS: u8 = 0x2b;
+}
+
+/// 0x30 Receive and transmit path enables.
+///
+/// See [Enable::rx()] and [Enable::tx()] for the settings used by the
+/// stock firmware.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Enable {
+    /// Receive DSP enable.
+    pub rx_dsp_enabled: bool,
+
+    /// Transmit DSP enable.
+    pub tx_dsp_enabled: bool,
+
+    /// Mic ADC enable.
+    pub mic_adc_enabled: bool,
+
+    /// PA gain enable.
+    pub pa_gain_enabled: bool,
+
+    /// PLL and VCO enable, 0b1111 for on.
+    #[bits(4)]
+    pub pll_vco: u8,
+
+    /// Discriminator enable.
+    pub discriminator_enabled: bool,
+
+    /// AF DAC enable.
+    pub af_dac_enabled: bool,
+
+    /// Receive link enable, 0b1111 for on.
+    #[bits(4)]
+    pub rx_link: u8,
+
+    /// Unknown field, set when transmitting.
+    pub unknown_b14: bool,
+
+    /// VCO calibration enable.
+    pub vco_calibration_enabled: bool,
+}
+
+impl Enable {
+    /// Everything needed to receive, 0xbff1.
+    pub fn rx() -> Self {
+        Self::new()
+            .with_rx_dsp_enabled(true)
+            .with_pll_vco(0b1111)
+            .with_discriminator_enabled(true)
+            .with_af_dac_enabled(true)
+            .with_rx_link(0b1111)
+            .with_vco_calibration_enabled(true)
+    }
+
+    /// Everything needed to transmit, 0xc1fe.
+    pub fn tx() -> Self {
+        Self::new()
+            .with_tx_dsp_enabled(true)
+            .with_mic_adc_enabled(true)
+            .with_pa_gain_enabled(true)
+            .with_pll_vco(0b1111)
+            .with_discriminator_enabled(true)
+            .with_unknown_b14(true)
+            .with_vco_calibration_enabled(true)
+    }
+}
+
+impl Register for Enable {
+    const ADDRESS: u8 = 0x30;
+}
+
+/// 0x31 Feature enables.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FeatureEnable {
+    /// Unknown field.
+    #[bits(1)]
+    pub unknown_b0: bool,
+
+    /// Voice scrambler enable.
+    pub scramble_enabled: bool,
+
+    /// VOX enable. See [VoxOnThreshold], [VoxOffThreshold], and [VoxDelay].
+    pub vox_enabled: bool,
+
+    /// Compander enable.
+    pub compander_enabled: bool,
+
+    /// Unknown field.
+    #[bits(12)]
+    pub unknown_b15_4: u16,
+}
+
+impl Register for FeatureEnable {
+    const ADDRESS: u8 = 0x31;
+}
+
 /// 0x33 GPIO output.
 #[cfg_attr(not(feature = "defmt"), bitfield(u16))]
 #[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
@@ -634,30 +951,302 @@ pub struct PowerControl {
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
-pub enum LdoVoltage {
-    /// 2.4 volts.
-    V2_4 = 0,
-    /// 2.7 volts.
-    V2_7 = 1,
+pub enum LdoVoltage {
+    /// 2.4 volts.
+    V2_4 = 0,
+    /// 2.7 volts.
+    V2_7 = 1,
+}
+
+impl LdoVoltage {
+    pub const fn into_bits(self) -> u8 {
+        self as u8
+    }
+
+    pub const fn from_bits(v: u8) -> Self {
+        match v {
+            0 => Self::V2_4,
+            _ => Self::V2_7,
+        }
+    }
+}
+
+impl Register for PowerControl {
+    const ADDRESS: u8 = 0x37;
+}
+
+/// 0x38 Frequency, low word.
+///
+/// The full frequency is a 32-bit word in units of 10Hz, split across
+/// this register and [FrequencyHigh].
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FrequencyLow {
+    /// Low 16 bits of the frequency, in 10Hz units.
+    #[bits(16)]
+    pub data: u16,
+}
+
+impl Register for FrequencyLow {
+    const ADDRESS: u8 = 0x38;
+}
+
+/// 0x39 Frequency, high word.
+///
+/// See [FrequencyLow].
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FrequencyHigh {
+    /// High 16 bits of the frequency, in 10Hz units.
+    #[bits(16)]
+    pub data: u16,
+}
+
+impl Register for FrequencyHigh {
+    const ADDRESS: u8 = 0x39;
+}
+
+/// 0x3d Unknown.
+///
+/// Probably IF related. The stock firmware writes 0x2aab, and SSB
+/// firmware mods write 0x0000 in SSB mode.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Unknown3d {
+    #[bits(16, default = 0x2aab)]
+    pub data: u16,
+}
+
+impl Register for Unknown3d {
+    const ADDRESS: u8 = 0x3d;
+}
+
+/// 0x3f Interrupt enables.
+///
+/// Each flag enables the matching flag in [Interrupts].
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InterruptEnable {
+    __: bool,
+
+    /// FSK Rx sync.
+    pub fsk_rx_sync: bool,
+    /// Squelch lost.
+    pub squelch_lost: bool,
+    /// Squelch found.
+    pub squelch_found: bool,
+    /// Vox lost.
+    pub vox_lost: bool,
+    /// Vox found.
+    pub vox_found: bool,
+    /// CTCSS lost.
+    pub ctcss_lost: bool,
+    /// CTCSS found.
+    pub ctcss_found: bool,
+    /// CDCSS lost.
+    pub cdcss_lost: bool,
+    /// CDCSS found.
+    pub cdcss_found: bool,
+    /// CTCSS/CDCSS tail found.
+    pub tail_found: bool,
+    /// DTMF/5TONE found.
+    pub tone_found: bool,
+    /// FSK FIFO almost full.
+    pub fsk_fifo_almost_full: bool,
+    /// FSK Rx finished.
+    pub fsk_rx_finished: bool,
+    /// FSK FIFO almost empty.
+    pub fsk_fifo_almost_empty: bool,
+    /// FSK Tx finished.
+    pub fsk_tx_finished: bool,
+}
+
+impl Register for InterruptEnable {
+    const ADDRESS: u8 = 0x3f;
+}
+
+/// 0x43 Rx filter bandwidth and Tx AF low-pass.
+///
+/// Not in the datasheet, these fields come from firmware mods. The
+/// stock firmware writes 0x3028 for wide (25kHz) channels, and 0x4048
+/// for narrow (12.5kHz) channels. See [crate::Bandwidth].
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FilterBandwidth {
+    /// Unknown field.
+    #[bits(2)]
+    pub unknown_b1_0: u8,
+
+    /// Add 6dB of gain after FM demodulation.
+    pub demod_gain: bool,
+
+    /// Unknown field, set by the stock firmware.
+    #[bits(1, default = true)]
+    pub unknown_b3: bool,
+
+    /// Channel spacing mode.
+    #[bits(2, default = Ok(FilterMode::Wide), from = FilterMode::from_bits, into = FilterMode::into_bits)]
+    pub mode: Result<FilterMode, u8>,
+
+    /// AF Tx low-pass filter bandwidth.
+    ///
+    /// 0 = 3.0kHz, 1 = 2.5kHz, 2 = 2.75kHz, 3 = 3.5kHz, 4 = 4.5kHz,
+    /// 5 = 4.25kHz.
+    #[bits(3, default = 0)]
+    pub af_tx_lpf: u8,
+
+    /// RF filter bandwidth while the signal is weak, as [Self::rf].
+    #[bits(3, default = 0)]
+    pub rf_weak: u8,
+
+    /// RF filter bandwidth.
+    ///
+    /// 0 = 1.7kHz, 1 = 2.0kHz, 2 = 2.5kHz, 3 = 3.0kHz, 4 = 3.75kHz,
+    /// 5 = 4.0kHz, 6 = 4.25kHz, 7 = 4.5kHz. These are doubled in
+    /// [FilterMode::Wide].
+    #[bits(3, default = 3)]
+    pub rf: u8,
+
+    /// Unknown field.
+    #[bits(1)]
+    pub unknown_b15: bool,
+}
+
+impl Register for FilterBandwidth {
+    const ADDRESS: u8 = 0x43;
+}
+
+/// Channel spacing mode, in [FilterBandwidth].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum FilterMode {
+    /// 12.5kHz.
+    Narrow = 0,
+    /// 6.25kHz.
+    Narrower = 1,
+    /// 25kHz or 20kHz.
+    Wide = 2,
+}
+
+impl FilterMode {
+    pub const fn into_bits(this: Result<Self, u8>) -> u8 {
+        match this {
+            Ok(v) => v as u8,
+            Err(v) => v,
+        }
+    }
+
+    pub const fn from_bits(v: u8) -> Result<Self, u8> {
+        match v {
+            0 => Ok(Self::Narrow),
+            1 => Ok(Self::Narrower),
+            2 => Ok(Self::Wide),
+            _ => Err(v),
+        }
+    }
+}
+
+/// 0x46 VOX enable threshold.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VoxOnThreshold {
+    /// VOX turns on when [VoiceAmplitude] rises above this.
+    #[bits(11)]
+    pub threshold: u16,
+
+    /// Unknown field.
+    #[bits(5, default = 0b10100)]
+    pub unknown_b15_11: u8,
+}
+
+impl Register for VoxOnThreshold {
+    const ADDRESS: u8 = 0x46;
+}
+
+/// 0x47 AF output control.
+///
+/// The defaults for the unknown bits are what the stock firmware writes.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AfControl {
+    #[bits(6, default = 0b000000)]
+    pub unknown_b5_0: u8,
+
+    #[bits(1, default = true)]
+    pub unknown_b6: bool,
+
+    #[bits(1, default = false)]
+    pub unknown_b7: bool,
+
+    /// AF output selection.
+    #[bits(4, default = Ok(AfOutput::Mute), from = AfOutput::from_bits, into = AfOutput::into_bits)]
+    pub output: Result<AfOutput, u8>,
+
+    #[bits(4, default = 0x6)]
+    pub unknown_b15_12: u8,
+}
+
+impl Register for AfControl {
+    const ADDRESS: u8 = 0x47;
+}
+
+/// AF output selection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum AfOutput {
+    /// Muted.
+    Mute = 0,
+    /// Normal FM demodulated audio.
+    Fm = 1,
+    /// Tone output for Rx alarm.
+    Alarm = 2,
+    /// Beep output for Tx.
+    Beep = 3,
+    /// Baseband output 1.
+    Baseband1 = 4,
+    /// Baseband output 2. Used by SSB firmware mods.
+    Baseband2 = 5,
+    /// CTCSS/CDCSS output.
+    Ctco = 6,
+    /// AM demodulated audio.
+    Am = 7,
+    /// FSK output.
+    Fsko = 8,
 }
 
-impl LdoVoltage {
-    pub const fn into_bits(self) -> u8 {
-        self as u8
+impl AfOutput {
+    pub const fn into_bits(this: Result<Self, u8>) -> u8 {
+        match this {
+            Ok(v) => v as u8,
+            Err(v) => v,
+        }
     }
 
-    pub const fn from_bits(v: u8) -> Self {
+    pub const fn from_bits(v: u8) -> Result<Self, u8> {
         match v {
-            0 => Self::V2_4,
-            _ => Self::V2_7,
+            0 => Ok(Self::Mute),
+            1 => Ok(Self::Fm),
+            2 => Ok(Self::Alarm),
+            3 => Ok(Self::Beep),
+            4 => Ok(Self::Baseband1),
+            5 => Ok(Self::Baseband2),
+            6 => Ok(Self::Ctco),
+            7 => Ok(Self::Am),
+            8 => Ok(Self::Fsko),
+            _ => Err(v),
         }
     }
 }
 
-impl Register for PowerControl {
-    const ADDRESS: u8 = 0x37;
-}
-
 /// 0x48 AF Rx gain.
 #[cfg_attr(not(feature = "defmt"), bitfield(u16))]
 #[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
@@ -690,26 +1279,223 @@ impl Register for AfGain {
     const ADDRESS: u8 = 0x48;
 }
 
-/// 0x49 Unknown.
+/// 0x49 AGC control.
 ///
-/// Might have something to do with AGC?
+/// The AGC steps through the [AgcGainTable] entries, lowering the
+/// gain when [Rssi] rises above the high threshold and raising it when
+/// it falls below the low threshold. The stock firmware writes 0x2a38.
+///
+/// Whether the AGC is automatic or held at a fixed index is set in
+/// [AgcFilters].
 #[cfg_attr(not(feature = "defmt"), bitfield(u16))]
 #[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Unknown49 {
+pub struct AgcControl {
+    /// RSSI threshold to raise the gain below.
     #[bits(7, default = 0b0110000)]
-    pub unknown_b6_0: u8,
+    pub low_threshold: u8,
 
+    /// RSSI threshold to lower the gain above.
     #[bits(7, default = 0b1010000)]
-    pub unknown_b13_7: u8,
+    pub high_threshold: u8,
 
-    #[bits(2, default = 0b00)]
-    pub unknown_b15_14: u8,
+    /// AGC attack speed.
+    ///
+    /// Not documented. The stock firmware always uses slow.
+    #[bits(1, default = AgcSpeed::Slow)]
+    pub speed: AgcSpeed,
+
+    /// Unknown field.
+    #[bits(1)]
+    pub unknown_b15: bool,
+}
+
+/// AGC attack speed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum AgcSpeed {
+    Slow = 0,
+    Fast = 1,
+}
+
+impl AgcSpeed {
+    pub const fn into_bits(self) -> u8 {
+        self as u8
+    }
+
+    pub const fn from_bits(v: u8) -> Self {
+        match v {
+            0 => Self::Slow,
+            _ => Self::Fast,
+        }
+    }
 }
-impl Register for Unknown49 {
+
+impl Register for AgcControl {
     const ADDRESS: u8 = 0x49;
 }
 
+/// 0x51 CTCSS/CDCSS control.
+///
+/// This sets up both tone transmit and tone detection. The tone
+/// itself is set in [CtcControl], and for CDCSS, [CdcssCode].
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CxcssControl {
+    /// CTCSS/CDCSS Tx gain 1. The stock firmware uses 74 for CTCSS
+    /// and 51 for CDCSS.
+    #[bits(7)]
+    pub tx_gain1: u8,
+
+    #[bits(1)]
+    __: bool,
+
+    /// Disable automatic CTCSS bandwidth.
+    pub auto_ctcss_bw_disabled: bool,
+
+    /// Disable automatic CDCSS bandwidth.
+    pub auto_cdcss_bw_disabled: bool,
+
+    /// Detect 1050Hz, instead of the usual CTCSS tone.
+    pub detect_1050hz: bool,
+
+    /// Use 24-bit CDCSS, instead of 23-bit.
+    pub cdcss_24bit: bool,
+
+    /// CTCSS mode if set, CDCSS mode if not.
+    pub ctcss_mode: bool,
+
+    /// Transmit CDCSS with negative polarity.
+    pub tx_cdcss_negative: bool,
+
+    /// Use GPIO6 as an input, for CDCSS.
+    pub gpio6_input: bool,
+
+    /// Enable CTCSS/CDCSS.
+    pub enabled: bool,
+}
+
+impl Register for CxcssControl {
+    const ADDRESS: u8 = 0x51;
+}
+
+/// 0x64 Voice amplitude.
+///
+/// This is the level of the Tx audio, after mic sensitivity and mic
+/// AGC, and is what the VOX thresholds are compared against.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VoiceAmplitude {
+    /// Voice amplitude.
+    #[bits(16, access = RO)]
+    pub amplitude: u16,
+}
+
+impl Register for VoiceAmplitude {
+    const ADDRESS: u8 = 0x64;
+}
+
+/// 0x67 RSSI.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Rssi {
+    /// Received signal strength, in 0.5dB steps. dBm is `rssi / 2 - 160`.
+    #[bits(9, access = RO)]
+    pub rssi: u16,
+
+    #[bits(7)]
+    __: u8,
+}
+
+impl Register for Rssi {
+    const ADDRESS: u8 = 0x67;
+}
+
+/// 0x6d AFC frequency error.
+///
+/// Not in the datasheet. Community firmware reads this as a signed
+/// error in steps of 10/3 Hz, or about 3.33Hz. Only meaningful while
+/// receiving with AFC enabled in [AfcControl].
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AfcError {
+    /// Measured frequency error, in steps of 10/3 Hz.
+    #[bits(16, access = RO)]
+    pub error: i16,
+}
+
+impl AfcError {
+    /// The measured frequency error, in Hz.
+    pub fn hz(&self) -> i32 {
+        self.error() as i32 * 10 / 3
+    }
+}
+
+impl Register for AfcError {
+    const ADDRESS: u8 = 0x6d;
+}
+
+/// 0x73 AFC control.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AfcControl {
+    #[bits(4)]
+    pub unknown_b3_0: u8,
+
+    /// Automatic frequency control disable.
+    pub afc_disabled: bool,
+
+    #[bits(11)]
+    pub unknown_b15_5: u16,
+}
+
+impl Register for AfcControl {
+    const ADDRESS: u8 = 0x73;
+}
+
+/// 0x79 VOX disable threshold.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VoxOffThreshold {
+    /// VOX turns off when [VoiceAmplitude] falls below this, after
+    /// [VoxDelay].
+    #[bits(11)]
+    pub threshold: u16,
+
+    /// Unknown field.
+    #[bits(5, default = 0b00011)]
+    pub unknown_b15_11: u8,
+}
+
+impl Register for VoxOffThreshold {
+    const ADDRESS: u8 = 0x79;
+}
+
+/// 0x7a VOX disable delay.
+#[cfg_attr(not(feature = "defmt"), bitfield(u16))]
+#[cfg_attr(feature = "defmt", bitfield(u16, defmt = true))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VoxDelay {
+    /// Unknown field.
+    #[bits(12, default = 0x89a)]
+    pub unknown_b11_0: u16,
+
+    /// Delay before VOX turns off, in 128ms steps.
+    #[bits(4, default = 2)]
+    pub delay: u8,
+}
+
+impl Register for VoxDelay {
+    const ADDRESS: u8 = 0x7a;
+}
+
 /// 0x7b Unknown.
 ///
 /// Might have something to do with AGC?
@@ -806,6 +1592,71 @@ impl Register for AgcFilters {
     const ADDRESS: u8 = 0x7e;
 }
 
+// a helper macro to list every register with a known layout
+macro_rules! known {
+    ($($name:ident),* $(,)?) => {
+        /// The addresses of every register with a known layout, in order.
+        pub const KNOWN: &[u8] = &[$($name::ADDRESS),*];
+
+        /// Log a register with [defmt], decoded field-by-field.
+        ///
+        /// `bits` is decoded as whichever register lives at `address`,
+        /// using its [defmt::Format] impl. Addresses not in [KNOWN] fall
+        /// back to plain hex.
+        #[cfg(feature = "defmt")]
+        pub fn log(address: u8, bits: u16) {
+            $(
+                if address == $name::ADDRESS {
+                    defmt::println!("bk: {:02x} {}", address, $name::from(bits));
+                    return;
+                }
+            )*
+            defmt::println!("bk: {:02x} {:04x}", address, bits);
+        }
+    };
+}
+
+known! {
+    Reset,
+    Interrupts,
+    CtcControl,
+    CdcssCode,
+    Status,
+    AgcGainTable0,
+    AgcGainTable1,
+    AgcGainTable2,
+    AgcGainTable3,
+    AgcGainTable4,
+    MicAgc,
+    Expander,
+    Compressor,
+    AfFilters,
+    Enable,
+    FeatureEnable,
+    GpioOutput,
+    PaControl,
+    PowerControl,
+    FrequencyLow,
+    FrequencyHigh,
+    Unknown3d,
+    InterruptEnable,
+    FilterBandwidth,
+    VoxOnThreshold,
+    AfControl,
+    AfGain,
+    AgcControl,
+    CxcssControl,
+    VoiceAmplitude,
+    Rssi,
+    AfcError,
+    AfcControl,
+    VoxOffThreshold,
+    VoxDelay,
+    Unknown7b,
+    MicSensitivity,
+    AgcFilters,
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -877,6 +1728,27 @@ mod test {
         });
     }
 
+    #[test]
+    fn r08_cdcss_code() {
+        assert_eq!(CdcssCode::ADDRESS, 0x08);
+        assert_eq!(CdcssCode::new().into_bits(), 0x0000);
+        check_bits!(CdcssCode {
+            high[15] = false,
+            data[11:0] = 0,
+        });
+    }
+
+    #[test]
+    fn r0c_status() {
+        assert_eq!(Status::ADDRESS, 0x0c);
+        check_bits!(Status {
+            vox_active[2] = false,
+            squelch_open[1] = false,
+            interrupt_pending[0] = false,
+        });
+        assert!(Status::from_bits(0x0004).vox_active());
+    }
+
     #[test]
     fn r10_r14_agc_gain_table() {
         assert_eq!(AgcGainTable0::ADDRESS, 0x10);
@@ -963,6 +1835,100 @@ mod test {
         );
     }
 
+    #[test]
+    fn r28_expander() {
+        assert_eq!(Expander::ADDRESS, 0x28);
+        assert_eq!(Expander::new().into_bits(), 0x6b38);
+        check_bits!(Expander {
+            ratio[15:14] = ExpanderRatio::Ratio1To2,
+            zero_point[13:7] = 86,
+            noise_point[6:0] = 56,
+        });
+
+        assert_eq!(
+            0x2b38,
+            Expander::new()
+                .with_ratio(ExpanderRatio::Disabled)
+                .into_bits()
+        );
+    }
+
+    #[test]
+    fn r29_compressor() {
+        assert_eq!(Compressor::ADDRESS, 0x29);
+        assert_eq!(Compressor::new().into_bits(), 0xab40);
+        check_bits!(Compressor {
+            ratio[15:14] = CompressorRatio::Ratio2To1,
+            zero_point[13:7] = 86,
+            noise_point[6:0] = 64,
+        });
+
+        assert_eq!(
+            0xeb40,
+            Compressor::new()
+                .with_ratio(CompressorRatio::Ratio4To1)
+                .into_bits()
+        );
+    }
+
+    #[test]
+    fn r2b_af_filters() {
+        assert_eq!(AfFilters::ADDRESS, 0x2b);
+        assert_eq!(AfFilters::new().into_bits(), 0x0000);
+        check_bits!(AfFilters {
+            rx_deemphasis[10] = Deemphasis::Enabled,
+            rx_lpf_disabled[9] = false,
+            rx_hpf_disabled[8] = false,
+            tx_preemphasis_disabled[2] = false,
+            tx_lpf_disabled[1] = false,
+            tx_hpf_disabled[0] = false,
+        });
+
+        assert_eq!(
+            0x0400,
+            AfFilters::new()
+                .with_rx_deemphasis(Deemphasis::Disabled)
+                .into_bits()
+        );
+    }
+
+    #[test]
+    fn r30_enable() {
+        assert_eq!(Enable::ADDRESS, 0x30);
+        check_bits!(Enable {
+            vco_calibration_enabled[15] = false,
+            unknown_b14[14] = false,
+            rx_link[13:10] = 0,
+            af_dac_enabled[9] = false,
+            discriminator_enabled[8] = false,
+            pll_vco[7:4] = 0,
+            pa_gain_enabled[3] = false,
+            mic_adc_enabled[2] = false,
+            tx_dsp_enabled[1] = false,
+            rx_dsp_enabled[0] = false,
+        });
+
+        assert_eq!(Enable::rx().into_bits(), 0xbff1);
+        assert_eq!(Enable::tx().into_bits(), 0xc1fe);
+    }
+
+    #[test]
+    fn r31_feature_enable() {
+        assert_eq!(FeatureEnable::ADDRESS, 0x31);
+        assert_eq!(FeatureEnable::new().into_bits(), 0x0000);
+        check_bits!(FeatureEnable {
+            unknown_b15_4[15:4],
+            compander_enabled[3] = false,
+            vox_enabled[2] = false,
+            scramble_enabled[1] = false,
+            unknown_b0[0],
+        });
+        assert_eq!(
+            0x0004,
+            FeatureEnable::new().with_vox_enabled(true).into_bits()
+        );
+    }
+
     #[test]
     fn r33_gpio_output() {
         assert_eq!(GpioOutput::ADDRESS, 0x33);
@@ -1037,6 +2003,111 @@ mod test {
         );
     }
 
+    #[test]
+    fn r38_frequency_low() {
+        assert_eq!(FrequencyLow::ADDRESS, 0x38);
+        check_bits!(FrequencyLow { data[15:0] = 0 });
+        assert_eq!(0x1234, FrequencyLow::new().with_data(0x1234).into_bits());
+    }
+
+    #[test]
+    fn r39_frequency_high() {
+        assert_eq!(FrequencyHigh::ADDRESS, 0x39);
+        check_bits!(FrequencyHigh { data[15:0] = 0 });
+        assert_eq!(0x0089, FrequencyHigh::new().with_data(0x0089).into_bits());
+    }
+
+    #[test]
+    fn r3d_unknown() {
+        assert_eq!(Unknown3d::ADDRESS, 0x3d);
+        assert_eq!(Unknown3d::new().into_bits(), 0x2aab);
+        check_bits!(Unknown3d {
+            data[15:0],
+        });
+    }
+
+    #[test]
+    fn r3f_interrupt_enable() {
+        assert_eq!(InterruptEnable::ADDRESS, 0x3f);
+        assert_eq!(InterruptEnable::new().into_bits(), 0x0000);
+        check_bits!(InterruptEnable {
+            fsk_tx_finished[15] = false,
+            fsk_fifo_almost_empty[14] = false,
+            fsk_rx_finished[13] = false,
+            fsk_fifo_almost_full[12] = false,
+            tone_found[11] = false,
+            tail_found[10] = false,
+            cdcss_found[9] = false,
+            cdcss_lost[8] = false,
+            ctcss_found[7] = false,
+            ctcss_lost[6] = false,
+            vox_found[5] = false,
+            vox_lost[4] = false,
+            squelch_found[3] = false,
+            squelch_lost[2] = false,
+            fsk_rx_sync[1] = false,
+        });
+    }
+
+    #[test]
+    fn r43_filter_bandwidth() {
+        assert_eq!(FilterBandwidth::ADDRESS, 0x43);
+        assert_eq!(FilterBandwidth::new().into_bits(), 0x3028);
+        check_bits!(FilterBandwidth {
+            unknown_b15[15] = false,
+            rf[14:12] = 3,
+            rf_weak[11:9] = 0,
+            af_tx_lpf[8:6] = 0,
+            mode[5:4] = Ok(FilterMode::Wide),
+            unknown_b3[3] = true,
+            demod_gain[2] = false,
+            unknown_b1_0[1:0] = 0,
+        });
+
+        assert_eq!(
+            0x4048,
+            FilterBandwidth::new()
+                .with_rf(4)
+                .with_af_tx_lpf(1)
+                .with_mode(Ok(FilterMode::Narrow))
+                .into_bits()
+        );
+        assert_eq!(Err(3), FilterBandwidth::from_bits(0x0030).mode());
+    }
+
+    #[test]
+    fn r46_vox_on_threshold() {
+        assert_eq!(VoxOnThreshold::ADDRESS, 0x46);
+        assert_eq!(VoxOnThreshold::new().into_bits(), 0xa000);
+        check_bits!(VoxOnThreshold {
+            unknown_b15_11[15:11] = 0b10100,
+            threshold[10:0] = 0,
+        });
+        assert_eq!(
+            0xa7ff,
+            VoxOnThreshold::new().with_threshold(0x7ff).into_bits()
+        );
+    }
+
+    #[test]
+    fn r47_af_control() {
+        assert_eq!(AfControl::ADDRESS, 0x47);
+        assert_eq!(AfControl::new().into_bits(), 0x6040);
+        check_bits!(AfControl {
+            unknown_b15_12[15:12] = 0x6,
+            output[11:8] = Ok(AfOutput::Mute),
+            unknown_b7[7] = false,
+            unknown_b6[6] = true,
+            unknown_b5_0[5:0] = 0,
+        });
+
+        assert_eq!(
+            0x6740,
+            AfControl::new().with_output(Ok(AfOutput::Am)).into_bits()
+        );
+        assert_eq!(Err(0xf), AfControl::from_bits(0x0f00).output());
+    }
+
     #[test]
     fn r48_af_gain() {
         assert_eq!(AfGain::ADDRESS, 0x48);
@@ -1060,25 +2131,108 @@ mod test {
     }
 
     #[test]
-    fn r49_unknown() {
-        assert_eq!(Unknown49::ADDRESS, 0x49);
-        assert_eq!(Unknown49::new().into_bits(), 0x2830);
-        check_bits!(Unknown49 {
-            unknown_b15_14[15:14],
-            unknown_b13_7[13:7],
-            unknown_b6_0[6:0],
+    fn r49_agc_control() {
+        assert_eq!(AgcControl::ADDRESS, 0x49);
+        assert_eq!(AgcControl::new().into_bits(), 0x2830);
+        check_bits!(AgcControl {
+            unknown_b15[15],
+            speed[14] = AgcSpeed::Slow,
+            high_threshold[13:7] = 0b1010000,
+            low_threshold[6:0] = 0b0110000,
         });
 
         assert_eq!(
             0x2a38,
-            Unknown49::new()
-                .with_unknown_b6_0(56)
-                .with_unknown_b13_7(84)
-                .with_unknown_b15_14(0)
+            AgcControl::new()
+                .with_low_threshold(56)
+                .with_high_threshold(84)
+                .with_speed(AgcSpeed::Slow)
+                .into_bits()
+        );
+        assert_eq!(
+            0x6a38,
+            AgcControl::from_bits(0x2a38)
+                .with_speed(AgcSpeed::Fast)
                 .into_bits()
         );
     }
 
+    #[test]
+    fn r51_cxcss_control() {
+        assert_eq!(CxcssControl::ADDRESS, 0x51);
+        assert_eq!(CxcssControl::new().into_bits(), 0x0000);
+        check_bits!(CxcssControl {
+            enabled[15] = false,
+            gpio6_input[14] = false,
+            tx_cdcss_negative[13] = false,
+            ctcss_mode[12] = false,
+            cdcss_24bit[11] = false,
+            detect_1050hz[10] = false,
+            auto_cdcss_bw_disabled[9] = false,
+            auto_ctcss_bw_disabled[8] = false,
+            tx_gain1[6:0] = 0,
+        });
+    }
+
+    #[test]
+    fn r64_voice_amplitude() {
+        assert_eq!(VoiceAmplitude::ADDRESS, 0x64);
+        check_bits!(VoiceAmplitude { amplitude[15:0] = 0 });
+        assert_eq!(0x1234, VoiceAmplitude::from_bits(0x1234).amplitude());
+    }
+
+    #[test]
+    fn r67_rssi() {
+        assert_eq!(Rssi::ADDRESS, 0x67);
+        check_bits!(Rssi { rssi[8:0] = 0 });
+        assert_eq!(0x0123, Rssi::from_bits(0xff23).rssi());
+    }
+
+    #[test]
+    fn r6d_afc_error() {
+        assert_eq!(AfcError::ADDRESS, 0x6d);
+        check_bits!(AfcError { error[15:0] = 0 });
+        assert_eq!(-2, AfcError::from_bits(0xfffe).error());
+        assert_eq!(100, AfcError::from_bits(30).hz());
+        assert_eq!(-100, AfcError::from_bits(-30i16 as u16).hz());
+    }
+
+    #[test]
+    fn r73_afc_control() {
+        assert_eq!(AfcControl::ADDRESS, 0x73);
+        check_bits!(AfcControl {
+            unknown_b15_5[15:5],
+            afc_disabled[4] = false,
+            unknown_b3_0[3:0],
+        });
+
+        assert_eq!(
+            0x0010,
+            AfcControl::new().with_afc_disabled(true).into_bits()
+        );
+    }
+
+    #[test]
+    fn r79_vox_off_threshold() {
+        assert_eq!(VoxOffThreshold::ADDRESS, 0x79);
+        assert_eq!(VoxOffThreshold::new().into_bits(), 0x1800);
+        check_bits!(VoxOffThreshold {
+            unknown_b15_11[15:11] = 0b00011,
+            threshold[10:0] = 0,
+        });
+    }
+
+    #[test]
+    fn r7a_vox_delay() {
+        assert_eq!(VoxDelay::ADDRESS, 0x7a);
+        assert_eq!(VoxDelay::new().into_bits(), 0x289a);
+        check_bits!(VoxDelay {
+            delay[15:12] = 2,
+            unknown_b11_0[11:0] = 0x89a,
+        });
+        assert_eq!(0x589a, VoxDelay::new().with_delay(5).into_bits());
+    }
+
     #[test]
     fn r7b_unknown() {
         assert_eq!(Unknown7b::ADDRESS, 0x7b);
@@ -1123,4 +2277,11 @@ mod test {
 
         assert_eq!(AgcFilters::from_bits(0x702e).agc_index(), -1);
     }
+
+    #[test]
+    fn known() {
+        assert!(KNOWN.windows(2).all(|w| w[0] < w[1]));
+        assert!(KNOWN.iter().all(|a| *a < 0x80));
+        assert!(KNOWN.contains(&PowerControl::ADDRESS));
+    }
 }
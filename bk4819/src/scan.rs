@@ -0,0 +1,377 @@
+//! A scanner, for stepping through frequencies looking for activity.
+//!
+//! [Scanner] does no timing of its own. Call [Scanner::tick()] at a
+//! steady rate, and each policy duration is counted in those ticks.
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{InputPin, OutputPin};
+
+use crate::{Bk4819, Error};
+
+/// The frequencies to scan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Channels<'a> {
+    /// A list of frequencies, in Hz.
+    List(&'a [u32]),
+    /// `count` frequencies spaced by `step` Hz, starting at `start` Hz.
+    Range { start: u32, step: u32, count: usize },
+}
+
+impl<'a> Channels<'a> {
+    /// The number of frequencies.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::List(list) => list.len(),
+            Self::Range { count, .. } => *count,
+        }
+    }
+
+    /// Are there no frequencies at all?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get a frequency, in Hz.
+    pub fn get(&self, index: usize) -> Option<u32> {
+        match self {
+            Self::List(list) => list.get(index).copied(),
+            Self::Range { start, step, count } => {
+                (index < *count).then(|| start.wrapping_add(step.wrapping_mul(index as u32)))
+            }
+        }
+    }
+}
+
+/// How to decide a frequency is busy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Squelch {
+    /// Busy when the RSSI is at least this, in the units of
+    /// [Bk4819::rssi()].
+    Rssi(u16),
+    /// Busy when the device's own squelch is open. See
+    /// [Bk4819::squelch_open()].
+    Device,
+}
+
+/// What to do after finding a busy frequency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Resume {
+    /// Stay on the frequency until [Scanner::resume()] is called.
+    Stop,
+    /// Move on after this many ticks, busy or not.
+    AfterTicks(u32),
+}
+
+/// Settings for a [Scanner].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Policy {
+    /// Ticks to wait on a frequency after tuning, before checking the
+    /// squelch. This gives the PLL and RSSI time to settle.
+    pub dwell: u32,
+    /// How to decide a frequency is busy.
+    pub squelch: Squelch,
+    /// What to do after finding a busy frequency.
+    pub resume: Resume,
+}
+
+/// What a [Scanner] did on a tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ScanState {
+    /// Still looking.
+    Scanning,
+    /// Stopped on a busy frequency, in Hz.
+    Busy(u32),
+    /// Left a busy frequency, and started looking again.
+    Resumed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum Phase {
+    /// Not tuned to anything yet.
+    Start,
+    /// Tuned, waiting this many more ticks before checking.
+    Dwell(u32),
+    /// Stopped on a busy frequency, for this many ticks.
+    Busy(u32),
+    /// Asked to resume by [Scanner::resume()].
+    Resume,
+}
+
+/// Steps through frequencies, stopping on activity.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Scanner<'a> {
+    channels: Channels<'a>,
+    policy: Policy,
+    index: usize,
+    phase: Phase,
+}
+
+impl<'a> Scanner<'a> {
+    /// Create a scanner. Nothing is tuned until the first tick.
+    pub fn new(channels: Channels<'a>, policy: Policy) -> Self {
+        Self {
+            channels,
+            policy,
+            index: 0,
+            phase: Phase::Start,
+        }
+    }
+
+    /// The frequency currently tuned, in Hz, if any.
+    pub fn frequency(&self) -> Option<u32> {
+        match self.phase {
+            Phase::Start => None,
+            _ => self.channels.get(self.index),
+        }
+    }
+
+    /// Move on from a busy frequency on the next tick.
+    ///
+    /// This is needed to leave a frequency with [Resume::Stop], but
+    /// works with any policy. It does nothing unless stopped.
+    pub fn resume(&mut self) {
+        if let Phase::Busy(_) = self.phase {
+            self.phase = Phase::Resume;
+        }
+    }
+
+    /// Advance the scan by one tick.
+    pub fn tick<Scn, Scl, Sda, Delay, E>(
+        &mut self,
+        radio: &mut Bk4819<Scn, Scl, Sda, Delay>,
+    ) -> Result<ScanState, Error<E>>
+    where
+        Scn: OutputPin<Error = E>,
+        Scl: OutputPin<Error = E>,
+        Sda: OutputPin<Error = E> + InputPin<Error = E>,
+        Delay: DelayNs,
+    {
+        if self.channels.is_empty() {
+            return Ok(ScanState::Scanning);
+        }
+
+        match self.phase {
+            Phase::Start => {
+                self.index = 0;
+                self.tune(radio)?;
+                Ok(ScanState::Scanning)
+            }
+
+            Phase::Dwell(0) => {
+                let busy = match self.policy.squelch {
+                    Squelch::Rssi(threshold) => radio.rssi()? >= threshold,
+                    Squelch::Device => radio.squelch_open()?,
+                };
+
+                if busy {
+                    self.phase = Phase::Busy(0);
+                    Ok(ScanState::Busy(self.frequency().unwrap_or(0)))
+                } else {
+                    self.next(radio)?;
+                    Ok(ScanState::Scanning)
+                }
+            }
+
+            Phase::Dwell(remaining) => {
+                self.phase = Phase::Dwell(remaining - 1);
+                Ok(ScanState::Scanning)
+            }
+
+            Phase::Busy(ticks) => {
+                let timeout = match self.policy.resume {
+                    Resume::Stop => false,
+                    Resume::AfterTicks(limit) => ticks + 1 >= limit,
+                };
+
+                if timeout {
+                    self.next(radio)?;
+                    Ok(ScanState::Resumed)
+                } else {
+                    self.phase = Phase::Busy(ticks + 1);
+                    Ok(ScanState::Busy(self.frequency().unwrap_or(0)))
+                }
+            }
+
+            Phase::Resume => {
+                self.next(radio)?;
+                Ok(ScanState::Resumed)
+            }
+        }
+    }
+
+    /// Tune to the next frequency, wrapping around at the end.
+    fn next<Scn, Scl, Sda, Delay, E>(
+        &mut self,
+        radio: &mut Bk4819<Scn, Scl, Sda, Delay>,
+    ) -> Result<(), Error<E>>
+    where
+        Scn: OutputPin<Error = E>,
+        Scl: OutputPin<Error = E>,
+        Sda: OutputPin<Error = E> + InputPin<Error = E>,
+        Delay: DelayNs,
+    {
+        self.index = (self.index + 1) % self.channels.len();
+        self.tune(radio)
+    }
+
+    /// Tune to the current index, and start dwelling.
+    fn tune<Scn, Scl, Sda, Delay, E>(
+        &mut self,
+        radio: &mut Bk4819<Scn, Scl, Sda, Delay>,
+    ) -> Result<(), Error<E>>
+    where
+        Scn: OutputPin<Error = E>,
+        Scl: OutputPin<Error = E>,
+        Sda: OutputPin<Error = E> + InputPin<Error = E>,
+        Delay: DelayNs,
+    {
+        if let Some(freq_hz) = self.channels.get(self.index) {
+            radio.set_frequency(freq_hz)?;
+        }
+        self.phase = Phase::Dwell(self.policy.dwell);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use core::cell::RefCell;
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::mock::{mock, MockBus};
+
+    // run a scanner, with the RSSI for each frequency word given by `rssi`
+    fn run(
+        channels: Channels,
+        policy: Policy,
+        rssi: impl Fn(u32) -> u16,
+        ticks: usize,
+    ) -> Vec<ScanState> {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+        let mut scanner = Scanner::new(channels, policy);
+
+        (0..ticks)
+            .map(|_| {
+                let word = {
+                    let bus = bus.borrow();
+                    (bus.registers[0x38] as u32) | ((bus.registers[0x39] as u32) << 16)
+                };
+                bus.borrow_mut().registers[0x67] = rssi(word * 10);
+                scanner.tick(&mut bk).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn channels() {
+        let range = Channels::Range {
+            start: 1000,
+            step: 25,
+            count: 3,
+        };
+        assert_eq!(range.len(), 3);
+        assert_eq!(range.get(2), Some(1050));
+        assert_eq!(range.get(3), None);
+
+        let list = Channels::List(&[5, 7]);
+        assert_eq!(list.get(1), Some(7));
+        assert!(Channels::List(&[]).is_empty());
+    }
+
+    #[test]
+    fn stop_on_activity() {
+        use ScanState::*;
+
+        let policy = Policy {
+            dwell: 1,
+            squelch: Squelch::Rssi(0x80),
+            resume: Resume::Stop,
+        };
+        let list = [100_000, 200_000, 300_000];
+        let states = run(
+            Channels::List(&list),
+            policy,
+            |f| if f == 200_000 { 0x90 } else { 0x40 },
+            8,
+        );
+
+        // tune, dwell, check 100k, dwell, check 200k and stop there
+        assert_eq!(
+            states,
+            [
+                Scanning,
+                Scanning,
+                Scanning,
+                Scanning,
+                Busy(200_000),
+                Busy(200_000),
+                Busy(200_000),
+                Busy(200_000),
+            ]
+        );
+    }
+
+    #[test]
+    fn resume_after_timeout() {
+        use ScanState::*;
+
+        let policy = Policy {
+            dwell: 0,
+            squelch: Squelch::Rssi(0x80),
+            resume: Resume::AfterTicks(2),
+        };
+        let range = Channels::Range {
+            start: 1_000_000,
+            step: 12_500,
+            count: 2,
+        };
+        let states = run(range, policy, |f| if f == 1_012_500 { 0x90 } else { 0 }, 8);
+
+        assert_eq!(
+            states,
+            [
+                Scanning,
+                Scanning,
+                Busy(1_012_500),
+                Busy(1_012_500),
+                Resumed,
+                Scanning,
+                Busy(1_012_500),
+                Busy(1_012_500),
+            ]
+        );
+    }
+
+    #[test]
+    fn manual_resume() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+        bus.borrow_mut().registers[0x67] = 0x1ff;
+
+        let policy = Policy {
+            dwell: 0,
+            squelch: Squelch::Rssi(0x80),
+            resume: Resume::Stop,
+        };
+        let mut scanner = Scanner::new(Channels::List(&[10, 20]), policy);
+        assert_eq!(scanner.frequency(), None);
+        assert_eq!(scanner.tick(&mut bk), Ok(ScanState::Scanning));
+        assert_eq!(scanner.tick(&mut bk), Ok(ScanState::Busy(10)));
+
+        scanner.resume();
+        assert_eq!(scanner.tick(&mut bk), Ok(ScanState::Resumed));
+        assert_eq!(scanner.frequency(), Some(20));
+        assert_eq!(scanner.tick(&mut bk), Ok(ScanState::Busy(20)));
+    }
+}
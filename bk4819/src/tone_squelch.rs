@@ -0,0 +1,384 @@
+//! Tone squelch, opening only for signals carrying a CTCSS tone or
+//! CDCSS code.
+//!
+//! [ToneSquelch::configure()] sets up the device to detect a tone,
+//! and enables the interrupts it needs. After that, call
+//! [ToneSquelch::poll()] regularly, and open the audio between
+//! [ToneState::Detected] and [ToneState::Lost] or
+//! [ToneState::TailDetected].
+//!
+//! Polling consumes every pending interrupt flag, through
+//! [Bk4819::take_interrupts()]. Anything else watching interrupts on
+//! the same device must share the flags some other way.
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{InputPin, OutputPin};
+
+use crate::registers::{CdcssCode, CtcControl, CtcMode, CxcssControl, InterruptEnable, Interrupts};
+use crate::{Bk4819, Error};
+
+/// The tone to listen for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Tone {
+    /// A CTCSS tone, in tenths of Hz, so 88.5Hz is 885.
+    Ctcss(u16),
+    /// A CDCSS code, written in octal, so D023N is `0o023`.
+    ///
+    /// `inverted` selects the inverted code, D023I.
+    Cdcss { code: u16, inverted: bool },
+}
+
+impl Tone {
+    /// The 23-bit Golay code word sent for a CDCSS code.
+    pub const fn cdcss_word(code: u16, inverted: bool) -> u32 {
+        // the code is sent as 9 bits of code and 3 bits of 0b100,
+        // followed by 11 bits of parity
+        let data = (code as u32 & 0x1ff) | 0x800;
+        let mut word = data;
+        let mut i = 0;
+        while i < 12 {
+            word <<= 1;
+            if word & 0x1000 != 0 {
+                word ^= 0x08ea;
+            }
+            i += 1;
+        }
+
+        let golay = data | ((word & 0x0ffe) << 11);
+        if inverted {
+            golay ^ 0x7fffff
+        } else {
+            golay
+        }
+    }
+}
+
+/// What a [ToneSquelch] saw on a poll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ToneState {
+    /// Nothing changed.
+    Idle,
+    /// The tone was found.
+    Detected,
+    /// The tone was lost.
+    Lost,
+    /// The transmitter sent a squelch tail, and is about to stop.
+    TailDetected,
+}
+
+/// Watches for a CTCSS tone or CDCSS code.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ToneSquelch {
+    tone: Tone,
+    detected: bool,
+}
+
+/// CTCSS/CDCSS Tx gain 1 used by the stock firmware for CTCSS.
+const CTCSS_TX_GAIN: u8 = 74;
+/// CTCSS/CDCSS Tx gain 1 used by the stock firmware for CDCSS.
+const CDCSS_TX_GAIN: u8 = 51;
+/// The frequency word for the 134.4Hz CDCSS bit rate.
+const CDCSS_BIT_RATE: u16 = 2775;
+/// The frequency word for 55Hz tail detection.
+const TAIL_55HZ: u16 = 461;
+
+impl ToneSquelch {
+    /// Create a tone squelch. Nothing is set up until
+    /// [Self::configure()].
+    pub fn new(tone: Tone) -> Self {
+        Self {
+            tone,
+            detected: false,
+        }
+    }
+
+    /// The tone being listened for.
+    pub fn tone(&self) -> Tone {
+        self.tone
+    }
+
+    /// Is the tone currently detected?
+    pub fn is_detected(&self) -> bool {
+        self.detected
+    }
+
+    /// Set up the device to detect the tone, and enable its
+    /// interrupts.
+    ///
+    /// This uses the register settings of the stock firmware. It also
+    /// sets the tone to transmit, as the device shares these.
+    pub fn configure<Scn, Scl, Sda, Delay, E>(
+        &mut self,
+        radio: &mut Bk4819<Scn, Scl, Sda, Delay>,
+    ) -> Result<(), Error<E>>
+    where
+        Scn: OutputPin<Error = E>,
+        Scl: OutputPin<Error = E>,
+        Sda: OutputPin<Error = E> + InputPin<Error = E>,
+        Delay: DelayNs,
+    {
+        self.detected = false;
+
+        match self.tone {
+            Tone::Ctcss(freq_dhz) => {
+                // freq_hz * 20.64888, for the 26MHz crystal
+                let word = ((freq_dhz as u32 * 206488 + 50000) / 100000) as u16;
+                radio.write(
+                    CxcssControl::new()
+                        .with_enabled(true)
+                        .with_ctcss_mode(true)
+                        .with_tx_gain1(CTCSS_TX_GAIN),
+                )?;
+                radio.write(
+                    CtcControl::new()
+                        .with_mode(Ok(CtcMode::Ctc1))
+                        .with_frequency(word),
+                )?;
+                radio.write(
+                    CtcControl::new()
+                        .with_mode(Ok(CtcMode::Ctc2))
+                        .with_frequency(TAIL_55HZ),
+                )?;
+            }
+
+            Tone::Cdcss { code, inverted } => {
+                let word = Tone::cdcss_word(code, inverted);
+                radio.write(
+                    CxcssControl::new()
+                        .with_enabled(true)
+                        .with_tx_gain1(CDCSS_TX_GAIN),
+                )?;
+                radio.write(
+                    CtcControl::new()
+                        .with_mode(Ok(CtcMode::Cdcss))
+                        .with_frequency(CDCSS_BIT_RATE),
+                )?;
+                radio.write(CdcssCode::new().with_data(word as u16 & 0xfff))?;
+                radio.write(
+                    CdcssCode::new()
+                        .with_high(true)
+                        .with_data((word >> 12) as u16 & 0xfff),
+                )?;
+            }
+        }
+
+        let ctcss = matches!(self.tone, Tone::Ctcss(_));
+        radio.modify(|r: InterruptEnable| {
+            r.with_ctcss_found(ctcss)
+                .with_ctcss_lost(ctcss)
+                .with_cdcss_found(!ctcss)
+                .with_cdcss_lost(!ctcss)
+                .with_tail_found(true)
+        })
+    }
+
+    /// Turn off tone detection and transmit, and its interrupts.
+    pub fn disable<Scn, Scl, Sda, Delay, E>(
+        &mut self,
+        radio: &mut Bk4819<Scn, Scl, Sda, Delay>,
+    ) -> Result<(), Error<E>>
+    where
+        Scn: OutputPin<Error = E>,
+        Scl: OutputPin<Error = E>,
+        Sda: OutputPin<Error = E> + InputPin<Error = E>,
+        Delay: DelayNs,
+    {
+        self.detected = false;
+        radio.write(CxcssControl::new())?;
+        radio.modify(|r: InterruptEnable| {
+            r.with_ctcss_found(false)
+                .with_ctcss_lost(false)
+                .with_cdcss_found(false)
+                .with_cdcss_lost(false)
+                .with_tail_found(false)
+        })
+    }
+
+    /// Check the interrupt flags for tone changes.
+    ///
+    /// If a tone is both found and lost between polls, this reports
+    /// it lost, so the squelch errs on the side of closing. A tail
+    /// beats both.
+    pub fn poll<Scn, Scl, Sda, Delay, E>(
+        &mut self,
+        radio: &mut Bk4819<Scn, Scl, Sda, Delay>,
+    ) -> Result<ToneState, Error<E>>
+    where
+        Scn: OutputPin<Error = E>,
+        Scl: OutputPin<Error = E>,
+        Sda: OutputPin<Error = E> + InputPin<Error = E>,
+        Delay: DelayNs,
+    {
+        Ok(match radio.take_interrupts()? {
+            Some(flags) => self.update(flags),
+            None => ToneState::Idle,
+        })
+    }
+
+    /// Update the detected state from a set of interrupt flags.
+    fn update(&mut self, flags: Interrupts) -> ToneState {
+        let (found, lost) = match self.tone {
+            Tone::Ctcss(_) => (flags.ctcss_found(), flags.ctcss_lost()),
+            Tone::Cdcss { .. } => (flags.cdcss_found(), flags.cdcss_lost()),
+        };
+
+        if flags.tail_found() {
+            self.detected = false;
+            ToneState::TailDetected
+        } else if lost {
+            self.detected = false;
+            ToneState::Lost
+        } else if found {
+            self.detected = true;
+            ToneState::Detected
+        } else {
+            ToneState::Idle
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use core::cell::RefCell;
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::mock::{mock, MockBus};
+
+    // bits of the interrupt flags, see registers::Interrupts
+    const CTCSS_LOST: u16 = 1 << 6;
+    const CTCSS_FOUND: u16 = 1 << 7;
+    const CDCSS_LOST: u16 = 1 << 8;
+    const CDCSS_FOUND: u16 = 1 << 9;
+    const TAIL: u16 = 1 << 10;
+    const SQUELCH_FOUND: u16 = 1 << 3;
+
+    // poll once per entry, with the given flags pending, or none
+    fn run(squelch: &mut ToneSquelch, script: &[Option<u16>]) -> Vec<ToneState> {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+        squelch.configure(&mut bk).unwrap();
+
+        script
+            .iter()
+            .map(|flags| {
+                if let Some(flags) = flags {
+                    let mut bus = bus.borrow_mut();
+                    bus.registers[0x02] = *flags;
+                    bus.registers[0x0c] |= 1;
+                }
+                let state = squelch.poll(&mut bk).unwrap();
+                // every pending interrupt is acknowledged
+                assert_eq!(bus.borrow().registers[0x0c] & 1, 0);
+                state
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cdcss_word() {
+        // D023N, as sent by the stock firmware
+        assert_eq!(Tone::cdcss_word(0o023, false), 0x763813);
+        assert_eq!(Tone::cdcss_word(0o023, true), 0x763813 ^ 0x7fffff);
+    }
+
+    #[test]
+    fn configure() {
+        let bus = RefCell::new(MockBus::new());
+        let mut bk = mock(&bus);
+
+        ToneSquelch::new(Tone::Ctcss(885))
+            .configure(&mut bk)
+            .unwrap();
+        assert_eq!(
+            bus.borrow().writes,
+            [
+                (0x51, 0x904a),
+                (0x07, 0x0723),
+                (0x07, 0x21cd),
+                (0x3f, 0x04c0)
+            ]
+        );
+
+        bus.borrow_mut().writes.clear();
+        let tone = Tone::Cdcss {
+            code: 0o023,
+            inverted: false,
+        };
+        ToneSquelch::new(tone).configure(&mut bk).unwrap();
+        assert_eq!(
+            bus.borrow().writes,
+            [
+                (0x51, 0x8033),
+                (0x07, 0x4ad7),
+                (0x08, 0x0813),
+                (0x08, 0x8763),
+                (0x3f, 0x0700),
+            ]
+        );
+    }
+
+    #[test]
+    fn ctcss_transitions() {
+        use ToneState::*;
+
+        let mut squelch = ToneSquelch::new(Tone::Ctcss(885));
+        let states = run(
+            &mut squelch,
+            &[
+                None,
+                Some(SQUELCH_FOUND),
+                Some(CTCSS_FOUND),
+                None,
+                Some(CTCSS_LOST),
+                Some(CTCSS_FOUND),
+                Some(TAIL),
+                // the other kind of tone is ignored
+                Some(CDCSS_FOUND),
+                Some(CTCSS_FOUND | CTCSS_LOST),
+            ],
+        );
+        assert_eq!(
+            states,
+            [
+                Idle,
+                Idle,
+                Detected,
+                Idle,
+                Lost,
+                Detected,
+                TailDetected,
+                Idle,
+                Lost
+            ]
+        );
+        assert!(!squelch.is_detected());
+    }
+
+    #[test]
+    fn cdcss_transitions() {
+        use ToneState::*;
+
+        let mut squelch = ToneSquelch::new(Tone::Cdcss {
+            code: 0o754,
+            inverted: true,
+        });
+        let states = run(
+            &mut squelch,
+            &[
+                Some(CTCSS_FOUND),
+                Some(CDCSS_FOUND),
+                None,
+                Some(CDCSS_LOST),
+                Some(CDCSS_FOUND),
+            ],
+        );
+        assert_eq!(states, [Idle, Detected, Idle, Lost, Detected]);
+        assert!(squelch.is_detected());
+    }
+}
@@ -9,6 +9,11 @@ use core::cell::UnsafeCell;
 
 use critical_section::CriticalSection;
 
+/// The range of addresses occupied by RAM on the DP32G030.
+///
+/// [Code] must live inside this range to be run. See [Code::is_in_ram()].
+pub const RAM: core::ops::Range<usize> = 0x2000_0000..0x2000_4000;
+
 /// Flash erase and programming times.
 ///
 /// These values are measured in cycles of the system clock, and must
@@ -87,6 +92,7 @@ pub struct Header {
     pub program_word: HeaderEntry<unsafe fn(CriticalSection, Area, u32, *mut u32)>,
     pub program: HeaderEntry<unsafe fn(CriticalSection, Area, &[u32], *mut u32)>,
     pub read_nvr_apb: HeaderEntry<unsafe fn(CriticalSection, u16) -> u32>,
+    pub read_main: HeaderEntry<unsafe fn(CriticalSection, u32, &mut [u8])>,
 }
 
 // we never write to Header
@@ -103,6 +109,7 @@ impl Header {
             program_word: HeaderEntry::from_offset(Self::read_u32le(4) as usize),
             program: HeaderEntry::from_offset(Self::read_u32le(5) as usize),
             read_nvr_apb: HeaderEntry::from_offset(Self::read_u32le(6) as usize),
+            read_main: HeaderEntry::from_offset(Self::read_u32le(7) as usize),
         }
     }
 
@@ -158,6 +165,7 @@ impl Code {
     /// sure you don't accidentally put it in flash!
     ///
     /// Ultimately, this can be a static, on the stack, or on the heap.
+    /// Use [Self::is_in_ram()] to check where it ended up.
     pub const fn new() -> Self {
         Self {
             data: UnsafeCell::new(CODE),
@@ -173,6 +181,28 @@ impl Code {
     pub const fn header(&self) -> &Header {
         &HEADER
     }
+
+    /// Does this code live entirely inside [RAM]?
+    ///
+    /// If this is false, the code is most likely in flash, and
+    /// running it will not work.
+    pub fn is_in_ram(&self) -> bool {
+        let start = self.data().as_ptr() as usize;
+        let end = start + self.data().len();
+        RAM.start <= start && end <= RAM.end
+    }
+
+    /// Panic if this code does not live inside [RAM].
+    ///
+    /// This is checked before any of the flash routines are run, to
+    /// catch linker script mistakes that would otherwise leave the
+    /// code in flash.
+    pub fn assert_in_ram(&self) {
+        assert!(
+            self.is_in_ram(),
+            "flash Code must be in RAM, but it is not (linker script mistake?)"
+        );
+    }
 }
 
 // if we share the same target as the blobs, add some helper functions to
@@ -381,5 +411,78 @@ mod same_target {
         pub unsafe fn read_nvr_apb(&self, cs: CriticalSection, src: u16) -> u32 {
             unsafe { self.resolve(&HEADER.read_nvr_apb)(cs, src) }
         }
+
+        /// Read a block of bytes from main flash.
+        ///
+        /// This is [Self::read_nvr()], but for the main program
+        /// area, for code that wants to look at the program itself,
+        /// for example to checksum it.
+        ///
+        /// The program runs from this same flash, so these reads
+        /// contend with code execution. Keep them brief, and read
+        /// large areas a piece at a time.
+        ///
+        /// # Safety
+        ///
+        /// The flash must not be in use anywhere else.
+        ///
+        /// `src` and `src + dest.len()` must be inside main flash.
+        pub unsafe fn read_main(&self, cs: CriticalSection, src: u32, dest: &mut [u8]) {
+            unsafe { self.resolve(&HEADER.read_main)(cs, src, dest) }
+        }
+    }
+}
+
+// on any other target, like a host running tests, the code can never
+// run. keep the same api so dependents still build, but panic if used.
+#[cfg(not(all(target_arch = "arm", target_os = "none")))]
+mod other_target {
+    use super::*;
+
+    #[allow(clippy::missing_safety_doc)]
+    impl Code {
+        pub unsafe fn init(&self, _cs: CriticalSection, _read_md: bool) {
+            unimplemented!("flash code only runs on the DP32G030")
+        }
+
+        pub unsafe fn set_times(&self, _cs: CriticalSection, _times: &Times) {
+            unimplemented!("flash code only runs on the DP32G030")
+        }
+
+        pub unsafe fn read_nvr(&self, _cs: CriticalSection, _src: u16, _dest: &mut [u8]) {
+            unimplemented!("flash code only runs on the DP32G030")
+        }
+
+        pub unsafe fn erase(&self, _cs: CriticalSection, _area: Area, _sector: *mut u32) {
+            unimplemented!("flash code only runs on the DP32G030")
+        }
+
+        pub unsafe fn program_word(
+            &self,
+            _cs: CriticalSection,
+            _area: Area,
+            _word: u32,
+            _dest: *mut u32,
+        ) {
+            unimplemented!("flash code only runs on the DP32G030")
+        }
+
+        pub unsafe fn program(
+            &self,
+            _cs: CriticalSection,
+            _area: Area,
+            _src: &[u32],
+            _dest: *mut u32,
+        ) {
+            unimplemented!("flash code only runs on the DP32G030")
+        }
+
+        pub unsafe fn read_nvr_apb(&self, _cs: CriticalSection, _src: u16) -> u32 {
+            unimplemented!("flash code only runs on the DP32G030")
+        }
+
+        pub unsafe fn read_main(&self, _cs: CriticalSection, _src: u32, _dest: &mut [u8]) {
+            unimplemented!("flash code only runs on the DP32G030")
+        }
     }
 }
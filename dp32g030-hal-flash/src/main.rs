@@ -15,6 +15,7 @@ header! {
     program_word,
     program,
     read_nvr_apb,
+    read_main,
 }
 
 // safety: see Code in lib.rs
@@ -127,6 +128,26 @@ pub unsafe fn read_nvr_apb(cs: CriticalSection, src: u16) -> u32 {
     })
 }
 
+// safety: see Code in lib.rs
+pub unsafe fn read_main(cs: CriticalSection, src: u32, dest: &mut [u8]) {
+    let mut flash = Flash::get(cs);
+
+    flash.with_area(Area::Main, |_flash| unsafe {
+        // main flash starts at address 0, which rust considers a null
+        // pointer and will not read from. go around it.
+        for (i, d) in dest.iter_mut().enumerate() {
+            let byte: u8;
+            core::arch::asm!(
+                "ldrb {byte}, [{address}]",
+                address = in(reg) src as usize + i,
+                byte = lateout(reg) byte,
+                options(nostack, readonly, preserves_flags),
+            );
+            *d = byte;
+        }
+    })
+}
+
 pub struct Flash {
     ctrl: FLASH_CTRL,
 }
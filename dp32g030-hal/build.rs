@@ -33,5 +33,8 @@ fn main() {
     println!("cargo:rerun-if-changed=memory.x");
 
     // Set the linker script to the one provided by cortex-m-rt.
-    println!("cargo:rustc-link-arg=-Tlink.x");
+    // Host builds (for tests) use the normal system linker.
+    if env::var("TARGET").unwrap().starts_with("thumb") {
+        println!("cargo:rustc-link-arg=-Tlink.x");
+    }
 }
@@ -0,0 +1,214 @@
+//! Interfaces for the CRC calculation unit.
+
+use core::ops::DerefMut;
+
+use crate::pac;
+
+use crate::power::Gate;
+
+/// Wrap the CRC register, using the given configuration.
+pub fn new(crc: pac::CRC, gate: Gate<pac::CRC>, config: Config) -> Crc {
+    Crc::new(crc, gate, config)
+}
+
+/// The polynomial used by the CRC unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Algorithm {
+    /// 16-bit, polynomial `0x1021`.
+    Crc16Ccitt,
+    /// 8-bit, polynomial `0x07`.
+    Crc8Atm,
+    /// 16-bit, polynomial `0x8005`.
+    Crc16,
+    /// 32-bit, polynomial `0x04c11db7`.
+    Crc32,
+}
+
+impl Algorithm {
+    /// Mask for the bits of the output this algorithm produces.
+    pub const fn mask(&self) -> u32 {
+        match self {
+            Self::Crc16Ccitt | Self::Crc16 => 0xffff,
+            Self::Crc8Atm => 0xff,
+            Self::Crc32 => 0xffffffff,
+        }
+    }
+}
+
+/// Configuration for the CRC unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Config {
+    /// Polynomial to use.
+    pub algorithm: Algorithm,
+    /// Initial value of the CRC.
+    pub init: u32,
+    /// Bit-reverse each input byte.
+    pub reflect_in: bool,
+    /// Invert each input byte.
+    pub invert_in: bool,
+    /// Bit-reverse the output.
+    pub reflect_out: bool,
+    /// Invert the output.
+    pub invert_out: bool,
+}
+
+impl Config {
+    /// 16-bit XModem, as used by host to radio frames.
+    pub const fn xmodem() -> Self {
+        Self {
+            algorithm: Algorithm::Crc16Ccitt,
+            init: 0,
+            reflect_in: false,
+            invert_in: false,
+            reflect_out: false,
+            invert_out: false,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::xmodem()
+    }
+}
+
+/// The hardware CRC calculation unit.
+pub struct Crc {
+    crc: pac::CRC,
+    config: Config,
+}
+
+impl core::fmt::Debug for Crc {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("Crc")
+            .field("crc", &self.crc)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Crc {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "Crc {{ crc: CRC, config: {} }}", self.config)
+    }
+}
+
+impl Crc {
+    /// Wrap the CRC register, using the given configuration.
+    pub fn new(crc: pac::CRC, mut gate: Gate<pac::CRC>, config: Config) -> Self {
+        gate.enable();
+
+        // we have exclusive access to crc
+        crc.cr().write(|w| {
+            let w = match config.algorithm {
+                Algorithm::Crc16Ccitt => w.crc_sel().crc_16_ccitt(),
+                Algorithm::Crc8Atm => w.crc_sel().crc_8_atm(),
+                Algorithm::Crc16 => w.crc_sel().crc_16(),
+                Algorithm::Crc32 => w.crc_sel().crc_32(),
+            };
+            w.data_width()
+                .bits8()
+                .input_rev()
+                .bit(config.reflect_in)
+                .input_inv()
+                .bit(config.invert_in)
+                .output_rev()
+                .bit(config.reflect_out)
+                .output_inv()
+                .bit(config.invert_out)
+                .crc_en()
+                .disabled()
+        });
+
+        Self { crc, config }
+    }
+
+    /// Wrap the CRC register, configured for 16-bit XModem.
+    pub fn xmodem(crc: pac::CRC, gate: Gate<pac::CRC>) -> Self {
+        Self::new(crc, gate, Config::xmodem())
+    }
+
+    /// Recover the raw CRC register.
+    pub fn free(self) -> (pac::CRC, Gate<pac::CRC>) {
+        // safety: we own self, which gives us control of this gate
+        let mut gate = unsafe { Gate::steal() };
+        gate.disable();
+        (self.crc, gate)
+    }
+
+    /// The configuration in use.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Start a new CRC calculation.
+    pub fn digest(&mut self) -> Digest<&mut Self> {
+        Digest::new(self)
+    }
+
+    /// Calculate the CRC of `bytes` in one go.
+    pub fn checksum(&mut self, bytes: &[u8]) -> u32 {
+        let mut digest = self.digest();
+        digest.update(bytes);
+        digest.finalize()
+    }
+}
+
+/// An in-progress CRC calculation.
+///
+/// This can hold any mutable reference to a [Crc], which is useful
+/// for guards like [core::cell::RefMut].
+#[derive(Debug)]
+pub struct Digest<C>
+where
+    C: DerefMut<Target = Crc>,
+{
+    crc: C,
+}
+
+#[cfg(feature = "defmt")]
+impl<C> defmt::Format for Digest<C>
+where
+    C: DerefMut<Target = Crc>,
+{
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "Digest {{ crc: {} }}", *self.crc)
+    }
+}
+
+impl<C> Digest<C>
+where
+    C: DerefMut<Target = Crc>,
+{
+    /// Start a new CRC calculation.
+    pub fn new(crc: C) -> Self {
+        // the unit restarts from the initial value when enabled
+        crc.crc.iv().write(|w| w.value().set(crc.config.init));
+        crc.crc.cr().modify(|_, w| w.crc_en().enabled());
+        Self { crc }
+    }
+
+    /// Feed more bytes into the calculation.
+    pub fn update(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.crc.crc.datain().write(|w| w.data().set(*b as u32));
+        }
+    }
+
+    /// Finish the calculation and return the CRC.
+    pub fn finalize(self) -> u32 {
+        self.crc.crc.dataout().read().data().bits() & self.crc.config.algorithm.mask()
+    }
+}
+
+impl<C> Drop for Digest<C>
+where
+    C: DerefMut<Target = Crc>,
+{
+    fn drop(&mut self) {
+        self.crc.crc.cr().modify(|_, w| w.crc_en().disabled());
+    }
+}
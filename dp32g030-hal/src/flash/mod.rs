@@ -0,0 +1,186 @@
+//! Interfaces for reading, erasing, and programming the main flash.
+//!
+//! Obtain a [Flash] from [crate::power::Config::freeze_with_flash()].
+
+use dp32g030_hal_flash::{Area, Code, RAM};
+
+mod writer;
+pub use writer::*;
+
+/// Size of the main flash area, in bytes.
+pub const SIZE: u32 = 0x10000;
+
+/// Size of an erasable flash sector, in bytes.
+pub const SECTOR_SIZE: u32 = 0x200;
+
+/// Size of a flash half-sector, in bytes.
+///
+/// Programming operations cannot cross a half-sector boundary.
+pub const HALF_SECTOR_SIZE: u32 = SECTOR_SIZE / 2;
+
+/// A flash error.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// Address is outside of the main flash area.
+    OutOfRange,
+    /// Address is not aligned to a word, or a sector for erases.
+    Misaligned,
+    /// Programming would cross a half-sector boundary.
+    CrossesHalfSector,
+    /// Source data for programming does not live in RAM.
+    NotInRam,
+    /// A word read back different data after programming.
+    Verify { address: u32 },
+    /// A page was received out of order.
+    OutOfOrder { expected: u16, got: u16 },
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "Flash Error {:?}", self)
+    }
+}
+
+/// Access to read, erase, and program the main flash area.
+///
+/// Each operation runs in a critical section, and blocks for the
+/// duration of a single erase or half-sector program.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Flash<'code> {
+    code: &'code Code,
+}
+
+impl<'code> Flash<'code> {
+    /// # Safety
+    /// This uses `code` to erase and program flash, and must be unique.
+    pub(crate) unsafe fn steal(code: &'code Code) -> Self {
+        code.assert_in_ram();
+        Self { code }
+    }
+
+    /// Read `dest.len()` bytes of main flash, starting at `address`.
+    ///
+    /// This reads through the flash peripheral with
+    /// [Code::read_main()], which is useful for checking the running
+    /// program, for example with a checksum.
+    ///
+    /// The read runs in a critical section, and contends with the
+    /// running program for the flash. Keep `dest` short, and read
+    /// large areas a piece at a time.
+    pub fn read(&mut self, address: u32, dest: &mut [u8]) -> Result<(), Error> {
+        let len = dest.len() as u32;
+        if address >= SIZE || SIZE - address < len {
+            return Err(Error::OutOfRange);
+        }
+
+        critical_section::with(|cs| {
+            // safety: we own the flash, and checked the range above
+            unsafe { self.code.read_main(cs, address, dest) }
+        });
+
+        Ok(())
+    }
+
+    /// Erase (set to 0xff) the sector starting at `address`.
+    ///
+    /// # Safety
+    ///
+    /// The sector must not contain code or data in use, including
+    /// the currently running program.
+    pub unsafe fn erase(&mut self, address: u32) -> Result<(), Error> {
+        if address >= SIZE {
+            return Err(Error::OutOfRange);
+        }
+        if !address.is_multiple_of(SECTOR_SIZE) {
+            return Err(Error::Misaligned);
+        }
+
+        critical_section::with(|cs| {
+            // safety: we own the flash, and the caller vouches for the sector
+            self.code.erase(cs, Area::Main, address as *mut u32)
+        });
+
+        Ok(())
+    }
+
+    /// Program `words` starting at `address`, and verify them.
+    ///
+    /// This enforces the rules in [Code::program()]: only one
+    /// half-sector may be programmed at once, programming cannot
+    /// cross a half-sector boundary, and `words` must live in RAM.
+    ///
+    /// Programming can only clear bits, so the destination should
+    /// usually be erased first with [Self::erase()].
+    ///
+    /// # Safety
+    ///
+    /// The destination must not contain code or data in use,
+    /// including the currently running program.
+    pub unsafe fn program(&mut self, address: u32, words: &[u32]) -> Result<(), Error> {
+        let len = core::mem::size_of_val(words) as u32;
+        if address >= SIZE || SIZE - address < len {
+            return Err(Error::OutOfRange);
+        }
+        if !address.is_multiple_of(4) {
+            return Err(Error::Misaligned);
+        }
+        if address % HALF_SECTOR_SIZE + len > HALF_SECTOR_SIZE {
+            return Err(Error::CrossesHalfSector);
+        }
+
+        let src = words.as_ptr() as usize;
+        if !RAM.contains(&src) || RAM.end - src < len as usize {
+            return Err(Error::NotInRam);
+        }
+
+        critical_section::with(|cs| {
+            // safety: we own the flash, checked the programming rules
+            // above, and the caller vouches for the destination
+            self.code
+                .program(cs, Area::Main, words, address as *mut u32)
+        });
+
+        for (i, word) in words.iter().enumerate() {
+            let word_address = address + (i * core::mem::size_of::<u32>()) as u32;
+            // safety: we checked this is inside the main flash area
+            if read_word(word_address) != *word {
+                return Err(Error::Verify {
+                    address: word_address,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Read a word of flash.
+///
+/// # Safety
+///
+/// `address` must be word-aligned and inside the main flash area.
+#[cfg(all(target_arch = "arm", target_os = "none"))]
+unsafe fn read_word(address: u32) -> u32 {
+    // flash starts at address 0, which rust considers a null pointer
+    // and will not read from. go around it.
+    let word: u32;
+    core::arch::asm!(
+        "ldr {word}, [{address}]",
+        address = in(reg) address,
+        word = lateout(reg) word,
+        options(nostack, readonly, preserves_flags),
+    );
+    word
+}
+
+/// Read a word of flash, which only exists on the chip.
+///
+/// # Safety
+///
+/// Never safe: this always panics off the chip.
+#[cfg(not(all(target_arch = "arm", target_os = "none")))]
+unsafe fn read_word(_address: u32) -> u32 {
+    unimplemented!("flash can only be read on the DP32G030")
+}
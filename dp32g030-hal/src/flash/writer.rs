@@ -0,0 +1,163 @@
+use super::{Error, Flash, HALF_SECTOR_SIZE, SECTOR_SIZE, SIZE};
+
+/// Size of a page given to [FlashWriter::write_page()], in bytes.
+///
+/// This matches the 0x100 byte payload in the bootloader `WriteFlash`
+/// message, and is exactly one half-sector.
+pub const PAGE_SIZE: usize = HALF_SECTOR_SIZE as usize;
+
+const PAGE_WORDS: usize = PAGE_SIZE / core::mem::size_of::<u32>();
+
+/// Writes a flash image one page at a time, as pages arrive.
+///
+/// Pages must arrive in order, starting at page 0. Each call to
+/// [Self::write_page()] erases the sector when the page starts one,
+/// programs the page as a single half-sector, and verifies it.
+/// Between pages the flash is idle, so the caller is free to go
+/// receive the next page.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FlashWriter<'code> {
+    flash: Flash<'code>,
+    base: u32,
+    max_page: u16,
+    next_page: u16,
+    // programming needs a word-aligned source in RAM
+    buffer: [u32; PAGE_WORDS],
+}
+
+impl<'code> FlashWriter<'code> {
+    /// Write an image of up to `max_page` pages, starting at `base`.
+    ///
+    /// # Safety
+    ///
+    /// The entire area written must not contain code or data in use,
+    /// including the currently running program.
+    pub unsafe fn new(flash: Flash<'code>, base: u32, max_page: u16) -> Result<Self, Error> {
+        if !base.is_multiple_of(SECTOR_SIZE) {
+            return Err(Error::Misaligned);
+        }
+        if base >= SIZE || (SIZE - base) / (PAGE_SIZE as u32) < max_page as u32 {
+            return Err(Error::OutOfRange);
+        }
+
+        Ok(Self {
+            flash,
+            base,
+            max_page,
+            next_page: 0,
+            buffer: [0; PAGE_WORDS],
+        })
+    }
+
+    /// The next page this writer expects.
+    pub fn next_page(&self) -> u16 {
+        self.next_page
+    }
+
+    /// Is this the last page of the image?
+    pub fn is_complete(&self) -> bool {
+        self.next_page >= self.max_page
+    }
+
+    /// Erase (if needed), program, and verify one page.
+    ///
+    /// Out of order pages, and pages past the end of the image, are
+    /// rejected without touching the flash.
+    pub fn write_page(&mut self, page: u16, data: &[u8; PAGE_SIZE]) -> Result<(), Error> {
+        if page != self.next_page {
+            return Err(Error::OutOfOrder {
+                expected: self.next_page,
+                got: page,
+            });
+        }
+        if page >= self.max_page {
+            return Err(Error::OutOfRange);
+        }
+
+        for (word, bytes) in self.buffer.iter_mut().zip(data.chunks_exact(4)) {
+            // unwrap: chunks_exact(4) always gives 4 bytes
+            *word = u32::from_le_bytes(bytes.try_into().unwrap());
+        }
+
+        let address = self.base + page as u32 * PAGE_SIZE as u32;
+
+        // safety: the caller of new() vouched for this whole area
+        unsafe {
+            if address.is_multiple_of(SECTOR_SIZE) {
+                self.flash.erase(address)?;
+            }
+            self.flash.program(address, &self.buffer)?;
+        }
+
+        self.next_page += 1;
+        Ok(())
+    }
+
+    /// Finish writing, returning the flash and the number of pages written.
+    pub fn finish(self) -> (Flash<'code>, u16) {
+        (self.flash, self.next_page)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use dp32g030_hal_flash::Code;
+
+    use super::*;
+
+    // the host has no flash, so any test that reaches the code panics
+    static CODE: Code = Code::new();
+
+    fn writer(max_page: u16) -> FlashWriter<'static> {
+        let flash = Flash { code: &CODE };
+        // safety: these tests never reach the flash
+        unsafe { FlashWriter::new(flash, 0x8000, max_page) }.unwrap()
+    }
+
+    #[test]
+    fn new_checks_area() {
+        let new = |base, max_page| {
+            let flash = Flash { code: &CODE };
+            // safety: these tests never reach the flash
+            unsafe { FlashWriter::new(flash, base, max_page) }.map(|_| ())
+        };
+        assert_eq!(new(0x8100, 1), Err(Error::Misaligned));
+        assert_eq!(new(SIZE, 0), Err(Error::OutOfRange));
+        assert_eq!(new(0xfe00, 3), Err(Error::OutOfRange));
+        assert_eq!(new(0xfe00, 2), Ok(()));
+    }
+
+    #[test]
+    fn out_of_order() {
+        let mut writer = writer(4);
+        let data = [0; PAGE_SIZE];
+        assert_eq!(
+            writer.write_page(1, &data),
+            Err(Error::OutOfOrder {
+                expected: 0,
+                got: 1
+            })
+        );
+        assert_eq!(
+            writer.write_page(u16::MAX, &data),
+            Err(Error::OutOfOrder {
+                expected: 0,
+                got: u16::MAX
+            })
+        );
+        assert_eq!(writer.next_page(), 0);
+        assert!(!writer.is_complete());
+    }
+
+    #[test]
+    fn past_the_end() {
+        let mut writer = writer(0);
+        assert!(writer.is_complete());
+        assert_eq!(
+            writer.write_page(0, &[0; PAGE_SIZE]),
+            Err(Error::OutOfRange)
+        );
+        assert_eq!(writer.finish().1, 0);
+    }
+}
@@ -1,4 +1,14 @@
 //! Types for using pins in alternate modes.
+//!
+//! The tables below list which alternate functions each pin supports,
+//! taken from the PORTCON pin selection registers in the DP32G030
+//! SVD. Each entry also adds a helper method to the pin, named after
+//! the peripheral and function, so that `pa7.into_uart1_tx()` selects
+//! the right alternate function and pin mode. Pins without an entry
+//! have no such helper, so pinmux mistakes fail to compile.
+//!
+//! [Pin::into_alternate()] is still available to select any
+//! alternate function by number.
 
 use super::*;
 
@@ -9,7 +19,7 @@ use crate::pac::portcon::portb_sel1::*;
 use crate::pac::portcon::portc_sel0::*;
 
 macro_rules! pin {
-    ($name:literal, enum $pinname:ident {$($var:ident<$mode:ty>),*$(,)?}) => {
+    ($name:literal, $helper:ident, enum $pinname:ident {$($var:ident<$mode:ty>),*$(,)?}) => {
         #[derive(Debug)]
         #[cfg_attr(feature = "defmt", derive(defmt::Format))]
         #[doc = concat!("Choices for pin ", stringify!($pinname), " on ", $name, ".")]
@@ -25,6 +35,13 @@ macro_rules! pin {
                     Self::$var(value.into_mode())
                 }
             }
+
+            impl<Mode> $var<Mode> where Mode: PinMode {
+                #[doc = concat!("Convert pin into ", stringify!($pinname), " on ", $name, ".")]
+                pub fn $helper(self) -> $var<$mode> {
+                    self.into_mode()
+                }
+            }
         )*
     };
 }
@@ -37,7 +54,7 @@ macro_rules! pins {
                 use super::*;
 
                 $(
-                    pin!($name, enum $pinname {
+                    pin!($name, [<into_ $mod _ $pinname:snake>], enum $pinname {
                         $(
                             [<P $var>]<Alternate<{[<PORT $var _A>]::$varname as u8}, $mode>>,
                         )*
@@ -152,6 +169,48 @@ pins!(uart2, "UART2", {
     }
 });
 
+pins!(saradc, "SARADC", {
+    enum Ch0: SaradcCh0 {
+        A5<Analog>,
+    }
+
+    enum Ch1: SaradcCh1 {
+        A6<Analog>,
+    }
+
+    enum Ch2: SaradcCh2 {
+        A7<Analog>,
+    }
+
+    enum Ch3: SaradcCh3 {
+        A8<Analog>,
+    }
+
+    enum Ch4: SaradcCh4 {
+        A9<Analog>,
+    }
+
+    enum Ch5: SaradcCh5 {
+        A10<Analog>,
+    }
+
+    enum Ch6: SaradcCh6 {
+        A11<Analog>,
+    }
+
+    enum Ch7: SaradcCh7 {
+        A12<Analog>,
+    }
+
+    enum Ch8: SaradcCh8 {
+        A13<Analog>,
+    }
+
+    enum Ch9: SaradcCh9 {
+        A14<Analog>,
+    }
+});
+
 // total guesses on pin modes
 pins!(xtah, "XTAH port", {
     enum Xi: XtahXi {
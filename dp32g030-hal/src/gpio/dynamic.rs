@@ -0,0 +1,414 @@
+//! Pins that switch between input and output at run time.
+//!
+//! The type-state pins should be preferred, as using them in the wrong
+//! mode fails to compile. A [DynamicPin] instead keeps its mode in a
+//! run-time flag, so that it stays one type and can live in a struct
+//! field while changing direction, like a bidirectional test pin.
+//! Using it in the wrong direction returns a [DynamicPinError].
+//!
+//! Each mode change rewrites every pin setting, inside a critical
+//! section.
+
+use super::{ErasedPin, Floating, Input, OpenDrain, Output, PinMode, PinState, Unspecified};
+
+/// The modes a [DynamicPin] can switch between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DynamicMode {
+    /// See [Input] and [Floating].
+    FloatingInput,
+    /// See [Input] and [PullUp][super::PullUp].
+    PullUpInput,
+    /// See [Input] and [PullDown][super::PullDown].
+    PullDownInput,
+    /// See [Output] and [PushPull][super::PushPull].
+    PushPullOutput,
+    /// See [Output] and [OpenDrain].
+    OpenDrainOutput,
+}
+
+impl DynamicMode {
+    /// Is this one of the input modes?
+    pub fn is_input(&self) -> bool {
+        matches!(
+            self,
+            Self::FloatingInput | Self::PullUpInput | Self::PullDownInput
+        )
+    }
+
+    /// Is this one of the output modes?
+    pub fn is_output(&self) -> bool {
+        !self.is_input()
+    }
+
+    /// Can the line be read in this mode? True for inputs, and for
+    /// open-drain outputs.
+    pub fn is_readable(&self) -> bool {
+        *self != Self::PushPullOutput
+    }
+}
+
+/// An error from using a [DynamicPin] in the wrong mode.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DynamicPinError {
+    /// The line was read while the pin is a push-pull output.
+    NotReadable,
+    /// The output was used while the pin is an input.
+    NotOutput,
+}
+
+impl core::fmt::Display for DynamicPinError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "Dynamic Pin Error {:?}", self)
+    }
+}
+
+/// Internal access to a pin's registers, for [DynamicPin].
+pub(super) trait DynamicAccess {
+    /// Reconfigure the pin into `mode`. Outputs start in `state`.
+    fn configure(&mut self, mode: DynamicMode, state: PinState);
+
+    /// Read the line while in an input mode.
+    fn read_input(&self) -> PinState;
+
+    /// Read the line while in open-drain output mode.
+    fn read_open_drain(&self) -> PinState;
+
+    /// Get the output data.
+    fn output(&self) -> PinState;
+
+    /// Set the output data.
+    fn set_output(&mut self, state: PinState);
+
+    /// Toggle the output data.
+    fn toggle_output(&mut self);
+}
+
+// the Unspecified mode forces a full reconfiguration on every change,
+// so the real mode only needs to live in DynamicPin
+impl DynamicAccess for ErasedPin<Unspecified> {
+    fn configure(&mut self, mode: DynamicMode, state: PinState) {
+        let (pin, port) = (self.pin(), self.port());
+        critical_section::with(|_cs| {
+            // safety: we own this pin through self, and this copy is
+            // dropped once it has changed mode
+            let stolen = unsafe { ErasedPin::<Unspecified>::steal(pin, port) };
+            match mode {
+                DynamicMode::FloatingInput => {
+                    stolen.into_floating_input();
+                }
+                DynamicMode::PullUpInput => {
+                    stolen.into_pull_up_input();
+                }
+                DynamicMode::PullDownInput => {
+                    stolen.into_pull_down_input();
+                }
+                DynamicMode::PushPullOutput => {
+                    stolen.into_push_pull_output_in_state(state);
+                }
+                DynamicMode::OpenDrainOutput => {
+                    stolen.into_open_drain_output_in_state(state);
+                }
+            }
+        });
+    }
+
+    fn read_input(&self) -> PinState {
+        self.read_data()
+    }
+
+    fn read_open_drain(&self) -> PinState {
+        // safety: the pin is in open-drain mode, and we only read
+        unsafe { ErasedPin::<Output<OpenDrain>>::steal(self.pin(), self.port()) }.read()
+    }
+
+    fn output(&self) -> PinState {
+        self.read_data()
+    }
+
+    fn set_output(&mut self, state: PinState) {
+        self.write_data(state)
+    }
+
+    fn toggle_output(&mut self) {
+        self.toggle_data()
+    }
+}
+
+/// A pin that switches between input and output modes at run time.
+///
+/// Create one with [Pin::into_dynamic()][super::Pin::into_dynamic] or
+/// [ErasedPin::into_dynamic()]. It starts out as a floating input.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DynamicPin<A = ErasedPin<Unspecified>> {
+    pin: A,
+    mode: DynamicMode,
+}
+
+impl DynamicPin {
+    /// Create a dynamic pin from an erased pin, as a floating input.
+    pub fn new<Mode>(pin: ErasedPin<Mode>) -> Self
+    where
+        Mode: PinMode,
+    {
+        let pin = pin.into_mode::<Input<Floating>>();
+        Self {
+            // safety: we consume the pin here, and track its mode
+            pin: unsafe { ErasedPin::steal(pin.pin(), pin.port()) },
+            mode: DynamicMode::FloatingInput,
+        }
+    }
+
+    /// Convert back into an erased pin in a fixed mode.
+    pub fn into_erased<Mode>(self) -> ErasedPin<Mode>
+    where
+        Mode: PinMode,
+    {
+        self.pin.into_mode()
+    }
+}
+
+#[allow(private_bounds)]
+impl<A> DynamicPin<A>
+where
+    A: DynamicAccess,
+{
+    /// Get the current mode.
+    pub fn mode(&self) -> DynamicMode {
+        self.mode
+    }
+
+    // change mode, setting the output state if it is an output
+    fn make(&mut self, mode: DynamicMode, state: PinState) {
+        if mode != self.mode {
+            self.pin.configure(mode, state);
+            self.mode = mode;
+        } else if mode.is_output() {
+            self.pin.set_output(state);
+        }
+    }
+
+    /// Make this pin a floating input.
+    pub fn make_floating_input(&mut self) {
+        self.make(DynamicMode::FloatingInput, PinState::Low)
+    }
+
+    /// Make this pin an input with a pull-up resistor.
+    pub fn make_pull_up_input(&mut self) {
+        self.make(DynamicMode::PullUpInput, PinState::Low)
+    }
+
+    /// Make this pin an input with a pull-down resistor.
+    pub fn make_pull_down_input(&mut self) {
+        self.make(DynamicMode::PullDownInput, PinState::Low)
+    }
+
+    /// Make this pin a push-pull output, initially low.
+    pub fn make_push_pull_output(&mut self) {
+        self.make_push_pull_output_in_state(PinState::Low)
+    }
+
+    /// Make this pin a push-pull output in the given state.
+    pub fn make_push_pull_output_in_state(&mut self, state: PinState) {
+        self.make(DynamicMode::PushPullOutput, state)
+    }
+
+    /// Make this pin an open-drain output, initially low.
+    pub fn make_open_drain_output(&mut self) {
+        self.make_open_drain_output_in_state(PinState::Low)
+    }
+
+    /// Make this pin an open-drain output in the given state.
+    pub fn make_open_drain_output_in_state(&mut self, state: PinState) {
+        self.make(DynamicMode::OpenDrainOutput, state)
+    }
+
+    /// Read the line. Fails for push-pull outputs.
+    ///
+    /// Open-drain outputs driven high are read as in
+    /// [ErasedPin::read()].
+    pub fn read(&self) -> Result<PinState, DynamicPinError> {
+        match self.mode {
+            DynamicMode::PushPullOutput => Err(DynamicPinError::NotReadable),
+            DynamicMode::OpenDrainOutput => Ok(self.pin.read_open_drain()),
+            _ => Ok(self.pin.read_input()),
+        }
+    }
+
+    /// Is the line high? Fails for push-pull outputs.
+    pub fn is_high(&self) -> Result<bool, DynamicPinError> {
+        self.read().map(|s| s.is_high())
+    }
+
+    /// Is the line low? Fails for push-pull outputs.
+    pub fn is_low(&self) -> Result<bool, DynamicPinError> {
+        self.read().map(|s| s.is_low())
+    }
+
+    // the output data, if this is an output
+    fn output(&self) -> Result<PinState, DynamicPinError> {
+        if self.mode.is_output() {
+            Ok(self.pin.output())
+        } else {
+            Err(DynamicPinError::NotOutput)
+        }
+    }
+
+    /// Get the output state. Fails for inputs.
+    pub fn get_state(&self) -> Result<PinState, DynamicPinError> {
+        self.output()
+    }
+
+    /// Is the output set high? Fails for inputs.
+    pub fn is_set_high(&self) -> Result<bool, DynamicPinError> {
+        self.output().map(|s| s.is_high())
+    }
+
+    /// Is the output set low? Fails for inputs.
+    pub fn is_set_low(&self) -> Result<bool, DynamicPinError> {
+        self.output().map(|s| s.is_low())
+    }
+
+    /// Set the output state. Fails for inputs.
+    pub fn set_state(&mut self, state: PinState) -> Result<(), DynamicPinError> {
+        self.output()?;
+        self.pin.set_output(state);
+        Ok(())
+    }
+
+    /// Set the output high. Fails for inputs.
+    pub fn set_high(&mut self) -> Result<(), DynamicPinError> {
+        self.set_state(PinState::High)
+    }
+
+    /// Set the output low. Fails for inputs.
+    pub fn set_low(&mut self) -> Result<(), DynamicPinError> {
+        self.set_state(PinState::Low)
+    }
+
+    /// Toggle the output. Fails for inputs.
+    pub fn toggle(&mut self) -> Result<(), DynamicPinError> {
+        self.output()?;
+        self.pin.toggle_output();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use PinState::{High as H, Low as L};
+
+    // a mock pin, with a line that can be driven from outside
+    struct MockPin {
+        mode: DynamicMode,
+        configured: usize,
+        data: PinState,
+        line: PinState,
+    }
+
+    impl DynamicAccess for MockPin {
+        fn configure(&mut self, mode: DynamicMode, state: PinState) {
+            self.mode = mode;
+            self.configured += 1;
+            if mode.is_output() {
+                self.data = state;
+            }
+        }
+
+        fn read_input(&self) -> PinState {
+            assert!(self.mode.is_input());
+            self.line
+        }
+
+        fn read_open_drain(&self) -> PinState {
+            assert_eq!(self.mode, DynamicMode::OpenDrainOutput);
+            if self.data.is_low() {
+                L
+            } else {
+                self.line
+            }
+        }
+
+        fn output(&self) -> PinState {
+            self.data
+        }
+
+        fn set_output(&mut self, state: PinState) {
+            assert!(self.mode.is_output());
+            self.data = state;
+        }
+
+        fn toggle_output(&mut self) {
+            assert!(self.mode.is_output());
+            self.data = !self.data;
+        }
+    }
+
+    fn new_pin() -> DynamicPin<MockPin> {
+        DynamicPin {
+            pin: MockPin {
+                mode: DynamicMode::FloatingInput,
+                configured: 0,
+                data: L,
+                line: H,
+            },
+            mode: DynamicMode::FloatingInput,
+        }
+    }
+
+    #[test]
+    fn switch_direction() {
+        let mut pin = new_pin();
+
+        // inputs read the line, and have no output
+        assert_eq!(pin.is_high(), Ok(true));
+        assert_eq!(pin.set_high(), Err(DynamicPinError::NotOutput));
+        assert_eq!(pin.toggle(), Err(DynamicPinError::NotOutput));
+        assert_eq!(pin.is_set_high(), Err(DynamicPinError::NotOutput));
+
+        // push-pull outputs write, but can't read the line
+        pin.make_push_pull_output();
+        assert_eq!(pin.mode(), DynamicMode::PushPullOutput);
+        assert_eq!(pin.is_set_low(), Ok(true));
+        assert_eq!(pin.is_high(), Err(DynamicPinError::NotReadable));
+        pin.set_high().unwrap();
+        assert_eq!(pin.is_set_high(), Ok(true));
+        pin.toggle().unwrap();
+        assert_eq!(pin.get_state(), Ok(L));
+
+        // open-drain outputs can do both
+        pin.make_open_drain_output_in_state(H);
+        pin.pin.line = L;
+        assert_eq!(pin.is_low(), Ok(true));
+        pin.pin.line = H;
+        assert_eq!(pin.is_high(), Ok(true));
+        pin.set_low().unwrap();
+        assert_eq!(pin.is_high(), Ok(false));
+
+        // and back to an input
+        pin.make_pull_up_input();
+        assert_eq!(pin.mode(), DynamicMode::PullUpInput);
+        assert_eq!(pin.read(), Ok(H));
+        assert_eq!(pin.set_low(), Err(DynamicPinError::NotOutput));
+        assert_eq!(pin.pin.configured, 3);
+    }
+
+    #[test]
+    fn same_mode() {
+        let mut pin = new_pin();
+
+        // no reconfiguration for the mode it's already in
+        pin.make_floating_input();
+        assert_eq!(pin.pin.configured, 0);
+
+        // but outputs still take the new state
+        pin.make_push_pull_output_in_state(H);
+        pin.make_push_pull_output();
+        assert_eq!(pin.pin.configured, 1);
+        assert_eq!(pin.is_set_low(), Ok(true));
+    }
+}
@@ -1,8 +1,8 @@
 use crate::pac;
 
 use super::{
-    Alternate, Floating, Input, IntoMode, OpenDrain, Output, PartiallyErasedPin, Pin, PinInfo,
-    PinMode, PinState, PullDown, PullUp, PushPull, WithMode,
+    open_drain, Alternate, Analog, DynamicPin, Floating, Input, IntoMode, OpenDrain, Output,
+    PartiallyErasedPin, Pin, PinInfo, PinMode, PinState, PullDown, PullUp, PushPull, WithMode,
 };
 
 /// An erased pin with dynamic port and pin number.
@@ -121,6 +121,11 @@ where
         }
     }
 
+    /// Convert into a [DynamicPin], which changes mode at run time.
+    pub fn into_dynamic(self) -> DynamicPin {
+        DynamicPin::new(self)
+    }
+
     /// Convert pin into a new mode.
     pub fn into_mode<M>(self) -> ErasedPin<M>
     where
@@ -204,7 +209,7 @@ where
     }
 
     // internal helper to read data register
-    fn read_data(&self) -> PinState {
+    pub(super) fn read_data(&self) -> PinState {
         let (pin, port) = self.pin_port();
 
         // safety: we control these registers, and can read them
@@ -222,7 +227,7 @@ where
         }
     }
 
-    // internal helper to write data register
+    // internal helper to write data register, see module docs
     pub(super) fn write_data(&mut self, state: PinState) {
         let (pin, port) = self.pin_port();
 
@@ -247,6 +252,36 @@ where
         });
     }
 
+    // internal helper to toggle data register
+    // this flips the bit in a single locked modify, so there is no
+    // separate read of the current state that could go stale
+    pub(super) fn toggle_data(&mut self) {
+        let (pin, port) = self.pin_port();
+
+        critical_section::with(|_cs| {
+            // safety: we control these registers and can write them
+            // and we are inside a critical section so we can modify
+            unsafe {
+                if port == 'A' {
+                    let gpio = pac::GPIOA::steal();
+                    gpio.data()
+                        .modify(|r, w| w.bits(super::toggled(r.bits(), pin)));
+                } else if port == 'B' {
+                    let gpio = pac::GPIOB::steal();
+                    gpio.data()
+                        .modify(|r, w| w.bits(super::toggled(r.bits(), pin)));
+                } else if port == 'C' {
+                    let gpio = pac::GPIOC::steal();
+                    gpio.data()
+                        .modify(|r, w| w.bits(super::toggled(r.bits(), pin)));
+                } else {
+                    // we never build these, someone did a naughty transmute
+                    panic!();
+                }
+            }
+        });
+    }
+
     super::mode::into_mode_aliases!(vis pub, (ErasedPin), ());
     super::mode::with_mode_aliases!(vis pub, (ErasedPin), ());
 
@@ -287,22 +322,23 @@ where
 
 impl ErasedPin<Output<OpenDrain>> {
     /// Read the input pin.
+    ///
+    /// If the pin is driven high, it is briefly made a floating input
+    /// to see if something else is pulling it low. See
+    /// [Self::read_stable()] to reject glitches on a shared line.
     pub fn read(&self) -> PinState {
-        if self.read_data().is_high() {
-            // high means high-Z, turn into an input briefly to see
-            // if something else is pulling us low
-            // safety: we own this pin, and it's undone at the end
-            unsafe {
-                let mut pin = Self::steal(self.pin(), self.port());
-                let r = pin.with_floating_input(|p| p.read());
-                // cursed: pin output defaults to last input
-                pin.write_data(PinState::High);
-                r
-            }
-        } else {
-            // we're pulling it low
-            PinState::Low
-        }
+        self.read_stable(1)
+    }
+
+    /// Read the input pin, taking `samples` samples and returning the
+    /// majority, to reject glitches on a shared line.
+    ///
+    /// The samples are taken back to back inside a critical section,
+    /// one load of the port data register each. Ties count as low,
+    /// and zero samples is the same as one. The pin is restored to
+    /// its drive state afterwards.
+    pub fn read_stable(&self, samples: u8) -> PinState {
+        open_drain::read_stable(self, samples)
     }
 
     /// Is the input pin high?
@@ -352,8 +388,7 @@ where
 
     /// Toggle the output.
     pub fn toggle(&mut self) {
-        // FIXME this could be done with xor
-        self.set_state(!self.get_state());
+        self.toggle_data();
     }
 }
 
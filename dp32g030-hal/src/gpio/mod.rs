@@ -1,9 +1,23 @@
 //! Interfaces for interacting with GPIO pins.
+//!
+//! Each port has a single data register shared by all of its pins.
+//! The DP32G030 has no set/clear registers for GPIO, and the M0+ has
+//! no bit-banding, so writing an output pin is a read-modify-write
+//! done inside a critical section. This keeps writes to different
+//! pins on the same port from clobbering each other at any interrupt
+//! priority, and each write is complete before the write call returns.
+//!
+//! PB11 and PB14 start out as the SWD debug port, in the [Swd] mode,
+//! and need an explicit [Pin::into_mode_releasing_swd()] to be used
+//! as anything else.
 
 use crate::pac;
 
 pub mod alt;
 
+mod dynamic;
+pub use dynamic::*;
+
 mod erased;
 pub use erased::*;
 
@@ -16,6 +30,8 @@ pub use inout::*;
 mod mode;
 pub use mode::*;
 
+mod open_drain;
+
 mod partial;
 pub use partial::*;
 
@@ -71,13 +87,20 @@ impl Ports {
     }
 }
 
+// a port data register with the bit for one pin flipped
+// there is no toggle register, but xor leaves every other pin as it was
+fn toggled(data: u32, pin: u8) -> u32 {
+    data ^ (1 << pin)
+}
+
 // macro for each port module
 macro_rules! port_mod {
-    ($reg:ident, $name:literal, $P:literal, $p:ident, $bigp:ident, {$($N:literal),+}) => {
+    ($reg:ident, $name:literal, $P:literal, $p:ident, $bigp:ident, {$($N:literal $(: $M:ident)?),+}) => {
         paste::paste! {
             #[doc = concat!("Helper types for ", $name, ".")]
             pub mod [<port_ $p>] {
-                use super::{Pin, Unspecified, PartiallyErasedPin};
+                #[allow(unused_imports)]
+                use super::{Pin, Swd, Unspecified, PartiallyErasedPin};
                 use crate::power::Gate;
                 use crate::pac::$reg;
 
@@ -85,7 +108,7 @@ macro_rules! port_mod {
                 #[derive(Debug)]
                 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
                 pub struct Pins {
-                    $(pub [<$p $N>]: Pin<$P, $N, Unspecified>),+
+                    $(pub [<$p $N>]: Pin<$P, $N, port_mod!(@mode $($M)?)>),+
                 }
 
                 impl Pins {
@@ -166,8 +189,52 @@ macro_rules! port_mod {
             )*
         }
     };
+
+    // pins start Unspecified, unless given a starting mode
+    (@mode) => { Unspecified };
+    (@mode $M:ident) => { $M };
 }
 
 port_mod!(GPIOA, "GPIO port A", 'A', a, A, {0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15});
-port_mod!(GPIOB, "GPIO port B", 'B', b, B, {0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15});
+port_mod!(GPIOB, "GPIO port B", 'B', b, B, {0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11: Swd, 12, 13, 14: Swd, 15});
 port_mod!(GPIOC, "GPIO port C", 'C', c, C, {0, 1, 2, 3, 4, 5, 6, 7});
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn toggle() {
+        let data = 0b1010_0101_1100_0011;
+        for pin in 0..16 {
+            let once = toggled(data, pin);
+            assert_eq!(once ^ data, 1 << pin);
+            assert_eq!(toggled(once, pin), data);
+
+            // toggling N times returns to the start when N is even
+            let mut d = data;
+            for n in 1..=8 {
+                d = toggled(d, pin);
+                assert_eq!(d == data, n % 2 == 0);
+            }
+        }
+    }
+
+    #[test]
+    fn pin_state_conversions() {
+        use embedded_hal_1::digital as hal1;
+
+        for (state, value, hal) in [
+            (PinState::Low, false, hal1::PinState::Low),
+            (PinState::High, true, hal1::PinState::High),
+        ] {
+            assert_eq!(PinState::from(value), state);
+            assert_eq!(PinState::from_bool(value), state);
+            assert_eq!(bool::from(state), value);
+            assert_eq!(state.as_bool(), value);
+            assert_eq!(PinState::from(hal), state);
+            assert_eq!(hal1::PinState::from(state), hal);
+            assert_eq!(!state, PinState::from(!value));
+        }
+    }
+}
@@ -30,6 +30,22 @@ pub(super) trait PinModeSealed {
     /// GPIO direction, 0 is input, 1 is output. Probably the same
     /// as ![Self::IE].
     const DIR: bool;
+
+    /// Whether this is the debug port mode, [Swd].
+    const SWD: bool = false;
+
+    /// The result of a static assert that this is not [Swd].
+    const NOT_SWD: () = assert!(
+        !Self::SWD,
+        "SWD pins must be converted with into_mode_releasing_swd()"
+    );
+
+    /// Call this to statically assert that [Self::NOT_SWD] is set.
+    #[allow(path_statements)]
+    fn static_assert_not_swd() {
+        #[allow(clippy::no_effect)]
+        Self::NOT_SWD;
+    }
 }
 
 /// A trait for pin mode type states.
@@ -174,6 +190,106 @@ impl PinModeSealed for Input<PullDown> {
     const DIR: bool = false;
 }
 
+/// Serial wire debug, the mode PB11 (SWDIO) and PB14 (SWCLK) start
+/// in. (type state)
+///
+/// While in this mode, these pins connect the debugger. Changing
+/// their mode disconnects it until the next reset or
+/// [Pin::reclaim_swd()][super::Pin::reclaim_swd], and a firmware
+/// that does this at startup can only be debugged by holding it in
+/// reset while connecting.
+///
+/// To keep this from happening by accident, pins in this mode cannot
+/// be changed with [Pin::into_mode()][super::Pin::into_mode] and
+/// friends. Doing so fails to compile:
+///
+/// ```compile_fail,E0080
+/// # use dp32g030_hal::gpio::{Output, Pin, PushPull, Swd};
+/// fn res(pin: Pin<'B', 11, Swd>) -> Pin<'B', 11, Output<PushPull>> {
+///     pin.into_push_pull_output()
+/// }
+/// # let _: fn(_) -> _ = res;
+/// ```
+///
+/// Use the explicit
+/// [Pin::into_mode_releasing_swd()][super::Pin::into_mode_releasing_swd]
+/// instead:
+///
+/// ```no_run
+/// # use dp32g030_hal::gpio::{Output, Pin, PushPull, Swd};
+/// // ok, but SWD is gone
+/// fn res(pin: Pin<'B', 11, Swd>) -> Pin<'B', 11, Output<PushPull>> {
+///     pin.into_mode_releasing_swd()
+/// }
+/// # let _: fn(_) -> _ = res;
+/// ```
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Swd;
+
+impl PinMode for Swd {
+    type Inner = Self;
+}
+
+impl PinModeSealed for Swd {
+    const VALID: () = ();
+    const UNSPECIFIED: bool = false;
+
+    const IE: bool = true;
+    const PD: bool = false;
+    const PU: bool = false;
+
+    const OD: bool = false;
+
+    const SEL: u8 = 1;
+    const DIR: bool = false;
+
+    const SWD: bool = true;
+}
+
+/// Analog mode, with the digital input disabled and no pulls. (type state)
+///
+/// This keeps the input buffer from drawing current when the pin sits
+/// between logic levels. It does not connect the pin to the SARADC on
+/// its own, as each ADC channel is also an alternate function. Use
+/// the helpers in [alt::saradc][super::alt::saradc] to get
+/// `Alternate<_, Analog>` pins with the right function selected. The
+/// pins that support analog input are:
+///
+/// | Channel | Pin  |
+/// |---------|------|
+/// | 0       | PA5  |
+/// | 1       | PA6  |
+/// | 2       | PA7  |
+/// | 3       | PA8  |
+/// | 4       | PA9  |
+/// | 5       | PA10 |
+/// | 6       | PA11 |
+/// | 7       | PA12 |
+/// | 8       | PA13 |
+/// | 9       | PA14 |
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Analog;
+
+impl PinMode for Analog {
+    type Inner = Self;
+}
+
+impl PinModeSealed for Analog {
+    const VALID: () = ();
+    const UNSPECIFIED: bool = false;
+
+    const IE: bool = false;
+    const PD: bool = false;
+    const PU: bool = false;
+
+    const OD: bool = false;
+
+    const SEL: u8 = 0;
+    const DIR: bool = false;
+}
+
 /// Push-pull output. (type state)
 #[derive(Debug, Default)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -309,6 +425,8 @@ where
 
     const SEL: u8 = A;
     const DIR: bool = false;
+
+    const SWD: bool = <Mode as PinModeSealed>::SWD;
 }
 
 // A macro to implement aliases on top of into_mode and into_mode_in_state.
@@ -329,6 +447,11 @@ macro_rules! into_mode_aliases {
             self.into_mode()
         }
 
+        /// Convert pin into analog mode.
+        $($vis)? fn into_analog(self) -> $($as)*<$($args)* Analog> {
+            self.into_mode()
+        }
+
         /// Convert pin into a push-pull output, initially low.
         $($vis)? fn into_push_pull_output(self) -> $($as)*<$($args)* Output<PushPull>> {
             self.into_mode_in_state(PinState::Low)
@@ -375,6 +498,11 @@ macro_rules! with_mode_aliases {
             self.with_mode(f)
         }
 
+        /// Temporarily configure pin in analog mode.
+        $($vis)? fn with_analog<R>(&mut self, f: impl FnOnce(&mut $($as)*<$($args)* Analog>) -> R) -> R {
+            self.with_mode(f)
+        }
+
         /// Temporarily configure pin as a push-pull output.
         ///
         /// The initial state is retained if the original mode was
@@ -459,3 +587,28 @@ pub trait WithMode {
 
     with_mode_aliases!((Self::With), ());
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn analog() {
+        assert!(!<Analog as PinModeSealed>::IE);
+        assert!(!<Analog as PinModeSealed>::PU);
+        assert!(!<Analog as PinModeSealed>::PD);
+
+        // the ADC channel keeps the analog pad settings
+        type Ch4 = Alternate<5, Analog>;
+        assert_eq!(<Ch4 as PinModeSealed>::SEL, 5);
+        assert!(!<Ch4 as PinModeSealed>::IE);
+    }
+
+    // never run, only checked that it compiles
+    #[allow(dead_code)]
+    fn into_analog(pin: crate::gpio::PA9<Unspecified>) -> crate::gpio::alt::saradc::Ch4 {
+        let mut pin = pin.into_analog();
+        pin.with_floating_input(|_| {});
+        pin.into_saradc_ch4().into()
+    }
+}
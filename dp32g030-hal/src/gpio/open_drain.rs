@@ -0,0 +1,198 @@
+//! Reading back open-drain outputs.
+//!
+//! An open-drain output driven high is high-Z, and the data register
+//! only reports what we are driving, not what is on the line. To see
+//! whether something else is pulling the line low, the pin is switched
+//! to a floating input, sampled, and switched back.
+//!
+//! The whole exchange happens inside one critical section, so no
+//! interrupt can observe or modify the pin while it is floating. The
+//! pin floats for the time it takes to write the mode registers, plus
+//! one load of the port data register per sample. Samples are taken
+//! back to back, a few CPU cycles apart, with no added delay, so they
+//! reject short glitches on the line rather than slow edges.
+//!
+//! Leaving input mode latches the last input into the output data, so
+//! the data is always rewritten high afterwards to restore the drive.
+
+use critical_section::CriticalSection;
+
+use super::{ErasedPin, OpenDrain, Output, PinInfo, PinState};
+
+/// Internal access to an open-drain pin, for [read()].
+pub(super) trait OpenDrainAccess {
+    /// Get the current output drive state.
+    fn drive(&self) -> PinState;
+
+    /// Set the current output drive state.
+    fn set_drive(&mut self, state: PinState);
+
+    /// Switch to a floating input, take `samples` samples, and return
+    /// how many were high. The pin is left in open-drain output mode.
+    fn count_floating_high(&mut self, samples: u8) -> u8;
+}
+
+impl OpenDrainAccess for ErasedPin<Output<OpenDrain>> {
+    fn drive(&self) -> PinState {
+        self.read_data()
+    }
+
+    fn set_drive(&mut self, state: PinState) {
+        self.write_data(state);
+    }
+
+    fn count_floating_high(&mut self, samples: u8) -> u8 {
+        self.with_floating_input(|p| {
+            let mut highs = 0;
+            for _ in 0..samples {
+                if p.read().is_high() {
+                    highs += 1;
+                }
+            }
+            highs
+        })
+    }
+}
+
+/// Read any open-drain pin, returning the majority of `samples`
+/// samples. Ties and zero samples count as a single sample.
+pub(super) fn read_stable<P>(pin: &P, samples: u8) -> PinState
+where
+    P: PinInfo<Mode = Output<OpenDrain>>,
+{
+    // safety: the caller owns this pin, and the mode and drive state
+    // are restored before returning
+    let mut pin = unsafe { ErasedPin::steal(pin.pin(), pin.port()) };
+    read(&mut pin, samples)
+}
+
+/// Read an open-drain pin, returning the majority of `samples`
+/// samples. Ties and zero samples count as a single sample.
+fn read<A>(pin: &mut A, samples: u8) -> PinState
+where
+    A: OpenDrainAccess,
+{
+    if pin.drive().is_low() {
+        // we're pulling it low
+        return PinState::Low;
+    }
+
+    critical_section::with(|cs| sample(pin, samples, cs))
+}
+
+// sample a pin driven high, and restore the drive
+fn sample<A>(pin: &mut A, samples: u8, _cs: CriticalSection) -> PinState
+where
+    A: OpenDrainAccess,
+{
+    let samples = samples.max(1);
+    let highs = pin.count_floating_high(samples);
+    // cursed: pin output defaults to last input
+    pin.set_drive(PinState::High);
+    majority(highs, samples)
+}
+
+// high if strictly more than half of the samples were high
+fn majority(highs: u8, samples: u8) -> PinState {
+    (highs as u16 * 2 > samples as u16).into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    extern crate std;
+    use std::vec::Vec;
+
+    // a mock pin register, with a line that can be pulled low
+    struct MockPin {
+        data: PinState,
+        line: Vec<PinState>,
+        floated: usize,
+    }
+
+    impl MockPin {
+        fn new(data: PinState, line: &[PinState]) -> Self {
+            Self {
+                data,
+                line: line.iter().rev().copied().collect(),
+                floated: 0,
+            }
+        }
+    }
+
+    impl OpenDrainAccess for MockPin {
+        fn drive(&self) -> PinState {
+            self.data
+        }
+
+        fn set_drive(&mut self, state: PinState) {
+            self.data = state;
+        }
+
+        fn count_floating_high(&mut self, samples: u8) -> u8 {
+            self.floated += 1;
+            let mut highs = 0;
+            for _ in 0..samples {
+                // the data register latches each input
+                self.data = self.line.pop().unwrap();
+                if self.data.is_high() {
+                    highs += 1;
+                }
+            }
+            highs
+        }
+    }
+
+    use PinState::{High as H, Low as L};
+
+    // the mock has no shared state to protect
+    fn cs() -> CriticalSection<'static> {
+        // safety: see above
+        unsafe { CriticalSection::new() }
+    }
+
+    #[test]
+    fn majority_logic() {
+        assert_eq!(majority(0, 1), L);
+        assert_eq!(majority(1, 1), H);
+        assert_eq!(majority(2, 3), H);
+        assert_eq!(majority(1, 3), L);
+        assert_eq!(majority(2, 4), L);
+        assert_eq!(majority(255, 255), H);
+    }
+
+    #[test]
+    fn read_single() {
+        let mut pin = MockPin::new(H, &[L]);
+        assert_eq!(sample(&mut pin, 1, cs()), L);
+        assert_eq!(pin.data, H);
+
+        let mut pin = MockPin::new(H, &[H]);
+        assert_eq!(sample(&mut pin, 0, cs()), H);
+        assert_eq!(pin.data, H);
+    }
+
+    #[test]
+    fn read_stable() {
+        // a glitch low at the end is rejected, and the drive restored
+        let mut pin = MockPin::new(H, &[H, H, H, H, L]);
+        assert_eq!(sample(&mut pin, 5, cs()), H);
+        assert_eq!(pin.data, H);
+        assert_eq!(pin.floated, 1);
+
+        // a glitch high is rejected too
+        let mut pin = MockPin::new(H, &[L, H, L]);
+        assert_eq!(sample(&mut pin, 3, cs()), L);
+        assert_eq!(pin.data, H);
+    }
+
+    #[test]
+    fn read_driven_low() {
+        // never floats a pin we are pulling low
+        let mut pin = MockPin::new(L, &[]);
+        assert_eq!(read(&mut pin, 5), L);
+        assert_eq!(pin.data, L);
+        assert_eq!(pin.floated, 0);
+    }
+}
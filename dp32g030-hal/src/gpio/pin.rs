@@ -1,8 +1,8 @@
 use crate::pac;
 
 use super::{
-    Alternate, ErasedPin, Floating, Input, IntoMode, OpenDrain, Output, PartiallyErasedPin,
-    PinMode, PullDown, PullUp, PushPull, WithMode,
+    open_drain, Alternate, Analog, DynamicPin, ErasedPin, Floating, Input, IntoMode, OpenDrain,
+    Output, PartiallyErasedPin, PinMode, PullDown, PullUp, PushPull, Swd, Unspecified, WithMode,
 };
 
 /// Digital pin state.
@@ -16,11 +16,13 @@ pub enum PinState {
 
 impl From<bool> for PinState {
     fn from(value: bool) -> Self {
-        if value {
-            Self::High
-        } else {
-            Self::Low
-        }
+        Self::from_bool(value)
+    }
+}
+
+impl From<PinState> for bool {
+    fn from(value: PinState) -> Self {
+        value.as_bool()
     }
 }
 
@@ -36,6 +38,20 @@ impl core::ops::Not for PinState {
 }
 
 impl PinState {
+    /// [PinState::High] if `true`, [PinState::Low] if `false`.
+    pub const fn from_bool(value: bool) -> Self {
+        if value {
+            Self::High
+        } else {
+            Self::Low
+        }
+    }
+
+    /// `true` if [PinState::High], `false` if [PinState::Low].
+    pub const fn as_bool(&self) -> bool {
+        matches!(self, Self::High)
+    }
+
     /// Is the pin high?
     pub fn is_high(&self) -> bool {
         *self == Self::High
@@ -92,6 +108,8 @@ where
 macro_rules! change_mode {
     ($Gpio:ty, $port:ident, $N:expr, $From:ty, $To:ty) => {
         <$To>::static_assert_valid();
+        <$From>::static_assert_not_swd();
+        <$To>::static_assert_not_swd();
         let force = <$From>::UNSPECIFIED;
         let portcon = pac::PORTCON::steal();
         let port = <$Gpio>::steal();
@@ -222,6 +240,11 @@ where
         PartiallyErasedPin::erase(self)
     }
 
+    /// Convert into a [DynamicPin], which changes mode at run time.
+    pub fn into_dynamic(self) -> DynamicPin {
+        DynamicPin::new(self.erase())
+    }
+
     /// Convert pin into a new mode.
     pub fn into_mode<M>(self) -> Pin<P, N, M>
     where
@@ -316,7 +339,7 @@ where
         }
     }
 
-    // internal helper to write data register
+    // internal helper to write data register, see module docs
     pub(super) fn write_data(&mut self, state: PinState) {
         critical_section::with(|_cs| {
             // safety: we control these registers and can write them
@@ -339,6 +362,34 @@ where
         });
     }
 
+    // internal helper to toggle data register
+    // this flips the bit in a single locked modify, so there is no
+    // separate read of the current state that could go stale
+    pub(super) fn toggle_data(&mut self) {
+        critical_section::with(|_cs| {
+            // safety: we control these registers and can write them
+            // and we are inside a critical section so we can modify
+            unsafe {
+                if P == 'A' {
+                    let gpio = pac::GPIOA::steal();
+                    gpio.data()
+                        .modify(|r, w| w.bits(super::toggled(r.bits(), N)));
+                } else if P == 'B' {
+                    let gpio = pac::GPIOB::steal();
+                    gpio.data()
+                        .modify(|r, w| w.bits(super::toggled(r.bits(), N)));
+                } else if P == 'C' {
+                    let gpio = pac::GPIOC::steal();
+                    gpio.data()
+                        .modify(|r, w| w.bits(super::toggled(r.bits(), N)));
+                } else {
+                    // we never build these, someone did a naughty transmute
+                    panic!();
+                }
+            }
+        });
+    }
+
     super::mode::into_mode_aliases!(vis pub, (Pin), (P, N,));
     super::mode::with_mode_aliases!(vis pub, (Pin), (P, N,));
 
@@ -357,6 +408,64 @@ where
     }
 }
 
+impl<const P: char, const N: u8> Pin<P, N, Swd> {
+    /// Convert an SWD pin into a new mode, disconnecting the debugger.
+    ///
+    /// See [Swd] for why this is separate from [Self::into_mode()].
+    pub fn into_mode_releasing_swd<M>(self) -> Pin<P, N, M>
+    where
+        M: PinMode,
+    {
+        // safety: we are consuming self, and Unspecified forces a
+        // full reconfiguration, leaving no trace of Swd behind
+        let pin: Pin<P, N, Unspecified> = unsafe { Pin::steal() };
+        pin.into_mode()
+    }
+
+    /// Convert an SWD pin into a new mode, in the given initial
+    /// state, disconnecting the debugger.
+    pub fn into_mode_in_state_releasing_swd<M>(self, state: PinState) -> Pin<P, N, Output<M>>
+    where
+        Output<M>: PinMode,
+    {
+        // safety: as above
+        let pin: Pin<P, N, Unspecified> = unsafe { Pin::steal() };
+        pin.into_mode_in_state(state)
+    }
+}
+
+impl<Mode> Pin<'B', 11, Mode>
+where
+    Mode: PinMode,
+{
+    /// Return this pin to SWDIO, reconnecting the debugger.
+    pub fn reclaim_swd(self) -> Pin<'B', 11, Swd> {
+        reclaim_swd(self)
+    }
+}
+
+impl<Mode> Pin<'B', 14, Mode>
+where
+    Mode: PinMode,
+{
+    /// Return this pin to SWCLK, reconnecting the debugger.
+    pub fn reclaim_swd(self) -> Pin<'B', 14, Swd> {
+        reclaim_swd(self)
+    }
+}
+
+// shared body of reclaim_swd for PB11 and PB14
+fn reclaim_swd<const N: u8, Mode>(pin: Pin<'B', N, Mode>) -> Pin<'B', N, Swd>
+where
+    Mode: PinMode,
+{
+    // Swd can't go through change_mode, so use the mode it matches:
+    // function 1, input enabled, no pulls, the same as at reset
+    let _: Pin<'B', N, Alternate<1, Input<Floating>>> = pin.into_mode();
+    // safety: the pin is now configured as Swd, and we consumed it
+    unsafe { Pin::steal() }
+}
+
 impl<const P: char, const N: u8, Pull> Pin<P, N, Input<Pull>>
 where
     Input<Pull>: PinMode,
@@ -379,22 +488,23 @@ where
 
 impl<const P: char, const N: u8> Pin<P, N, Output<OpenDrain>> {
     /// Read the input pin.
+    ///
+    /// If the pin is driven high, it is briefly made a floating input
+    /// to see if something else is pulling it low. See
+    /// [Self::read_stable()] to reject glitches on a shared line.
     pub fn read(&self) -> PinState {
-        if self.read_data().is_high() {
-            // high means high-Z, turn into an input briefly to see
-            // if something else is pulling us low
-            // safety: we own this pin, and it's undone at the end
-            unsafe {
-                let mut pin = Self::steal();
-                let r = pin.with_floating_input(|p| p.read());
-                // cursed: pin output defaults to last input
-                pin.write_data(PinState::High);
-                r
-            }
-        } else {
-            // we're pulling it low
-            PinState::Low
-        }
+        self.read_stable(1)
+    }
+
+    /// Read the input pin, taking `samples` samples and returning the
+    /// majority, to reject glitches on a shared line.
+    ///
+    /// The samples are taken back to back inside a critical section,
+    /// one load of the port data register each. Ties count as low,
+    /// and zero samples is the same as one. The pin is restored to
+    /// its drive state afterwards.
+    pub fn read_stable(&self, samples: u8) -> PinState {
+        open_drain::read_stable(self, samples)
     }
 
     /// Is the input pin high?
@@ -444,8 +554,7 @@ where
 
     /// Toggle the output.
     pub fn toggle(&mut self) {
-        // FIXME this could be done with xor
-        self.set_state(!self.get_state());
+        self.toggle_data();
     }
 }
 
@@ -3,8 +3,8 @@ use core::convert::Infallible;
 use embedded_hal_02::digital::v2 as hal02;
 
 use super::{
-    ErasedPin, Floating, Input, OpenDrain, Output, PartiallyErasedPin, Pin, PinInfo, PinMode,
-    PinState, PullDown, PullUp, PushPull, WithMode,
+    Analog, ErasedPin, Floating, Input, OpenDrain, Output, PartiallyErasedPin, Pin, PinInfo,
+    PinMode, PinState, PullDown, PullUp, PushPull, WithMode,
 };
 
 /// A pin that can be shared between users.
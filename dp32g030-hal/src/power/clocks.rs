@@ -4,6 +4,7 @@ use dp32g030_hal_flash::{Code, Times};
 
 use crate::pac;
 
+use crate::flash::Flash;
 use crate::gpio::alt::{xtah, xtal};
 use crate::time::{Hertz, RateExtU32};
 
@@ -67,6 +68,15 @@ fn saradc_div(d: SaradcSel) -> u32 {
     1 << (d as u32)
 }
 
+// the smallest divider that keeps the sample clock at or under target,
+// or the largest divider if none do
+fn saradc_sel_for(sys_clk: Hertz, target: Hertz) -> SaradcSel {
+    [SaradcSel::Div1, SaradcSel::Div2, SaradcSel::Div4]
+        .into_iter()
+        .find(|d| sys_clk / saradc_div(*d) <= target)
+        .unwrap_or(SaradcSel::Div8)
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Choices for RTC clock.
@@ -314,6 +324,16 @@ impl Clocks {
         }
     }
 
+    /// Clocks with only a system clock, for testing code that reads it.
+    #[cfg(test)]
+    pub(crate) fn with_sys_clk(sys_clk: Hertz) -> Self {
+        Self {
+            sys_clk,
+            // safety: this is never installed
+            ..unsafe { Self::zero() }
+        }
+    }
+
     /// # Safety
     /// This token might be used to unconfigure the clocks,
     /// and should be unique.
@@ -368,6 +388,10 @@ impl<'code> Config<'code> {
     /// Notably, owning this allows you to change the clock out from
     /// under running peripherals.
     unsafe fn steal(flash_code: &'code Code) -> Self {
+        // every Config is built here. misplacing this code in flash
+        // breaks everything, so catch it early
+        flash_code.assert_in_ram();
+
         Self {
             flash_code,
             xtal: None,
@@ -398,6 +422,34 @@ impl<'code> Config<'code> {
         }
     }
 
+    /// Set the ADC sample divisor to get as close as possible to
+    /// `target` without going over.
+    ///
+    /// This uses the system clock chosen so far, so call it after
+    /// choosing the system clock. The internal oscillator is assumed
+    /// to be exactly 48MHz, so the real rate may be off by the RCHF
+    /// trim, which is a fraction of a percent. If `target` is under
+    /// the system clock divided by 8, this saturates at
+    /// [SaradcSel::Div8].
+    ///
+    /// The sample clock is at most the system clock itself, 48MHz
+    /// on the internal oscillator. Check the resulting rate with
+    /// [Clocks::saradc_sample_clk()].
+    pub fn saradc_sample_near(self, target: Hertz) -> Self {
+        let sys_clk = self.sys.freq(&self.nominal_freqs());
+        self.saradc_sample(saradc_sel_for(sys_clk, target))
+    }
+
+    // source frequencies before freeze(), with RCHF untrimmed
+    fn nominal_freqs(&self) -> SourceFreqs {
+        SourceFreqs {
+            rchf_high: 48.MHz(),
+            rclf: 32_768.Hz(),
+            xtal: self.xtal,
+            xtah: self.xtah,
+        }
+    }
+
     /// Set the RTC clock source.
     pub fn rtc(self, rtc: RtcSel) -> Self {
         Self { rtc, ..self }
@@ -486,6 +538,16 @@ impl<'code> Config<'code> {
         unsafe { Power::steal(clocks) }
     }
 
+    /// Freeze the configuration, and also obtain access to the flash.
+    pub fn freeze_with_flash(self) -> (Power, Flash<'code>) {
+        let flash_code = self.flash_code;
+        let power = self.freeze();
+
+        // safety: we own (from new) FLASH_CTRL, and freeze() has
+        // configured the flash timings for the final clock
+        (power, unsafe { Flash::steal(flash_code) })
+    }
+
     fn freeze_critical(self, cs: critical_section::CriticalSection) -> Clocks {
         // This is mission-critical code written by using a machine-translated
         // PDF as reference.
@@ -702,3 +764,28 @@ impl<'code> Config<'code> {
         clocks
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn saradc_near() {
+        let at_24 = |target: u32| saradc_sel_for(24.MHz(), target.kHz());
+        assert_eq!(at_24(24_000), SaradcSel::Div1);
+        assert_eq!(at_24(30_000), SaradcSel::Div1);
+        assert_eq!(at_24(20_000), SaradcSel::Div2);
+        assert_eq!(at_24(12_000), SaradcSel::Div2);
+        assert_eq!(at_24(6_000), SaradcSel::Div4);
+        assert_eq!(at_24(3_000), SaradcSel::Div8);
+        assert_eq!(at_24(1_000), SaradcSel::Div8);
+
+        let at_48 = |target: u32| saradc_sel_for(48.MHz(), target.kHz());
+        assert_eq!(at_48(48_000), SaradcSel::Div1);
+        assert_eq!(at_48(30_000), SaradcSel::Div2);
+        assert_eq!(at_48(24_000), SaradcSel::Div2);
+        assert_eq!(at_48(20_000), SaradcSel::Div4);
+        assert_eq!(at_48(6_000), SaradcSel::Div8);
+        assert_eq!(at_48(0), SaradcSel::Div8);
+    }
+}
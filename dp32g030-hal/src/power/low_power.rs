@@ -0,0 +1,179 @@
+use crate::pac;
+
+/// Wakeup sources for [LowPower::enter_deep_sleep()] and
+/// [LowPower::enter_stop()].
+///
+/// These are handled by the PMU, and are the only things that can
+/// wake the chip from those modes. [LowPower::enter_sleep()] will wake
+/// on any enabled interrupt, including UART, and ignores these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct WakeSources {
+    /// Wake on a GPIO interrupt.
+    pub gpio: bool,
+    /// Wake on the RTC time signal.
+    pub rtc_time: bool,
+    /// Wake on the RTC alarm.
+    pub rtc_alarm: bool,
+}
+
+impl WakeSources {
+    /// No wakeup sources.
+    pub const fn new() -> Self {
+        Self {
+            gpio: false,
+            rtc_time: false,
+            rtc_alarm: false,
+        }
+    }
+
+    /// Also wake on a GPIO interrupt.
+    pub const fn gpio(self) -> Self {
+        Self { gpio: true, ..self }
+    }
+
+    /// Also wake on the RTC time signal.
+    pub const fn rtc_time(self) -> Self {
+        Self {
+            rtc_time: true,
+            ..self
+        }
+    }
+
+    /// Also wake on the RTC alarm.
+    pub const fn rtc_alarm(self) -> Self {
+        Self {
+            rtc_alarm: true,
+            ..self
+        }
+    }
+}
+
+/// Access to the chip's low-power modes.
+///
+/// All modes stop the CPU clock until a wakeup. They differ in what
+/// else keeps running:
+///
+///  * **Sleep**: only the CPU stops. All clocks and peripherals keep
+///    running, and any enabled interrupt wakes the chip.
+///  * **Deep sleep**: the high-speed clocks (RCHF, XTAH, and the PLL)
+///    stop. The low-speed clocks (RCLF and XTAL) and the RTC keep
+///    running, and only [WakeSources] can wake the chip.
+///  * **Stop**: as deep sleep, but with more of the chip powered
+///    down. Wake-up takes longer.
+///
+/// In every mode, the frozen [Clocks](super::Clocks) are still valid
+/// on resume. Deep sleep and stop wait for the PLL to lock again, if
+/// it is in use, before returning. Peripherals running off the
+/// high-speed clocks will have missed any time spent asleep.
+pub struct LowPower {
+    _private: (),
+}
+
+impl core::fmt::Debug for LowPower {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_tuple("LowPower").finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for LowPower {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "LowPower");
+    }
+}
+
+impl LowPower {
+    /// # Safety:
+    /// This peripheral reads and writes:
+    ///  * `PMU`: `lpow_md`, `lpmd_wken`, `lpmd_wkst`
+    ///  * `SYSCON`: reads `pll_ctrl`, `pll_st`
+    pub(crate) unsafe fn steal() -> Self {
+        Self { _private: () }
+    }
+
+    /// Enter sleep mode, returning after any enabled interrupt fires.
+    pub fn enter_sleep(&mut self, scb: &mut cortex_m::peripheral::SCB) {
+        critical_section::with(|_cs| {
+            // safety: we only access the low power registers, which we own
+            let pmu = unsafe { pac::PMU::steal() };
+
+            scb.clear_sleepdeep();
+            pmu.lpow_md().write(|w| w.sleep().enabled());
+            cortex_m::asm::dsb();
+            cortex_m::asm::wfi();
+        })
+    }
+
+    /// Enter deep sleep mode, returning once one of `wake` fires.
+    ///
+    /// Returns the wakeup sources that fired.
+    pub fn enter_deep_sleep(
+        &mut self,
+        scb: &mut cortex_m::peripheral::SCB,
+        wake: WakeSources,
+    ) -> WakeSources {
+        self.enter_deep(scb, wake, |w| w.deepsleep().enabled())
+    }
+
+    /// Enter stop mode, returning once one of `wake` fires.
+    ///
+    /// Returns the wakeup sources that fired.
+    pub fn enter_stop(
+        &mut self,
+        scb: &mut cortex_m::peripheral::SCB,
+        wake: WakeSources,
+    ) -> WakeSources {
+        self.enter_deep(scb, wake, |w| w.stop().enabled())
+    }
+
+    fn enter_deep(
+        &mut self,
+        scb: &mut cortex_m::peripheral::SCB,
+        wake: WakeSources,
+        mode: impl FnOnce(&mut pac::pmu::lpow_md::W) -> &mut pac::pmu::lpow_md::W,
+    ) -> WakeSources {
+        // this touches the same PMU as the clock config, so like
+        // freeze(), we'd rather not be interrupted
+        critical_section::with(|_cs| {
+            // safety: we only access the low power registers, which we own,
+            // and read the PLL status
+            let pmu = unsafe { pac::PMU::steal() };
+            let syscon = unsafe { pac::SYSCON::steal() };
+
+            // clear old wakeup flags
+            pmu.lpmd_wkst().write(|w| {
+                w.io_wkst().unset();
+                w.rtc_tim_wkst().unset();
+                w.rtc_ala_wkst().unset()
+            });
+
+            pmu.lpmd_wken().write(|w| {
+                w.io_wken().bit(wake.gpio);
+                w.rtc_tim_wken().bit(wake.rtc_time);
+                w.rtc_ala_wken().bit(wake.rtc_alarm)
+            });
+
+            scb.set_sleepdeep();
+            pmu.lpow_md().write(mode);
+            cortex_m::asm::dsb();
+            cortex_m::asm::wfi();
+            scb.clear_sleepdeep();
+
+            // the PLL stops in these modes, wait for it to come back
+            if syscon.pll_ctrl().read().pll_en().is_enabled() {
+                while syscon.pll_st().read().pll_lock().is_unlocked() {
+                    // expected to take 30us
+                    cortex_m::asm::nop();
+                }
+            }
+
+            let woke = pmu.lpmd_wkst().read();
+            WakeSources {
+                gpio: woke.io_wkst().is_set(),
+                rtc_time: woke.rtc_tim_wkst().is_set(),
+                rtc_alarm: woke.rtc_ala_wkst().is_set(),
+            }
+        })
+    }
+}
@@ -13,6 +13,9 @@ pub use clocks::*;
 mod gate;
 pub use gate::*;
 
+mod low_power;
+pub use low_power::*;
+
 /// Create a clock and power configurator from the relevant registers.
 ///
 /// This uses the built-in flash [Code] that will be loaded in RAM
@@ -34,7 +37,7 @@ pub fn new_with_code(
     pmu: pac::PMU,
     flash: pac::FLASH_CTRL,
     flash_code: &Code,
-) -> Config {
+) -> Config<'_> {
     Config::new(syscon, pmu, flash, flash_code)
 }
 
@@ -45,6 +48,7 @@ pub struct Power {
     pub chip_id: ChipId,
     pub clocks: Clocks,
     pub gates: Gates,
+    pub low_power: LowPower,
 }
 
 impl Power {
@@ -57,6 +61,7 @@ impl Power {
             chip_id: ChipId::steal(),
             clocks,
             gates: Gates::steal(),
+            low_power: LowPower::steal(),
         }
     }
 
@@ -1,3 +1,3 @@
 //! A module that re-exports common useful traits.
 
-pub use crate::time::{DurationExtU32, DurationExtU32Ceil, RateExtU32};
+pub use crate::time::{DurationExtU32, DurationExtU32Ceil, HertzExt, RateExtU32};
@@ -1,4 +1,5 @@
 use crate::power::Gate;
+use crate::time::Hertz;
 
 use crate::pac;
 
@@ -12,6 +13,20 @@ where
     Config::new(spi, gate)
 }
 
+/// SPI configuration error.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// Requested baud rate is out of range.
+    OutOfRange,
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "SPI Error {:?}", self)
+    }
+}
+
 /// An SPI configurator.
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -22,6 +37,30 @@ pub struct Config<Spi> {
 /// Choices for baud rate divider.
 pub type ClockDivider = pac::spi0::cr::SPR_A;
 
+/// All clock dividers, fastest first.
+const DIVIDERS: [ClockDivider; 8] = [
+    ClockDivider::Div4,
+    ClockDivider::Div8,
+    ClockDivider::Div16,
+    ClockDivider::Div32,
+    ClockDivider::Div64,
+    ClockDivider::Div128,
+    ClockDivider::Div256,
+    ClockDivider::Div512,
+];
+
+/// The system clock division ratio for a divider.
+fn divider_ratio(div: ClockDivider) -> u32 {
+    4 << div as u32
+}
+
+/// Find the fastest divider giving at most `baud` from `sys_clk`.
+fn divider_for(sys_clk: Hertz, baud: Hertz) -> Option<ClockDivider> {
+    DIVIDERS
+        .into_iter()
+        .find(|div| sys_clk / divider_ratio(*div) <= baud)
+}
+
 /// Choices for clock phase.
 pub type Phase = pac::spi0::cr::CPHA_A;
 
@@ -81,6 +120,12 @@ where
         Self { spi }
     }
 
+    fn sys_clk(&self) -> Hertz {
+        // safety: we own this peripheral, and can access this gate
+        let gate = unsafe { Gate::<Spi>::steal() };
+        gate.clocks().sys_clk()
+    }
+
     /// Recover the SPI register from a configurator.
     pub fn free(self) -> (Spi, Gate<Spi>) {
         // safety: we own this peripheral in self, and are dropping self
@@ -102,6 +147,22 @@ where
         self.spi.cr().read().spr().variant()
     }
 
+    /// Set the clock divider for the fastest baud rate that is no
+    /// faster than `baud`. Returns [Err] if even the slowest divider
+    /// is too fast.
+    ///
+    /// The rate actually used is [Self::get_baud_rate()]. To pick a
+    /// divider by hand, use [Self::divider()].
+    pub fn baud_rate(self, baud: Hertz) -> Result<Self, Error> {
+        let div = divider_for(self.sys_clk(), baud).ok_or(Error::OutOfRange)?;
+        Ok(self.divider(div))
+    }
+
+    /// Get the baud rate.
+    pub fn get_baud_rate(&self) -> Hertz {
+        self.sys_clk() / divider_ratio(self.get_divider())
+    }
+
     /// Set the clock phase.
     pub fn phase(self, phase: Phase) -> Self {
         self.spi.cr().modify(|_r, w| w.cpha().variant(phase));
@@ -202,3 +263,46 @@ where
         MasterTx::new_master_tx_ssn(self.master_mode(), clk, mosi, ssn)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::time::RateExtU32;
+
+    // the divider picked for `baud`, and the rate it actually gives
+    fn pick(sys_clk: Hertz, baud: Hertz) -> Option<(ClockDivider, Hertz)> {
+        let div = divider_for(sys_clk, baud)?;
+        Some((div, sys_clk / divider_ratio(div)))
+    }
+
+    #[test]
+    fn baud_at_24mhz() {
+        let at_24 = |baud: u32| pick(24.MHz(), baud.kHz());
+        // faster than possible gets the fastest divider
+        assert_eq!(at_24(12_000), Some((ClockDivider::Div4, 6.MHz())));
+        // exact rates
+        assert_eq!(at_24(6_000), Some((ClockDivider::Div4, 6.MHz())));
+        assert_eq!(at_24(3_000), Some((ClockDivider::Div8, 3.MHz())));
+        assert_eq!(at_24(750), Some((ClockDivider::Div32, 750.kHz())));
+        // in between rounds down to the next slower divider
+        assert_eq!(at_24(5_000), Some((ClockDivider::Div8, 3.MHz())));
+        assert_eq!(at_24(2_000), Some((ClockDivider::Div16, 1_500.kHz())));
+        assert_eq!(at_24(1_000), Some((ClockDivider::Div32, 750.kHz())));
+        // the slowest divider
+        assert_eq!(at_24(47), Some((ClockDivider::Div512, 46_875.Hz())));
+        assert_eq!(at_24(46), None);
+    }
+
+    #[test]
+    fn baud_at_48mhz() {
+        let at_48 = |baud: u32| pick(48.MHz(), baud.kHz());
+        assert_eq!(at_48(12_000), Some((ClockDivider::Div4, 12.MHz())));
+        // the LCD's rate
+        assert_eq!(at_48(3_000), Some((ClockDivider::Div16, 3.MHz())));
+        assert_eq!(at_48(5_000), Some((ClockDivider::Div16, 3.MHz())));
+        assert_eq!(at_48(1_000), Some((ClockDivider::Div64, 750.kHz())));
+        assert_eq!(at_48(94), Some((ClockDivider::Div512, 93_750.Hz())));
+        assert_eq!(at_48(93), None);
+    }
+}
@@ -13,6 +13,9 @@ where
     type Error = Infallible;
 }
 
+// read, write, and transfer all wait for every byte to come back
+// through the RX FIFO, so they are complete when they return. flush
+// only has work to do after the non-blocking FullDuplex writes.
 impl<Spi, Miso, Mosi> hal1::SpiBus<u8> for Port<Spi, Master, Miso, Mosi, ()>
 where
     Spi: Instance,
@@ -11,3 +11,5 @@ pub use instance::*;
 
 mod port;
 pub use port::*;
+
+mod transfer;
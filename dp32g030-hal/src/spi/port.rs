@@ -2,10 +2,25 @@ use core::convert::Infallible;
 
 use crate::block;
 
+use super::transfer::{self, FifoAccess};
 use super::{Config, Instance};
 
 /// How many bytes there are in an SPI FIFO.
-const FIFO_SIZE: u8 = 8;
+///
+/// Each byte written to the TX FIFO clocks one reply into the RX FIFO,
+/// so no more than this many bytes can be in flight before reading
+/// replies back, or else the RX FIFO overflows and replies are lost.
+pub const FIFO_SIZE: u8 = 8;
+
+/// Decode a FIFO level field from FIFOST. The field only counts up to
+/// 7, so a full FIFO reads as level 0 with the full flag set.
+fn fifo_level(level: u8, full: bool) -> u8 {
+    if level == 0 && full {
+        FIFO_SIZE
+    } else {
+        level
+    }
+}
 
 /// An SPI port, either in master or slave mode.
 #[derive(Debug)]
@@ -17,6 +32,8 @@ pub struct Port<Spi: Instance, Mode, Miso, Mosi, Ssn> {
     miso: Miso,
     mosi: Mosi,
     ssn: Ssn,
+    /// Bytes written to the TX FIFO that have not been read back.
+    in_flight: u8,
 }
 
 /// SPI master mode. (type state)
@@ -52,6 +69,7 @@ where
             miso,
             mosi,
             ssn: (),
+            in_flight: 0,
         }
         .setup()
     }
@@ -76,6 +94,7 @@ where
             miso,
             mosi,
             ssn,
+            in_flight: 0,
         }
         .setup()
     }
@@ -94,6 +113,7 @@ where
             miso,
             mosi: (),
             ssn: (),
+            in_flight: 0,
         }
         .setup()
     }
@@ -118,6 +138,7 @@ where
             miso,
             mosi: (),
             ssn,
+            in_flight: 0,
         }
         .setup()
     }
@@ -136,6 +157,7 @@ where
             miso: (),
             mosi,
             ssn: (),
+            in_flight: 0,
         }
         .setup()
     }
@@ -160,6 +182,7 @@ where
             miso: (),
             mosi,
             ssn,
+            in_flight: 0,
         }
         .setup()
     }
@@ -200,8 +223,12 @@ where
     }
 
     /// Clear the RX FIFO.
+    ///
+    /// Any bytes still being shifted out are forgotten, and their
+    /// replies may show up in the RX FIFO later.
     pub fn clear_rx(&mut self) {
         self.spi.cr().modify(|_r, w| w.rf_clr().clear());
+        self.in_flight = 0;
     }
 
     /// Is the RX FIFO full?
@@ -219,18 +246,24 @@ where
         self.spi.fifost().read().rfe().bit()
     }
 
-    /// Get the RX FIFO level, 0 is empty and 8 is full.
+    /// Get the RX FIFO level, 0 is empty and [FIFO_SIZE] is full.
     pub fn rx_level(&self) -> u8 {
-        match self.spi.fifost().read().rf_level().bits() {
-            0 => {
-                if self.is_rx_full() {
-                    8
-                } else {
-                    0
-                }
-            }
-            l => l,
-        }
+        let fifost = self.spi.fifost().read();
+        fifo_level(fifost.rf_level().bits(), fifost.rff().bit())
+    }
+
+    /// Has the RX FIFO overflowed?
+    ///
+    /// This happens when a reply arrives with [FIFO_SIZE] bytes
+    /// already waiting in the RX FIFO, and the reply is lost. It stays
+    /// set until [Self::clear_errors()].
+    pub fn is_rx_overflow(&self) -> bool {
+        self.spi.if_().read().rxfifo_ovf().is_set()
+    }
+
+    /// Clear the RX FIFO overflow flag.
+    pub fn clear_errors(&mut self) {
+        self.spi.if_().modify(|_r, w| w.rxfifo_ovf().set_());
     }
 
     /// Clear the TX FIFO.
@@ -253,18 +286,10 @@ where
         self.spi.fifost().read().tfe().bit()
     }
 
-    /// Get the TX FIFO level, 0 is empty and 8 is full.
+    /// Get the TX FIFO level, 0 is empty and [FIFO_SIZE] is full.
     pub fn tx_level(&self) -> u8 {
-        match self.spi.fifost().read().tf_level().bits() {
-            0 => {
-                if self.is_tx_full() {
-                    8
-                } else {
-                    0
-                }
-            }
-            l => l,
-        }
+        let fifost = self.spi.fifost().read();
+        fifo_level(fifost.tf_level().bits(), fifost.tff().bit())
     }
 }
 
@@ -279,6 +304,7 @@ where
         if self.is_rx_empty() {
             Err(block::Error::WouldBlock)
         } else {
+            self.in_flight = self.in_flight.saturating_sub(1);
             Ok(self.spi.rdr().read().data().bits())
         }
     }
@@ -289,13 +315,20 @@ where
             Err(block::Error::WouldBlock)
         } else {
             self.spi.wdr().write(|w| w.data().set(value));
+            self.in_flight = self.in_flight.saturating_add(1);
             Ok(())
         }
     }
 
     /// Flush all pending writes and clear the FIFOs.
+    ///
+    /// An empty TX FIFO does not mean the last byte has left the
+    /// shift register, and there is no busy flag. Instead, this waits
+    /// until every byte written has clocked a reply into the RX FIFO
+    /// (or the RX FIFO is full), which only happens once the byte is
+    /// completely sent.
     pub fn flush(&mut self) -> block::Result<(), Infallible> {
-        if !self.is_tx_empty() {
+        if !self.is_tx_empty() || self.rx_level() < self.in_flight.min(FIFO_SIZE) {
             Err(block::Error::WouldBlock)
         } else {
             self.clear_rx();
@@ -310,108 +343,22 @@ where
     /// after the end of write.
     pub fn transfer_iter<'a>(
         &'a mut self,
-        mut read: impl Iterator<Item = &'a mut u8>
+        read: impl Iterator<Item = &'a mut u8>
             + core::iter::ExactSizeIterator
             + core::iter::FusedIterator,
         write: impl Iterator<Item = u8> + core::iter::FusedIterator,
     ) -> Result<(), Infallible> {
-        let mut write = write.peekable();
         block::block!(self.flush())?;
-
-        // handle *all* of read first
-        let mut remaining_tx = read.len();
-        let mut amount = 0;
-        while amount > 0 || remaining_tx > 0 {
-            // push some bytes into the tx fifo if we can
-            while amount < FIFO_SIZE && remaining_tx > 0 {
-                match self.write_one(*write.peek().unwrap_or(&0x00)) {
-                    Ok(()) => {
-                        remaining_tx -= 1;
-                        amount += 1;
-                        write.next();
-                    }
-                    Err(block::Error::WouldBlock) => break,
-                    Err(block::Error::Other(e)) => Err(e)?,
-                }
-            }
-
-            // read some bytes from the rx fifo if we can
-            while amount > 0 {
-                match self.read_one() {
-                    Ok(val) => {
-                        if let Some(dest) = read.next() {
-                            *dest = val
-                        }
-                        amount -= 1;
-                    }
-                    Err(block::Error::WouldBlock) => break,
-                    Err(block::Error::Other(e)) => Err(e)?,
-                }
-            }
-        }
-
-        // there may still be some bytes left in write
-        let mut extra = true;
-        while extra || amount > 0 {
-            // push some bytes into the tx fifo if we can
-            while extra && amount < FIFO_SIZE {
-                let Some(val) = write.peek() else {
-                    extra = false;
-                    break;
-                };
-                match self.write_one(*val) {
-                    Ok(()) => {
-                        amount += 1;
-                        write.next();
-                    }
-                    Err(block::Error::WouldBlock) => break,
-                    Err(block::Error::Other(e)) => Err(e)?,
-                }
-            }
-
-            // read some bytes from the rx fifo if we can
-            while amount > 0 {
-                match self.read_one() {
-                    Ok(_) => amount -= 1,
-                    Err(block::Error::WouldBlock) => break,
-                    Err(block::Error::Other(e)) => Err(e)?,
-                }
-            }
-        }
-
-        Ok(())
+        transfer::transfer_iter(self, read, write)
     }
 
     /// Write and read to SPI simultaneously, overwriting the buffer.
+    ///
+    /// Each byte sent is replaced by the byte received while sending
+    /// it, in order.
     pub fn transfer_in_place(&mut self, buffer: &mut [u8]) -> Result<(), Infallible> {
         block::block!(self.flush())?;
-
-        let mut tx = 0;
-        let mut rx = 0;
-        while rx < buffer.len() {
-            // push some bytes into the tx fifo if we can
-            while ((tx - rx) as u8) < FIFO_SIZE && tx < buffer.len() {
-                match self.write_one(buffer[tx]) {
-                    Ok(()) => tx += 1,
-                    Err(block::Error::WouldBlock) => break,
-                    Err(block::Error::Other(e)) => Err(e)?,
-                }
-            }
-
-            // read some bytes from the rx fifo if we can
-            while rx < tx {
-                match self.read_one() {
-                    Ok(val) => {
-                        buffer[rx] = val;
-                        rx += 1;
-                    }
-                    Err(block::Error::WouldBlock) => break,
-                    Err(block::Error::Other(e)) => Err(e)?,
-                }
-            }
-        }
-
-        Ok(())
+        transfer::transfer_in_place(self, buffer)
     }
 
     /// Read a buffer from SPI, sending 0x00.
@@ -434,6 +381,19 @@ where
     }
 }
 
+impl<Spi, Miso, Mosi, Ssn> FifoAccess for Port<Spi, Master, Miso, Mosi, Ssn>
+where
+    Spi: Instance,
+{
+    fn write_one(&mut self, value: u8) -> block::Result<(), Infallible> {
+        Port::write_one(self, value)
+    }
+
+    fn read_one(&mut self) -> block::Result<u8, Infallible> {
+        Port::read_one(self)
+    }
+}
+
 impl<Spi, Miso, Mosi> Port<Spi, Master, Miso, Mosi, Spi::Ssn>
 where
     Spi: Instance,
@@ -448,3 +408,17 @@ where
         self.spi.cr().modify(|_r, w| w.msr_ssn().high());
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fifo_levels() {
+        assert_eq!(fifo_level(0, false), 0);
+        assert_eq!(fifo_level(0, true), FIFO_SIZE);
+        for level in 1..FIFO_SIZE {
+            assert_eq!(fifo_level(level, false), level);
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//! Full-duplex transfers through the SPI FIFOs.
+//!
+//! Every byte written to the TX FIFO clocks one byte into the RX FIFO,
+//! so transfers keep the TX FIFO topped up while draining the RX FIFO,
+//! with never more than [FIFO_SIZE] bytes in flight. Any more, and the
+//! RX FIFO overflows and replies are lost.
+//!
+//! Replies come back in the order the bytes were sent, so the `n`th
+//! byte read is always the reply to the `n`th byte written.
+
+use core::convert::Infallible;
+
+use crate::block;
+
+use super::FIFO_SIZE;
+
+/// Internal access to the SPI FIFOs, for the transfers here.
+pub(super) trait FifoAccess {
+    /// Write a byte to the TX FIFO, or block if it is full.
+    fn write_one(&mut self, value: u8) -> block::Result<(), Infallible>;
+
+    /// Read a byte from the RX FIFO, or block if it is empty.
+    fn read_one(&mut self) -> block::Result<u8, Infallible>;
+}
+
+/// Write and read simultaneously.
+///
+/// If read is shorter than write, discard all incoming bytes after
+/// that point. If write is shorter than read, write 0x00 after the end
+/// of write.
+pub(super) fn transfer_iter<'a, A>(
+    fifo: &mut A,
+    mut read: impl core::iter::ExactSizeIterator<Item = &'a mut u8> + core::iter::FusedIterator,
+    write: impl core::iter::FusedIterator<Item = u8>,
+) -> Result<(), Infallible>
+where
+    A: FifoAccess,
+{
+    let mut write = write.peekable();
+
+    // handle *all* of read first
+    let mut remaining_tx = read.len();
+    let mut amount = 0;
+    while amount > 0 || remaining_tx > 0 {
+        // push some bytes into the tx fifo if we can
+        while amount < FIFO_SIZE && remaining_tx > 0 {
+            match fifo.write_one(*write.peek().unwrap_or(&0x00)) {
+                Ok(()) => {
+                    remaining_tx -= 1;
+                    amount += 1;
+                    write.next();
+                }
+                Err(block::Error::WouldBlock) => break,
+                Err(block::Error::Other(e)) => Err(e)?,
+            }
+        }
+
+        // read some bytes from the rx fifo if we can
+        while amount > 0 {
+            match fifo.read_one() {
+                Ok(val) => {
+                    if let Some(dest) = read.next() {
+                        *dest = val
+                    }
+                    amount -= 1;
+                }
+                Err(block::Error::WouldBlock) => break,
+                Err(block::Error::Other(e)) => Err(e)?,
+            }
+        }
+    }
+
+    // there may still be some bytes left in write
+    let mut extra = true;
+    while extra || amount > 0 {
+        // push some bytes into the tx fifo if we can
+        while extra && amount < FIFO_SIZE {
+            let Some(val) = write.peek() else {
+                extra = false;
+                break;
+            };
+            match fifo.write_one(*val) {
+                Ok(()) => {
+                    amount += 1;
+                    write.next();
+                }
+                Err(block::Error::WouldBlock) => break,
+                Err(block::Error::Other(e)) => Err(e)?,
+            }
+        }
+
+        // read some bytes from the rx fifo if we can
+        while amount > 0 {
+            match fifo.read_one() {
+                Ok(_) => amount -= 1,
+                Err(block::Error::WouldBlock) => break,
+                Err(block::Error::Other(e)) => Err(e)?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Write and read simultaneously, overwriting the buffer.
+pub(super) fn transfer_in_place<A>(fifo: &mut A, buffer: &mut [u8]) -> Result<(), Infallible>
+where
+    A: FifoAccess,
+{
+    let mut tx = 0;
+    let mut rx = 0;
+    while rx < buffer.len() {
+        // push some bytes into the tx fifo if we can
+        while ((tx - rx) as u8) < FIFO_SIZE && tx < buffer.len() {
+            match fifo.write_one(buffer[tx]) {
+                Ok(()) => tx += 1,
+                Err(block::Error::WouldBlock) => break,
+                Err(block::Error::Other(e)) => Err(e)?,
+            }
+        }
+
+        // read some bytes from the rx fifo if we can
+        while rx < tx {
+            match fifo.read_one() {
+                Ok(val) => {
+                    buffer[rx] = val;
+                    rx += 1;
+                }
+                Err(block::Error::WouldBlock) => break,
+                Err(block::Error::Other(e)) => Err(e)?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    extern crate std;
+    use std::collections::VecDeque;
+    use std::vec::Vec;
+
+    // a loopback, with MISO fed from MOSI through a shift register
+    //
+    // the shift register runs at its own pace, independent of the
+    // fifos: one byte moves from tx to rx every `period` accesses,
+    // so the transfer sees full and empty fifos like it would on
+    // the chip, with a bus that is slower than the cpu.
+    struct Loopback {
+        period: usize,
+        ticks: usize,
+        tx: VecDeque<u8>,
+        rx: VecDeque<u8>,
+        overflow: bool,
+        sent: Vec<u8>,
+    }
+
+    impl Loopback {
+        fn new(period: usize) -> Self {
+            Self {
+                period,
+                ticks: 0,
+                tx: VecDeque::new(),
+                rx: VecDeque::new(),
+                overflow: false,
+                sent: Vec::new(),
+            }
+        }
+
+        // time passes each time the fifos are touched
+        fn tick(&mut self) {
+            self.ticks += 1;
+            if self.ticks % self.period != 0 {
+                return;
+            }
+            let Some(val) = self.tx.pop_front() else {
+                return;
+            };
+            self.sent.push(val);
+            if self.rx.len() >= FIFO_SIZE as usize {
+                self.overflow = true;
+            } else {
+                // make replies distinct from what was sent
+                self.rx.push_back(!val);
+            }
+        }
+
+        fn done(&self) -> bool {
+            !self.overflow && self.tx.is_empty() && self.rx.is_empty()
+        }
+    }
+
+    impl FifoAccess for Loopback {
+        fn write_one(&mut self, value: u8) -> block::Result<(), Infallible> {
+            self.tick();
+            if self.tx.len() >= FIFO_SIZE as usize {
+                Err(block::Error::WouldBlock)
+            } else {
+                self.tx.push_back(value);
+                Ok(())
+            }
+        }
+
+        fn read_one(&mut self) -> block::Result<u8, Infallible> {
+            self.tick();
+            self.rx.pop_front().ok_or(block::Error::WouldBlock)
+        }
+    }
+
+    fn bytes(len: u8) -> Vec<u8> {
+        (1..=len).collect()
+    }
+
+    fn inverted(len: u8) -> Vec<u8> {
+        (1..=len).map(|b| !b).collect()
+    }
+
+    // fast and slow buses, relative to the fifo accesses
+    const PERIODS: [usize; 4] = [1, 2, 3, 17];
+
+    #[test]
+    fn loopback_overflows() {
+        // check the mock: filling tx without draining rx loses replies
+        let mut spi = Loopback::new(1);
+        for b in bytes(3 * FIFO_SIZE) {
+            let _ = spi.write_one(b);
+        }
+        for _ in 0..(3 * FIFO_SIZE) {
+            spi.tick();
+        }
+        assert!(spi.overflow);
+    }
+
+    #[test]
+    fn in_place() {
+        for period in PERIODS {
+            let mut spi = Loopback::new(period);
+            let mut buffer = bytes(20);
+            transfer_in_place(&mut spi, &mut buffer).unwrap();
+
+            assert_eq!(spi.sent, bytes(20));
+            assert_eq!(buffer, inverted(20));
+            assert!(spi.done());
+        }
+    }
+
+    #[test]
+    fn full_duplex() {
+        for period in PERIODS {
+            // same length
+            let mut spi = Loopback::new(period);
+            let mut read = [0; 20];
+            transfer_iter(&mut spi, read.iter_mut(), bytes(20).into_iter()).unwrap();
+            assert_eq!(spi.sent, bytes(20));
+            assert_eq!(read[..], inverted(20));
+            assert!(spi.done());
+
+            // write runs out, and 0x00 is sent
+            let mut spi = Loopback::new(period);
+            let mut read = [0; 20];
+            transfer_iter(&mut spi, read.iter_mut(), bytes(12).into_iter()).unwrap();
+            assert_eq!(spi.sent[..12], bytes(12));
+            assert_eq!(spi.sent[12..], [0x00; 8]);
+            assert_eq!(read[..12], inverted(12));
+            assert_eq!(read[12..], [0xff; 8]);
+            assert!(spi.done());
+
+            // read runs out, and replies are discarded
+            let mut spi = Loopback::new(period);
+            let mut read = [0; 12];
+            transfer_iter(&mut spi, read.iter_mut(), bytes(20).into_iter()).unwrap();
+            assert_eq!(spi.sent, bytes(20));
+            assert_eq!(read[..], inverted(12));
+            assert!(spi.done());
+        }
+    }
+}
@@ -1,6 +1,20 @@
 //! Generic types and units for working with time.
 //!
-//! This is a re-export of parts of the [fugit] crate.
+//! This is a re-export of parts of the [fugit] crate, plus
+//! [HertzExt] for overflow-checked frequency math.
+//!
+//! [period_ticks()] and [hz_from_period()] convert between
+//! frequencies and periods in const context, so rates known at
+//! compile time can be used in const generics. The [Hertz]
+//! constructors and [Hertz::to_Hz()] are also const:
+//!
+//! ```ignore
+//! // ticks of a 1MHz timer in a 1kHz period
+//! const TICKS: u32 = period_ticks(Hertz::MHz(1).to_Hz(), 1_000);
+//! assert_eq!(TICKS, 1_000);
+//! ```
+
+use fugit::Duration;
 
 pub use fugit::HertzU32 as Hertz;
 pub use fugit::HoursDurationU32 as HoursDuration;
@@ -16,3 +30,162 @@ pub use fugit::TimerInstantU32 as TimerInstant;
 pub use fugit::ExtU32 as DurationExtU32;
 pub use fugit::ExtU32Ceil as DurationExtU32Ceil;
 pub use fugit::RateExtU32;
+
+/// Checked arithmetic and conversions for [Hertz].
+///
+/// The operators on [Hertz] wrap or panic on overflow. These return
+/// [None] instead, so it can be turned into an error.
+pub trait HertzExt: Sized {
+    /// Multiply by an integer, or [None] on overflow.
+    fn checked_mul(self, rhs: u32) -> Option<Self>;
+
+    /// Divide by an integer, or [None] if `rhs` is zero.
+    fn checked_div(self, rhs: u32) -> Option<Self>;
+
+    /// Create from a wide frequency in Hz, or [None] if it does not
+    /// fit in [Hertz].
+    fn try_from_hz(hz: u64) -> Option<Self>;
+
+    /// The period of this frequency, or [None] if it is zero or the
+    /// period does not fit in the duration.
+    fn to_duration<const NOM: u32, const DENOM: u32>(self) -> Option<Duration<u32, NOM, DENOM>>;
+
+    /// The frequency with the given period, or [None] if the period is
+    /// zero or the frequency does not fit in [Hertz].
+    fn from_period<const NOM: u32, const DENOM: u32>(
+        period: Duration<u32, NOM, DENOM>,
+    ) -> Option<Self>;
+}
+
+impl HertzExt for Hertz {
+    fn checked_mul(self, rhs: u32) -> Option<Self> {
+        Some(Self::from_raw(self.raw().checked_mul(rhs)?))
+    }
+
+    fn checked_div(self, rhs: u32) -> Option<Self> {
+        Some(Self::from_raw(self.raw().checked_div(rhs)?))
+    }
+
+    fn try_from_hz(hz: u64) -> Option<Self> {
+        Some(Self::from_raw(hz.try_into().ok()?))
+    }
+
+    fn to_duration<const NOM: u32, const DENOM: u32>(self) -> Option<Duration<u32, NOM, DENOM>> {
+        self.try_into_duration()
+    }
+
+    fn from_period<const NOM: u32, const DENOM: u32>(
+        period: Duration<u32, NOM, DENOM>,
+    ) -> Option<Self> {
+        Self::try_from_duration(period)
+    }
+}
+
+/// The number of ticks at `freq_hz` in one period of `target_hz`,
+/// rounded to the nearest tick.
+///
+/// Panics if `target_hz` is zero, which is a compile error in const
+/// context.
+pub const fn period_ticks(freq_hz: u32, target_hz: u32) -> u32 {
+    assert!(target_hz > 0, "target frequency must not be zero");
+    // freq_hz / target_hz always fits, but the rounding may not
+    ((freq_hz as u64 + target_hz as u64 / 2) / target_hz as u64) as u32
+}
+
+/// The frequency of a period `ticks` long at `freq_hz`, rounded down.
+///
+/// This is the frequency a timer at `freq_hz` actually runs at when
+/// divided by `ticks`. Panics if `ticks` is zero, which is a compile
+/// error in const context.
+pub const fn hz_from_period(freq_hz: u32, ticks: u32) -> u32 {
+    assert!(ticks > 0, "period must not be zero");
+    freq_hz / ticks
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // carries a value computed in a const generic position
+    struct Ticks<const N: u32>;
+
+    impl<const N: u32> Ticks<N> {
+        const VALUE: u32 = N;
+    }
+
+    #[test]
+    fn period_ticks_const() {
+        assert_eq!(
+            Ticks::<{ period_ticks(Hertz::MHz(1).to_Hz(), 1_000) }>::VALUE,
+            1_000
+        );
+        assert_eq!(Ticks::<{ period_ticks(48_000_000, 30) }>::VALUE, 1_600_000);
+        // rounded to nearest
+        assert_eq!(Ticks::<{ period_ticks(1_000, 3) }>::VALUE, 333);
+        assert_eq!(Ticks::<{ period_ticks(2_000, 3) }>::VALUE, 667);
+        // no overflow at the top of the range
+        assert_eq!(Ticks::<{ period_ticks(u32::MAX, 1) }>::VALUE, u32::MAX);
+    }
+
+    #[test]
+    fn hz_from_period_const() {
+        assert_eq!(
+            Ticks::<{ hz_from_period(Hertz::MHz(48).to_Hz(), 48) }>::VALUE,
+            1_000_000
+        );
+        assert_eq!(Ticks::<{ hz_from_period(1_000, 3) }>::VALUE, 333);
+
+        // round trip through fugit
+        const HZ: u32 = hz_from_period(24_000_000, period_ticks(24_000_000, 200_000));
+        assert_eq!(Hertz::from_raw(HZ), Hertz::kHz(200));
+    }
+
+    #[test]
+    fn checked_pll_range() {
+        // PLL N multiplies by 2 to 64, M divides by 1 to 16
+        for input in [Hertz::MHz(24), Hertz::MHz(32), Hertz::MHz(48)] {
+            for n in (2..=64).step_by(2) {
+                let vco = input.checked_mul(n).unwrap();
+                let wide = input.raw() as u64 * n as u64;
+                assert_eq!(vco.raw() as u64, wide);
+                assert_eq!(Hertz::try_from_hz(wide), Some(vco));
+                for m in 1..=16 {
+                    assert_eq!(vco.checked_div(m), Some(vco / m));
+                }
+            }
+        }
+
+        // one more doubling past the top of the range overflows u32
+        let top = Hertz::MHz(48).checked_mul(64).unwrap();
+        assert_eq!(top, Hertz::MHz(3_072));
+        assert_eq!(top.checked_mul(2), None);
+        assert_eq!(Hertz::try_from_hz(top.raw() as u64 * 2), None);
+        assert_eq!(Hertz::MHz(80).checked_mul(64), None);
+        assert_eq!(
+            Hertz::try_from_hz(u32::MAX as u64),
+            Some(Hertz::from_raw(u32::MAX))
+        );
+        assert_eq!(Hertz::try_from_hz(u32::MAX as u64 + 1), None);
+
+        assert_eq!(Hertz::MHz(48).checked_div(0), None);
+    }
+
+    #[test]
+    fn periods() {
+        assert_eq!(
+            Hertz::kHz(1).to_duration(),
+            Some(MicrosDuration::micros(1_000))
+        );
+        assert_eq!(
+            Hertz::MHz(1).to_duration(),
+            Some(NanosDuration::nanos(1_000))
+        );
+        assert_eq!(Hertz::from_raw(0).to_duration::<1, 1_000_000>(), None);
+        // a 2ms period is 500Hz
+        assert_eq!(
+            Hertz::from_period(MillisDuration::millis(2)),
+            Some(Hertz::Hz(500))
+        );
+        assert_eq!(Hertz::from_period(MillisDuration::millis(0)), None);
+    }
+}
@@ -1,5 +1,5 @@
-use crate::power::Gate;
-use crate::time::Hertz;
+use crate::power::{Clocks, Gate};
+use crate::time::{hz_from_period, period_ticks, Hertz};
 
 use super::{static_assert_timer_hz_not_zero, BaseInstance, Error, High, Low, Timer};
 
@@ -81,22 +81,40 @@ where
     ///
     /// If the frequency is too or high to be matched, returns [Err].
     pub fn frequency<const C_HZ: u32>(self) -> Result<Config<T, C_HZ>, Error> {
-        let div = self
-            .sys_clk()
-            .to_Hz()
-            .checked_add(C_HZ / 2)
-            .ok_or(Error::OutOfRange)?
-            .checked_div(C_HZ)
-            .ok_or(Error::OutOfRange)?
-            .checked_sub(1)
-            .ok_or(Error::OutOfRange)?
-            .try_into()
-            .map_err(|_| Error::OutOfRange)?;
+        let (div, _) = divider_for(self.sys_clk(), Hertz::Hz(C_HZ))?;
 
         // safety: we have calculated the divider correctly above
         unsafe { Ok(self.divider(div)) }
     }
 
+    /// Use `sys_clk` from `clocks` as input, and set the divider to
+    /// most closely match a frequency only known at run-time.
+    ///
+    /// Returns the configured timer along with the actual frequency
+    /// it runs at after the divider is rounded, or [Err] if the target
+    /// is too low or high to be matched.
+    ///
+    /// # Safety
+    ///
+    /// Like [Self::divider()], the const frequency `C_HZ` is not
+    /// checked against the real one. Native precision in
+    /// [super::TimingMode] trusts it, so only use the result with
+    /// forced precision or [super::DynamicDelay], or set `C_HZ` to the
+    /// returned frequency.
+    ///
+    /// Use [Self::frequency()] instead when the frequency is known at
+    /// compile time.
+    pub unsafe fn try_frequency<const C_HZ: u32>(
+        self,
+        target: Hertz,
+        clocks: &Clocks,
+    ) -> Result<(Config<T, C_HZ>, Hertz), Error> {
+        let (div, actual) = divider_for(clocks.sys_clk(), target)?;
+
+        // safety: the caller vouches for C_HZ
+        Ok((self.divider(div), actual))
+    }
+
     /// Get the configured timer input frequency.
     ///
     /// This may differ from the statically known frequency, as this
@@ -151,3 +169,71 @@ where
         }
     }
 }
+
+// find the divider that most closely matches target, and the actual
+// frequency it results in
+fn divider_for(sys_clk: Hertz, target: Hertz) -> Result<(u16, Hertz), Error> {
+    let sys_clk = sys_clk.to_Hz();
+    let target = target.to_Hz();
+    if target == 0 {
+        return Err(Error::OutOfRange);
+    }
+
+    let div: u16 = period_ticks(sys_clk, target)
+        .checked_sub(1)
+        .ok_or(Error::OutOfRange)?
+        .try_into()
+        .map_err(|_| Error::OutOfRange)?;
+
+    Ok((div, Hertz::Hz(hz_from_period(sys_clk, div as u32 + 1))))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn actual(sys_clk: u32, target: u32) -> Result<u32, Error> {
+        divider_for(Hertz::Hz(sys_clk), Hertz::Hz(target)).map(|(_, hz)| hz.to_Hz())
+    }
+
+    #[test]
+    fn exact() {
+        for sys_clk in [24_000_000, 48_000_000] {
+            for target in [1_000, 200_000, 1_000_000, sys_clk] {
+                assert_eq!(actual(sys_clk, target), Ok(target));
+            }
+        }
+        assert_eq!(
+            divider_for(Hertz::MHz(48), Hertz::kHz(1)),
+            Ok((47_999, Hertz::kHz(1)))
+        );
+    }
+
+    #[test]
+    fn rounded() {
+        // 6857.14 rounds down to 6857
+        assert_eq!(actual(48_000_000, 7_000), Ok(7_000));
+        // 3428.57 rounds up to 3429
+        assert_eq!(actual(24_000_000, 7_000), Ok(6_999));
+        // 1.6 rounds up to 2
+        assert_eq!(actual(48_000_000, 30_000_000), Ok(24_000_000));
+        // 2.4 rounds down to 2
+        assert_eq!(actual(24_000_000, 10_000_000), Ok(12_000_000));
+        // 1.33 rounds down to 1
+        assert_eq!(actual(24_000_000, 18_000_000), Ok(24_000_000));
+    }
+
+    #[test]
+    fn out_of_range() {
+        // too slow, divider over 65536
+        assert_eq!(actual(24_000_000, 300), Err(Error::OutOfRange));
+        assert_eq!(actual(48_000_000, 700), Err(Error::OutOfRange));
+        assert_eq!(actual(48_000_000, 0), Err(Error::OutOfRange));
+
+        // slowest possible
+        assert_eq!(actual(48_000_000, 733), Ok(733));
+
+        // too fast, divider under 1
+        assert_eq!(actual(24_000_000, 48_000_001), Err(Error::OutOfRange));
+    }
+}
@@ -0,0 +1,157 @@
+use embedded_hal_1::delay as hal1;
+
+use crate::power::Clocks;
+use crate::time::{DurationExtU32, TimerDuration};
+
+use super::{Error, TimingInstance, TimingMode, TimingModeNs};
+
+/// A blocking delay that calculates ticks from the current system clock.
+///
+/// [TimingMode] with native precision bakes the timer frequency in at
+/// compile time, and forced precision captures it once when the timer
+/// is configured. This instead keeps a copy of the system clock that
+/// can be updated with [Self::recalibrate()] whenever it changes.
+///
+/// This always uses nanosecond precision, so it pays for a
+/// multiply and divide on every delay.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DynamicDelay<Timer> {
+    timing: TimingModeNs<Timer>,
+}
+
+impl<Timer> DynamicDelay<Timer>
+where
+    Timer: TimingInstance<1_000_000_000, true>,
+{
+    /// Create a delay from a timer, using the system clock in `clocks`.
+    pub fn new(timer: Timer, clocks: &Clocks) -> Self {
+        let mut timing = TimingMode::new(timer);
+        timing.recalibrate(clocks);
+        Self { timing }
+    }
+
+    /// Free the delay and return the underlying timer.
+    pub fn free(self) -> Timer {
+        self.timing.free()
+    }
+
+    /// Update the system clock used to calculate ticks.
+    pub fn recalibrate(&mut self, clocks: &Clocks) {
+        self.timing.recalibrate(clocks)
+    }
+
+    /// Blocking wait for a duration.
+    pub fn delay(&mut self, duration: TimerDuration<1_000_000_000>) -> Result<(), Error> {
+        self.timing.delay(duration)
+    }
+}
+
+impl<Timer> hal1::DelayNs for DynamicDelay<Timer>
+where
+    Timer: TimingInstance<1_000_000_000, true>,
+{
+    fn delay_ns(&mut self, ns: u32) {
+        self.delay(ns.nanos()).unwrap()
+    }
+
+    fn delay_us(&mut self, mut us: u32) {
+        // nanosecond durations top out around 4 seconds
+        while us > 1_000_000 {
+            self.delay(1.secs()).unwrap();
+            us -= 1_000_000;
+        }
+        self.delay((us * 1_000).nanos()).unwrap()
+    }
+
+    fn delay_ms(&mut self, mut ms: u32) {
+        while ms > 1_000 {
+            self.delay(1.secs()).unwrap();
+            ms -= 1_000;
+        }
+        self.delay((ms * 1_000_000).nanos()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+    use std::cell::RefCell;
+    use std::vec::Vec;
+
+    use hal1::DelayNs;
+
+    use super::super::timing::{clocks_for, max_for, TimingInstanceSealed};
+    use super::*;
+    use crate::block;
+    use crate::time::{Hertz, TimerInstant};
+
+    // a 16-bit timer running at the system clock, recording how many
+    // input clock cycles each count is started with
+    struct MockTimer<'a> {
+        input_clk: Hertz,
+        starts: &'a RefCell<Vec<u32>>,
+    }
+
+    impl TimingInstance<1_000_000_000, true> for MockTimer<'_> {}
+
+    impl TimingInstanceSealed<1_000_000_000, true> for MockTimer<'_> {
+        fn now(&mut self) -> TimerInstant<1_000_000_000> {
+            TimerInstant::from_ticks(0)
+        }
+
+        fn start(&mut self, duration: TimerDuration<1_000_000_000>) -> Result<(), Error> {
+            let clocks = clocks_for(duration, self.input_clk)?;
+            if clocks > u16::MAX as u32 + 1 {
+                return Err(Error::OutOfRange);
+            }
+            self.starts.borrow_mut().push(clocks);
+            Ok(())
+        }
+
+        fn max(&self) -> Result<TimerDuration<1_000_000_000>, Error> {
+            max_for(u16::MAX as u32 + 1, self.input_clk)
+        }
+
+        fn cancel(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn wait(&mut self) -> block::Result<(), Error> {
+            Ok(())
+        }
+
+        fn recalibrate(&mut self, clocks: &Clocks) {
+            self.input_clk = clocks.sys_clk();
+        }
+    }
+
+    fn delays(delay: &mut DynamicDelay<MockTimer>, starts: &RefCell<Vec<u32>>) -> Vec<u32> {
+        delay.delay_ns(1_000);
+        delay.delay_us(10);
+        delay.delay_us(1_000);
+        delay.delay_ms(2);
+        starts.take()
+    }
+
+    #[test]
+    fn ticks_follow_clock() {
+        let starts = RefCell::new(Vec::new());
+        let timer = MockTimer {
+            input_clk: Hertz::Hz(0),
+            starts: &starts,
+        };
+        let mut delay = DynamicDelay::new(timer, &Clocks::with_sys_clk(Hertz::MHz(24)));
+        assert_eq!(delays(&mut delay, &starts), [24, 240, 24_000, 48_000]);
+
+        // twice the clock, twice the ticks, and 2ms no longer fits
+        delay.recalibrate(&Clocks::with_sys_clk(Hertz::MHz(48)));
+        assert_eq!(
+            delays(&mut delay, &starts),
+            [48, 480, 48_000, 65_536, 30_465]
+        );
+
+        delay.recalibrate(&Clocks::with_sys_clk(Hertz::MHz(24)));
+        assert_eq!(delays(&mut delay, &starts), [24, 240, 24_000, 48_000]);
+    }
+}
@@ -8,6 +8,9 @@ use crate::time::Hertz;
 mod config;
 pub use config::*;
 
+mod dynamic;
+pub use dynamic::*;
+
 mod fugit;
 mod hal02;
 mod hal1;
@@ -15,6 +18,9 @@ mod hal1;
 mod peripherals;
 pub use peripherals::*;
 
+mod scheduler;
+pub use scheduler::*;
+
 mod timing;
 pub use timing::*;
 
@@ -136,6 +142,11 @@ macro_rules! timing_methods {
         pub fn timing_ms(self) -> TimingModeMs<Self> {
             self.timing_hz()
         }
+
+        /// Use this timer as a [DynamicDelay], following `clocks`.
+        pub fn dynamic_delay(self, clocks: &Clocks) -> DynamicDelay<Self> {
+            DynamicDelay::new(self, clocks)
+        }
     };
 
     (native) => {
@@ -0,0 +1,225 @@
+use crate::block;
+use crate::time::TimerDuration;
+
+use super::{Error, TimingInstance, TimingMode};
+
+/// A handle to a task registered with a [Scheduler].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TaskId(usize);
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Task {
+    name: &'static str,
+    // both in ticks
+    period: u32,
+    remaining: u32,
+}
+
+/// Runs up to `N` periodic tasks from a single timer tick.
+///
+/// A [TimingMode] timer waits out one period at a time: after
+/// [TimingMode::start_frequency()], [TimingMode::wait()] succeeds
+/// once per period. Firmware with several periodic jobs would need
+/// one timer for each. Instead, a [Scheduler] counts ticks of one
+/// timer, and [Self::poll()] reports every task that is due.
+///
+/// Tasks run at a fixed rate. A late poll does not push back the
+/// following due times, and if a task misses several periods at
+/// once, it is only reported once.
+///
+/// ```ignore
+/// let mut tick = timer.timing();
+/// let mut sched = Scheduler::<2, 1_000>::new(1.millis());
+/// let rssi = sched.add("rssi", 500.millis())?;
+/// let display = sched.add("display", 33.millis())?;
+/// tick.start_frequency(1.kHz())?;
+///
+/// loop {
+///     let due = sched.poll(&mut tick)?;
+///     if due.contains(rssi) {
+///         // ...
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Scheduler<const N: usize, const HZ: u32> {
+    tick: TimerDuration<HZ>,
+    tasks: [Option<Task>; N],
+}
+
+/// The set of tasks due, returned by [Scheduler::poll()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Due<const N: usize>([bool; N]);
+
+impl<const N: usize> Due<N> {
+    /// No tasks are due.
+    pub const fn none() -> Self {
+        Self([false; N])
+    }
+
+    /// Is this task due?
+    pub fn contains(&self, task: TaskId) -> bool {
+        self.0.get(task.0).copied().unwrap_or(false)
+    }
+
+    /// Are no tasks due?
+    pub fn is_empty(&self) -> bool {
+        !self.0.iter().any(|d| *d)
+    }
+
+    /// Iterate over the tasks due, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = TaskId> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| **d)
+            .map(|(i, _)| TaskId(i))
+    }
+}
+
+impl<const N: usize, const HZ: u32> Scheduler<N, HZ> {
+    /// Create a scheduler driven by a timer that ticks every `tick`.
+    ///
+    /// This should match the period the timer is started with.
+    pub const fn new(tick: TimerDuration<HZ>) -> Self {
+        Self {
+            tick,
+            tasks: [None; N],
+        }
+    }
+
+    /// Add a task that is due every `period`, first due one `period`
+    /// from now.
+    ///
+    /// Returns [Error::OutOfRange] if `period` is zero, or if there is
+    /// no room left for another task.
+    pub fn add(&mut self, name: &'static str, period: TimerDuration<HZ>) -> Result<TaskId, Error> {
+        let period = period.ticks();
+        if period == 0 {
+            return Err(Error::OutOfRange);
+        }
+
+        let (i, slot) = self
+            .tasks
+            .iter_mut()
+            .enumerate()
+            .find(|(_, t)| t.is_none())
+            .ok_or(Error::OutOfRange)?;
+        *slot = Some(Task {
+            name,
+            period,
+            remaining: period,
+        });
+        Ok(TaskId(i))
+    }
+
+    /// The name a task was added with.
+    pub fn name(&self, task: TaskId) -> Option<&'static str> {
+        self.tasks.get(task.0)?.map(|t| t.name)
+    }
+
+    /// Check the tick timer, and return the tasks that are due.
+    ///
+    /// This never blocks. If the timer has not ticked since the last
+    /// poll, no tasks are due.
+    pub fn poll<T, const FORCED: bool>(
+        &mut self,
+        timer: &mut TimingMode<T, HZ, FORCED>,
+    ) -> Result<Due<N>, Error>
+    where
+        T: TimingInstance<HZ, FORCED>,
+    {
+        match timer.wait() {
+            Ok(()) => Ok(self.advance(self.tick)),
+            Err(block::Error::WouldBlock) => Ok(Due::none()),
+            Err(block::Error::Other(e)) => Err(e),
+        }
+    }
+
+    /// Move time forward by `elapsed`, and return the tasks that are
+    /// due.
+    ///
+    /// [Self::poll()] calls this once per tick. Call it directly to
+    /// drive the scheduler from some other clock.
+    pub fn advance(&mut self, elapsed: TimerDuration<HZ>) -> Due<N> {
+        let elapsed = elapsed.ticks();
+        let mut due = Due::none();
+        for (i, task) in self.tasks.iter_mut().enumerate() {
+            let Some(task) = task else {
+                continue;
+            };
+
+            if elapsed < task.remaining {
+                task.remaining -= elapsed;
+            } else {
+                // keep the original phase, even when late
+                let late = (elapsed - task.remaining) % task.period;
+                task.remaining = task.period - late;
+                due.0[i] = true;
+            }
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    type Ms = TimerDuration<1_000>;
+
+    #[test]
+    fn due_times() {
+        let mut sched = Scheduler::<3, 1_000>::new(Ms::millis(1));
+        let fast = sched.add("fast", Ms::millis(2)).unwrap();
+        let slow = sched.add("slow", Ms::millis(5)).unwrap();
+        assert_eq!(sched.name(slow), Some("slow"));
+
+        // step through 10ms one tick at a time
+        let mut fast_at = [0; 10];
+        let mut slow_at = [0; 10];
+        let (mut nfast, mut nslow) = (0, 0);
+        for t in 1..=10 {
+            let due = sched.advance(Ms::millis(1));
+            if due.contains(fast) {
+                fast_at[nfast] = t;
+                nfast += 1;
+            }
+            if due.contains(slow) {
+                slow_at[nslow] = t;
+                nslow += 1;
+            }
+        }
+        assert_eq!(&fast_at[..nfast], &[2, 4, 6, 8, 10]);
+        assert_eq!(&slow_at[..nslow], &[5, 10]);
+    }
+
+    #[test]
+    fn late() {
+        let mut sched = Scheduler::<1, 1_000>::new(Ms::millis(1));
+        let task = sched.add("task", Ms::millis(10)).unwrap();
+
+        assert!(sched.advance(Ms::millis(9)).is_empty());
+
+        // 3 periods late, reported once, and the phase is kept
+        let due = sched.advance(Ms::millis(33));
+        let mut due = due.iter();
+        assert_eq!(due.next(), Some(task));
+        assert_eq!(due.next(), None);
+        assert!(sched.advance(Ms::millis(7)).is_empty());
+        assert!(sched.advance(Ms::millis(1)).contains(task));
+    }
+
+    #[test]
+    fn add_errors() {
+        let mut sched = Scheduler::<1, 1_000>::new(Ms::millis(1));
+        assert_eq!(sched.add("zero", Ms::millis(0)), Err(Error::OutOfRange));
+        assert!(sched.add("one", Ms::millis(1)).is_ok());
+        assert_eq!(sched.add("two", Ms::millis(1)), Err(Error::OutOfRange));
+        assert_eq!(sched.name(TaskId(3)), None);
+    }
+}
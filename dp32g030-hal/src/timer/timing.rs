@@ -3,7 +3,8 @@ use muldiv::MulDiv;
 use crate::pac;
 
 use crate::block;
-use crate::time::{Hertz, TimerDuration, TimerInstant};
+use crate::power::Clocks;
+use crate::time::{Hertz, HertzExt, TimerDuration, TimerInstant};
 
 use super::{static_assert_timer_hz_not_zero, BaseInstance, Error, System, Timer, TimerHalf};
 
@@ -15,7 +16,7 @@ pub trait TimingInstance<const HZ: u32, const FORCED: bool>:
 }
 
 /// Timers that can be used in [TimingMode].
-trait TimingInstanceSealed<const HZ: u32, const FORCED: bool> {
+pub(crate) trait TimingInstanceSealed<const HZ: u32, const FORCED: bool> {
     /// What is the current count? At minimum this time has passed.
     fn now(&mut self) -> TimerInstant<HZ>;
 
@@ -30,6 +31,33 @@ trait TimingInstanceSealed<const HZ: u32, const FORCED: bool> {
 
     /// Wait for the count to end.
     fn wait(&mut self) -> block::Result<(), Error>;
+
+    /// Recompute the input clock from the system clock in `clocks`.
+    fn recalibrate(&mut self, clocks: &Clocks);
+}
+
+/// Input clock cycles needed to wait out at least `duration`.
+pub(super) fn clocks_for<const HZ: u32>(
+    duration: TimerDuration<HZ>,
+    input_clk: Hertz,
+) -> Result<u32, Error> {
+    duration
+        .ticks()
+        .mul_div_ceil(input_clk.to_Hz(), HZ)
+        .ok_or(Error::OutOfRange)
+}
+
+/// The longest duration that fits in `max_clocks` input clock cycles.
+pub(super) fn max_for<const HZ: u32>(
+    max_clocks: u32,
+    input_clk: Hertz,
+) -> Result<TimerDuration<HZ>, Error> {
+    // we should use floor(max_clocks * HZ / input_clk)
+    // this ensures max_ticks * input_clk / HZ <= max_clocks
+    let max_ticks = max_clocks
+        .mul_div_floor(HZ, input_clk.to_Hz())
+        .ok_or(Error::OutOfRange)?;
+    Ok(TimerDuration::from_ticks(max_ticks))
 }
 
 /// Helper for making sure either FORCED is set, or T_HZ matches C_HZ
@@ -84,10 +112,7 @@ where
 
         let clocks = if FORCED {
             // use input_clk
-            duration
-                .ticks()
-                .mul_div_ceil(self.input_clk.to_Hz(), C_HZ)
-                .ok_or(Error::OutOfRange)?
+            clocks_for(duration, self.input_clk)?
         } else {
             // T_HZ == C_HZ
             duration.ticks()
@@ -112,14 +137,13 @@ where
         static_assert_forced_or_hz_same::<T_HZ, C_HZ, FORCED>();
 
         // careful: input_clk is assumed to be exactly T_HZ unless FORCED
-        let input_clk = if FORCED { self.input_clk.to_Hz() } else { T_HZ };
-
-        // we should use floor((u16::MAX + 1) * C_HZ / input_clk)
-        // this ensures max_ticks * input_clk / C_HZ <= u16::MAX + 1
-        let max_ticks = (u16::MAX as u32 + 1)
-            .mul_div_floor(C_HZ, input_clk)
-            .ok_or(Error::OutOfRange)?;
-        Ok(TimerDuration::from_ticks(max_ticks))
+        let input_clk = if FORCED {
+            self.input_clk
+        } else {
+            Hertz::Hz(T_HZ)
+        };
+
+        max_for(u16::MAX as u32 + 1, input_clk)
     }
 
     fn cancel(&mut self) -> Result<(), Error> {
@@ -149,6 +173,10 @@ where
             Err(block::Error::Other(Error::NotStarted))
         }
     }
+
+    fn recalibrate(&mut self, clocks: &Clocks) {
+        self.input_clk = clocks.sys_clk() / (self.timer.get_div() as u32 + 1);
+    }
 }
 
 impl<const C_HZ: u32> TimingInstance<C_HZ, true> for System {}
@@ -166,11 +194,7 @@ impl<const C_HZ: u32> TimingInstanceSealed<C_HZ, true> for System {
     }
 
     fn start(&mut self, duration: TimerDuration<C_HZ>) -> Result<(), Error> {
-        let clocks = duration
-            .ticks()
-            .mul_div_ceil(self.input_clk.to_Hz(), C_HZ)
-            .ok_or(Error::OutOfRange)?
-            .saturating_sub(1);
+        let clocks = clocks_for(duration, self.input_clk)?.saturating_sub(1);
 
         if clocks > 0x00ffffff {
             return Err(Error::OutOfRange);
@@ -186,12 +210,7 @@ impl<const C_HZ: u32> TimingInstanceSealed<C_HZ, true> for System {
     }
 
     fn max(&self) -> Result<TimerDuration<C_HZ>, Error> {
-        // we should use floor((MAX + 1) * C_HZ / input_clk)
-        // this ensures max_ticks * input_clk / C_HZ <= MAX + 1
-        let max_ticks = 0x01000000
-            .mul_div_floor(C_HZ, self.input_clk.to_Hz())
-            .ok_or(Error::OutOfRange)?;
-        Ok(TimerDuration::from_ticks(max_ticks))
+        max_for(0x01000000, self.input_clk)
     }
 
     fn cancel(&mut self) -> Result<(), Error> {
@@ -215,6 +234,10 @@ impl<const C_HZ: u32> TimingInstanceSealed<C_HZ, true> for System {
             Err(block::Error::Other(Error::NotStarted))
         }
     }
+
+    fn recalibrate(&mut self, clocks: &Clocks) {
+        self.input_clk = clocks.sys_clk();
+    }
 }
 
 /// A timer in TimingMode, that can wait out durations.
@@ -260,7 +283,7 @@ where
 
     /// Start the count, rolling over at the given rate.
     pub fn start_frequency(&mut self, rate: Hertz) -> Result<(), Error> {
-        self.start(rate.into_duration())
+        self.start(rate.to_duration().ok_or(Error::OutOfRange)?)
     }
 
     /// Start the count, rolling over at the native timer frequency.
@@ -288,6 +311,11 @@ where
         self.timer.wait()
     }
 
+    /// Recompute the input clock from the system clock in `clocks`.
+    pub(super) fn recalibrate(&mut self, clocks: &Clocks) {
+        self.timer.recalibrate(clocks)
+    }
+
     /// Blocking wait for a duration.
     pub fn delay(&mut self, mut duration: TimerDuration<HZ>) -> Result<(), Error> {
         match self.start(duration) {
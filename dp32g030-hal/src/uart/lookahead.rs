@@ -0,0 +1,151 @@
+//! A one-byte lookahead in front of the RX FIFO.
+//!
+//! The hardware has no way to look at the next byte in the RX FIFO
+//! without removing it, so [Lookahead] keeps at most one byte read
+//! early. Anything reading from the FIFO must go through it, or that
+//! byte will come out of order.
+
+use core::convert::Infallible;
+
+use crate::block;
+
+use super::Instance;
+
+/// Internal access to the RX FIFO, for [Lookahead].
+pub(super) trait RxFifo {
+    /// Is the FIFO empty?
+    fn fifo_is_empty(&self) -> bool;
+
+    /// Get the FIFO level, 0 is empty and 8 is full.
+    fn fifo_level(&self) -> u8;
+
+    /// Remove the next byte from the FIFO. It must not be empty.
+    fn fifo_read(&mut self) -> u8;
+}
+
+impl<Uart> RxFifo for Uart
+where
+    Uart: Instance,
+{
+    fn fifo_is_empty(&self) -> bool {
+        self.if_().read().rxfifo_empty().is_empty()
+    }
+
+    fn fifo_level(&self) -> u8 {
+        match self.if_().read().rf_level().bits() {
+            0 => {
+                if self.if_().read().rxfifo_full().is_full() {
+                    8
+                } else {
+                    0
+                }
+            }
+            l => l,
+        }
+    }
+
+    fn fifo_read(&mut self) -> u8 {
+        self.rdr().read().data().bits()
+    }
+}
+
+/// At most one byte, taken from the RX FIFO before it was asked for.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(super) struct Lookahead {
+    next: Option<u8>,
+}
+
+impl Lookahead {
+    /// Forget any byte held here.
+    pub(super) fn clear(&mut self) {
+        self.next = None;
+    }
+
+    /// Is there nothing here or in the FIFO?
+    pub(super) fn is_empty(&self, fifo: &impl RxFifo) -> bool {
+        self.next.is_none() && fifo.fifo_is_empty()
+    }
+
+    /// The number of bytes here and in the FIFO.
+    pub(super) fn available(&self, fifo: &impl RxFifo) -> usize {
+        self.next.is_some() as usize + fifo.fifo_level() as usize
+    }
+
+    /// Look at the next byte without removing it.
+    pub(super) fn peek(&mut self, fifo: &mut impl RxFifo) -> Option<u8> {
+        if self.next.is_none() && !fifo.fifo_is_empty() {
+            self.next = Some(fifo.fifo_read());
+        }
+        self.next
+    }
+
+    /// Remove the next byte, from here first, then from the FIFO.
+    pub(super) fn read_one(&mut self, fifo: &mut impl RxFifo) -> block::Result<u8, Infallible> {
+        if let Some(b) = self.next.take() {
+            Ok(b)
+        } else if fifo.fifo_is_empty() {
+            Err(block::Error::WouldBlock)
+        } else {
+            Ok(fifo.fifo_read())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    extern crate std;
+    use std::collections::VecDeque;
+
+    struct Fifo(VecDeque<u8>);
+
+    impl RxFifo for Fifo {
+        fn fifo_is_empty(&self) -> bool {
+            self.0.is_empty()
+        }
+
+        fn fifo_level(&self) -> u8 {
+            self.0.len() as u8
+        }
+
+        fn fifo_read(&mut self) -> u8 {
+            self.0.pop_front().unwrap()
+        }
+    }
+
+    #[test]
+    fn peek() {
+        let mut fifo = Fifo([1, 2, 3].into_iter().collect());
+        let mut rx = Lookahead::default();
+        assert_eq!(rx.available(&fifo), 3);
+
+        // peek is idempotent, and doesn't change what is available
+        assert_eq!(rx.peek(&mut fifo), Some(1));
+        assert_eq!(rx.peek(&mut fifo), Some(1));
+        assert_eq!(rx.available(&fifo), 3);
+        assert!(!rx.is_empty(&fifo));
+
+        // the peeked byte is read next, then the rest in order
+        assert_eq!(rx.read_one(&mut fifo), Ok(1));
+        assert_eq!(rx.peek(&mut fifo), Some(2));
+        assert_eq!(rx.read_one(&mut fifo), Ok(2));
+        assert_eq!(rx.read_one(&mut fifo), Ok(3));
+
+        // nothing left
+        assert!(rx.is_empty(&fifo));
+        assert_eq!(rx.available(&fifo), 0);
+        assert_eq!(rx.peek(&mut fifo), None);
+        assert_eq!(rx.read_one(&mut fifo), Err(block::Error::WouldBlock));
+
+        // the last byte in the fifo can sit in the lookahead alone
+        fifo.0.push_back(4);
+        assert_eq!(rx.peek(&mut fifo), Some(4));
+        assert!(fifo.0.is_empty());
+        assert!(!rx.is_empty(&fifo));
+        assert_eq!(rx.available(&fifo), 1);
+        rx.clear();
+        assert!(rx.is_empty(&fifo));
+    }
+}
@@ -12,6 +12,8 @@ mod hal1;
 mod instance;
 pub use instance::*;
 
+mod lookahead;
+
 mod port;
 pub use port::*;
 
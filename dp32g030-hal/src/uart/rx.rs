@@ -1,16 +1,34 @@
 use core::convert::Infallible;
 
 use crate::block;
+use crate::time::TimerDuration;
+use crate::timer::{TimingInstance, TimingMode};
 
+use super::lookahead::{Lookahead, RxFifo};
 use super::{Config, Flow, Instance, Lonely, Paired, UartData};
 
+/// Returned by [Rx::read_timeout()] when time ran out before the
+/// buffer was filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimedOut {
+    /// The number of bytes read before the timeout.
+    pub read: usize,
+}
+
 /// The Rx half of a UART.
+///
+/// The hardware FIFO can't be read without removing a byte, so
+/// [Self::peek()] holds on to one byte of lookahead. That byte is
+/// counted by [Self::is_empty()] and [Self::available()], and is
+/// always the next one returned by [Self::read_one()] and friends.
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Rx<Uart: Instance, Data = u8, Pair = Paired> {
     uart: Uart,
     rx: Uart::Rx,
     rts: Flow<Uart::Rts>,
+    lookahead: Lookahead,
     // this produces data
     _marker: core::marker::PhantomData<(Data, Pair)>,
 }
@@ -77,6 +95,7 @@ where
             uart,
             rx,
             rts,
+            lookahead: Default::default(),
             _marker: Default::default(),
         };
 
@@ -95,8 +114,9 @@ where
         (self.uart, self.rx, self.rts)
     }
 
-    /// Clear the FIFO.
+    /// Clear the FIFO, and any byte held by [Self::peek()].
     pub fn clear(&mut self) {
+        self.lookahead.clear();
         critical_section::with(|_cs| {
             // this register is shared but we're in a critical section
             self.uart.fifo().modify(|_r, w| w.rf_clr().clear());
@@ -113,32 +133,37 @@ where
         self.uart.if_().read().rxfifo_hfull().is_half_full()
     }
 
-    /// Is the FIFO empty?
+    /// Is there nothing to read, in the FIFO or held by [Self::peek()]?
     pub fn is_empty(&self) -> bool {
-        self.uart.if_().read().rxfifo_empty().is_empty()
+        self.lookahead.is_empty(&self.uart)
     }
 
     /// Get the FIFO level, 0 is empty and 8 is full.
+    ///
+    /// This does not count a byte held by [Self::peek()].
     pub fn level(&self) -> u8 {
-        match self.uart.if_().read().rf_level().bits() {
-            0 => {
-                if self.is_full() {
-                    8
-                } else {
-                    0
-                }
-            }
-            l => l,
-        }
+        RxFifo::fifo_level(&self.uart)
+    }
+
+    /// The number of bytes that can be read without blocking.
+    ///
+    /// This is [Self::level()], plus one if [Self::peek()] is holding
+    /// a byte, so it can be as high as 9.
+    pub fn available(&self) -> usize {
+        self.lookahead.available(&self.uart)
+    }
+
+    /// Look at the next byte without removing it, or `None` if there
+    /// is nothing to read.
+    ///
+    /// Calling this again returns the same byte, until it is read.
+    pub fn peek(&mut self) -> Option<u8> {
+        self.lookahead.peek(&mut self.uart)
     }
 
     /// Read a single byte from the UART.
     pub fn read_one(&mut self) -> block::Result<u8, Infallible> {
-        if self.is_empty() {
-            Err(block::Error::WouldBlock)
-        } else {
-            Ok(self.uart.rdr().read().data().bits())
-        }
+        self.lookahead.read_one(&mut self.uart)
     }
 
     /// Read at least one byte from the UART.
@@ -164,6 +189,26 @@ where
         Ok(amt)
     }
 
+    /// Read bytes from the UART until the buffer is full, or until
+    /// `timeout` passes on `timer`.
+    ///
+    /// This uses `timer` for the whole call, restarting it as needed
+    /// for timeouts longer than [TimingMode::max()]. Any count already
+    /// running on it is lost. If the timer can't be started at all,
+    /// this times out right away, after taking whatever is already
+    /// waiting in the FIFO.
+    pub fn read_timeout<Timer, const HZ: u32, const FORCED: bool>(
+        &mut self,
+        buf: &mut [u8],
+        timeout: TimerDuration<HZ>,
+        timer: &mut TimingMode<Timer, HZ, FORCED>,
+    ) -> Result<usize, TimedOut>
+    where
+        Timer: TimingInstance<HZ, FORCED>,
+    {
+        read_timeout_with(buf, timeout, timer, |buf| self.read(buf))
+    }
+
     /// Read bytes from the UART, filling the buffer and blocking if needed.
     pub fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Infallible> {
         let mut start = 0;
@@ -174,3 +219,199 @@ where
         Ok(())
     }
 }
+
+// the body of Rx::read_timeout(), reading with `read`
+fn read_timeout_with<Timer, const HZ: u32, const FORCED: bool>(
+    buf: &mut [u8],
+    timeout: TimerDuration<HZ>,
+    timer: &mut TimingMode<Timer, HZ, FORCED>,
+    mut read: impl FnMut(&mut [u8]) -> block::Result<usize, Infallible>,
+) -> Result<usize, TimedOut>
+where
+    Timer: TimingInstance<HZ, FORCED>,
+{
+    let mut amt = 0;
+    let mut remaining = timeout;
+    let mut running = false;
+
+    while amt < buf.len() {
+        match read(&mut buf[amt..]) {
+            Ok(n) => {
+                amt += n;
+                continue;
+            }
+            Err(block::Error::WouldBlock) => {}
+            Err(block::Error::Other(e)) => match e {},
+        }
+
+        if running {
+            match timer.wait() {
+                // this part is over, fall through to the next
+                Ok(()) => {}
+                Err(block::Error::WouldBlock) => continue,
+                Err(block::Error::Other(_)) => return Err(TimedOut { read: amt }),
+            }
+        }
+
+        if remaining.ticks() == 0 {
+            return Err(TimedOut { read: amt });
+        }
+
+        // start the next part of the timeout
+        let Ok(max) = timer.max() else {
+            return Err(TimedOut { read: amt });
+        };
+        let part = remaining.min(max);
+        if timer.start(part).is_err() {
+            return Err(TimedOut { read: amt });
+        }
+        remaining -= part;
+        running = true;
+    }
+
+    if running {
+        let _ = timer.cancel();
+    }
+    Ok(amt)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    extern crate std;
+    use std::cell::{Cell, RefCell};
+    use std::vec::Vec;
+
+    use crate::power::Clocks;
+    use crate::time::TimerInstant;
+    use crate::timer::{Error, TimingInstanceSealed};
+
+    // a timer counting ticks of a fake clock, which advances one tick
+    // every time the timer is waited on, and records each count started
+    struct MockTimer<'a> {
+        now: &'a Cell<u32>,
+        max: u32,
+        deadline: Option<u32>,
+        starts: &'a RefCell<Vec<u32>>,
+    }
+
+    impl TimingInstance<1_000, false> for MockTimer<'_> {}
+
+    impl TimingInstanceSealed<1_000, false> for MockTimer<'_> {
+        fn now(&mut self) -> TimerInstant<1_000> {
+            TimerInstant::from_ticks(self.now.get())
+        }
+
+        fn start(&mut self, duration: TimerDuration<1_000>) -> Result<(), Error> {
+            if duration.ticks() > self.max {
+                return Err(Error::OutOfRange);
+            }
+            self.starts.borrow_mut().push(duration.ticks());
+            self.deadline = Some(self.now.get() + duration.ticks());
+            Ok(())
+        }
+
+        fn max(&self) -> Result<TimerDuration<1_000>, Error> {
+            Ok(TimerDuration::from_ticks(self.max))
+        }
+
+        fn cancel(&mut self) -> Result<(), Error> {
+            self.deadline.take().map(|_| ()).ok_or(Error::NotStarted)
+        }
+
+        fn wait(&mut self) -> block::Result<(), Error> {
+            let deadline = self.deadline.ok_or(Error::NotStarted)?;
+            self.now.set(self.now.get() + 1);
+            if self.now.get() >= deadline {
+                self.deadline = None;
+                Ok(())
+            } else {
+                Err(block::Error::WouldBlock)
+            }
+        }
+
+        fn recalibrate(&mut self, _clocks: &Clocks) {}
+    }
+
+    // bytes arriving at the given ticks of the fake clock
+    struct MockReader<'a> {
+        now: &'a Cell<u32>,
+        arrivals: Vec<(u32, u8)>,
+    }
+
+    impl MockReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> block::Result<usize, Infallible> {
+            let mut amt = 0;
+            while amt < buf.len() && self.arrivals.first().is_some_and(|a| a.0 <= self.now.get()) {
+                buf[amt] = self.arrivals.remove(0).1;
+                amt += 1;
+            }
+            if amt == 0 {
+                Err(block::Error::WouldBlock)
+            } else {
+                Ok(amt)
+            }
+        }
+    }
+
+    fn read_timeout(
+        arrivals: &[(u32, u8)],
+        buf: &mut [u8],
+        timeout: u32,
+    ) -> (Result<usize, TimedOut>, u32, Vec<u32>) {
+        let now = Cell::new(0);
+        let starts = RefCell::new(Vec::new());
+        let mut timer = TimingMode::new(MockTimer {
+            now: &now,
+            max: 8,
+            deadline: None,
+            starts: &starts,
+        });
+        let mut reader = MockReader {
+            now: &now,
+            arrivals: arrivals.to_vec(),
+        };
+        let result =
+            read_timeout_with(buf, TimerDuration::from_ticks(timeout), &mut timer, |buf| {
+                reader.read(buf)
+            });
+        (result, now.get(), starts.take())
+    }
+
+    #[test]
+    fn timeout_with_partial_data() {
+        let mut buf = [0; 8];
+        let (result, now, starts) = read_timeout(&[(0, 1), (1, 2), (5, 3), (30, 4)], &mut buf, 20);
+
+        // the first three bytes arrive in time, the last does not
+        assert_eq!(result, Err(TimedOut { read: 3 }));
+        assert_eq!(buf[..3], [1, 2, 3]);
+        // the whole timeout passed, split into parts no longer than max
+        assert_eq!(now, 20);
+        assert_eq!(starts, [8, 8, 4]);
+    }
+
+    #[test]
+    fn full_before_timeout() {
+        let mut buf = [0; 3];
+        let (result, now, starts) = read_timeout(&[(0, 1), (1, 2), (5, 3), (30, 4)], &mut buf, 20);
+
+        assert_eq!(result, Ok(3));
+        assert_eq!(buf, [1, 2, 3]);
+        assert_eq!(now, 5);
+        assert_eq!(starts, [8]);
+    }
+
+    #[test]
+    fn zero_timeout() {
+        let mut buf = [0; 8];
+        let (result, now, starts) = read_timeout(&[(0, 1), (0, 2), (1, 3)], &mut buf, 0);
+
+        // only what was already waiting
+        assert_eq!(result, Err(TimedOut { read: 2 }));
+        assert_eq!(buf[..2], [1, 2]);
+        assert_eq!(now, 0);
+        assert!(starts.is_empty());
+    }
+}
@@ -32,6 +32,11 @@ fn main() {
     // `memory.x` is changed.
     println!("cargo:rerun-if-changed=memory.x");
 
+    // Host builds (for tests) use the normal system linker.
+    if !env::var("TARGET").unwrap().starts_with("thumb") {
+        return;
+    }
+
     // Set the linker script to the one provided by cortex-m-rt.
     println!("cargo:rustc-link-arg=-Tlink.x");
 
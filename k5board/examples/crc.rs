@@ -0,0 +1,84 @@
+#![no_std]
+#![no_main]
+
+use k5board::hal;
+use panic_halt as _;
+
+use k5board::prelude::*;
+
+use cortex_m::peripheral::{syst::SystClkSource, SYST};
+use k5lib::protocol::crc::{CrcDigest, CrcStyle};
+
+k5board::version!(concat!(env!("CARGO_PKG_VERSION"), "crc"));
+
+// a full-size frame body
+const FRAME_SIZE: usize = 256;
+
+// count the system clock cycles taken to CRC the frame
+fn bench<C>(syst: &mut SYST, crc: &C, frame: &[u8]) -> (u16, u32)
+where
+    C: CrcStyle,
+{
+    syst.set_reload(0x00ffffff);
+    syst.clear_current();
+    syst.enable_counter();
+
+    let start = SYST::get_current();
+    let mut digest = crc.digest();
+    digest.update(frame);
+    let value = digest.finalize();
+    let end = SYST::get_current();
+
+    syst.disable_counter();
+    (value, start.wrapping_sub(end) & 0x00ffffff)
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    // grab peripherals and initialize the clock
+    let mut cp = cortex_m::Peripherals::take().unwrap();
+    let p = hal::pac::Peripherals::take().unwrap();
+    let power = hal::power::new(p.SYSCON, p.PMU, p.FLASH_CTRL)
+        .sys_internal_24mhz()
+        .freeze();
+
+    // turn on GPIOA
+    let ports = hal::gpio::new(p.PORTCON, p.GPIOA, p.GPIOB, p.GPIOC);
+    let pins_a = ports.port_a.enable(power.gates.gpio_a);
+
+    // set up the uart and install it globally
+    let uart_parts = k5board::uart::Parts {
+        uart: p.UART1,
+        gate: power.gates.uart1,
+        tx: pins_a.a7.into_mode(),
+        rx: pins_a.a8.into_mode(),
+    };
+    let uart = k5board::uart::new(38_400.Hz(), uart_parts).unwrap();
+    k5board::uart::install(uart);
+
+    // count cycles with SysTick on the core clock
+    cp.SYST.set_clock_source(SystClkSource::Core);
+
+    let software = k5lib::protocol::crc::CrcXModem::new();
+    let hardware = k5board::crc::new(p.CRC, power.gates.crc);
+
+    let mut frame = [0u8; FRAME_SIZE];
+    for (i, b) in frame.iter_mut().enumerate() {
+        *b = i as u8;
+    }
+
+    loop {
+        let (sw, sw_cycles) = bench(&mut cp.SYST, &software, &frame);
+        let (hw, hw_cycles) = bench(&mut cp.SYST, &hardware, &frame);
+
+        println!("{} byte frame:", FRAME_SIZE);
+        println!("  software: {:04x} in {} cycles", sw, sw_cycles);
+        println!("  hardware: {:04x} in {} cycles", hw, hw_cycles);
+        if sw != hw {
+            println!("  mismatch!");
+        }
+
+        // delay a bit
+        cortex_m::asm::delay(power.clocks.sys_clk().to_Hz());
+    }
+}
@@ -0,0 +1,61 @@
+#![no_std]
+#![no_main]
+
+use k5board::hal;
+use panic_halt as _;
+
+use k5board::prelude::*;
+
+k5board::version!(concat!(env!("CARGO_PKG_VERSION"), "flashrd"));
+
+// a piece of the program, past the vector table
+const START: u32 = 0x100;
+const LEN: usize = 0x100;
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    // grab peripherals and initialize the clock, keeping the flash
+    let p = hal::pac::Peripherals::take().unwrap();
+    let (power, mut flash) = hal::power::new(p.SYSCON, p.PMU, p.FLASH_CTRL)
+        .sys_internal_24mhz()
+        .freeze_with_flash();
+
+    // turn on GPIOA
+    let ports = hal::gpio::new(p.PORTCON, p.GPIOA, p.GPIOB, p.GPIOC);
+    let pins_a = ports.port_a.enable(power.gates.gpio_a);
+
+    // set up the uart and install it globally
+    let uart_parts = k5board::uart::Parts {
+        uart: p.UART1,
+        gate: power.gates.uart1,
+        tx: pins_a.a7.into_mode(),
+        rx: pins_a.a8.into_mode(),
+    };
+    let uart = k5board::uart::new(38_400.Hz(), uart_parts).unwrap();
+    k5board::uart::install(uart);
+
+    // the same bytes, read directly from the bus
+    // safety: this is inside main flash, and not at address 0
+    let direct = unsafe { core::slice::from_raw_parts(START as *const u8, LEN) };
+
+    loop {
+        let mut read = [0u8; LEN];
+        match flash.read(START, &mut read) {
+            Ok(()) if read[..] == direct[..] => {
+                println!("read {} bytes at {:#06x}: ok", LEN, START)
+            }
+            Ok(()) => println!("read {} bytes at {:#06x}: mismatch!", LEN, START),
+            Err(e) => println!("read {} bytes at {:#06x}: {}", LEN, START, e),
+        }
+
+        // reads past the end of flash are refused
+        let end = hal::flash::SIZE - 2;
+        match flash.read(end, &mut [0; 4]) {
+            Err(hal::flash::Error::OutOfRange) => println!("read past end: refused"),
+            other => println!("read past end: {:?}!", other),
+        }
+
+        // delay a bit
+        cortex_m::asm::delay(power.clocks.sys_clk().to_Hz());
+    }
+}
@@ -0,0 +1,94 @@
+#![no_std]
+#![no_main]
+
+use panic_halt as _;
+
+use k5board::hal;
+use k5board::hal::time::Hertz;
+use k5board::prelude::*;
+
+use embedded_hal_1::i2c::I2c;
+
+k5board::version!(concat!(env!("CARGO_PKG_VERSION"), "i2cdrv"));
+
+// a stand-in for a third-party driver crate, generic over any
+// embedded-hal 1.0 I2C bus. this one reads an LM75-style temperature
+// sensor, attached to the same bus as the EEPROM.
+struct Lm75<I> {
+    i2c: I,
+    address: u8,
+}
+
+impl<I> Lm75<I>
+where
+    I: I2c,
+{
+    fn new(i2c: I, address: u8) -> Self {
+        Self { i2c, address }
+    }
+
+    // temperature in units of 1/256 degrees C
+    fn read_temperature(&mut self) -> Result<i16, I::Error> {
+        let mut buf = [0; 2];
+        self.i2c.write_read(self.address, &[0x00], &mut buf)?;
+        Ok(i16::from_be_bytes(buf))
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    // grab peripherals and initialize the clock
+    let p = hal::pac::Peripherals::take().unwrap();
+    let power = hal::power::new(p.SYSCON, p.PMU, p.FLASH_CTRL)
+        .sys_internal_24mhz()
+        .freeze();
+
+    // turn on GPIOA
+    let ports = hal::gpio::new(p.PORTCON, p.GPIOA, p.GPIOB, p.GPIOC);
+    let pins_a = ports.port_a.enable(power.gates.gpio_a);
+
+    // set up the uart and install it globally so we can print data out
+    let uart_parts = k5board::uart::Parts {
+        uart: p.UART1,
+        gate: power.gates.uart1,
+        tx: pins_a.a7.into_mode(),
+        rx: pins_a.a8.into_mode(),
+    };
+    let uart = k5board::uart::new(38_400.Hz(), uart_parts).unwrap();
+    k5board::uart::install(uart);
+
+    // bit-bang i2c needs a timer at twice the desired frequency
+    // we'll use TIMER_BASE0 at 200kHz
+    let mut i2c_timer = hal::timer::new(p.TIMER_BASE0, power.gates.timer_base0)
+        .frequency::<{ Hertz::kHz(200).to_Hz() }>()
+        .unwrap()
+        .split()
+        .low
+        .timing();
+    i2c_timer.start_native().unwrap();
+
+    // set up the i2c bus the eeprom lives on
+    let i2c_parts = k5board::shared_i2c::Parts {
+        clk: i2c_timer,
+        scl: pins_a.a10.into_mode().into(),
+        sda: pins_a.a11.into_mode().into(),
+    };
+    let i2c = k5board::shared_i2c::new(i2c_parts);
+
+    // the eeprom and our generic driver each get their own handle
+    let mut eeprom = k5board::eeprom::new(i2c.acquire());
+    let mut sensor = Lm75::new(i2c.acquire(), 0x48);
+
+    let mut first = [0; 1];
+    loop {
+        eeprom.read(0, &mut first).unwrap();
+        print!("eeprom[0] = 0x{:02x}, ", first[0]);
+
+        match sensor.read_temperature() {
+            Ok(t) => println!("temperature = {} / 256 C", t),
+            Err(e) => println!("sensor error: {:?}", e),
+        }
+
+        cortex_m::asm::delay(power.clocks.sys_clk().to_Hz());
+    }
+}
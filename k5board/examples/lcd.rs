@@ -44,7 +44,7 @@ fn main() -> ! {
         clk: pins_b.b8.into_mode(),
         a0: pins_b.b9.into_mode(),
         mosi: pins_b.b10.into_mode(),
-        res: pins_b.b11.into_mode(),
+        res: pins_b.b11.into_mode_releasing_swd(),
     };
     let mut lcd = k5board::lcd::new(&mut delay, lcd_parts).unwrap();
 
@@ -0,0 +1,80 @@
+#![no_std]
+#![no_main]
+
+use panic_halt as _;
+
+use k5board::hal;
+use k5board::prelude::*;
+
+use hal::time::Hertz;
+
+k5board::version!(concat!(env!("CARGO_PKG_VERSION"), "radio"));
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    // grab peripherals and initialize the clock
+    let p = hal::pac::Peripherals::take().unwrap();
+    let power = hal::power::new(p.SYSCON, p.PMU, p.FLASH_CTRL)
+        .sys_internal_48mhz()
+        .freeze();
+
+    // turn on GPIOA and GPIOC
+    let ports = hal::gpio::new(p.PORTCON, p.GPIOA, p.GPIOB, p.GPIOC);
+    let pins_a = ports.port_a.enable(power.gates.gpio_a);
+    let pins_c = ports.port_c.enable(power.gates.gpio_c);
+
+    // set up the uart and install it globally
+    let uart_parts = k5board::uart::Parts {
+        uart: p.UART1,
+        gate: power.gates.uart1,
+        tx: pins_a.a7.into_mode(),
+        rx: pins_a.a8.into_mode(),
+    };
+    let uart = k5board::uart::new(38_400.Hz(), uart_parts).unwrap();
+    k5board::uart::install(uart);
+
+    // the BK4819 is bit-banged on C0 (scn), C1 (scl), and C2 (sda)
+    let scn = pins_c.c0.into_push_pull_output();
+    let scl = pins_c.c1.into_push_pull_output();
+    let sda = hal::gpio::InputOutputPin::new_from_output(pins_c.c2.into_push_pull_output(), |p| {
+        p.into_floating_input()
+    });
+
+    // C4 turns on the speaker amplifier
+    let speaker = pins_c.c4.into_push_pull_output();
+
+    // the BK4819 needs a 1MHz timer for its clock
+    let timer = hal::timer::new(p.TIMER_BASE0, power.gates.timer_base0)
+        .frequency::<{ Hertz::MHz(1).to_Hz() }>()
+        .unwrap()
+        .split();
+    let bk4819 = bk4819::Bk4819::new(scn, scl, sda, timer.low.timing()).unwrap();
+
+    // only receiving here, so allow no transmitting at all
+    let limits = k5board::transmit::Limits::new(1, 0);
+    let mut radio = k5board::radio::Radio::new(bk4819, speaker, limits).unwrap();
+
+    // tune to the 2m national simplex calling frequency
+    radio.tune(146_520_000).unwrap();
+    radio.set_volume(8).unwrap();
+
+    // a 1kHz timer for pacing
+    let mut delay = hal::timer::new(p.TIMER_BASE1, power.gates.timer_base1)
+        .frequency::<{ Hertz::kHz(1).to_Hz() }>()
+        .unwrap()
+        .split()
+        .low
+        .timing();
+
+    loop {
+        let rssi = radio.rssi().unwrap();
+        println!(
+            "{} Hz: rssi {} ({} dBm)",
+            radio.frequency(),
+            rssi,
+            rssi as i32 / 2 - 160
+        );
+
+        delay.delay(500.millis()).unwrap();
+    }
+}
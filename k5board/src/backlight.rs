@@ -1,13 +1,72 @@
-//! The backlight, turn it on or off.
-// FIXME pwm?
+//! The backlight, turn it on or off, or dim it.
+//!
+//! PB6 is not on a hardware PWM channel, so dimming is done in
+//! software: set a level with [Backlight::set_brightness()], and then
+//! call [Backlight::tick()] at a steady rate, such as from a 1kHz
+//! timer.
+//!
+//! Each tick costs only an add and a pin write, but the pin is only
+//! as fast as the tick. Rather than one long pulse per period, the
+//! high ticks are spread as evenly as possible across the period of
+//! [MAX_BRIGHTNESS] ticks (see [DutyAccumulator]). At 1kHz, middling
+//! levels switch every few milliseconds and look steady, but levels
+//! very close to off or full on only change a few times a second, and
+//! may be seen to flicker. A faster tick flickers less, at the cost of
+//! more interrupts.
 
 use crate::hal::gpio::{Output, PushPull, PB6};
 
+/// The brightness level for a fully-on backlight.
+pub const MAX_BRIGHTNESS: u8 = u8::MAX;
+
+/// Spreads a duty cycle of `level` out of [MAX_BRIGHTNESS] ticks as
+/// evenly as possible over each period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DutyAccumulator {
+    level: u8,
+    accumulator: u8,
+}
+
+impl DutyAccumulator {
+    /// Create a new accumulator with the given level.
+    pub const fn new(level: u8) -> Self {
+        Self {
+            level,
+            accumulator: 0,
+        }
+    }
+
+    /// The current level.
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// Change the level, starting from the current point in the period.
+    pub fn set_level(&mut self, level: u8) {
+        self.level = level;
+    }
+
+    /// Advance by one tick, and return whether the output should be
+    /// high for this tick.
+    pub fn tick(&mut self) -> bool {
+        let sum = self.accumulator as u16 + self.level as u16;
+        if sum >= MAX_BRIGHTNESS as u16 {
+            self.accumulator = (sum - MAX_BRIGHTNESS as u16) as u8;
+            true
+        } else {
+            self.accumulator = sum as u8;
+            false
+        }
+    }
+}
+
 /// The backlight and keypad light.
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Backlight {
     pin: PB6<Output<PushPull>>,
+    duty: DutyAccumulator,
 }
 
 /// Set up the backlight for use.
@@ -18,7 +77,8 @@ pub fn new(pin: PB6<Output<PushPull>>) -> Backlight {
 impl Backlight {
     /// Set up the backlight for use.
     pub fn new(pin: PB6<Output<PushPull>>) -> Self {
-        Self { pin }
+        let duty = DutyAccumulator::new(if pin.is_set_high() { MAX_BRIGHTNESS } else { 0 });
+        Self { pin, duty }
     }
 
     /// Free the flaslight pin for use elsewhere.
@@ -26,28 +86,87 @@ impl Backlight {
         self.pin
     }
 
-    /// Turn the backlight on.
+    /// Turn the backlight fully on.
     pub fn on(&mut self) {
-        self.pin.set_high();
+        self.set_brightness(MAX_BRIGHTNESS);
     }
 
     /// Turn the backlight off.
     pub fn off(&mut self) {
-        self.pin.set_low();
+        self.set_brightness(0);
     }
 
-    /// Turn the backlight state.
+    /// Turn the backlight fully on, or off.
     pub fn set(&mut self, on: bool) {
-        self.pin.set_state(on.into());
+        self.set_brightness(if on { MAX_BRIGHTNESS } else { 0 });
     }
 
     /// Toggle the backlight on or off.
     pub fn toggle(&mut self) {
-        self.pin.toggle();
+        self.set(!self.is_on());
     }
 
-    /// Is the backlight on?
+    /// Is the backlight on, at any brightness?
     pub fn is_on(&self) -> bool {
-        self.pin.is_set_high()
+        self.duty.level() > 0
+    }
+
+    /// Get the current brightness, out of [MAX_BRIGHTNESS].
+    pub fn brightness(&self) -> u8 {
+        self.duty.level()
+    }
+
+    /// Set the brightness, out of [MAX_BRIGHTNESS].
+    ///
+    /// Fully on and off take effect immediately. Any other level needs
+    /// [Self::tick()] to be called regularly.
+    pub fn set_brightness(&mut self, level: u8) {
+        self.duty.set_level(level);
+        match level {
+            0 => self.pin.set_low(),
+            MAX_BRIGHTNESS => self.pin.set_high(),
+            _ => (),
+        }
+    }
+
+    /// Advance the software PWM by one tick. Call this at a steady
+    /// rate, see the [module documentation](self) for the tradeoffs.
+    pub fn tick(&mut self) {
+        let high = self.duty.tick();
+        self.pin.set_state(high.into());
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+
+    use std::vec::Vec;
+
+    fn period(level: u8) -> Vec<bool> {
+        let mut duty = DutyAccumulator::new(level);
+        (0..MAX_BRIGHTNESS).map(|_| duty.tick()).collect()
+    }
+
+    #[test]
+    fn duty_accumulator() {
+        assert!(period(0).iter().all(|h| !h));
+        assert!(period(MAX_BRIGHTNESS).iter().all(|h| *h));
+
+        for level in 0..=MAX_BRIGHTNESS {
+            let highs = period(level).iter().filter(|h| **h).count();
+            assert_eq!(highs, level as usize);
+        }
+
+        // a third on is every third tick
+        let third = period(85);
+        assert_eq!(&third[..6], [false, false, true, false, false, true]);
+
+        // and the pattern repeats each period
+        let mut duty = DutyAccumulator::new(85);
+        let first: Vec<bool> = (0..MAX_BRIGHTNESS).map(|_| duty.tick()).collect();
+        let second: Vec<bool> = (0..MAX_BRIGHTNESS).map(|_| duty.tick()).collect();
+        assert_eq!(first, second);
     }
 }
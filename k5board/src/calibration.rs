@@ -0,0 +1,29 @@
+//! Factory calibration for the radio.
+//!
+//! The board calibration (transmit power and battery divider) is
+//! written into the end of the EEPROM at the factory, in the layout
+//! described by [Calibration]. The chip's own oscillator and
+//! reference trims are kept in flash NVR instead, but that layout is
+//! not documented, so it is not read here. The unique chip ID is
+//! available from [ChipId], in [hal::power::Power].
+//!
+//! [hal::power::Power]: crate::hal::power::Power
+
+use embedded_hal_02::timer::{CountDown, Periodic};
+
+pub use crate::hal::power::ChipId;
+pub use k5lib::eeprom::{Calibration, CALIBRATION_ADDRESS, CALIBRATION_LEN};
+
+use crate::eeprom::{Eeprom, Error};
+
+/// Read the factory calibration out of the EEPROM.
+pub fn read<Timer>(eeprom: &mut Eeprom<'_, Timer>) -> Result<Calibration, Error>
+where
+    Timer: CountDown + Periodic,
+{
+    let mut data = [0; CALIBRATION_LEN];
+    eeprom.read(CALIBRATION_ADDRESS, &mut data)?;
+    let (_, calibration) =
+        Calibration::parse(&data[..]).expect("calibration buffer is the right size");
+    Ok(calibration)
+}
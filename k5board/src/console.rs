@@ -0,0 +1,321 @@
+//! A line editor for an interactive console over the UART.
+//!
+//! [LineEditor] takes input one byte at a time, echoes it back, and
+//! hands over each line as it is completed with enter. It understands
+//! backspace, and keeps a small history of previous lines that can be
+//! recalled with the up and down arrow keys. Everything is kept in
+//! fixed-size buffers, no allocator needed.
+//!
+//! Only printable ASCII is accepted into the line. Anything else,
+//! including bytes past the end of a full line, is dropped and answered
+//! with a bell.
+
+use embedded_io::Write;
+
+const BELL: &[u8] = b"\x07";
+const ERASE: &[u8] = b"\x08 \x08";
+const NEWLINE: &[u8] = b"\r\n";
+
+/// Where we are in an incoming escape sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum Escape {
+    None,
+    /// Seen ESC.
+    Start,
+    /// Seen ESC [, waiting for the final byte.
+    Csi,
+}
+
+/// A line editor holding up to `N` bytes per line, and remembering the
+/// last `H` lines.
+///
+/// Echo is sent to the writer `W`. Echo is best-effort: write errors
+/// are ignored, so a stalled output never loses input.
+#[derive(Debug)]
+pub struct LineEditor<W, const N: usize, const H: usize> {
+    echo: W,
+    line: [u8; N],
+    len: usize,
+    escape: Escape,
+    last_cr: bool,
+
+    history: [[u8; N]; H],
+    history_len: [usize; H],
+    // next history slot to write
+    history_head: usize,
+    // number of filled history slots
+    history_count: usize,
+    // how far back we are browsing, 0 is the line being edited
+    browse: usize,
+}
+
+impl<W, const N: usize, const H: usize> LineEditor<W, N, H>
+where
+    W: Write,
+{
+    /// Create a new, empty line editor echoing to `echo`.
+    pub fn new(echo: W) -> Self {
+        Self {
+            echo,
+            line: [0; N],
+            len: 0,
+            escape: Escape::None,
+            last_cr: false,
+
+            history: [[0; N]; H],
+            history_len: [0; H],
+            history_head: 0,
+            history_count: 0,
+            browse: 0,
+        }
+    }
+
+    /// Free the echo writer for use elsewhere.
+    pub fn free(self) -> W {
+        self.echo
+    }
+
+    /// The line being edited, so far.
+    pub fn line(&self) -> &str {
+        // only printable ASCII is ever stored
+        core::str::from_utf8(&self.line[..self.len]).unwrap_or_default()
+    }
+
+    /// Number of lines currently in the history.
+    pub fn history_len(&self) -> usize {
+        self.history_count
+    }
+
+    /// Get a line from the history. 0 is the most recent line.
+    pub fn history(&self, index: usize) -> Option<&str> {
+        if index >= self.history_count {
+            return None;
+        }
+        let slot = (self.history_head + H - 1 - index) % H;
+        core::str::from_utf8(&self.history[slot][..self.history_len[slot]]).ok()
+    }
+
+    /// Discard the line being edited, without echoing anything.
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.escape = Escape::None;
+        self.browse = 0;
+    }
+
+    /// Feed in one byte of input.
+    ///
+    /// Returns the completed line when `byte` is enter (`\r` or
+    /// `\n`). The line stays valid until the next call.
+    pub fn push(&mut self, byte: u8) -> Option<&str> {
+        let last_cr = core::mem::replace(&mut self.last_cr, false);
+
+        match self.escape {
+            Escape::None => (),
+            Escape::Start => {
+                if byte == b'[' {
+                    self.escape = Escape::Csi;
+                    return None;
+                }
+                // a lone ESC, treat this byte normally
+                self.escape = Escape::None;
+            }
+            Escape::Csi => {
+                // parameters and intermediates until a final byte
+                if (0x40..=0x7e).contains(&byte) {
+                    self.escape = Escape::None;
+                    match byte {
+                        b'A' => self.history_up(),
+                        b'B' => self.history_down(),
+                        _ => (),
+                    }
+                }
+                return None;
+            }
+        }
+
+        match byte {
+            b'\r' | b'\n' => {
+                if byte == b'\n' && last_cr {
+                    // second half of a \r\n
+                    return None;
+                }
+                self.last_cr = byte == b'\r';
+                self.write(NEWLINE);
+                return self.finish();
+            }
+            0x08 | 0x7f => {
+                if self.len > 0 {
+                    self.len -= 1;
+                    self.write(ERASE);
+                } else {
+                    self.write(BELL);
+                }
+            }
+            0x1b => self.escape = Escape::Start,
+            0x20..=0x7e if self.len < N => {
+                self.line[self.len] = byte;
+                self.len += 1;
+                self.write(&[byte]);
+            }
+            _ => self.write(BELL),
+        }
+
+        None
+    }
+
+    fn write(&mut self, data: &[u8]) {
+        // best effort, ignore errors
+        let _ = self.echo.write_all(data);
+    }
+
+    fn finish(&mut self) -> Option<&str> {
+        let len = core::mem::replace(&mut self.len, 0);
+        self.browse = 0;
+
+        if H > 0 && len > 0 {
+            let slot = self.history_head;
+            self.history[slot][..len].copy_from_slice(&self.line[..len]);
+            self.history_len[slot] = len;
+            self.history_head = (slot + 1) % H;
+            self.history_count = (self.history_count + 1).min(H);
+        }
+
+        core::str::from_utf8(&self.line[..len]).ok()
+    }
+
+    fn history_up(&mut self) {
+        if self.browse < self.history_count {
+            self.browse += 1;
+            self.recall();
+        } else {
+            self.write(BELL);
+        }
+    }
+
+    fn history_down(&mut self) {
+        if self.browse > 0 {
+            self.browse -= 1;
+            self.recall();
+        } else {
+            self.write(BELL);
+        }
+    }
+
+    // replace the line with the history entry we are browsing, or an
+    // empty line if we are back at the bottom
+    fn recall(&mut self) {
+        for _ in 0..self.len {
+            self.write(ERASE);
+        }
+
+        self.len = 0;
+        if self.browse > 0 {
+            let slot = (self.history_head + H - self.browse) % H;
+            let len = self.history_len[slot];
+            self.line[..len].copy_from_slice(&self.history[slot][..len]);
+            self.len = len;
+        }
+
+        let _ = self.echo.write_all(&self.line[..self.len]);
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+
+    use std::vec::Vec;
+
+    #[derive(Debug, Default)]
+    struct Echo(Vec<u8>);
+
+    impl embedded_io::ErrorType for Echo {
+        type Error = core::convert::Infallible;
+    }
+
+    impl Write for Echo {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.0.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    fn feed<const N: usize, const H: usize>(
+        editor: &mut LineEditor<Echo, N, H>,
+        input: &[u8],
+    ) -> Vec<std::string::String> {
+        let mut lines = Vec::new();
+        for b in input {
+            if let Some(line) = editor.push(*b) {
+                lines.push(line.into());
+            }
+        }
+        lines
+    }
+
+    #[test]
+    fn lines() {
+        let mut editor = LineEditor::<_, 16, 4>::new(Echo::default());
+        assert_eq!(
+            feed(&mut editor, b"hello\r\nworld\rpar"),
+            ["hello", "world"]
+        );
+        assert_eq!(editor.line(), "par");
+        assert_eq!(editor.free().0, b"hello\r\nworld\r\npar");
+    }
+
+    #[test]
+    fn backspace() {
+        let mut editor = LineEditor::<_, 16, 4>::new(Echo::default());
+        assert_eq!(feed(&mut editor, b"\x08abd\x7fc\r"), ["abc"]);
+        assert_eq!(editor.free().0, b"\x07abd\x08 \x08c\r\n");
+    }
+
+    #[test]
+    fn over_length() {
+        let mut editor = LineEditor::<_, 4, 4>::new(Echo::default());
+        assert_eq!(feed(&mut editor, b"abcdef\x08g\r"), ["abcg"]);
+        assert_eq!(editor.free().0, b"abcd\x07\x07\x08 \x08g\r\n");
+    }
+
+    #[test]
+    fn non_printable() {
+        let mut editor = LineEditor::<_, 16, 4>::new(Echo::default());
+        assert_eq!(feed(&mut editor, b"a\x01\xffb\r"), ["ab"]);
+    }
+
+    #[test]
+    fn history() {
+        let mut editor = LineEditor::<_, 16, 2>::new(Echo::default());
+        feed(&mut editor, b"one\rtwo\r\rthree\r");
+        assert_eq!(editor.history_len(), 2);
+        assert_eq!(editor.history(0), Some("three"));
+        assert_eq!(editor.history(1), Some("two"));
+        assert_eq!(editor.history(2), None);
+
+        // up twice, down once, and edit
+        assert_eq!(feed(&mut editor, b"x\x1b[A\x1b[A\x1b[B!\r"), ["three!"]);
+
+        // up past the end of history stays on the oldest
+        assert_eq!(feed(&mut editor, b"\x1b[A\x1b[A\x1b[A\r"), ["three"]);
+
+        // down back to the bottom is an empty line
+        assert_eq!(feed(&mut editor, b"\x1b[A\x1b[B\r"), [""]);
+
+        // other escape sequences are ignored
+        assert_eq!(feed(&mut editor, b"a\x1b[1;5Cb\r"), ["ab"]);
+    }
+
+    #[test]
+    fn no_history() {
+        let mut editor = LineEditor::<_, 16, 0>::new(Echo::default());
+        assert_eq!(feed(&mut editor, b"one\r\x1b[Atwo\r"), ["one", "two"]);
+        assert_eq!(editor.history_len(), 0);
+    }
+}
@@ -0,0 +1,62 @@
+//! Hardware CRC for [k5lib] frames.
+
+use core::cell::{RefCell, RefMut};
+
+use k5lib::protocol::crc::{CrcDigest, CrcStyle};
+
+use crate::hal::crc::{Crc, Digest};
+use crate::hal::power::Gate;
+use crate::pac;
+
+/// Set up the CRC unit to check host to radio frames.
+pub fn new(crc: pac::CRC, gate: Gate<pac::CRC>) -> CrcXModem {
+    CrcXModem::new(crc, gate)
+}
+
+/// A 16-bit XModem [CrcStyle], calculated by the CRC unit.
+///
+/// This is a drop-in replacement for [k5lib::protocol::crc::CrcXModem].
+/// Only one digest can be in progress at a time, starting a second
+/// will panic.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CrcXModem {
+    crc: RefCell<Crc>,
+}
+
+/// A 16-bit XModem [CrcDigest], calculated by the CRC unit.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CrcXModemDigest<'a>(Digest<RefMut<'a, Crc>>);
+
+impl CrcXModem {
+    /// Set up the CRC unit to check host to radio frames.
+    pub fn new(crc: pac::CRC, gate: Gate<pac::CRC>) -> Self {
+        Self {
+            crc: RefCell::new(Crc::xmodem(crc, gate)),
+        }
+    }
+
+    /// Free the CRC unit for use elsewhere.
+    pub fn free(self) -> Crc {
+        self.crc.into_inner()
+    }
+}
+
+impl CrcStyle for CrcXModem {
+    type Digest<'a> = CrcXModemDigest<'a>;
+
+    fn digest(&self) -> Self::Digest<'_> {
+        CrcXModemDigest(Digest::new(self.crc.borrow_mut()))
+    }
+}
+
+impl<'a> CrcDigest for CrcXModemDigest<'a> {
+    fn update(&mut self, bytes: &[u8]) {
+        self.0.update(bytes)
+    }
+
+    fn finalize(self) -> u16 {
+        self.0.finalize() as u16
+    }
+}
@@ -5,6 +5,7 @@ use eeprom24x::{
 };
 use embedded_hal_02::blocking::delay::DelayMs;
 use embedded_hal_02::timer::{CountDown, Periodic};
+use embedded_hal_1::i2c::I2c;
 
 use crate::hal::gpio::{OpenDrain, Output, SharedPin, PA10, PA11};
 use crate::shared_i2c::SharedI2c;
@@ -15,6 +16,12 @@ pub const SIZE: usize = 0x2000;
 /// The page size of this EEPROM in bytes.
 pub const PAGE_SIZE: usize = 32;
 
+/// EEPROM sizes [Eeprom::detect_size()] can tell apart, in bytes.
+pub const PROBE_SIZES: [usize; 5] = [0x2000, 0x4000, 0x8000, 0x10000, 0x20000];
+
+/// The I2C address of the EEPROM.
+const ADDRESS: u8 = 0x50;
+
 /// An EEPROM error.
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -46,6 +53,8 @@ pub type EepromI2c<'a, Timer> =
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Eeprom<'a, Timer> {
     eeprom: Eeprom24x<EepromI2c<'a, Timer>, B32, TwoBytes, No>,
+    // for probing past the end of the 24x64 the driver assumes
+    bus: EepromI2c<'a, Timer>,
 }
 
 /// Create an EEPROM interface from a shared bus.
@@ -63,6 +72,7 @@ where
     /// Create an EEPROM interface from a shared bus.
     pub fn new(i2c: EepromI2c<'a, Timer>) -> Self {
         Self {
+            bus: i2c.clone(),
             eeprom: Eeprom24x::new_24x64(i2c, SlaveAddr::default()),
         }
     }
@@ -72,6 +82,29 @@ where
         self.eeprom.destroy()
     }
 
+    /// Find the size of the EEPROM, one of [PROBE_SIZES].
+    ///
+    /// Smaller EEPROMs ignore the high address bits, so an address
+    /// just past the end reads back address 0. This reads both, and if
+    /// they differ, the EEPROM is bigger. If they match, it writes the
+    /// inverted byte past the end and checks whether address 0
+    /// changed.
+    ///
+    /// **This may write to the EEPROM.** Every byte written is
+    /// restored before returning, but losing power part way through
+    /// will corrupt address 0, or an address in the upper half. These
+    /// parts have no ID to read instead, so there is no
+    /// non-destructive variant.
+    ///
+    /// The rest of this interface still assumes [SIZE], so this is
+    /// only useful for bounding access by other means.
+    pub fn detect_size<Delay>(&mut self, delay: &mut Delay) -> Result<usize, Error>
+    where
+        Delay: DelayMs<u8>,
+    {
+        probe_size(&mut self.bus, delay)
+    }
+
     /// Read data from the eeprom.
     pub fn read(&mut self, address: usize, data: &mut [u8]) -> Result<(), Error> {
         Ok(self.eeprom.read_data(address as u32, data)?)
@@ -115,3 +148,150 @@ where
         Ok(())
     }
 }
+
+/// Read a byte with 17-bit addressing, the top bit in the I2C address.
+fn read_byte<I: I2c>(i2c: &mut I, address: usize) -> Result<u8, Error> {
+    let mut data = [0];
+    i2c.write_read(
+        ADDRESS | (address >> 16) as u8,
+        &(address as u16).to_be_bytes(),
+        &mut data,
+    )
+    .map_err(|_| Error::I2c)?;
+    Ok(data[0])
+}
+
+/// Write a byte with 17-bit addressing, and wait for it to finish.
+fn write_byte<I, Delay>(
+    i2c: &mut I,
+    delay: &mut Delay,
+    address: usize,
+    value: u8,
+) -> Result<(), Error>
+where
+    I: I2c,
+    Delay: DelayMs<u8>,
+{
+    let [hi, lo] = (address as u16).to_be_bytes();
+    i2c.write(ADDRESS | (address >> 16) as u8, &[hi, lo, value])
+        .map_err(|_| Error::I2c)?;
+    // eeprom takes 5ms, lets be safe
+    delay.delay_ms(6);
+    Ok(())
+}
+
+/// See [Eeprom::detect_size()].
+fn probe_size<I, Delay>(i2c: &mut I, delay: &mut Delay) -> Result<usize, Error>
+where
+    I: I2c,
+    Delay: DelayMs<u8>,
+{
+    let first = read_byte(i2c, 0)?;
+
+    let (&largest, smaller) = PROBE_SIZES.split_last().unwrap();
+    for &size in smaller {
+        let probe = match read_byte(i2c, size) {
+            // 128KiB parts answer on a second I2C address for the upper
+            // half, anything smaller does not
+            Err(_) if size >= 0x10000 => return Ok(size),
+            r => r?,
+        };
+        if probe != first {
+            continue;
+        }
+
+        // these might be the same byte, try changing one
+        write_byte(i2c, delay, size, !probe)?;
+        let aliased = read_byte(i2c, 0)? != first;
+        write_byte(i2c, delay, size, probe)?;
+        if aliased {
+            return Ok(size);
+        }
+    }
+
+    Ok(largest)
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+
+    use embedded_hal_1::i2c::{ErrorKind, ErrorType, NoAcknowledgeSource, Operation};
+    use std::vec::Vec;
+
+    struct NoDelay;
+
+    impl DelayMs<u8> for NoDelay {
+        fn delay_ms(&mut self, _ms: u8) {}
+    }
+
+    // a 24Cxx that ignores address bits past its size
+    struct MockEeprom {
+        memory: Vec<u8>,
+        address: usize,
+    }
+
+    impl MockEeprom {
+        fn new(size: usize) -> Self {
+            Self {
+                memory: (0..size).map(|i| (i * 7) as u8).collect(),
+                address: 0,
+            }
+        }
+    }
+
+    impl ErrorType for MockEeprom {
+        type Error = ErrorKind;
+    }
+
+    impl I2c for MockEeprom {
+        fn transaction(
+            &mut self,
+            address: u8,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            let high = match address {
+                0x50 => 0,
+                0x51 if self.memory.len() > 0x10000 => 0x10000,
+                _ => return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)),
+            };
+
+            for op in operations {
+                match op {
+                    Operation::Write(data) => {
+                        self.address = high | u16::from_be_bytes([data[0], data[1]]) as usize;
+                        for &b in &data[2..] {
+                            let len = self.memory.len();
+                            self.memory[self.address % len] = b;
+                            self.address += 1;
+                        }
+                    }
+                    Operation::Read(data) => {
+                        for b in data.iter_mut() {
+                            *b = self.memory[self.address % self.memory.len()];
+                            self.address += 1;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn detect_size() {
+        for size in PROBE_SIZES {
+            let mut eeprom = MockEeprom::new(size);
+            let original = eeprom.memory.clone();
+            assert_eq!(probe_size(&mut eeprom, &mut NoDelay).ok(), Some(size));
+            assert_eq!(eeprom.memory, original);
+
+            // worst case, where every probed byte matches address 0
+            let mut eeprom = MockEeprom::new(size);
+            eeprom.memory.fill(0x42);
+            assert_eq!(probe_size(&mut eeprom, &mut NoDelay).ok(), Some(size));
+            assert!(eeprom.memory.iter().all(|&b| b == 0x42));
+        }
+    }
+}
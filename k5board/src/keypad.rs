@@ -1,4 +1,9 @@
 //! Read the keypad and other buttons.
+//!
+//! The keypad is scanned as a grid of [ROWS] rows by [COLUMNS]
+//! columns. A [KeypadLayout] decides which [Key] sits at each scan
+//! position, so [Keypad::poll()] reports logical keys no matter how
+//! the keypad is wired. [Layout::STOCK] matches the stock UV-K5.
 
 use crate::bitflags;
 use crate::hal::gpio::{
@@ -81,6 +86,134 @@ bitflags! {
     }
 }
 
+/// Number of rows in the keypad scan.
+pub const ROWS: usize = 4;
+
+/// Number of columns in the keypad scan.
+///
+/// The last column is read with no column driven, and holds the
+/// buttons wired straight to ground, like the side buttons.
+pub const COLUMNS: usize = 5;
+
+/// A logical key, independent of where it is wired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Key {
+    Menu,
+    Up,
+    Down,
+    Exit,
+    Star,
+    Function,
+    N0,
+    N1,
+    N2,
+    N3,
+    N4,
+    N5,
+    N6,
+    N7,
+    N8,
+    N9,
+    Side1,
+    Side2,
+    Ptt,
+}
+
+impl Key {
+    /// The flag for this key in [State].
+    pub fn state(self) -> State {
+        match self {
+            Self::Menu => State::MENU,
+            Self::Up => State::UP,
+            Self::Down => State::DOWN,
+            Self::Exit => State::EXIT,
+            Self::Star => State::STAR,
+            Self::Function => State::FUNCTION,
+            Self::N0 => State::N0,
+            Self::N1 => State::N1,
+            Self::N2 => State::N2,
+            Self::N3 => State::N3,
+            Self::N4 => State::N4,
+            Self::N5 => State::N5,
+            Self::N6 => State::N6,
+            Self::N7 => State::N7,
+            Self::N8 => State::N8,
+            Self::N9 => State::N9,
+            Self::Side1 => State::SIDE1,
+            Self::Side2 => State::SIDE2,
+            Self::Ptt => State::PTT,
+        }
+    }
+}
+
+impl From<Key> for State {
+    fn from(key: Key) -> Self {
+        key.state()
+    }
+}
+
+/// Maps keypad scan positions to logical keys.
+pub trait KeypadLayout {
+    /// The key at `row` in scan column `col`, if any.
+    fn key(&self, row: usize, col: usize) -> Option<Key>;
+
+    /// Map a raw scan to logical keys.
+    ///
+    /// In `raw`, bit `col * ROWS + row` is set if the key at `row`
+    /// and `col` is down.
+    fn map(&self, raw: u32) -> State {
+        let mut state = State::empty();
+        for col in 0..COLUMNS {
+            for row in 0..ROWS {
+                if raw & (1 << (col * ROWS + row)) == 0 {
+                    continue;
+                }
+                if let Some(key) = self.key(row, col) {
+                    state |= key.state();
+                }
+            }
+        }
+        state
+    }
+}
+
+/// A keypad layout given as a table, indexed by column then row.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Layout(pub [[Option<Key>; ROWS]; COLUMNS]);
+
+impl Layout {
+    /// The stock UV-K5 keypad layout.
+    pub const STOCK: Self = {
+        use Key::*;
+        Self([
+            // PA10 active
+            [Some(Menu), Some(N1), Some(N4), Some(N7)],
+            // PA11 active
+            [Some(Up), Some(N2), Some(N5), Some(N8)],
+            // PA12 active
+            [Some(Down), Some(N3), Some(N6), Some(N9)],
+            // PA13 active
+            [Some(Exit), Some(Star), Some(N0), Some(Function)],
+            // sporadics, none active
+            [Some(Side1), Some(Side2), None, None],
+        ])
+    };
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::STOCK
+    }
+}
+
+impl KeypadLayout for Layout {
+    fn key(&self, row: usize, col: usize) -> Option<Key> {
+        *self.0.get(col)?.get(row)?
+    }
+}
+
 // useful methods, but way too much duplication, so use a macro
 macro_rules! helper {
     ($doc:expr, $name:ident, $flag:ident) => {
@@ -164,8 +297,9 @@ impl State {
 /// Using powers of two for DEBOUNCE can avoid a modulus operation.
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct Keypad<const DEBOUNCE: usize = { 1 << 3 }> {
+pub struct Keypad<const DEBOUNCE: usize = { 1 << 3 }, L = Layout> {
     pins: Parts,
+    layout: L,
     history: [State; DEBOUNCE],
     next: usize,
     state: State,
@@ -255,10 +389,23 @@ where
 }
 
 impl<const DEBOUNCE: usize> Keypad<DEBOUNCE> {
-    /// Create the keypad interface from the keypad pins.
+    /// Create the keypad interface from the keypad pins, with the
+    /// stock layout.
     pub fn new(parts: Parts) -> Self {
+        Self::with_layout(parts, Layout::STOCK)
+    }
+}
+
+impl<const DEBOUNCE: usize, L> Keypad<DEBOUNCE, L>
+where
+    L: KeypadLayout,
+{
+    /// Create the keypad interface from the keypad pins, with a
+    /// custom layout.
+    pub fn with_layout(parts: Parts, layout: L) -> Self {
         Self {
             pins: parts,
+            layout,
             history: [State::empty(); DEBOUNCE],
             next: 0,
             state: State::empty(),
@@ -272,6 +419,11 @@ impl<const DEBOUNCE: usize> Keypad<DEBOUNCE> {
         self.pins
     }
 
+    /// Get the keypad layout.
+    pub fn layout(&self) -> &L {
+        &self.layout
+    }
+
     /// Get the shared I2C SCL pin.
     pub fn get_shared_scl(&self) -> &SharedPin<PA10<Output<OpenDrain>>> {
         &self.pins.col.0
@@ -292,16 +444,16 @@ impl<const DEBOUNCE: usize> Keypad<DEBOUNCE> {
         &self.pins.col.3
     }
 
-    // scan the keys, returning a raw, bouncy state
-    fn scan(row: Row, mut col: Col<OpenDrain>) -> State {
-        let mut state = State::empty();
+    // scan the keys, returning raw, bouncy scan positions
+    fn scan(row: Row, mut col: Col<OpenDrain>) -> u32 {
+        let mut raw = 0;
 
         let mut mask = 0b1111;
         col.scan(|idx| {
             // read the rows
             let bits = row.read(mask);
-            // sporadics (None) maps to shifting by 16
-            state |= State::from_bits_truncate(bits << (idx.unwrap_or(4) << 2));
+            // sporadics (None) maps to the last column
+            raw |= bits << (idx.unwrap_or(4) << 2);
             if idx.is_none() {
                 // the sporadics affect all reads after this, so mask them out
                 mask &= !bits;
@@ -330,7 +482,7 @@ impl<const DEBOUNCE: usize> Keypad<DEBOUNCE> {
         vclk.set_low();
         vdata.set_high();
 
-        state
+        raw
     }
 
     /// Poll the keypad, returning any newly-pressed keys.
@@ -344,12 +496,13 @@ impl<const DEBOUNCE: usize> Keypad<DEBOUNCE> {
             &self.pins.row.3,
         );
 
-        let mut state = self.pins.col.2.with(|c2| {
+        let raw = self.pins.col.2.with(|c2| {
             self.pins.col.3.with(|c3| {
                 let mut col = Col(&mut self.pins.col.0, &mut self.pins.col.1, c2, c3);
                 col.with_open_drain(|col| Self::scan(row, col))
             })
         });
+        let mut state = self.layout.map(raw);
 
         // shuffle on PTT
         if self.pins.ptt.is_low() {
@@ -399,3 +552,44 @@ impl<const DEBOUNCE: usize> Keypad<DEBOUNCE> {
         self.down | self.up
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+
+    // raw scan bit for a position
+    fn pos(row: usize, col: usize) -> u32 {
+        1 << (col * ROWS + row)
+    }
+
+    #[test]
+    fn stock_layout() {
+        // the stock layout matches the raw bit order of State
+        for bit in 0..(ROWS * COLUMNS) {
+            let raw = 1 << bit;
+            assert_eq!(Layout::STOCK.map(raw).bits(), raw & State::all().bits());
+        }
+        assert_eq!(Layout::STOCK.key(3, 3), Some(Key::Function));
+        assert_eq!(Layout::STOCK.key(2, 4), None);
+        assert_eq!(Layout::STOCK.key(0, 5), None);
+    }
+
+    #[test]
+    fn custom_layout() {
+        // rotate the number pad, and swap the side buttons
+        let mut layout = Layout::STOCK;
+        layout.0[0] = [Some(Key::Menu), Some(Key::N7), Some(Key::N4), Some(Key::N1)];
+        layout.0[1] = [Some(Key::Up), Some(Key::N8), Some(Key::N5), Some(Key::N2)];
+        layout.0[2] = [Some(Key::Down), Some(Key::N9), Some(Key::N6), Some(Key::N3)];
+        layout.0[4] = [Some(Key::Side2), Some(Key::Side1), None, None];
+
+        let raw = pos(1, 0) | pos(3, 2) | pos(0, 4) | pos(2, 4);
+        assert_eq!(layout.map(raw), State::N7 | State::N3 | State::SIDE2);
+        assert_eq!(Layout::STOCK.map(raw), State::N1 | State::N9 | State::SIDE1);
+
+        // unmapped positions are dropped
+        layout.0[3][1] = None;
+        assert_eq!(layout.map(pos(1, 3) | pos(2, 3)), State::N0);
+    }
+}
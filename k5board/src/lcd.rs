@@ -1,32 +1,87 @@
 //! LCD control.
+//!
+//! Nothing drawn shows up on the screen until the framebuffer is
+//! flushed. [Lcd::flush()] sends the whole frame before returning,
+//! which takes a few milliseconds at [SPI_BAUD].
+//!
+//! With the `lcd-poll-flush` feature, there is also a non-blocking
+//! flush. To do other work meanwhile, use `Lcd::start_flush()` and
+//! then call `Lcd::poll_flush()` regularly until it is done:
+//!
+//! ```ignore
+//! lcd.start_flush()?;
+//! while lcd.poll_flush().is_err() {
+//!     // ... service the radio ...
+//! }
+//! ```
+//!
+//! Each call to `Lcd::poll_flush()` sends a small chunk of at most
+//! `FLUSH_CHUNK` bytes. The frame is copied into a queue of about a
+//! kilobyte when the flush starts, so the framebuffer can be drawn on
+//! right away. Other LCD commands issued during a flush wait for it
+//! to finish first. Without the feature, there is no queue, and
+//! everything is sent straight to the LCD.
+//!
+//! Firmware that redraws the same screen over and over can also turn
+//! on `Lcd::set_skip_unchanged()`, so that flushing a frame identical
+//! to the last one sends nothing. This costs a checksum of the frame
+//! on every flush.
 
 use core::cell::UnsafeCell;
 use core::convert::Infallible;
 
-use display_interface_spi::SPIInterface;
+use display_interface::{DataFormat, WriteOnlyDataCommand};
 use embedded_graphics_core::draw_target::DrawTarget;
 use embedded_graphics_core::geometry::{OriginDimensions, Size};
 use embedded_graphics_core::pixelcolor::BinaryColor;
 use embedded_graphics_core::primitives::Rectangle;
 use embedded_graphics_core::Pixel;
 use embedded_hal_02::blocking::delay::DelayMs;
+use embedded_hal_1::digital::OutputPin;
+use embedded_hal_1::spi::{ErrorType, Operation, SpiBus, SpiDevice};
 use st7565::modes::GraphicsMode;
 use st7565::types::{BoosterRatio, PowerControlMode};
 use st7565::{GraphicsPageBuffer, ST7565};
 
+#[cfg(feature = "lcd-poll-flush")]
+use crate::hal::block;
 use crate::hal::gpio::alt::spi0;
 use crate::hal::gpio::{Alternate, Output, PushPull, PB10, PB11, PB7, PB8, PB9};
 use crate::hal::power::Gate;
 use crate::hal::spi;
+use crate::hal::time::Hertz;
 use crate::pac::portcon::portb_sel1;
 use crate::pac::SPI0;
 
+#[cfg(feature = "lcd-poll-flush")]
+mod queue;
+#[cfg(feature = "lcd-poll-flush")]
+use queue::{Capture, QUEUE};
+
+#[cfg(feature = "std")]
+mod sim;
+#[cfg(feature = "std")]
+pub use sim::LcdSim;
+
 /// The width of the LCD.
 pub const WIDTH: usize = 128;
 
 /// The height of the LCD.
 pub const HEIGHT: usize = 64;
 
+/// The SPI clock used for the LCD.
+///
+/// This is what the old fixed divider of 16 gave at the default 48MHz
+/// system clock, well within what the ST7565 accepts. The actual
+/// clock may be slower, but never faster.
+pub const SPI_BAUD: Hertz = Hertz::MHz(3);
+
+/// The most bytes sent by one call to [Lcd::poll_flush()].
+///
+/// At [SPI_BAUD], this takes under 100us.
+#[cfg(feature = "lcd-poll-flush")]
+pub const FLUSH_CHUNK: usize = 32;
+
 /// Number of pages in the display buffer.
 const PAGES: usize = HEIGHT / 8;
 
@@ -50,6 +105,152 @@ pub struct Parts {
     pub res: PB11<Output<PushPull>>,
 }
 
+/// The pins required for an LCD on an SPI bus shared with other devices.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DeviceParts<Spi> {
+    /// The SPI device, which must handle the LCD chip select pin
+    /// (PB7) itself. The bus must be in mode 3, MSB first.
+    pub spi: Spi,
+    /// The LCD A0 pin.
+    pub a0: PB9<Output<PushPull>>,
+    /// The LCD reset pin.
+    pub res: PB11<Output<PushPull>>,
+}
+
+/// The SPI device used by [Lcd::new()]: SPI0 in write-only master
+/// mode, with chip select on PB7.
+pub type OwnedSpi = CsDevice<spi::MasterTx<SPI0>, PB7<Output<PushPull>>>;
+
+/// An SPI device made of a bus it owns outright, and a GPIO chip
+/// select pin that is held low during each transaction.
+///
+/// There is no delay available, so [Operation::DelayNs] panics.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CsDevice<Bus, Cs> {
+    bus: Bus,
+    cs: Cs,
+}
+
+impl<Bus, Cs> CsDevice<Bus, Cs>
+where
+    Bus: SpiBus,
+    Cs: OutputPin<Error = Infallible>,
+{
+    /// Create a new device, deselecting it.
+    pub fn new(bus: Bus, mut cs: Cs) -> Self {
+        cs.set_high().unwrap_or_else(|e| match e {});
+        Self { bus, cs }
+    }
+
+    /// Recover the bus and chip select pin.
+    pub fn release(self) -> (Bus, Cs) {
+        (self.bus, self.cs)
+    }
+}
+
+impl<Bus, Cs> ErrorType for CsDevice<Bus, Cs>
+where
+    Bus: SpiBus,
+{
+    type Error = Bus::Error;
+}
+
+impl<Bus, Cs> SpiDevice for CsDevice<Bus, Cs>
+where
+    Bus: SpiBus,
+    Cs: OutputPin<Error = Infallible>,
+{
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        self.cs.set_low().unwrap_or_else(|e| match e {});
+        let result = operations.iter_mut().try_for_each(|op| match op {
+            Operation::Read(buf) => self.bus.read(buf),
+            Operation::Write(buf) => self.bus.write(buf),
+            Operation::Transfer(read, write) => self.bus.transfer(read, write),
+            Operation::TransferInPlace(buf) => self.bus.transfer_in_place(buf),
+            Operation::DelayNs(_) => unimplemented!("CsDevice has no delay"),
+        });
+        // always release chip select, even after an error
+        let flushed = self.bus.flush();
+        self.cs.set_high().unwrap_or_else(|e| match e {});
+        result.and(flushed)
+    }
+}
+
+/// A display interface for the ST7565 on top of an SPI device.
+///
+/// The ST7565 uses its A0 line to tell commands from pixel data, but
+/// A0 is not part of the SPI device abstraction. A0 is set before
+/// each SPI transaction and left alone afterwards, so any other
+/// device sharing the bus must ignore it. Chip select is handled
+/// entirely by the SPI device, and is only active during a transaction.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Interface<Spi, A0> {
+    spi: Spi,
+    a0: A0,
+}
+
+impl<Spi, A0> Interface<Spi, A0>
+where
+    Spi: SpiDevice,
+    A0: OutputPin,
+{
+    /// Create a new interface from an SPI device and an A0 pin.
+    pub fn new(spi: Spi, a0: A0) -> Self {
+        Self { spi, a0 }
+    }
+
+    /// Recover the SPI device and A0 pin.
+    pub fn release(self) -> (Spi, A0) {
+        (self.spi, self.a0)
+    }
+
+    fn send(&mut self, words: DataFormat<'_>) -> Result<(), Error> {
+        match words {
+            DataFormat::U8(buf) => self.spi.write(buf).map_err(|_| Error::BusWriteError),
+            DataFormat::U8Iter(iter) => {
+                // one transaction per chunk
+                let mut buf = [0; 32];
+                let mut i = 0;
+                for v in iter {
+                    buf[i] = v;
+                    i += 1;
+                    if i == buf.len() {
+                        self.spi.write(&buf).map_err(|_| Error::BusWriteError)?;
+                        i = 0;
+                    }
+                }
+                if i > 0 {
+                    self.spi
+                        .write(&buf[..i])
+                        .map_err(|_| Error::BusWriteError)?;
+                }
+                Ok(())
+            }
+            // st7565 only ever sends bytes
+            _ => Err(Error::DataFormatNotImplemented),
+        }
+    }
+}
+
+impl<Spi, A0> WriteOnlyDataCommand for Interface<Spi, A0>
+where
+    Spi: SpiDevice,
+    A0: OutputPin,
+{
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), Error> {
+        self.a0.set_low().map_err(|_| Error::DCError)?;
+        self.send(cmd)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), Error> {
+        self.a0.set_high().map_err(|_| Error::DCError)?;
+        self.send(buf)
+    }
+}
+
 /// The LCD's display spec for st7565 crate.
 #[derive(Debug, Clone, Copy, Default)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -74,19 +275,30 @@ impl st7565::DisplaySpecs<WIDTH, HEIGHT, PAGES> for DisplaySpec {
 /// The LCD interface.
 ///
 /// This can be used as a draw target for the embedded-graphics crate.
-pub struct Lcd {
-    #[allow(clippy::type_complexity)]
-    lcd: ST7565<
-        SPIInterface<spi::MasterTx<SPI0>, PB9<Output<PushPull>>, PB7<Output<PushPull>>>,
-        DisplaySpec,
-        GraphicsMode<'static, WIDTH, PAGES>,
-        WIDTH,
-        HEIGHT,
-        PAGES,
-    >,
+///
+/// By default this owns SPI0, see [Lcd::new()]. To share the bus with
+/// other devices, use [Lcd::new_device()] with any SPI device, such as
+/// those provided by the embedded-hal-bus crate.
+pub struct Lcd<Spi = OwnedSpi> {
+    #[cfg(not(feature = "lcd-poll-flush"))]
+    lcd: Driver<Interface<Spi, PB9<Output<PushPull>>>>,
+    // the driver writes into the queue, and we send it on from there
+    #[cfg(feature = "lcd-poll-flush")]
+    lcd: Driver<Capture>,
+    #[cfg(feature = "lcd-poll-flush")]
+    interface: Interface<Spi, PB9<Output<PushPull>>>,
     res: PB11<Output<PushPull>>,
+    // checksum of the last frame flushed, if skipping unchanged frames
+    #[cfg(feature = "lcd-poll-flush")]
+    skip_unchanged: bool,
+    #[cfg(feature = "lcd-poll-flush")]
+    last_frame: Option<u32>,
 }
 
+/// The st7565 driver, writing to display interface `DI`.
+type Driver<DI> =
+    ST7565<DI, DisplaySpec, GraphicsMode<'static, WIDTH, PAGES>, WIDTH, HEIGHT, PAGES>;
+
 /// An LCD error.
 pub type Error = display_interface::DisplayError;
 
@@ -99,94 +311,160 @@ where
 }
 
 impl Lcd {
-    /// Create a new LCD from parts.
+    /// Create a new LCD from parts, owning SPI0.
     pub fn new<Delay>(delay: &mut Delay, parts: Parts) -> Result<Self, (Parts, Error)>
     where
         Delay: DelayMs<u8>,
     {
         let spi = spi::new(parts.spi, parts.gate)
-            .divider(spi::ClockDivider::Div16)
+            .baud_rate(SPI_BAUD)
+            // only fails for system clocks over 512 * SPI_BAUD
+            .unwrap_or_else(|_| unreachable!())
             .mode(spi::Mode::MODE_3)
             .bit_order(spi::BitOrder::Msb)
             .master_tx(parts.clk.into(), parts.mosi.into());
 
-        let lcd_interface = SPIInterface::new(spi, parts.a0, parts.cs);
+        let device_parts = DeviceParts {
+            spi: CsDevice::new(spi, parts.cs),
+            a0: parts.a0,
+            res: parts.res,
+        };
+
+        Self::new_device(delay, device_parts).map_err(|(p, e)| (Self::free_owned(p), e))
+    }
+
+    /// Free the components of the LCD.
+    pub fn free(self) -> Parts {
+        Self::free_owned(self.free_device())
+    }
+
+    fn free_owned(parts: DeviceParts<OwnedSpi>) -> Parts {
+        let (spi, cs) = parts.spi.release();
+        let (config, clk, (), mosi, ()) = spi.free();
+        let (spi, gate) = config.free();
+        Parts {
+            spi,
+            gate,
+            cs,
+            clk: match clk {
+                spi0::Clk::PB8(pb8) => pb8,
+                _ => unreachable!(), // not set in new()
+            },
+            a0: parts.a0,
+            mosi: match mosi {
+                spi0::Mosi::PB10(pb10) => pb10,
+                _ => unreachable!(), // not set in new()
+            },
+            res: parts.res,
+        }
+    }
+}
+
+impl<Spi> Lcd<Spi>
+where
+    Spi: SpiDevice,
+{
+    /// Create a new LCD from an SPI device and pins.
+    pub fn new_device<Delay>(
+        delay: &mut Delay,
+        parts: DeviceParts<Spi>,
+    ) -> Result<Self, (DeviceParts<Spi>, Error)>
+    where
+        Delay: DelayMs<u8>,
+    {
+        let interface = Interface::new(parts.spi, parts.a0);
 
         // use a static backing buffer, no matter where this struct ends up
         static mut PAGE_BUFFER: UnsafeCell<GraphicsPageBuffer<WIDTH, PAGES>> =
             UnsafeCell::new(GraphicsPageBuffer::new());
 
-        // safety: we possess multiple unique tokens that ensure this static
-        // cannot be borrowed more than once.
-        // free() returns these tokens while also dropping this borrow.
+        #[cfg(not(feature = "lcd-poll-flush"))]
+        let driver = interface;
+        // the same tokens also make us the only user of the queue,
+        // but it may hold leftovers from a previous Lcd
+        #[cfg(feature = "lcd-poll-flush")]
+        let driver = {
+            QUEUE.lock().clear();
+            Capture::new()
+        };
+
+        // safety: we possess multiple unique tokens (the A0 and reset
+        // pins) that ensure this static cannot be borrowed more than once.
+        // free_device() returns these tokens while also dropping this borrow.
         // we are relying on st7565 crate not to stash this reference
         // somewhere unexpected.
         let lcd = unsafe {
-            ST7565::new(lcd_interface, DisplaySpec)
-                .into_graphics_mode(PAGE_BUFFER.get().as_mut().unwrap())
+            ST7565::new(driver, DisplaySpec).into_graphics_mode(PAGE_BUFFER.get().as_mut().unwrap())
         };
 
         let mut lcd = Self {
             lcd,
+            #[cfg(feature = "lcd-poll-flush")]
+            interface,
             res: parts.res,
+            #[cfg(feature = "lcd-poll-flush")]
+            skip_unchanged: false,
+            #[cfg(feature = "lcd-poll-flush")]
+            last_frame: None,
         };
 
         if let Err(e) = lcd.reset(delay) {
-            return Err((lcd.free(), e));
+            return Err((lcd.free_device(), e));
         }
 
         if let Err(e) = lcd.flush() {
-            return Err((lcd.free(), e));
+            return Err((lcd.free_device(), e));
         }
 
         if let Err(e) = lcd.set_display_on(true) {
-            return Err((lcd.free(), e));
+            return Err((lcd.free_device(), e));
         }
 
         Ok(lcd)
     }
 
-    /// Free the components of the LCD.
-    pub fn free(self) -> Parts {
-        let (_, lcd_interface) = self.lcd.release_display_interface();
-        let (spi, a0, cs) = lcd_interface.release();
-        let (config, clk, (), mosi, ()) = spi.free();
-        let (spi, gate) = config.free();
-        Parts {
+    /// Free the SPI device and pins of the LCD.
+    ///
+    /// Anything not yet sent to the LCD, including an unfinished
+    /// flush, is discarded.
+    pub fn free_device(self) -> DeviceParts<Spi> {
+        #[cfg(feature = "lcd-poll-flush")]
+        let interface = {
+            QUEUE.lock().clear();
+            self.interface
+        };
+        #[cfg(not(feature = "lcd-poll-flush"))]
+        let (_, interface) = self.lcd.release_display_interface();
+        let (spi, a0) = interface.release();
+        DeviceParts {
             spi,
-            gate,
-            cs,
-            clk: match clk {
-                spi0::Clk::PB8(pb8) => pb8,
-                _ => unreachable!(), // not set in new()
-            },
             a0,
-            mosi: match mosi {
-                spi0::Mosi::PB10(pb10) => pb10,
-                _ => unreachable!(), // not set in new()
-            },
             res: self.res,
         }
     }
 
     /// Sets the line offset, effectively scrolling the display through memory.
     pub fn set_line_offset(&mut self, offset: u8) -> Result<(), Error> {
-        self.lcd.set_line_offset(offset)
+        self.lcd.set_line_offset(offset)?;
+        self.wait_flush()
     }
 
     /// Sets whether the pixels should be inverted.
     pub fn set_inverted(&mut self, inverted: bool) -> Result<(), Error> {
-        self.lcd.set_inverted(inverted)
+        self.lcd.set_inverted(inverted)?;
+        self.wait_flush()
     }
 
     /// Displays all points of the display.
     pub fn display_all_points(&mut self, enable: bool) -> Result<(), Error> {
-        self.lcd.display_all_points(enable)
+        self.lcd.display_all_points(enable)?;
+        self.wait_flush()
     }
 
     /// Enable/disable the display output.
     pub fn set_display_on(&mut self, on: bool) -> Result<(), Error> {
-        self.lcd.set_display_on(on)
+        self.lcd.set_display_on(on)?;
+        self.wait_flush()
     }
 
     /// Reset the LCD.
@@ -194,25 +472,127 @@ impl Lcd {
     where
         Delay: DelayMs<u8>,
     {
+        // the reset pin must not move in the middle of a flush
+        self.wait_flush()?;
+        // the screen contents are gone
+        #[cfg(feature = "lcd-poll-flush")]
+        {
+            self.last_frame = None;
+        }
         self.lcd.reset(&mut self.res, delay).map_err(|e| match e {
             st7565::Error::Comm(comm) => comm,
             st7565::Error::Pin(pin) => match pin {},
-        })
+        })?;
+        self.wait_flush()
     }
 
     /// Write the LCD framebuffer to the screen.
+    #[cfg(not(feature = "lcd-poll-flush"))]
     pub fn flush(&mut self) -> Result<(), Error> {
         self.lcd.flush()
     }
+
+    /// Write the LCD framebuffer to the screen.
+    ///
+    /// This goes through the flush queue, like [Self::start_flush()].
+    #[cfg(feature = "lcd-poll-flush")]
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.start_flush()?;
+        self.wait_flush()
+    }
+
+    // without the queue, everything is sent right away
+    #[cfg(not(feature = "lcd-poll-flush"))]
+    fn wait_flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
 }
 
-impl OriginDimensions for Lcd {
+#[cfg(feature = "lcd-poll-flush")]
+impl<Spi> Lcd<Spi>
+where
+    Spi: SpiDevice,
+{
+    /// Write the LCD framebuffer to the screen, even if it is
+    /// unchanged. See [Self::set_skip_unchanged()].
+    pub fn force_flush(&mut self) -> Result<(), Error> {
+        self.last_frame = None;
+        self.flush()
+    }
+
+    /// Start writing the LCD framebuffer to the screen.
+    ///
+    /// This only copies the frame, and returns immediately. Use
+    /// [Self::poll_flush()] to send it. If a flush is already in
+    /// progress, this waits for it to finish first.
+    ///
+    /// If [Self::set_skip_unchanged()] is on and the frame is the
+    /// same as the last one, nothing is sent, and the flush is done
+    /// right away.
+    pub fn start_flush(&mut self) -> Result<(), Error> {
+        self.wait_flush()?;
+        if let Err(e) = self.lcd.flush() {
+            // whatever is on the screen now, it is not the last frame
+            self.last_frame = None;
+            return Err(e);
+        }
+        if self.skip_unchanged {
+            QUEUE.lock().drop_unchanged(&mut self.last_frame);
+        }
+        Ok(())
+    }
+
+    /// Skip flushes of frames identical to the last one flushed.
+    ///
+    /// This is off by default. When on, each flush takes a checksum
+    /// of the frame, and sends nothing if it matches the last. Use
+    /// [Self::force_flush()] to send the frame regardless.
+    pub fn set_skip_unchanged(&mut self, skip: bool) {
+        self.skip_unchanged = skip;
+        self.last_frame = None;
+    }
+
+    /// Send the next chunk of a flush started with [Self::start_flush()].
+    ///
+    /// Returns [block::Error::WouldBlock] until the flush is done.
+    pub fn poll_flush(&mut self) -> block::Result<(), Error> {
+        let mut queue = QUEUE.lock();
+        if let Err(e) = queue.send(&mut self.interface, FLUSH_CHUNK) {
+            // a frame cut short is not worth skipping next time
+            self.last_frame = None;
+            return Err(block::Error::Other(e));
+        }
+        if queue.is_empty() {
+            Ok(())
+        } else {
+            Err(block::Error::WouldBlock)
+        }
+    }
+
+    /// Is there no flush in progress?
+    pub fn flush_done(&self) -> bool {
+        QUEUE.lock().is_empty()
+    }
+
+    /// Block until any flush in progress is done.
+    pub fn wait_flush(&mut self) -> Result<(), Error> {
+        block::block!(self.poll_flush())
+    }
+}
+
+impl<Spi> OriginDimensions for Lcd<Spi>
+where
+    Spi: SpiDevice,
+{
     fn size(&self) -> Size {
         self.lcd.size()
     }
 }
 
-impl DrawTarget for Lcd {
+impl<Spi> DrawTarget for Lcd<Spi>
+where
+    Spi: SpiDevice,
+{
     type Color = BinaryColor;
     type Error = Infallible;
 
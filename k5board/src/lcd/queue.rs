@@ -0,0 +1,369 @@
+//! A queue of LCD commands and data, waiting to be sent.
+//!
+//! The st7565 driver writes to the display interface synchronously,
+//! with no way to pause a flush part way through. Instead, it writes
+//! into this queue, and [Lcd][super::Lcd] sends the queue over SPI in
+//! small chunks, as fast or as slow as the firmware likes.
+//!
+//! Everything is copied into the queue, including the whole frame
+//! during a flush. This costs about a kilobyte of RAM, but it means
+//! the framebuffer is free to be drawn on again as soon as the flush
+//! has started. Since not all firmware wants that trade, the queue
+//! only exists with the `lcd-poll-flush` feature.
+
+use display_interface::{DataFormat, WriteOnlyDataCommand};
+
+use super::{Error, PAGES, WIDTH};
+
+/// Bytes the queue can hold: a whole frame, plus room for commands.
+const QUEUE_LEN: usize = PAGES * (WIDTH + 8);
+
+/// Runs the queue can hold, where A0 is the same for every byte in
+/// a run. A flush needs two runs per page.
+const RUNS: usize = 4 * PAGES + 8;
+
+/// The queue shared between [Lcd][super::Lcd] and its [Capture].
+pub(super) static QUEUE: spin::Mutex<Queue> = spin::Mutex::new(Queue::new());
+
+/// A run of bytes that all share an A0 level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Run {
+    /// A0 high, this is pixel data rather than commands.
+    data: bool,
+    /// End of this run in the queue.
+    end: usize,
+}
+
+/// A fixed-size queue of commands and data.
+#[derive(Debug)]
+pub(super) struct Queue {
+    bytes: [u8; QUEUE_LEN],
+    len: usize,
+    runs: [Run; RUNS],
+    runs_len: usize,
+
+    // how far through the queue we have sent
+    sent: usize,
+    run: usize,
+}
+
+impl Queue {
+    const fn new() -> Self {
+        Self {
+            bytes: [0; QUEUE_LEN],
+            len: 0,
+            runs: [Run {
+                data: false,
+                end: 0,
+            }; RUNS],
+            runs_len: 0,
+
+            sent: 0,
+            run: 0,
+        }
+    }
+
+    /// Is everything in the queue sent?
+    pub(super) fn is_empty(&self) -> bool {
+        self.sent == self.len
+    }
+
+    /// Forget everything in the queue, sent or not.
+    pub(super) fn clear(&mut self) {
+        self.len = 0;
+        self.runs_len = 0;
+        self.sent = 0;
+        self.run = 0;
+    }
+
+    /// Add commands (A0 low) or data (A0 high) to the queue.
+    ///
+    /// If they do not all fit, nothing is added and this returns
+    /// [Error::OutOfBoundsError].
+    pub(super) fn push(&mut self, data: bool, words: DataFormat<'_>) -> Result<(), Error> {
+        let start = self.len;
+        if let Err(e) = self.push_words(words) {
+            self.len = start;
+            return Err(e);
+        }
+
+        if self.len == start {
+            // nothing to add
+            return Ok(());
+        }
+
+        if self.runs_len > self.run && self.runs[self.runs_len - 1].data == data {
+            // extend the last run
+            self.runs[self.runs_len - 1].end = self.len;
+        } else if self.runs_len < RUNS {
+            self.runs[self.runs_len] = Run {
+                data,
+                end: self.len,
+            };
+            self.runs_len += 1;
+        } else {
+            self.len = start;
+            return Err(Error::OutOfBoundsError);
+        }
+
+        Ok(())
+    }
+
+    fn push_words(&mut self, words: DataFormat<'_>) -> Result<(), Error> {
+        match words {
+            DataFormat::U8(buf) => {
+                for b in buf {
+                    self.push_byte(*b)?;
+                }
+            }
+            DataFormat::U8Iter(iter) => {
+                for b in iter {
+                    self.push_byte(b)?;
+                }
+            }
+            // st7565 only ever sends bytes
+            _ => return Err(Error::DataFormatNotImplemented),
+        }
+        Ok(())
+    }
+
+    fn push_byte(&mut self, byte: u8) -> Result<(), Error> {
+        let slot = self
+            .bytes
+            .get_mut(self.len)
+            .ok_or(Error::OutOfBoundsError)?;
+        *slot = byte;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Get up to `max` of the next bytes to send, all sharing the same
+    /// A0 level, or [None] if the queue is empty.
+    ///
+    /// Call [Self::advance()] once they are sent.
+    pub(super) fn chunk(&self, max: usize) -> Option<(bool, &[u8])> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let run = self.runs[self.run];
+        let end = run.end.min(self.sent + max);
+        Some((run.data, &self.bytes[self.sent..end]))
+    }
+
+    /// Mark `amount` bytes from [Self::chunk()] as sent.
+    pub(super) fn advance(&mut self, amount: usize) {
+        self.sent += amount;
+        if self.sent >= self.runs[self.run].end {
+            self.run += 1;
+        }
+
+        if self.is_empty() {
+            // everything is sent, start over at the beginning
+            self.clear();
+        }
+    }
+
+    /// An FNV-1a checksum of everything not yet sent.
+    pub(super) fn checksum(&self) -> u32 {
+        self.bytes[self.sent..self.len]
+            .iter()
+            .fold(0x811c9dc5, |h, b| (h ^ *b as u32).wrapping_mul(0x01000193))
+    }
+
+    /// Forget everything not yet sent if its checksum is `last`, and
+    /// update `last` to the new checksum. Returns [true] if anything
+    /// was kept.
+    pub(super) fn drop_unchanged(&mut self, last: &mut Option<u32>) -> bool {
+        let checksum = self.checksum();
+        let changed = *last != Some(checksum);
+        if !changed {
+            self.clear();
+        }
+        *last = Some(checksum);
+        changed
+    }
+
+    /// Send up to `max` bytes from the queue over `interface`.
+    pub(super) fn send<I>(&mut self, interface: &mut I, max: usize) -> Result<(), Error>
+    where
+        I: WriteOnlyDataCommand,
+    {
+        let Some((data, chunk)) = self.chunk(max) else {
+            return Ok(());
+        };
+
+        let amount = chunk.len();
+        if data {
+            interface.send_data(DataFormat::U8(chunk))?;
+        } else {
+            interface.send_commands(DataFormat::U8(chunk))?;
+        }
+        self.advance(amount);
+        Ok(())
+    }
+}
+
+/// A display interface for the st7565 driver that writes into [QUEUE].
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(super) struct Capture {
+    _priv: (),
+}
+
+impl Capture {
+    pub(super) fn new() -> Self {
+        Self { _priv: () }
+    }
+}
+
+impl WriteOnlyDataCommand for Capture {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), Error> {
+        QUEUE.lock().push(false, cmd)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), Error> {
+        QUEUE.lock().push(true, buf)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+
+    use std::boxed::Box;
+    use std::vec;
+    use std::vec::Vec;
+
+    // records everything sent, as (a0, bytes)
+    #[derive(Debug, Default)]
+    struct Record(Vec<(bool, Vec<u8>)>);
+
+    impl WriteOnlyDataCommand for Record {
+        fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), Error> {
+            let DataFormat::U8(buf) = cmd else { panic!() };
+            self.0.push((false, buf.into()));
+            Ok(())
+        }
+
+        fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), Error> {
+            let DataFormat::U8(buf) = buf else { panic!() };
+            self.0.push((true, buf.into()));
+            Ok(())
+        }
+    }
+
+    fn drain(queue: &mut Queue, max: usize) -> Vec<(bool, Vec<u8>)> {
+        let mut record = Record::default();
+        while !queue.is_empty() {
+            queue.send(&mut record, max).unwrap();
+        }
+        record.0
+    }
+
+    #[test]
+    fn runs() {
+        let mut queue = Box::new(Queue::new());
+        assert!(queue.is_empty());
+        assert_eq!(queue.chunk(4), None);
+
+        queue.push(false, DataFormat::U8(&[1, 2])).unwrap();
+        queue.push(false, DataFormat::U8(&[3])).unwrap();
+        queue.push(true, DataFormat::U8(&[4, 5, 6, 7, 8])).unwrap();
+        queue
+            .push(false, DataFormat::U8Iter(&mut [9, 10].into_iter()))
+            .unwrap();
+        queue.push(true, DataFormat::U8(&[])).unwrap();
+        assert!(!queue.is_empty());
+
+        assert_eq!(
+            drain(&mut queue, 4),
+            [
+                (false, vec![1, 2, 3]),
+                (true, vec![4, 5, 6, 7]),
+                (true, vec![8]),
+                (false, vec![9, 10]),
+            ]
+        );
+        assert!(queue.is_empty());
+        assert_eq!(queue.len, 0);
+    }
+
+    #[test]
+    fn push_while_sending() {
+        let mut queue = Box::new(Queue::new());
+        let mut record = Record::default();
+
+        queue.push(true, DataFormat::U8(&[1, 2, 3])).unwrap();
+        queue.send(&mut record, 2).unwrap();
+
+        // same A0 as the run being sent extends it
+        queue.push(true, DataFormat::U8(&[4])).unwrap();
+        queue.push(false, DataFormat::U8(&[5])).unwrap();
+
+        assert_eq!(record.0, [(true, vec![1, 2])]);
+        assert_eq!(drain(&mut queue, 8), [(true, vec![3, 4]), (false, vec![5])]);
+    }
+
+    // a page of pixels, like a flush sends
+    fn push_page(queue: &mut Queue, pixels: &[u8]) {
+        queue
+            .push(false, DataFormat::U8(&[0xb0, 0x10, 0x04]))
+            .unwrap();
+        queue.push(true, DataFormat::U8(pixels)).unwrap();
+    }
+
+    #[test]
+    fn drop_unchanged() {
+        let mut queue = Box::new(Queue::new());
+        let mut last = None;
+        let mut pixels = [0x55; WIDTH];
+
+        // the first frame is always sent
+        push_page(&mut queue, &pixels);
+        assert!(queue.drop_unchanged(&mut last));
+        assert_eq!(drain(&mut queue, WIDTH).len(), 2);
+
+        // the same frame again sends nothing
+        push_page(&mut queue, &pixels);
+        assert!(!queue.drop_unchanged(&mut last));
+        assert!(queue.is_empty());
+        assert_eq!(drain(&mut queue, WIDTH), []);
+
+        // one pixel changed is sent in full
+        pixels[17] ^= 0x08;
+        push_page(&mut queue, &pixels);
+        assert!(queue.drop_unchanged(&mut last));
+        assert_eq!(
+            drain(&mut queue, WIDTH),
+            [(false, vec![0xb0, 0x10, 0x04]), (true, pixels.to_vec())]
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let mut queue = Box::new(Queue::new());
+        queue.push(false, DataFormat::U8(&[0xaa])).unwrap();
+
+        let full = [0x55; QUEUE_LEN];
+        assert!(matches!(
+            queue.push(true, DataFormat::U8(&full)),
+            Err(Error::OutOfBoundsError)
+        ));
+        assert!(matches!(
+            queue.push(true, DataFormat::U16(&[0])),
+            Err(Error::DataFormatNotImplemented)
+        ));
+
+        // nothing partial was left behind
+        assert_eq!(drain(&mut queue, 8), [(false, vec![0xaa])]);
+
+        // a whole frame fits
+        queue
+            .push(true, DataFormat::U8(&full[..PAGES * WIDTH]))
+            .unwrap();
+        assert_eq!(queue.len, PAGES * WIDTH);
+    }
+}
@@ -0,0 +1,205 @@
+//! An off-device LCD, for testing drawing code on a host.
+//!
+//! [LcdSim] is a draw target with the same size and color as [Lcd],
+//! so code written against [DrawTarget] can draw to either. Images
+//! can be saved and loaded as plain PBM files, which are small enough
+//! to keep alongside tests as reference images.
+//!
+//! [Lcd]: super::Lcd
+
+use core::convert::Infallible;
+
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::string::{String, ToString};
+
+use embedded_graphics_core::draw_target::DrawTarget;
+use embedded_graphics_core::geometry::{OriginDimensions, Point, Size};
+use embedded_graphics_core::pixelcolor::BinaryColor;
+use embedded_graphics_core::Pixel;
+
+use super::{HEIGHT, WIDTH};
+
+/// A simulated LCD, backed by a plain array.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct LcdSim {
+    pixels: [[BinaryColor; WIDTH]; HEIGHT],
+}
+
+impl Default for LcdSim {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl core::fmt::Debug for LcdSim {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "LcdSim")?;
+        for row in self.pixels.iter() {
+            for p in row.iter() {
+                f.write_str(if p.is_on() { "#" } else { "." })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl LcdSim {
+    /// Create a new, blank LCD.
+    pub fn new() -> Self {
+        Self {
+            pixels: [[BinaryColor::Off; WIDTH]; HEIGHT],
+        }
+    }
+
+    /// Get the color of a pixel, or [None] if it is off-screen.
+    pub fn pixel(&self, point: Point) -> Option<BinaryColor> {
+        let x: usize = point.x.try_into().ok()?;
+        let y: usize = point.y.try_into().ok()?;
+        self.pixels.get(y)?.get(x).copied()
+    }
+
+    /// Write the image as a plain (P1) PBM file. [BinaryColor::On] is
+    /// black, like on the real LCD.
+    pub fn write_pbm<W: Write>(&self, mut out: W) -> Result<()> {
+        writeln!(out, "P1")?;
+        writeln!(out, "{} {}", WIDTH, HEIGHT)?;
+        for row in self.pixels.iter() {
+            let line: String = row
+                .iter()
+                .map(|p| if p.is_on() { '1' } else { '0' })
+                .collect();
+            writeln!(out, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// Read an image from a plain (P1) PBM file, which must be exactly
+    /// the size of the LCD.
+    pub fn read_pbm<R: Read>(mut input: R) -> Result<Self> {
+        let mut data = String::new();
+        input.read_to_string(&mut data)?;
+        let invalid = |msg| Error::new(ErrorKind::InvalidData, msg);
+
+        // comments run from # to end of line
+        let mut chars = data
+            .lines()
+            .flat_map(|l| l.split('#').next().unwrap_or("").chars().chain([' ']));
+
+        let mut token = || {
+            let t: String = chars
+                .by_ref()
+                .skip_while(|c| c.is_ascii_whitespace())
+                .take_while(|c| !c.is_ascii_whitespace())
+                .collect();
+            t
+        };
+
+        if token() != "P1" {
+            return Err(invalid("not a plain PBM file"));
+        }
+        if token() != WIDTH.to_string() || token() != HEIGHT.to_string() {
+            return Err(invalid("image is not the size of the LCD"));
+        }
+
+        // pixels may or may not be separated by whitespace
+        let mut pixels = chars.filter(|c| !c.is_ascii_whitespace());
+        let mut sim = Self::new();
+        for row in sim.pixels.iter_mut() {
+            for p in row.iter_mut() {
+                *p = match pixels.next() {
+                    Some('0') => BinaryColor::Off,
+                    Some('1') => BinaryColor::On,
+                    Some(_) => return Err(invalid("bad pixel value")),
+                    None => return Err(invalid("not enough pixels")),
+                };
+            }
+        }
+
+        Ok(sim)
+    }
+
+    /// Save the image to a plain PBM file.
+    pub fn save_pbm<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        self.write_pbm(std::io::BufWriter::new(std::fs::File::create(path)?))
+    }
+
+    /// Load an image from a plain PBM file.
+    pub fn load_pbm<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        Self::read_pbm(std::fs::File::open(path)?)
+    }
+
+    /// Iterate over the points where this image and another differ.
+    pub fn diff<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = Point> + 'a {
+        self.pixels
+            .iter()
+            .zip(other.pixels.iter())
+            .enumerate()
+            .flat_map(|(y, (a, b))| {
+                a.iter()
+                    .zip(b.iter())
+                    .enumerate()
+                    .filter(|(_, (a, b))| a != b)
+                    .map(move |(x, _)| Point::new(x as i32, y as i32))
+            })
+    }
+
+    /// Panic if this image does not match a reference image, naming
+    /// the first differing pixel and showing both images.
+    #[track_caller]
+    pub fn assert_matches(&self, reference: &Self) {
+        let mut diff = self.diff(reference);
+        if let Some(first) = diff.next() {
+            panic!(
+                "LCD image differs from reference in {} pixels, first at ({}, {})\n\
+                 got: {:?}\nexpected: {:?}",
+                diff.count() + 1,
+                first.x,
+                first.y,
+                self,
+                reference,
+            );
+        }
+    }
+
+    /// Panic if this image does not match a reference PBM file.
+    #[track_caller]
+    pub fn assert_matches_pbm<P: AsRef<std::path::Path>>(&self, path: P) {
+        let path = path.as_ref();
+        match Self::load_pbm(path) {
+            Ok(reference) => self.assert_matches(&reference),
+            Err(e) => panic!("could not load {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl OriginDimensions for LcdSim {
+    fn size(&self) -> Size {
+        Size::new(WIDTH as u32, HEIGHT as u32)
+    }
+}
+
+impl DrawTarget for LcdSim {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> core::result::Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            let (Ok(x), Ok(y)) = (usize::try_from(point.x), usize::try_from(point.y)) else {
+                continue;
+            };
+            if let Some(p) = self.pixels.get_mut(y).and_then(|row| row.get_mut(x)) {
+                *p = color;
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> core::result::Result<(), Self::Error> {
+        self.pixels = [[color; WIDTH]; HEIGHT];
+        Ok(())
+    }
+}
@@ -1,5 +1,8 @@
 #![no_std]
 
+#[cfg(feature = "std")]
+extern crate std;
+
 pub mod prelude;
 
 /// HAL crate, providing structured access to peripherals.
@@ -9,14 +12,22 @@ pub use dp32g030_hal as hal;
 pub use hal::pac;
 
 pub mod backlight;
+pub mod calibration;
+pub mod console;
+pub mod crc;
 #[cfg(feature = "defmt-logger")]
 pub mod defmt_logger;
 pub mod eeprom;
 pub mod flashlight;
 pub mod keypad;
 pub mod lcd;
+pub mod radio;
+pub mod settings;
 pub mod shared_i2c;
+pub mod transmit;
 pub mod uart;
+#[cfg(feature = "ui")]
+pub mod ui;
 
 #[cfg(not(feature = "defmt"))]
 use bitflags::bitflags;
@@ -0,0 +1,169 @@
+//! A high-level interface to the BK4819 radio.
+//!
+//! [Radio] wraps a [Transmitter], and with it the BK4819 and the
+//! speaker amplifier pin. It brings the BK4819 up from reset into
+//! receive, and then keeps track of the tuned frequency, so receiving
+//! and transmitting are a single call each:
+//!
+//! ```ignore
+//! let bk4819 = bk4819::Bk4819::new(scn, scl, sda, delay)?;
+//! let limits = k5board::transmit::Limits::new(144_000_000, 148_000_000);
+//! let mut radio = k5board::radio::Radio::new(bk4819, speaker_enable, limits)?;
+//!
+//! radio.tune(146_520_000)?;
+//! radio.set_volume(10)?;
+//! let rssi = radio.rssi()?;
+//! ```
+//!
+//! Everything not covered here is still available on the BK4819
+//! itself, through [Radio::bk4819_mut()]. See [crate::transmit] for
+//! the rules around transmitting, which all apply here.
+
+use bk4819::{registers, Bk4819, Modulation, PaSettings, TxPower};
+
+use embedded_hal_1::delay::DelayNs;
+use embedded_hal_1::digital::{InputPin, OutputPin, StatefulOutputPin};
+
+use crate::transmit::{Error, Limits, Transmitter, RX_ENABLE_GPIO};
+
+/// The loudest level accepted by [Radio::set_volume()].
+pub const MAX_VOLUME: u8 = 0xf;
+
+/// The BK4819, speaker and PA, behind one receive and transmit API.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Radio<Scn, Scl, Sda, Delay, Speaker> {
+    tx: Transmitter<Scn, Scl, Sda, Delay, Speaker>,
+    freq_hz: u32,
+    power: PaSettings,
+}
+
+impl<Scn, Scl, Sda, Delay, Speaker, E> Radio<Scn, Scl, Sda, Delay, Speaker>
+where
+    Scn: OutputPin<Error = E>,
+    Scl: OutputPin<Error = E>,
+    Sda: OutputPin<Error = E> + InputPin<Error = E>,
+    Delay: DelayNs,
+    Speaker: StatefulOutputPin<Error = E>,
+{
+    /// Reset the BK4819 and start receiving FM, with the speaker on.
+    ///
+    /// Transmitting is only allowed within `limits`. The radio is not
+    /// tuned to anything until [Self::tune()].
+    pub fn new(
+        mut bk4819: Bk4819<Scn, Scl, Sda, Delay>,
+        mut speaker: Speaker,
+        limits: Limits,
+    ) -> Result<Self, Error<E>> {
+        bk4819.init_defaults()?;
+
+        speaker.set_high().map_err(Error::Pin)?;
+        let mut tx = Transmitter::new(bk4819, speaker, limits)?;
+        let radio = tx.radio();
+        radio.set_modulation(Modulation::Fm)?;
+        radio.write(registers::Enable::rx())?;
+        radio.gpio_set_high(RX_ENABLE_GPIO)?;
+
+        Ok(Self {
+            tx,
+            freq_hz: 0,
+            power: TxPower::Low.into(),
+        })
+    }
+
+    /// Free the BK4819 and speaker pin for use elsewhere.
+    ///
+    /// This does not stop transmitting. Call [Self::receive()] first.
+    pub fn free(self) -> (Bk4819<Scn, Scl, Sda, Delay>, Speaker) {
+        self.tx.free()
+    }
+
+    /// Borrow the BK4819, for anything not covered here.
+    ///
+    /// Changing the frequency or PA settings here will confuse
+    /// [Radio], and while transmitting will bypass the [Limits].
+    pub fn bk4819_mut(&mut self) -> &mut Bk4819<Scn, Scl, Sda, Delay> {
+        self.tx.radio()
+    }
+
+    /// Borrow the [Transmitter], to change the [Limits].
+    pub fn transmitter_mut(&mut self) -> &mut Transmitter<Scn, Scl, Sda, Delay, Speaker> {
+        &mut self.tx
+    }
+
+    /// The tuned frequency, in Hz.
+    pub fn frequency(&self) -> u32 {
+        self.freq_hz
+    }
+
+    /// Is the radio transmitting?
+    pub fn is_transmitting(&self) -> bool {
+        self.tx.is_keyed()
+    }
+
+    /// Tune to a frequency, in Hz, for both receive and transmit.
+    ///
+    /// Fails with [Error::Keyed] while transmitting.
+    pub fn tune(&mut self, freq_hz: u32) -> Result<(), Error<E>> {
+        if self.tx.is_keyed() {
+            return Err(Error::Keyed);
+        }
+        self.tx.radio().set_frequency(freq_hz)?;
+        self.freq_hz = freq_hz;
+        Ok(())
+    }
+
+    /// Set the modulation.
+    ///
+    /// This affects receive only. The transmitter only supports FM.
+    pub fn set_modulation(&mut self, modulation: Modulation) -> Result<(), Error<E>> {
+        self.tx.radio().set_modulation(modulation)?;
+        Ok(())
+    }
+
+    /// Set the PA settings used by [Self::transmit()].
+    ///
+    /// This can be a [TxPower] preset, or calibrated [PaSettings]. It
+    /// takes effect on the next transmit. The default is
+    /// [TxPower::Low].
+    pub fn set_tx_power(&mut self, power: impl Into<PaSettings>) {
+        self.power = power.into();
+    }
+
+    /// Stop transmitting, if needed, and receive on the tuned
+    /// frequency.
+    pub fn receive(&mut self) -> Result<(), Error<E>> {
+        self.tx.unkey()
+    }
+
+    /// Start transmitting on the tuned frequency.
+    ///
+    /// Fails without touching the radio if the frequency is outside
+    /// the [Limits], or if already transmitting.
+    pub fn transmit(&mut self) -> Result<(), Error<E>> {
+        self.tx.key(self.freq_hz, self.power)
+    }
+
+    /// Read the current RSSI, in 0.5dB steps. See [registers::Rssi].
+    pub fn rssi(&mut self) -> Result<u16, Error<E>> {
+        Ok(self.tx.radio().rssi()?)
+    }
+
+    /// Set the receive volume, from 0 (mute) to [MAX_VOLUME].
+    ///
+    /// Each step is about 2dB. Larger values are treated as
+    /// [MAX_VOLUME].
+    pub fn set_volume(&mut self, volume: u8) -> Result<(), Error<E>> {
+        let volume = volume.min(MAX_VOLUME);
+        self.tx.radio().modify(|r: registers::AfGain| {
+            if volume == 0 {
+                // the DAC gain alone never quite mutes
+                r.with_dac_gain(0).with_gain2(0)
+            } else {
+                r.with_dac_gain(volume)
+                    .with_gain2(registers::AfGain::new().gain2())
+            }
+        })?;
+        Ok(())
+    }
+}
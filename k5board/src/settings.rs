@@ -0,0 +1,487 @@
+//! Settings that persist in the EEPROM.
+//!
+//! [Settings] stores any type implementing [Persist] in a small
+//! region of the EEPROM, behind a header:
+//!
+//! | Offset | Size | Contents                                |
+//! |--------|------|-----------------------------------------|
+//! | 0      | 2    | [MAGIC]                                 |
+//! | 2      | 2    | [Persist::VERSION], little-endian       |
+//! | 4      | 2    | body length, little-endian              |
+//! | 6      | 2    | XModem CRC of bytes 2.. to end of body  |
+//! | 8      | ..   | body, written by [Persist::encode()]    |
+//!
+//! By default this lives at [REGION_ADDRESS], in the gap after the
+//! stock firmware's channel names (see [k5lib::eeprom]). That is
+//! only [REGION_LEN] bytes, so firmware with bigger settings, or
+//! that uses that gap for something else, can pick its own region
+//! with [Settings::with_region()].
+//!
+//! Writing only touches EEPROM pages whose contents changed, so
+//! storing the same settings twice writes nothing.
+//!
+//! ## Versions
+//!
+//! The header records the [Persist::VERSION] the body was written
+//! with, and [Persist::decode()] is handed that version. When the
+//! layout of a type changes, bump the version and keep decoding the
+//! old layouts to migrate them. Anything that does not decode, or
+//! fails the magic or CRC checks, is replaced by the default.
+
+use core::marker::PhantomData;
+use core::ops::Range;
+
+use embedded_hal_02::blocking::delay::DelayMs;
+use embedded_hal_02::timer::{CountDown, Periodic};
+
+use k5lib::protocol::crc::{CrcDigest, CrcStyle, CrcXModem};
+use k5lib::protocol::serialize::{Serializer, SerializerSlice};
+
+use crate::eeprom::{Eeprom, Error, PAGE_SIZE, SIZE};
+
+/// The default address of the settings region.
+pub const REGION_ADDRESS: usize = 0x1bd0;
+
+/// The default size of the settings region, in bytes.
+pub const REGION_LEN: usize = 0x30;
+
+/// The largest settings region, in bytes.
+pub const MAX_REGION_LEN: usize = 0x100;
+
+/// Magic bytes marking the start of a settings region.
+pub const MAGIC: [u8; 2] = *b"k5";
+
+/// Size of the header before the body.
+pub const HEADER_LEN: usize = 8;
+
+/// A type that can be stored in [Settings].
+pub trait Persist: Default + Sized {
+    /// The version of the layout written by [Self::encode()].
+    const VERSION: u16;
+
+    /// Write the body.
+    fn encode<S>(&self, ser: &mut S) -> Result<(), S::Error>
+    where
+        S: Serializer;
+
+    /// Read a body written with the given version.
+    ///
+    /// Return [None] if it can't be read, and the default is used.
+    fn decode(version: u16, body: &[u8]) -> Option<Self>;
+}
+
+/// Somewhere [Settings] can be stored.
+///
+/// This is usually an [EepromStorage], from [storage()].
+pub trait Storage {
+    /// Read data starting at `address`.
+    fn read(&mut self, address: usize, data: &mut [u8]) -> Result<(), Error>;
+
+    /// Write data starting at `address`, and wait for it to finish.
+    fn write(&mut self, address: usize, data: &[u8]) -> Result<(), Error>;
+}
+
+/// The EEPROM, and a delay for waiting out writes, as [Storage].
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EepromStorage<'e, 'a, Timer, Delay> {
+    eeprom: &'e mut Eeprom<'a, Timer>,
+    delay: &'e mut Delay,
+}
+
+/// Use the EEPROM as [Storage] for [Settings].
+pub fn storage<'e, 'a, Timer, Delay>(
+    eeprom: &'e mut Eeprom<'a, Timer>,
+    delay: &'e mut Delay,
+) -> EepromStorage<'e, 'a, Timer, Delay>
+where
+    Timer: CountDown + Periodic,
+    Delay: DelayMs<u8>,
+{
+    EepromStorage { eeprom, delay }
+}
+
+impl<'e, 'a, Timer, Delay> Storage for EepromStorage<'e, 'a, Timer, Delay>
+where
+    Timer: CountDown + Periodic,
+    Delay: DelayMs<u8>,
+{
+    fn read(&mut self, address: usize, data: &mut [u8]) -> Result<(), Error> {
+        self.eeprom.read(address, data)
+    }
+
+    fn write(&mut self, address: usize, data: &[u8]) -> Result<(), Error> {
+        self.eeprom.write(self.delay, address, data)
+    }
+}
+
+/// Settings of type `T`, stored in a region of the EEPROM.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Settings<T> {
+    address: usize,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Default for Settings<T>
+where
+    T: Persist,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Settings<T>
+where
+    T: Persist,
+{
+    /// Settings stored in the default region, [REGION_ADDRESS].
+    pub const fn new() -> Self {
+        Self {
+            address: REGION_ADDRESS,
+            len: REGION_LEN,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Settings stored in `len` bytes starting at `address`.
+    ///
+    /// Returns [Error::InvalidAddr] if the region is off the end of
+    /// the EEPROM, or [Error::TooMuchData] if it is too small for the
+    /// header, or longer than [MAX_REGION_LEN].
+    pub fn with_region(address: usize, len: usize) -> Result<Self, Error> {
+        if address + len > SIZE {
+            return Err(Error::InvalidAddr);
+        }
+        if !(HEADER_LEN..=MAX_REGION_LEN).contains(&len) {
+            return Err(Error::TooMuchData);
+        }
+        Ok(Self {
+            address,
+            len,
+            _marker: PhantomData,
+        })
+    }
+
+    /// The EEPROM addresses these settings are stored in.
+    pub fn region(&self) -> Range<usize> {
+        self.address..self.address + self.len
+    }
+
+    /// Load the stored settings, or the default if there are none,
+    /// or they are corrupted.
+    pub fn load<S>(&self, storage: &mut S) -> Result<T, Error>
+    where
+        S: Storage,
+    {
+        Ok(self.try_load(storage)?.unwrap_or_default())
+    }
+
+    /// Load the stored settings, or [None] if there are none, or they
+    /// are corrupted.
+    pub fn try_load<S>(&self, storage: &mut S) -> Result<Option<T>, Error>
+    where
+        S: Storage,
+    {
+        let mut buf = [0; MAX_REGION_LEN];
+        let buf = &mut buf[..self.len];
+        storage.read(self.address, &mut buf[..HEADER_LEN])?;
+
+        let (magic, version, len, crc) = parse_header(buf);
+        if magic != MAGIC || HEADER_LEN + len > self.len {
+            return Ok(None);
+        }
+
+        let used = &mut buf[..HEADER_LEN + len];
+        storage.read(self.address + HEADER_LEN, &mut used[HEADER_LEN..])?;
+        if checksum(used) != crc {
+            return Ok(None);
+        }
+
+        Ok(T::decode(version, &used[HEADER_LEN..]))
+    }
+
+    /// Store the settings, writing only the pages that changed.
+    ///
+    /// Returns [Error::TooMuchData] if they do not fit in the region.
+    pub fn store<S>(&self, storage: &mut S, settings: &T) -> Result<(), Error>
+    where
+        S: Storage,
+    {
+        let mut buf = [0; MAX_REGION_LEN];
+        let buf = &mut buf[..self.len];
+
+        let (header, body) = buf.split_at_mut(HEADER_LEN);
+        let mut ser = SerializerSlice::new(body);
+        settings.encode(&mut ser).map_err(|_| Error::TooMuchData)?;
+        let len = ser.len();
+
+        header[..2].copy_from_slice(&MAGIC);
+        header[2..4].copy_from_slice(&T::VERSION.to_le_bytes());
+        header[4..6].copy_from_slice(&(len as u16).to_le_bytes());
+        let used = &mut buf[..HEADER_LEN + len];
+        let crc = checksum(used);
+        used[6..8].copy_from_slice(&crc.to_le_bytes());
+
+        // compare and write one page at a time
+        let mut address = self.address;
+        let mut rest = &used[..];
+        while !rest.is_empty() {
+            let chunk_len = rest.len().min(PAGE_SIZE - address % PAGE_SIZE);
+            let (chunk, next) = rest.split_at(chunk_len);
+
+            let mut old = [0; PAGE_SIZE];
+            let old = &mut old[..chunk_len];
+            storage.read(address, old)?;
+            if old != chunk {
+                storage.write(address, chunk)?;
+            }
+
+            address += chunk_len;
+            rest = next;
+        }
+
+        Ok(())
+    }
+}
+
+// magic, version, body length, crc
+fn parse_header(header: &[u8]) -> ([u8; 2], u16, usize, u16) {
+    let u16_at = |i: usize| u16::from_le_bytes([header[i], header[i + 1]]);
+    (
+        [header[0], header[1]],
+        u16_at(2),
+        u16_at(4) as usize,
+        u16_at(6),
+    )
+}
+
+// the CRC of a header and body, skipping the magic and CRC itself
+fn checksum(used: &[u8]) -> u16 {
+    let crc = CrcXModem::new();
+    let mut digest = crc.digest();
+    digest.update(&used[2..6]);
+    digest.update(&used[HEADER_LEN..]);
+    digest.finalize()
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+
+    use std::vec;
+    use std::vec::Vec;
+
+    // a blank EEPROM that records which pages were written
+    struct MockStorage {
+        memory: Vec<u8>,
+        writes: Vec<usize>,
+    }
+
+    impl MockStorage {
+        fn new() -> Self {
+            Self {
+                memory: vec![0xff; SIZE],
+                writes: Vec::new(),
+            }
+        }
+    }
+
+    impl Storage for MockStorage {
+        fn read(&mut self, address: usize, data: &mut [u8]) -> Result<(), Error> {
+            data.copy_from_slice(&self.memory[address..address + data.len()]);
+            Ok(())
+        }
+
+        fn write(&mut self, address: usize, data: &[u8]) -> Result<(), Error> {
+            assert!(address % PAGE_SIZE + data.len() <= PAGE_SIZE);
+            self.memory[address..address + data.len()].copy_from_slice(data);
+            self.writes.push(address / PAGE_SIZE);
+            Ok(())
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct User {
+        freq_hz: u32,
+        volume: u8,
+    }
+
+    impl Default for User {
+        fn default() -> Self {
+            Self {
+                freq_hz: 146_520_000,
+                volume: 5,
+            }
+        }
+    }
+
+    impl Persist for User {
+        const VERSION: u16 = 2;
+
+        fn encode<S>(&self, ser: &mut S) -> Result<(), S::Error>
+        where
+            S: Serializer,
+        {
+            ser.write_le_u32(self.freq_hz)?;
+            ser.write_u8(self.volume)
+        }
+
+        fn decode(version: u16, body: &[u8]) -> Option<Self> {
+            let freq_hz = u32::from_le_bytes(body.get(..4)?.try_into().ok()?);
+            match (version, body.len()) {
+                // version 1 had no volume
+                (1, 4) => Some(Self {
+                    freq_hz,
+                    ..Default::default()
+                }),
+                (2, 5) => Some(Self {
+                    freq_hz,
+                    volume: body[4],
+                }),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut storage = MockStorage::new();
+        let settings = Settings::<User>::new();
+        assert_eq!(settings.try_load(&mut storage).unwrap(), None);
+        assert_eq!(settings.load(&mut storage).unwrap(), User::default());
+
+        let user = User {
+            freq_hz: 446_000_000,
+            volume: 9,
+        };
+        settings.store(&mut storage, &user).unwrap();
+        assert_eq!(settings.try_load(&mut storage).unwrap(), Some(user.clone()));
+        assert_eq!(
+            &storage.memory[REGION_ADDRESS..REGION_ADDRESS + 6],
+            &[b'k', b'5', 2, 0, 5, 0]
+        );
+
+        // only the region was touched
+        assert!(storage.memory[..REGION_ADDRESS].iter().all(|b| *b == 0xff));
+        assert!(storage.memory[REGION_ADDRESS + HEADER_LEN + 5..]
+            .iter()
+            .all(|b| *b == 0xff));
+    }
+
+    #[test]
+    fn changed_pages() {
+        let mut storage = MockStorage::new();
+        let settings = Settings::<User>::new();
+        let mut user = User::default();
+
+        // 13 bytes at 0x1bd0 all fit in page 0xde
+        settings.store(&mut storage, &user).unwrap();
+        assert_eq!(storage.writes, [0xde]);
+
+        // the same again writes nothing
+        storage.writes.clear();
+        settings.store(&mut storage, &user).unwrap();
+        assert!(storage.writes.is_empty());
+
+        // the volume and the CRC are both in the first page
+        user.volume = 7;
+        settings.store(&mut storage, &user).unwrap();
+        assert_eq!(storage.writes, [0xde]);
+
+        // a region that straddles two pages
+        let settings = Settings::<User>::with_region(0x1bfc, 0x10).unwrap();
+        storage.writes.clear();
+        settings.store(&mut storage, &user).unwrap();
+        assert_eq!(storage.writes, [0xdf, 0xe0]);
+        assert_eq!(settings.load(&mut storage).unwrap(), user);
+    }
+
+    #[test]
+    fn corrupted() {
+        let mut storage = MockStorage::new();
+        let settings = Settings::<User>::new();
+        let user = User {
+            freq_hz: 145_500_000,
+            volume: 1,
+        };
+        settings.store(&mut storage, &user).unwrap();
+
+        // bad magic
+        storage.memory[REGION_ADDRESS] = b'K';
+        assert_eq!(settings.try_load(&mut storage).unwrap(), None);
+        assert_eq!(settings.load(&mut storage).unwrap(), User::default());
+
+        // storing again recovers
+        settings.store(&mut storage, &user).unwrap();
+        assert_eq!(settings.load(&mut storage).unwrap(), user);
+
+        // bad body
+        storage.memory[REGION_ADDRESS + HEADER_LEN] ^= 1;
+        assert_eq!(settings.try_load(&mut storage).unwrap(), None);
+        storage.memory[REGION_ADDRESS + HEADER_LEN] ^= 1;
+
+        // bad length
+        storage.memory[REGION_ADDRESS + 4] = 0xff;
+        assert_eq!(settings.try_load(&mut storage).unwrap(), None);
+    }
+
+    #[test]
+    fn migrate() {
+        let mut storage = MockStorage::new();
+        let settings = Settings::<User>::new();
+
+        // write a version 1 body by hand
+        let mut used = vec![b'k', b'5', 1, 0, 4, 0, 0, 0];
+        used.extend_from_slice(&433_000_000u32.to_le_bytes());
+        let crc = checksum(&used);
+        used[6..8].copy_from_slice(&crc.to_le_bytes());
+        storage.memory[REGION_ADDRESS..REGION_ADDRESS + used.len()].copy_from_slice(&used);
+
+        assert_eq!(
+            settings.load(&mut storage).unwrap(),
+            User {
+                freq_hz: 433_000_000,
+                volume: 5,
+            }
+        );
+
+        // unknown versions fall back to the default
+        storage.memory[REGION_ADDRESS + 2] = 3;
+        let crc = checksum(&storage.memory[REGION_ADDRESS..REGION_ADDRESS + used.len()]);
+        storage.memory[REGION_ADDRESS + 6..REGION_ADDRESS + 8].copy_from_slice(&crc.to_le_bytes());
+        assert_eq!(settings.load(&mut storage).unwrap(), User::default());
+    }
+
+    #[test]
+    fn regions() {
+        assert_eq!(
+            Settings::<User>::new().region(),
+            REGION_ADDRESS..REGION_ADDRESS + REGION_LEN
+        );
+        assert!(Settings::<User>::with_region(SIZE - 0x10, 0x10).is_ok());
+        assert!(matches!(
+            Settings::<User>::with_region(SIZE - 0x10, 0x20),
+            Err(Error::InvalidAddr)
+        ));
+        assert!(matches!(
+            Settings::<User>::with_region(0, HEADER_LEN - 1),
+            Err(Error::TooMuchData)
+        ));
+        assert!(matches!(
+            Settings::<User>::with_region(0, MAX_REGION_LEN + 1),
+            Err(Error::TooMuchData)
+        ));
+
+        // too small for the body
+        let mut storage = MockStorage::new();
+        let settings = Settings::<User>::with_region(0, HEADER_LEN + 4).unwrap();
+        assert!(matches!(
+            settings.store(&mut storage, &User::default()),
+            Err(Error::TooMuchData)
+        ));
+    }
+}
@@ -1,4 +1,21 @@
 //! Bit-banged I2C bus that can be shared, with raw access.
+//!
+//! Create a [SharedI2cController] with [new()], then hand each driver
+//! its own [SharedI2c] from [SharedI2cController::acquire()]. Every
+//! handle implements the embedded-hal 1.0 [I2c](hal1::I2c) trait (and
+//! the 0.2 blocking traits), so any generic driver crate can sit on
+//! the bus next to the on-board devices like [crate::eeprom].
+//!
+//! Handles only borrow the controller, in the same way as
+//! `embedded-hal-bus` devices do, so the controller must outlive them.
+//!
+//! Each transaction runs entirely inside a
+//! [critical_section](critical_section::with), which keeps
+//! transactions from interleaving even when a handle is used from an
+//! interrupt. The flip side is that interrupts are held off for the
+//! whole transaction, which at bit-bang speeds can take a while.
+//! The bus itself is not re-entrant: starting a transaction from inside
+//! [SharedI2c::with_raw()] on the same bus will panic.
 
 use core::cell::{RefCell, RefMut};
 
@@ -60,12 +77,22 @@ pub struct SharedI2cController<Timer, Scl, Sda> {
 }
 
 /// A shared I2C bus.
+///
+/// Implements the embedded-hal I2C traits, see the [module
+/// documentation](self) for how sharing works.
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SharedI2c<'a, Timer, Scl, Sda> {
     parts: &'a Mutex<RefCell<PartsTuple<Timer, Scl, Sda>>>,
 }
 
+// derive would require Timer, Scl, Sda: Clone
+impl<'a, Timer, Scl, Sda> Clone for SharedI2c<'a, Timer, Scl, Sda> {
+    fn clone(&self) -> Self {
+        Self { parts: self.parts }
+    }
+}
+
 /// Create a shared I2C bus from parts.
 pub fn new<Timer, Scl, Sda>(parts: Parts<Timer, Scl, Sda>) -> SharedI2cController<Timer, Scl, Sda>
 where
@@ -96,6 +123,8 @@ where
     }
 
     /// Acquire an instance of the shared I2C bus.
+    ///
+    /// This can be called as many times as needed, once per driver.
     pub fn acquire(&self) -> SharedI2c<'_, Timer, Scl, Sda> {
         SharedI2c { parts: &self.parts }
     }
@@ -309,6 +338,32 @@ where
     }
 }
 
+impl<'a, Timer, Scl, Sda> bk1080::Interface for SharedI2c<'a, Timer, Scl, Sda>
+where
+    Timer: CountDown + Periodic,
+    Scl: OutputPin,
+    Sda: OutputPin<Error = Scl::Error> + InputPin<Error = Scl::Error>,
+{
+    type Error = Error;
+
+    fn write(&mut self, output: &[u8]) -> Result<(), Self::Error> {
+        self.with_raw(|raw| {
+            raw.start_raw()?;
+            raw.write_raw(output)?;
+            raw.stop_raw()
+        })
+    }
+
+    fn write_then_read(&mut self, output: &[u8], input: &mut [u8]) -> Result<(), Self::Error> {
+        self.with_raw(|raw| {
+            raw.start_raw()?;
+            raw.write_raw(output)?;
+            raw.read_raw(input)?;
+            raw.stop_raw()
+        })
+    }
+}
+
 impl hal1::Error for Error {
     fn kind(&self) -> hal1::ErrorKind {
         match self {
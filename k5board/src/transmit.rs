@@ -0,0 +1,384 @@
+//! Keying the transmitter.
+//!
+//! **Transmitting is regulated.** Only key the transmitter on
+//! frequencies, at power levels, and with modulation you are licensed
+//! to use. Keying into a missing or mismatched antenna can also
+//! damage the PA. [Limits] guards against tuning outside a configured
+//! range, but it cannot know what is legal where you are, so set it
+//! conservatively.
+//!
+//! A [Transmitter] owns the BK4819 and the speaker amplifier pin, and
+//! switches between receive and transmit in the order the stock
+//! firmware uses:
+//!
+//! * [Transmitter::key()] mutes the speaker so it does not feed back
+//!   into the mic, switches off the receive path, tunes, enables the
+//!   transmit path, sets the PA, and only then powers the PA.
+//! * [Transmitter::unkey()] powers down the PA first, then restores the
+//!   receive path and the speaker.
+//!
+//! There are short waits between steps for the PLL and PA to settle,
+//! so keying takes around 16ms. Only FM is supported.
+
+use bk4819::registers;
+use bk4819::{Bk4819, PaSettings};
+
+use embedded_hal_1::delay::DelayNs;
+use embedded_hal_1::digital::{InputPin, OutputPin, StatefulOutputPin};
+
+/// The BK4819 GPIO that enables the receive path.
+pub const RX_ENABLE_GPIO: u8 = 0;
+
+/// The BK4819 GPIO that powers the PA.
+pub const PA_ENABLE_GPIO: u8 = 1;
+
+/// Milliseconds to wait for the PLL after tuning.
+const PLL_SETTLE_MS: u32 = 10;
+
+/// Milliseconds to wait for the PA bias before powering the PA.
+const PA_SETTLE_MS: u32 = 5;
+
+/// Milliseconds to wait after powering the PA.
+const PA_ENABLE_MS: u32 = 1;
+
+/// The range of frequencies a [Transmitter] may key on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Limits {
+    /// Lowest allowed frequency, in Hz, inclusive.
+    pub min_hz: u32,
+    /// Highest allowed frequency, in Hz, inclusive.
+    pub max_hz: u32,
+}
+
+impl Limits {
+    /// Allow keying from `min_hz` through `max_hz`, inclusive.
+    pub const fn new(min_hz: u32, max_hz: u32) -> Self {
+        Self { min_hz, max_hz }
+    }
+
+    /// Is a frequency within these limits?
+    pub fn contains(&self, freq_hz: u32) -> bool {
+        (self.min_hz..=self.max_hz).contains(&freq_hz)
+    }
+}
+
+/// Errors from keying the transmitter.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error<E> {
+    /// Error talking to the radio.
+    Radio(bk4819::Error<E>),
+    /// Error on the speaker pin.
+    Pin(E),
+    /// The frequency, in Hz, is outside the configured [Limits].
+    OutOfLimits(u32),
+    /// The transmitter is already keyed.
+    Keyed,
+}
+
+impl<E> From<bk4819::Error<E>> for Error<E> {
+    fn from(other: bk4819::Error<E>) -> Self {
+        Self::Radio(other)
+    }
+}
+
+/// The state kept while keyed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Keyed {
+    freq_hz: u32,
+    speaker_on: bool,
+}
+
+/// The BK4819 and speaker, for switching between receive and transmit.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Transmitter<Scn, Scl, Sda, Delay, Speaker> {
+    radio: Bk4819<Scn, Scl, Sda, Delay>,
+    speaker: Speaker,
+    limits: Limits,
+    keyed: Option<Keyed>,
+}
+
+impl<Scn, Scl, Sda, Delay, Speaker, E> Transmitter<Scn, Scl, Sda, Delay, Speaker>
+where
+    Scn: OutputPin<Error = E>,
+    Scl: OutputPin<Error = E>,
+    Sda: OutputPin<Error = E> + InputPin<Error = E>,
+    Delay: DelayNs,
+    Speaker: StatefulOutputPin<Error = E>,
+{
+    /// Set up the transmitter, starting with the PA off.
+    pub fn new(
+        mut radio: Bk4819<Scn, Scl, Sda, Delay>,
+        speaker: Speaker,
+        limits: Limits,
+    ) -> Result<Self, Error<E>> {
+        radio.gpio_set_low(PA_ENABLE_GPIO)?;
+        radio.gpio_set_output_enabled(PA_ENABLE_GPIO, true)?;
+        radio.gpio_set_output_enabled(RX_ENABLE_GPIO, true)?;
+
+        Ok(Self {
+            radio,
+            speaker,
+            limits,
+            keyed: None,
+        })
+    }
+
+    /// Free the radio and speaker pin for use elsewhere.
+    ///
+    /// This does not unkey the transmitter. Call [Self::unkey()] first.
+    pub fn free(self) -> (Bk4819<Scn, Scl, Sda, Delay>, Speaker) {
+        (self.radio, self.speaker)
+    }
+
+    /// Borrow the radio, for receiving.
+    ///
+    /// Changing the frequency or PA settings here while keyed will
+    /// bypass the [Limits].
+    pub fn radio(&mut self) -> &mut Bk4819<Scn, Scl, Sda, Delay> {
+        &mut self.radio
+    }
+
+    /// Get the current limits.
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Change the limits. This takes effect on the next [Self::key()].
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Is the transmitter keyed?
+    pub fn is_keyed(&self) -> bool {
+        self.keyed.is_some()
+    }
+
+    /// The frequency being transmitted on, in Hz, if keyed.
+    pub fn frequency(&self) -> Option<u32> {
+        self.keyed.map(|k| k.freq_hz)
+    }
+
+    /// Start transmitting on `freq_hz` with the given PA settings.
+    ///
+    /// `power` can be a [bk4819::TxPower] preset, or calibrated
+    /// [PaSettings]. Fails without touching the radio if `freq_hz` is
+    /// outside the [Limits], or if already keyed.
+    pub fn key(&mut self, freq_hz: u32, power: impl Into<PaSettings>) -> Result<(), Error<E>> {
+        if self.keyed.is_some() {
+            return Err(Error::Keyed);
+        }
+        if !self.limits.contains(freq_hz) {
+            return Err(Error::OutOfLimits(freq_hz));
+        }
+
+        let speaker_on = self.speaker.is_set_high().map_err(Error::Pin)?;
+        self.speaker.set_low().map_err(Error::Pin)?;
+
+        self.radio.gpio_set_low(RX_ENABLE_GPIO)?;
+        self.radio.set_frequency(freq_hz)?;
+        self.radio.write(registers::Enable::tx())?;
+        self.radio.delay().delay_ms(PLL_SETTLE_MS);
+
+        self.radio.set_pa(power.into())?;
+        self.radio.delay().delay_ms(PA_SETTLE_MS);
+
+        self.radio.gpio_set_high(PA_ENABLE_GPIO)?;
+        self.radio.delay().delay_ms(PA_ENABLE_MS);
+
+        self.keyed = Some(Keyed {
+            freq_hz,
+            speaker_on,
+        });
+        Ok(())
+    }
+
+    /// Stop transmitting, and go back to receiving on the same
+    /// frequency. Does nothing if not keyed.
+    pub fn unkey(&mut self) -> Result<(), Error<E>> {
+        let Some(keyed) = self.keyed else {
+            return Ok(());
+        };
+
+        self.radio.gpio_set_low(PA_ENABLE_GPIO)?;
+        self.radio.write(registers::PaControl::new())?;
+
+        self.radio.write(registers::Enable::new())?;
+        self.radio.write(registers::Enable::rx())?;
+        self.radio.gpio_set_high(RX_ENABLE_GPIO)?;
+
+        if keyed.speaker_on {
+            self.speaker.set_high().map_err(Error::Pin)?;
+        }
+
+        self.keyed = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+
+    use core::cell::RefCell;
+    use core::convert::Infallible;
+    use std::vec::Vec;
+
+    use bk4819::mock::{MockBus, NoDelay, Pin};
+    use embedded_hal_1::digital::ErrorType;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Event {
+        Write(u8, u16),
+        Speaker(bool),
+    }
+
+    // the radio bus, plus the speaker and how many writes preceded each
+    // change to it
+    struct Mock {
+        bus: RefCell<MockBus>,
+        speaker: RefCell<Vec<(usize, bool)>>,
+    }
+
+    impl Mock {
+        fn clear(&self) {
+            self.bus.borrow_mut().writes.clear();
+            self.speaker.borrow_mut().clear();
+        }
+
+        // register writes and speaker changes, in order
+        fn events(&self) -> Vec<Event> {
+            let bus = self.bus.borrow();
+            let speaker = self.speaker.borrow();
+            let mut events = Vec::new();
+            let mut changes = speaker.iter().peekable();
+            for (i, &(address, value)) in bus.writes.iter().enumerate() {
+                while let Some(&(_, high)) = changes.next_if(|(at, _)| *at <= i) {
+                    events.push(Event::Speaker(high));
+                }
+                events.push(Event::Write(address, value));
+            }
+            events.extend(changes.map(|&(_, high)| Event::Speaker(high)));
+            events
+        }
+    }
+
+    struct Speaker<'a>(&'a Mock);
+
+    impl ErrorType for Speaker<'_> {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for Speaker<'_> {
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            let at = self.0.bus.borrow().writes.len();
+            self.0.speaker.borrow_mut().push((at, false));
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            let at = self.0.bus.borrow().writes.len();
+            self.0.speaker.borrow_mut().push((at, true));
+            Ok(())
+        }
+    }
+
+    impl StatefulOutputPin for Speaker<'_> {
+        fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+            // the speaker starts on
+            Ok(self.0.speaker.borrow().last().is_none_or(|c| c.1))
+        }
+
+        fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+            Ok(!self.is_set_high()?)
+        }
+    }
+
+    type MockTransmitter<'a> = Transmitter<Pin<'a>, Pin<'a>, Pin<'a>, NoDelay, Speaker<'a>>;
+
+    fn transmitter(mock: &Mock) -> MockTransmitter<'_> {
+        let tx = Transmitter::new(
+            bk4819::mock::mock(&mock.bus),
+            Speaker(mock),
+            Limits::new(144_000_000, 148_000_000),
+        )
+        .unwrap();
+        mock.clear();
+        tx
+    }
+
+    fn mock() -> Mock {
+        Mock {
+            bus: RefCell::new(MockBus::new()),
+            speaker: RefCell::new(Vec::new()),
+        }
+    }
+
+    #[test]
+    fn key_unkey_order() {
+        use Event::*;
+
+        let mock = mock();
+        let mut tx = transmitter(&mock);
+
+        // new() leaves both GPIOs as outputs, PA off
+        assert_eq!(mock.bus.borrow().registers[0x33], 0x0000);
+
+        tx.key(146_520_000, bk4819::TxPower::Low).unwrap();
+        assert!(tx.is_keyed());
+        assert_eq!(tx.frequency(), Some(146_520_000));
+        assert_eq!(
+            mock.events(),
+            [
+                Speaker(false),
+                Write(0x33, 0x0000),
+                Write(0x38, 0x9260),
+                Write(0x39, 0x00df),
+                Write(0x30, 0x0000),
+                Write(0x30, 0x0000),
+                Write(0x30, 0xc1fe),
+                Write(0x36, 0x1ea2),
+                Write(0x33, 0x0020),
+            ]
+        );
+
+        mock.clear();
+        tx.unkey().unwrap();
+        assert!(!tx.is_keyed());
+        assert_eq!(
+            mock.events(),
+            [
+                Write(0x33, 0x0000),
+                Write(0x36, 0x003f),
+                Write(0x30, 0x0000),
+                Write(0x30, 0xbff1),
+                Write(0x33, 0x0040),
+                Speaker(true),
+            ]
+        );
+    }
+
+    #[test]
+    fn limits() {
+        let mock = mock();
+        let mut tx = transmitter(&mock);
+
+        assert_eq!(
+            tx.key(440_000_000, bk4819::TxPower::Low),
+            Err(Error::OutOfLimits(440_000_000))
+        );
+        assert!(!tx.is_keyed());
+        assert!(mock.events().is_empty());
+
+        tx.key(148_000_000, bk4819::TxPower::Low).unwrap();
+        assert_eq!(tx.key(148_000_000, bk4819::TxPower::Low), Err(Error::Keyed));
+
+        // unkey twice is fine
+        tx.unkey().unwrap();
+        tx.unkey().unwrap();
+    }
+}
@@ -1,4 +1,18 @@
 //! UART on the headset connector.
+//!
+//! Most firmware will [install()] the UART globally, for use with
+//! [println!()][crate::println!] and [GlobalUart::client()]. The HAL's
+//! [Uart] port also implements [embedded_io::Read] and
+//! [embedded_io::Write] on its own, so a [k5lib::ClientRadio] can own
+//! it directly instead:
+//!
+//! ```ignore
+//! let uart = k5board::uart::new(38_400.Hz(), uart_parts)?;
+//! let mut client: k5lib::ClientRadio<_> = k5lib::ClientRadio::new(uart);
+//! if let Ok(Some(msg)) = client.read_host().map(|v| v.ok()) {
+//!     // ...
+//! }
+//! ```
 
 use core::cell::UnsafeCell;
 use core::fmt;
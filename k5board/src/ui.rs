@@ -0,0 +1,403 @@
+//! Reusable widgets for drawing a user interface.
+//!
+//! Each widget is an [embedded_graphics] [Drawable] that draws only
+//! inside its `bounds`, onto any [DrawTarget] with [BinaryColor]
+//! pixels, such as [Lcd][crate::lcd::Lcd]. Widgets clear their bounds
+//! before drawing, so redrawing one in place is enough to update it.
+//! None of them allocate.
+//!
+//! ```ignore
+//! let status = ui::StatusBar {
+//!     bounds: Rectangle::new(Point::zero(), Size::new(128, 8)),
+//!     battery: Some(80),
+//!     rssi: Some(3),
+//!     activity: ui::Activity::Rx,
+//! };
+//! status.draw(&mut lcd)?;
+//! ```
+
+use embedded_graphics::mono_font::ascii::{FONT_10X20, FONT_4X6, FONT_6X10};
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+use embedded_graphics::text::{Alignment, Baseline, Text, TextStyleBuilder};
+
+/// Fill a rectangle with a solid color.
+fn fill<D>(target: &mut D, area: Rectangle, color: BinaryColor) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    area.into_styled(PrimitiveStyle::with_fill(color))
+        .draw(target)
+}
+
+/// What the radio is doing, for [StatusBar].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Activity {
+    /// Neither receiving nor transmitting.
+    Idle,
+    /// Receiving.
+    Rx,
+    /// Transmitting.
+    Tx,
+}
+
+/// Number of bars in the [StatusBar] signal meter.
+pub const RSSI_BARS: u8 = 5;
+
+/// A status bar, usually drawn along the top of the screen.
+///
+/// From left to right, this shows the signal meter, the [Activity],
+/// and the battery. It is designed for a height of 8 pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StatusBar {
+    /// Where to draw the status bar.
+    pub bounds: Rectangle,
+    /// Battery charge in percent, or [None] to hide the battery.
+    pub battery: Option<u8>,
+    /// Signal strength, 0 through [RSSI_BARS], or [None] to hide the
+    /// signal meter.
+    pub rssi: Option<u8>,
+    /// Whether the radio is receiving or transmitting.
+    pub activity: Activity,
+}
+
+impl Drawable for StatusBar {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let mut target = target.clipped(&self.bounds);
+        let Rectangle { top_left, size } = self.bounds;
+        fill(&mut target, self.bounds, BinaryColor::Off)?;
+
+        // signal meter, one rising bar per level, with a dot for empty bars
+        if let Some(rssi) = self.rssi {
+            for i in 0..RSSI_BARS {
+                let height = if i < rssi {
+                    (size.height * (i as u32 + 1) / RSSI_BARS as u32).max(1)
+                } else {
+                    1
+                };
+                let bar = Rectangle::new(
+                    top_left + Point::new(i as i32 * 3, size.height.saturating_sub(height) as i32),
+                    Size::new(2, height),
+                );
+                fill(&mut target, bar, BinaryColor::On)?;
+            }
+        }
+
+        // activity, transmit is inverted so it stands out
+        let label = match self.activity {
+            Activity::Idle => None,
+            Activity::Rx => Some(("RX", BinaryColor::On)),
+            Activity::Tx => Some(("TX", BinaryColor::Off)),
+        };
+        if let Some((label, color)) = label {
+            let area = Rectangle::new(top_left + Point::new(17, 0), Size::new(11, size.height));
+            if color == BinaryColor::Off {
+                fill(&mut target, area, BinaryColor::On)?;
+            }
+            let style = TextStyleBuilder::new()
+                .alignment(Alignment::Center)
+                .baseline(Baseline::Middle)
+                .build();
+            let font = MonoTextStyle::new(&FONT_4X6, color);
+            Text::with_text_style(label, area.center(), font, style).draw(&mut target)?;
+        }
+
+        // battery, filled from the left, with a nub on the right
+        if let Some(battery) = self.battery {
+            let right = top_left.x + size.width as i32;
+            let body = Rectangle::new(
+                Point::new(right - 15, top_left.y + 1),
+                Size::new(13, size.height.saturating_sub(2)),
+            );
+            body.into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+                .draw(&mut target)?;
+            let nub = Rectangle::new(
+                Point::new(right - 2, top_left.y + 3),
+                Size::new(1, size.height.saturating_sub(6)),
+            );
+            fill(&mut target, nub, BinaryColor::On)?;
+
+            let inner = body.offset(-2);
+            let width = inner.size.width * battery.min(100) as u32 / 100;
+            fill(
+                &mut target,
+                Rectangle::new(inner.top_left, Size::new(width, inner.size.height)),
+                BinaryColor::On,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A scrolling list of menu items, with one selected.
+///
+/// The list scrolls just far enough to keep the selected item
+/// visible, and shows a scroll bar on the right if the items do not
+/// all fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Menu<'a> {
+    /// Where to draw the menu.
+    pub bounds: Rectangle,
+    /// The menu items.
+    pub items: &'a [&'a str],
+    /// Index of the selected item.
+    pub selected: usize,
+}
+
+impl<'a> Menu<'a> {
+    /// Height of a single item, in pixels.
+    pub const ROW_HEIGHT: u32 = 10;
+
+    /// Number of items that fit inside the bounds.
+    pub fn rows(&self) -> usize {
+        (self.bounds.size.height / Self::ROW_HEIGHT) as usize
+    }
+
+    /// Index of the first visible item.
+    pub fn first_visible(&self) -> usize {
+        let rows = self.rows().max(1);
+        let selected = self.selected.min(self.items.len().saturating_sub(1));
+        (selected + 1).saturating_sub(rows)
+    }
+}
+
+impl<'a> Drawable for Menu<'a> {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let mut target = target.clipped(&self.bounds);
+        let Rectangle { top_left, size } = self.bounds;
+        fill(&mut target, self.bounds, BinaryColor::Off)?;
+
+        let rows = self.rows();
+        let first = self.first_visible();
+        let scrolling = self.items.len() > rows;
+        let width = if scrolling {
+            size.width.saturating_sub(3)
+        } else {
+            size.width
+        };
+
+        let visible = self.items.iter().enumerate().skip(first).take(rows);
+        for (row, (i, item)) in visible.enumerate() {
+            let area = Rectangle::new(
+                top_left + Point::new(0, (row as u32 * Self::ROW_HEIGHT) as i32),
+                Size::new(width, Self::ROW_HEIGHT),
+            );
+            let color = if i == self.selected {
+                fill(&mut target, area, BinaryColor::On)?;
+                BinaryColor::Off
+            } else {
+                BinaryColor::On
+            };
+
+            let style = MonoTextStyle::new(&FONT_6X10, color);
+            Text::with_baseline(item, area.top_left + Point::new(2, 0), style, Baseline::Top)
+                .draw(&mut target.clipped(&area))?;
+        }
+
+        if scrolling {
+            let track = size.height;
+            let thumb = (track * rows as u32 / self.items.len() as u32).max(2);
+            let offset =
+                track.saturating_sub(thumb) * first as u32 / (self.items.len() - rows) as u32;
+            let bar = Rectangle::new(
+                top_left + Point::new(size.width as i32 - 2, offset as i32),
+                Size::new(2, thumb),
+            );
+            fill(&mut target, bar, BinaryColor::On)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A frequency, in MHz, in a large font.
+///
+/// This is drawn right-aligned and centered vertically, with five
+/// decimal places, like `146.52000`. That takes 90 pixels across and
+/// 20 pixels down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FrequencyDisplay {
+    /// Where to draw the frequency.
+    pub bounds: Rectangle,
+    /// The frequency to show, in Hz.
+    pub hz: u32,
+}
+
+impl Drawable for FrequencyDisplay {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let mut target = target.clipped(&self.bounds);
+        fill(&mut target, self.bounds, BinaryColor::Off)?;
+
+        let mut text = TextBuf::new();
+        let mhz = self.hz / 1_000_000;
+        let frac = (self.hz % 1_000_000) / 10;
+        // TextBuf is large enough for any u32
+        let _ = core::fmt::Write::write_fmt(&mut text, format_args!("{}.{:05}", mhz, frac));
+
+        let position = Point::new(
+            self.bounds.top_left.x + self.bounds.size.width as i32 - 1,
+            self.bounds.center().y,
+        );
+        let style = TextStyleBuilder::new()
+            .alignment(Alignment::Right)
+            .baseline(Baseline::Middle)
+            .build();
+        Text::with_text_style(
+            text.as_str(),
+            position,
+            MonoTextStyle::new(&FONT_10X20, BinaryColor::On),
+            style,
+        )
+        .draw(&mut target)?;
+
+        Ok(())
+    }
+}
+
+/// A small, fixed-size string buffer for formatting without alloc.
+struct TextBuf {
+    data: [u8; 16],
+    len: usize,
+}
+
+impl TextBuf {
+    fn new() -> Self {
+        Self {
+            data: [0; 16],
+            len: 0,
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        // only ever written by write_str, so always valid
+        core::str::from_utf8(&self.data[..self.len]).unwrap_or("")
+    }
+}
+
+impl core::fmt::Write for TextBuf {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.len + s.len();
+        self.data
+            .get_mut(self.len..end)
+            .ok_or(core::fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use crate::lcd::LcdSim;
+
+    #[test]
+    fn menu() {
+        let items = ["Squelch", "Step", "Power", "Bandwidth", "Roger", "VOX"];
+        let mut lcd = LcdSim::new();
+        Menu {
+            bounds: Rectangle::new(Point::new(0, 12), Size::new(128, 52)),
+            items: &items,
+            selected: 5,
+        }
+        .draw(&mut lcd)
+        .unwrap();
+
+        lcd.assert_matches_pbm(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/ui-menu.pbm"));
+    }
+
+    #[test]
+    fn menu_scroll() {
+        let items = ["a", "b", "c", "d", "e", "f", "g"];
+        let menu = |selected| Menu {
+            bounds: Rectangle::new(Point::zero(), Size::new(128, 30)),
+            items: &items,
+            selected,
+        };
+        assert_eq!(menu(0).rows(), 3);
+        assert_eq!(menu(0).first_visible(), 0);
+        assert_eq!(menu(2).first_visible(), 0);
+        assert_eq!(menu(3).first_visible(), 1);
+        assert_eq!(menu(6).first_visible(), 4);
+        assert_eq!(menu(100).first_visible(), 4);
+    }
+
+    #[test]
+    fn tiny_bounds() {
+        let items = ["a", "b", "c"];
+        for height in 0..3 {
+            let bounds = Rectangle::new(Point::zero(), Size::new(20, height));
+            let mut lcd = LcdSim::new();
+            StatusBar {
+                bounds,
+                battery: Some(100),
+                rssi: Some(RSSI_BARS),
+                activity: Activity::Tx,
+            }
+            .draw(&mut lcd)
+            .unwrap();
+            Menu {
+                bounds,
+                items: &items,
+                selected: 2,
+            }
+            .draw(&mut lcd)
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn stays_in_bounds() {
+        let bounds = Rectangle::new(Point::new(10, 10), Size::new(60, 20));
+        let mut lcd = LcdSim::new();
+        StatusBar {
+            bounds,
+            battery: Some(100),
+            rssi: Some(RSSI_BARS),
+            activity: Activity::Tx,
+        }
+        .draw(&mut lcd)
+        .unwrap();
+        FrequencyDisplay {
+            bounds,
+            hz: u32::MAX,
+        }
+        .draw(&mut lcd)
+        .unwrap();
+
+        for y in 0..64 {
+            for x in 0..128 {
+                let p = Point::new(x, y);
+                if !bounds.contains(p) {
+                    assert_eq!(lcd.pixel(p), Some(BinaryColor::Off), "{:?}", p);
+                }
+            }
+        }
+    }
+}
@@ -11,7 +11,6 @@ use k5lib::protocol::messages::custom::DebugInput;
 
 use hal::time::Hertz;
 
-pub mod bk1080;
 pub mod error;
 
 k5board::version!(env!("CARGO_PKG_VERSION"));
@@ -118,7 +117,7 @@ fn go() -> error::Result<()> {
         // PB10 ST7565 si
         mosi: pins_b.b10.into_mode(),
         // PB11 ST7565 res / swdio / tp14
-        res: pins_b.b11.into_mode(),
+        res: pins_b.b11.into_mode_releasing_swd(),
     };
 
     // PB14 BK4819 gpio2 / swclk / tp13
@@ -173,23 +172,8 @@ fn go() -> error::Result<()> {
     let bk4819_timer = timer1m.high.timing();
     let mut radio = bk4819::Bk4819::new(bk4819_scn, bk4819_scl, bk4819_sda, bk4819_timer)?;
 
-    // bk4819 setup (very opaque)
-    radio.reset()?;
-    radio.write(
-        bk4819::registers::PowerControl::new()
-            .with_band_gap_enabled(true)
-            .with_xtal_enabled(true)
-            .with_dsp_enabled(true)
-            .with_unknown_b3(true)
-            .with_rf_ldo_select(bk4819::registers::LdoVoltage::V2_4),
-    )?;
-    radio.write(
-        bk4819::registers::PaControl::new()
-            .with_gain2(0b010)
-            .with_gain1(0b100),
-    )?;
-    radio.gpio_set_output_enabled(6, true)?; // green led
-    radio.gpio_set_output_enabled(5, true)?; // red led
+    // bk4819 setup, with the green (6) and red (5) led outputs enabled
+    radio.init_defaults()?;
 
     // the lcd display
     let mut lcd = k5board::lcd::new(&mut delay, lcd_parts)?;
@@ -284,8 +268,8 @@ fn go() -> error::Result<()> {
     loop {
         if let Ok(()) = rssi_update.wait() {
             // update rssi
-            rssi = fm.read(bk1080::REG_RSSI)?;
-            vox = radio.read_raw(0x64)?;
+            rssi = fm.read::<bk1080::registers::Rssi>()?.into_bits();
+            vox = radio.voice_amplitude()?;
         }
 
         if let Ok(()) = poll_keypad.wait() {
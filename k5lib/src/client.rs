@@ -1,11 +1,16 @@
+use nom::{InputIter, Slice};
+
 use crate::protocol;
 use crate::protocol::crc;
-use crate::protocol::parse::FoundFrame;
+use crate::protocol::messages::{bootloader, radio};
+use crate::protocol::obfuscation::Key;
+use crate::protocol::parse::{FoundFrame, FrameScanner};
 use crate::protocol::serialize;
 use crate::protocol::{
-    HostMessage, Message, MessageParse, MessageSerialize, Parse, ParseMut, ParseResult,
-    RadioMessage, MAX_FRAME_SIZE,
+    HostMessage, Message, MessageParse, MessageSerialize, MessageType, Parse, ParseMut,
+    ParseResult, RadioMessage, MAX_FRAME_SIZE,
 };
+use crate::Clock;
 
 /// Re-export to allow using [Client] with [std::io] streams.
 #[cfg(feature = "std")]
@@ -17,6 +22,8 @@ pub use embedded_io_adapters::std::FromStd;
 pub enum ClientError<E> {
     /// EOF in underlying stream.
     UnexpectedEof,
+    /// A message was too large to fit in one frame.
+    FrameTooLarge,
     /// Other IO error in underlying stream.
     Io(E),
 }
@@ -31,6 +38,7 @@ where
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         match self {
             Self::UnexpectedEof => write!(f, "unexpected eof"),
+            Self::FrameTooLarge => write!(f, "message too large for one frame"),
             Self::Io(e) => write!(f, "io error: {:?}", e),
         }
     }
@@ -42,6 +50,217 @@ impl<E> From<E> for ClientError<E> {
     }
 }
 
+/// The mode a radio is running in, as found by [ClientHost::detect_mode()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RadioMode {
+    /// The radio is in the bootloader, ready to flash.
+    Bootloader,
+    /// The radio is running firmware.
+    Firmware,
+}
+
+/// What a radio says about itself, as found by [ClientHost::identify()].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RadioIdentity {
+    /// The bootloader's announcement, with chip ID and bootloader version.
+    Bootloader(bootloader::BootloaderReady),
+    /// The firmware's reply to [radio::Hello].
+    Firmware(radio::HelloReply),
+}
+
+impl RadioIdentity {
+    /// The mode the radio is running in.
+    pub fn mode(&self) -> RadioMode {
+        match self {
+            Self::Bootloader(_) => RadioMode::Bootloader,
+            Self::Firmware(_) => RadioMode::Firmware,
+        }
+    }
+}
+
+/// An error type for [ClientHost::detect_mode()] and
+/// [ClientHost::identify()].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DetectModeError<E> {
+    /// Nothing answered before the port timed out. Radio not present?
+    Timeout,
+    /// The radio answered with a message of this unexpected type.
+    UnexpectedReply(u16),
+    /// Other error in the underlying client.
+    Client(ClientError<E>),
+}
+
+#[cfg(feature = "std")]
+impl<E> std::error::Error for DetectModeError<E> where E: core::fmt::Debug {}
+
+impl<E> core::fmt::Display for DetectModeError<E>
+where
+    E: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::Timeout => write!(f, "timed out waiting for radio"),
+            Self::UnexpectedReply(typ) => write!(f, "unexpected reply: 0x{:04x}", typ),
+            Self::Client(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl<E> From<ClientError<E>> for DetectModeError<E> {
+    fn from(other: ClientError<E>) -> Self {
+        Self::Client(other)
+    }
+}
+
+/// An error type for [ClientHost::bootloader_handshake()].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BootloaderHandshakeError<E> {
+    /// The bootloader did not announce itself before the port timed out.
+    Timeout,
+    /// The radio sent a message of this type, instead of
+    /// [bootloader::BootloaderReady].
+    UnexpectedMessage(u16),
+    /// Other error in the underlying client.
+    Client(ClientError<E>),
+}
+
+#[cfg(feature = "std")]
+impl<E> std::error::Error for BootloaderHandshakeError<E> where E: core::fmt::Debug {}
+
+impl<E> core::fmt::Display for BootloaderHandshakeError<E>
+where
+    E: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::Timeout => write!(f, "timed out waiting for bootloader"),
+            Self::UnexpectedMessage(typ) => write!(f, "unexpected message: 0x{:04x}", typ),
+            Self::Client(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl<E> From<ClientError<E>> for BootloaderHandshakeError<E> {
+    fn from(other: ClientError<E>) -> Self {
+        Self::Client(other)
+    }
+}
+
+/// Handle one read while waiting for the bootloader, for clients that
+/// wrap their own reads around [ClientHost::bootloader_handshake()].
+///
+/// Returns [None] to keep listening, or else the announcement or the
+/// error the handshake stops with. After an announcement, the caller
+/// replies with [bootloader::BootloaderReadyReply].
+pub fn bootloader_handshake_step<I, E>(
+    read: Result<ParseResult<I, RadioMessage<I>>, ClientError<E>>,
+) -> Option<Result<bootloader::BootloaderReady, BootloaderHandshakeError<E>>>
+where
+    E: embedded_io::Error,
+{
+    match read {
+        Ok(ParseResult::Ok(_, RadioMessage::BootloaderReady(m))) => Some(Ok(m)),
+        Ok(ParseResult::Ok(_, m)) => Some(Err(BootloaderHandshakeError::UnexpectedMessage(
+            m.message_type(),
+        ))),
+        // garbage or corrupt frames, keep listening
+        Ok(_) => None,
+        Err(ClientError::Io(e)) if e.kind() == embedded_io::ErrorKind::TimedOut => {
+            Some(Err(BootloaderHandshakeError::Timeout))
+        }
+        Err(e) => Some(Err(e.into())),
+    }
+}
+
+/// How many bytes [ClientHost::read_eeprom_range()] asks for at once.
+pub const READ_EEPROM_CHUNK_SIZE: usize = 0x80;
+
+/// How many times [ClientHost::read_eeprom_range()] will re-request a
+/// block after a CRC error before giving up.
+pub const READ_EEPROM_RETRIES: usize = 5;
+
+/// An error type for [ClientHost::read_eeprom_range()].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReadEepromError<E> {
+    /// The radio skipped ahead, replying with a later address than requested.
+    AddressGap { expected: u16, found: u16 },
+    /// Too many CRC errors while reading the block at this address.
+    TooManyRetries(u16),
+    /// Other error in the underlying client.
+    Client(ClientError<E>),
+}
+
+#[cfg(feature = "std")]
+impl<E> std::error::Error for ReadEepromError<E> where E: core::fmt::Debug {}
+
+impl<E> core::fmt::Display for ReadEepromError<E>
+where
+    E: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::AddressGap { expected, found } => write!(
+                f,
+                "address gap: expected 0x{:04x}, found 0x{:04x}",
+                expected, found
+            ),
+            Self::TooManyRetries(address) => {
+                write!(f, "too many crc errors reading 0x{:04x}", address)
+            }
+            Self::Client(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl<E> From<ClientError<E>> for ReadEepromError<E> {
+    fn from(other: ClientError<E>) -> Self {
+        Self::Client(other)
+    }
+}
+
+/// An error type for [Client::request()].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RequestError<E> {
+    /// No reply arrived in time, even after retrying.
+    Timeout,
+    /// The last attempt ended in a frame that failed its CRC check.
+    CrcErr,
+    /// A reply of this type arrived, but did not parse as the
+    /// requested reply.
+    BadReply(u16),
+    /// Other error in the underlying client.
+    Client(ClientError<E>),
+}
+
+#[cfg(feature = "std")]
+impl<E> std::error::Error for RequestError<E> where E: core::fmt::Debug {}
+
+impl<E> core::fmt::Display for RequestError<E>
+where
+    E: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::Timeout => write!(f, "timed out waiting for reply"),
+            Self::CrcErr => write!(f, "crc error in reply"),
+            Self::BadReply(typ) => write!(f, "bad reply: 0x{:04x}", typ),
+            Self::Client(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl<E> From<ClientError<E>> for RequestError<E> {
+    fn from(other: ClientError<E>) -> Self {
+        Self::Client(other)
+    }
+}
+
 /// A trait to encapsulate a buffer with filled and unfilled areas.
 pub trait ClientBuffer {
     type Slice<'a>: Parse
@@ -58,6 +277,12 @@ pub trait ClientBuffer {
     /// Returns [true] if the buffer is full.
     fn is_full(&self) -> bool;
 
+    /// The most data this buffer can hold, or [None] if it can grow
+    /// without limit.
+    fn capacity(&self) -> Option<usize> {
+        None
+    }
+
     /// Read data from a reader into the filled part, consuming unfilled areas.
     fn read<R>(&mut self, reader: &mut R) -> Result<usize, R::Error>
     where
@@ -73,6 +298,18 @@ pub trait ClientBuffer {
     fn clear(&mut self);
 }
 
+/// A [ClientBuffer] that can also be filled from an async reader, for
+/// use with [AsyncClient].
+#[cfg(feature = "embedded-io-async")]
+pub trait ClientBufferAsync: ClientBuffer {
+    /// Read data from an async reader into the filled part, consuming
+    /// unfilled areas.
+    #[allow(async_fn_in_trait)]
+    async fn read_async<R>(&mut self, reader: &mut R) -> Result<usize, R::Error>
+    where
+        R: embedded_io_async::Read;
+}
+
 // would be nice to do this for &'b mut B, but 'static is all we really use
 impl<B> ClientBuffer for &'static mut B
 where
@@ -89,6 +326,10 @@ where
         (**self).is_full()
     }
 
+    fn capacity(&self) -> Option<usize> {
+        (**self).capacity()
+    }
+
     fn read<R>(&mut self, reader: &mut R) -> Result<usize, R::Error>
     where
         R: embedded_io::Read,
@@ -109,6 +350,19 @@ where
     }
 }
 
+#[cfg(feature = "embedded-io-async")]
+impl<B> ClientBufferAsync for &'static mut B
+where
+    B: ClientBufferAsync,
+{
+    async fn read_async<R>(&mut self, reader: &mut R) -> Result<usize, R::Error>
+    where
+        R: embedded_io_async::Read,
+    {
+        (**self).read_async(reader).await
+    }
+}
+
 /// A [ClientBuffer] using a flat array.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -145,6 +399,10 @@ impl<const SIZE: usize> ClientBuffer for ArrayBuffer<SIZE> {
         self.len >= SIZE
     }
 
+    fn capacity(&self) -> Option<usize> {
+        Some(SIZE)
+    }
+
     fn read<R>(&mut self, reader: &mut R) -> Result<usize, R::Error>
     where
         R: embedded_io::Read,
@@ -167,6 +425,211 @@ impl<const SIZE: usize> ClientBuffer for ArrayBuffer<SIZE> {
     }
 }
 
+#[cfg(feature = "embedded-io-async")]
+impl<const SIZE: usize> ClientBufferAsync for ArrayBuffer<SIZE> {
+    async fn read_async<R>(&mut self, reader: &mut R) -> Result<usize, R::Error>
+    where
+        R: embedded_io_async::Read,
+    {
+        let amt = reader.read(&mut self.buffer[self.len..]).await?;
+        self.len += amt;
+        Ok(amt)
+    }
+}
+
+/// A [ClientBuffer] using a [Vec](alloc::vec::Vec) that grows as
+/// needed, up to an optional cap.
+///
+/// Unlike [ArrayBuffer], a partial frame behind a lot of line noise
+/// won't be lost to the buffer filling up. Frames themselves are still
+/// limited to [MAX_FRAME_SIZE] by the parser, unless changed with
+/// [Client::set_max_frame_size()].
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct VecBuffer {
+    buffer: alloc::vec::Vec<u8>,
+    cap: Option<usize>,
+}
+
+#[cfg(feature = "alloc")]
+impl VecBuffer {
+    /// How much to grow the buffer by before each read.
+    const READ_SIZE: usize = MAX_FRAME_SIZE;
+
+    /// Create a buffer that grows without limit.
+    pub const fn new() -> Self {
+        Self {
+            buffer: alloc::vec::Vec::new(),
+            cap: None,
+        }
+    }
+
+    /// Create a buffer that grows up to `cap` bytes.
+    pub const fn with_cap(cap: usize) -> Self {
+        Self {
+            buffer: alloc::vec::Vec::new(),
+            cap: Some(cap),
+        }
+    }
+
+    /// The cap on this buffer's size, if any.
+    pub fn cap(&self) -> Option<usize> {
+        self.cap
+    }
+
+    /// Grow the buffer with space for a read, returning the old length.
+    fn grow(&mut self) -> usize {
+        let len = self.buffer.len();
+        let mut new_len = len + Self::READ_SIZE;
+        if let Some(cap) = self.cap {
+            new_len = new_len.min(cap.max(len));
+        }
+        self.buffer.resize(new_len, 0);
+        len
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl ClientBuffer for VecBuffer {
+    type Slice<'a> = &'a [u8];
+    type SliceMut<'a> = &'a mut [u8];
+
+    fn skip(&mut self, n: usize) {
+        self.buffer.drain(..n.min(self.buffer.len()));
+    }
+
+    fn is_full(&self) -> bool {
+        self.cap.is_some_and(|cap| self.buffer.len() >= cap)
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.cap
+    }
+
+    fn read<R>(&mut self, reader: &mut R) -> Result<usize, R::Error>
+    where
+        R: embedded_io::Read,
+    {
+        let len = self.grow();
+        let result = reader.read(&mut self.buffer[len..]);
+        self.buffer.truncate(len + *result.as_ref().unwrap_or(&0));
+        result
+    }
+
+    fn data_mut(&mut self) -> Self::SliceMut<'_> {
+        &mut self.buffer[..]
+    }
+
+    fn data(&self) -> Self::Slice<'_> {
+        &self.buffer[..]
+    }
+
+    fn clear(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+#[cfg(all(feature = "alloc", feature = "embedded-io-async"))]
+impl ClientBufferAsync for VecBuffer {
+    async fn read_async<R>(&mut self, reader: &mut R) -> Result<usize, R::Error>
+    where
+        R: embedded_io_async::Read,
+    {
+        let len = self.grow();
+        let result = reader.read(&mut self.buffer[len..]).await;
+        self.buffer.truncate(len + *result.as_ref().unwrap_or(&0));
+        result
+    }
+}
+
+/// A [ClientBuffer] using a [heapless::Vec], holding up to `SIZE`
+/// bytes without an allocator.
+///
+/// This behaves like [ArrayBuffer], but keeps its length in the
+/// [heapless::Vec]. Readers need initialized space to read into, so
+/// each read zero-fills the spare capacity first, and then truncates
+/// the vector back to what was actually read.
+#[cfg(feature = "heapless")]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HeaplessBuffer<const SIZE: usize = MAX_FRAME_SIZE> {
+    buffer: heapless::Vec<u8, SIZE>,
+}
+
+#[cfg(feature = "heapless")]
+impl<const SIZE: usize> HeaplessBuffer<SIZE> {
+    pub const fn new() -> Self {
+        Self {
+            buffer: heapless::Vec::new(),
+        }
+    }
+
+    /// Fill the buffer with space for a read, returning the old length.
+    fn grow(&mut self) -> usize {
+        let len = self.buffer.len();
+        // never fails, we resize to exactly the capacity
+        let _ = self.buffer.resize_default(SIZE);
+        len
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<const SIZE: usize> ClientBuffer for HeaplessBuffer<SIZE> {
+    type Slice<'a> = &'a [u8];
+    type SliceMut<'a> = &'a mut [u8];
+
+    fn skip(&mut self, n: usize) {
+        let len = self.buffer.len();
+        let n = n.min(len);
+        self.buffer.copy_within(n..len, 0);
+        self.buffer.truncate(len - n);
+    }
+
+    fn is_full(&self) -> bool {
+        self.buffer.is_full()
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(SIZE)
+    }
+
+    fn read<R>(&mut self, reader: &mut R) -> Result<usize, R::Error>
+    where
+        R: embedded_io::Read,
+    {
+        let len = self.grow();
+        let result = reader.read(&mut self.buffer[len..]);
+        self.buffer.truncate(len + *result.as_ref().unwrap_or(&0));
+        result
+    }
+
+    fn data_mut(&mut self) -> Self::SliceMut<'_> {
+        &mut self.buffer[..]
+    }
+
+    fn data(&self) -> Self::Slice<'_> {
+        &self.buffer[..]
+    }
+
+    fn clear(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+#[cfg(all(feature = "heapless", feature = "embedded-io-async"))]
+impl<const SIZE: usize> ClientBufferAsync for HeaplessBuffer<SIZE> {
+    async fn read_async<R>(&mut self, reader: &mut R) -> Result<usize, R::Error>
+    where
+        R: embedded_io_async::Read,
+    {
+        let len = self.grow();
+        let result = reader.read(&mut self.buffer[len..]).await;
+        self.buffer.truncate(len + *result.as_ref().unwrap_or(&0));
+        result
+    }
+}
+
 /// A client for the UV-K5 serial protocol.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -175,6 +638,7 @@ pub struct Client<F, B, InC, OutC> {
     buffer: B,
     skip: usize,
     found: Option<FoundFrame>,
+    scanner: FrameScanner,
     needs_read: bool,
     in_crc: InC,
     out_crc: OutC,
@@ -203,7 +667,291 @@ where
     pub fn new_with(buffer: B, port: F) -> Self {
         Client::new_crc_with(
             buffer,
-            crc::CrcConstantIgnore(0xffff),
+            crc::CrcConstantIgnore(0xffff),
+            crc::CrcXModem::new(),
+            port,
+        )
+    }
+}
+
+impl<F, B> ClientHost<F, B>
+where
+    B: ClientBuffer,
+    F: embedded_io::Read + embedded_io::Write,
+{
+    /// Probe the radio to find out if it is in the bootloader or
+    /// running firmware.
+    ///
+    /// This relies on the port having a read timeout set, so that a
+    /// missing radio shows up as [DetectModeError::Timeout].
+    pub fn detect_mode(&mut self) -> Result<RadioMode, DetectModeError<F::Error>> {
+        self.identify().map(|id| id.mode())
+    }
+
+    /// Probe the radio like [Self::detect_mode()], and return what it
+    /// said about itself.
+    ///
+    /// This never replies to the bootloader, so the radio is left
+    /// ready for [Self::bootloader_handshake()].
+    pub fn identify(&mut self) -> Result<RadioIdentity, DetectModeError<F::Error>> {
+        use embedded_io::Error;
+
+        // firmware answers hello, bootloader announces itself unprompted
+        self.write(&radio::Hello {
+            session_id: radio::HELLO_SESSION_ID,
+        })?;
+
+        loop {
+            match self.read_radio() {
+                Ok(ParseResult::Ok(_, RadioMessage::HelloReply(m))) => {
+                    return Ok(RadioIdentity::Firmware(m));
+                }
+                Ok(ParseResult::Ok(_, RadioMessage::BootloaderReady(m))) => {
+                    return Ok(RadioIdentity::Bootloader(m));
+                }
+                Ok(ParseResult::Ok(_, m)) => {
+                    return Err(DetectModeError::UnexpectedReply(m.message_type()));
+                }
+                // garbage or corrupt frames, keep listening
+                Ok(_) => {}
+                Err(ClientError::Io(e)) if e.kind() == embedded_io::ErrorKind::TimedOut => {
+                    return Err(DetectModeError::Timeout);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Wait for the bootloader to announce itself, and reply with the
+    /// version of the firmware about to be flashed.
+    ///
+    /// Returns the announcement, with the radio's chip ID and
+    /// bootloader version. After this, the bootloader is ready for
+    /// [bootloader::WriteFlash].
+    ///
+    /// This relies on the port having a read timeout set, so that a
+    /// radio not in bootloader mode shows up as
+    /// [BootloaderHandshakeError::Timeout].
+    pub fn bootloader_handshake(
+        &mut self,
+        version: crate::Version,
+    ) -> Result<bootloader::BootloaderReady, BootloaderHandshakeError<F::Error>> {
+        let ready = loop {
+            if let Some(ready) = bootloader_handshake_step(self.read_radio()) {
+                break ready?;
+            }
+        };
+
+        self.write(&bootloader::BootloaderReadyReply { version })?;
+        Ok(ready)
+    }
+
+    /// Read `len` bytes of EEPROM starting at `start`.
+    ///
+    /// The session ID must match one previously sent in a
+    /// [radio::Hello]. The `progress` callback is called with the
+    /// number of bytes read so far after every block.
+    ///
+    /// Blocks start out at [READ_EEPROM_CHUNK_SIZE] bytes, and shrink
+    /// for radios that reply with shorter blocks. Unlike
+    /// [Self::read_eeprom_range_adaptive()], a timeout is an error.
+    ///
+    /// If the radio returns a short block, the data read so far is
+    /// returned, which may be less than `len`.
+    ///
+    /// Panics if the range does not fit in the 16-bit address space.
+    #[cfg(feature = "alloc")]
+    pub fn read_eeprom_range(
+        &mut self,
+        start: u16,
+        len: usize,
+        session_id: u32,
+        progress: impl FnMut(usize),
+    ) -> Result<alloc::vec::Vec<u8>, ReadEepromError<F::Error>> {
+        self.read_eeprom_blocks(
+            start,
+            len,
+            session_id,
+            READ_EEPROM_CHUNK_SIZE,
+            false,
+            progress,
+        )
+        .map(|(data, _)| data)
+    }
+
+    /// Read `len` bytes of EEPROM starting at `start`, in blocks of at
+    /// most `max_block` bytes.
+    ///
+    /// Radios differ in the largest block they will answer. Some reply
+    /// with a short block, and some time out without replying. Until a
+    /// full block has been read, either one shrinks the block size,
+    /// down to the length of the short reply or half the size that
+    /// timed out, and the read carries on from there. Once a block of
+    /// some size succeeds, that size is kept, and a short block means
+    /// the end of the EEPROM as in [Self::read_eeprom_range()].
+    ///
+    /// Returns the data read, and the block size settled on. This
+    /// relies on the port having a read timeout set.
+    ///
+    /// Panics if the range does not fit in the 16-bit address space.
+    #[cfg(feature = "alloc")]
+    pub fn read_eeprom_range_adaptive(
+        &mut self,
+        start: u16,
+        len: usize,
+        session_id: u32,
+        max_block: usize,
+        progress: impl FnMut(usize),
+    ) -> Result<(alloc::vec::Vec<u8>, usize), ReadEepromError<F::Error>> {
+        self.read_eeprom_blocks(start, len, session_id, max_block, true, progress)
+    }
+
+    // the guts of both read_eeprom_range and read_eeprom_range_adaptive
+    #[cfg(feature = "alloc")]
+    fn read_eeprom_blocks(
+        &mut self,
+        start: u16,
+        len: usize,
+        session_id: u32,
+        max_block: usize,
+        shrink_on_timeout: bool,
+        mut progress: impl FnMut(usize),
+    ) -> Result<(alloc::vec::Vec<u8>, usize), ReadEepromError<F::Error>> {
+        use embedded_io::Error;
+
+        assert!(start as usize + len <= u16::MAX as usize + 1);
+
+        let mut data = alloc::vec::Vec::with_capacity(len);
+        let mut block = max_block.clamp(1, u8::MAX as usize);
+        let mut settled = false;
+        let mut retries = 0;
+        'blocks: while data.len() < len {
+            let address = start + data.len() as u16;
+            let chunk = (len - data.len()).min(block);
+            self.write(&radio::ReadEeprom {
+                address,
+                len: chunk as u8,
+                _pad: Default::default(),
+                session_id,
+            })?;
+
+            loop {
+                let reply = match self.read::<radio::ReadEepromReply<B::Slice<'_>>>() {
+                    Err(ClientError::Io(e))
+                        if shrink_on_timeout
+                            && !settled
+                            && block > 1
+                            && e.kind() == embedded_io::ErrorKind::TimedOut =>
+                    {
+                        // no answer, try a smaller block
+                        block /= 2;
+                        continue 'blocks;
+                    }
+                    r => r?,
+                };
+
+                match reply {
+                    ParseResult::Ok(_, m) => {
+                        if m.address < address {
+                            // stale reply to an earlier request
+                            continue;
+                        }
+                        if m.address > address {
+                            return Err(ReadEepromError::AddressGap {
+                                expected: address,
+                                found: m.address,
+                            });
+                        }
+
+                        // never take more than we asked for
+                        let mut got = 0;
+                        for slice in m.data.iter_slices() {
+                            let slice = &slice[..slice.len().min(chunk - got)];
+                            data.extend_from_slice(slice);
+                            got += slice.len();
+                        }
+                        retries = 0;
+                        progress(data.len());
+
+                        if got >= chunk {
+                            settled |= chunk == block;
+                            continue 'blocks;
+                        }
+                        if settled || got == 0 {
+                            // radio ran out of eeprom, we're done
+                            break 'blocks;
+                        }
+                        // maybe the end, maybe a smaller limit
+                        block = got;
+                        continue 'blocks;
+                    }
+                    ParseResult::CrcErr(_, _) => {
+                        retries += 1;
+                        if retries > READ_EEPROM_RETRIES {
+                            return Err(ReadEepromError::TooManyRetries(address));
+                        }
+                        // ask for this block again
+                        continue 'blocks;
+                    }
+                    // not a reply, keep listening
+                    _ => {}
+                }
+            }
+        }
+
+        Ok((data, block))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<F, B> ClientHost<FromStd<F>, B>
+where
+    B: ClientBuffer,
+{
+    /// Create a new host client using an [std::io] port.
+    pub fn new_std(port: F) -> Self
+    where
+        B: Default,
+    {
+        Self::new(FromStd::new(port))
+    }
+
+    /// Create a new host client using and [std::io] port and the
+    /// provided internal buffer.
+    pub fn new_std_with(buffer: B, port: F) -> Self {
+        Self::new_with(buffer, FromStd::new(port))
+    }
+}
+
+/// A host-sided client that checks incoming CRCs, but also accepts
+/// frames carrying a constant CRC. See [crc::CrcAny].
+pub type ClientHostAny<F, B = ArrayBuffer> = Client<F, B, crc::CrcAny, crc::CrcXModem>;
+
+/// A host-sided client using an [std::io] port that checks incoming
+/// CRCs, but also accepts frames carrying a constant CRC.
+#[cfg(feature = "std")]
+pub type ClientHostAnyStd<F, B = ArrayBuffer> = ClientHostAny<FromStd<F>, B>;
+
+impl<F, B> ClientHostAny<F, B>
+where
+    B: ClientBuffer,
+{
+    /// Create a new host client, accepting incoming frames with
+    /// either a valid CRC or the given constant.
+    pub fn new_any(constant: u16, port: F) -> Self
+    where
+        B: Default,
+    {
+        Self::new_any_with(B::default(), constant, port)
+    }
+
+    /// Create a new host client with the provided internal buffer,
+    /// accepting incoming frames with either a valid CRC or the given
+    /// constant.
+    pub fn new_any_with(buffer: B, constant: u16, port: F) -> Self {
+        Client::new_crc_with(
+            buffer,
+            crc::CrcAny::new(constant),
             crc::CrcXModem::new(),
             port,
         )
@@ -211,22 +959,17 @@ where
 }
 
 #[cfg(feature = "std")]
-impl<F, B> ClientHost<FromStd<F>, B>
+impl<F, B> ClientHostAny<FromStd<F>, B>
 where
     B: ClientBuffer,
 {
-    /// Create a new host client using an [std::io] port.
-    pub fn new_std(port: F) -> Self
+    /// Create a new host client using an [std::io] port, accepting
+    /// incoming frames with either a valid CRC or the given constant.
+    pub fn new_any_std(constant: u16, port: F) -> Self
     where
         B: Default,
     {
-        Self::new(FromStd::new(port))
-    }
-
-    /// Create a new host client using and [std::io] port and the
-    /// provided internal buffer.
-    pub fn new_std_with(buffer: B, port: F) -> Self {
-        Self::new_with(buffer, FromStd::new(port))
+        Self::new_any(constant, FromStd::new(port))
     }
 }
 
@@ -302,6 +1045,7 @@ where
             buffer,
             skip: 0,
             found: None,
+            scanner: FrameScanner::new(),
             needs_read: true,
             in_crc,
             out_crc,
@@ -354,11 +1098,69 @@ where
         self.skip
     }
 
+    /// Largest full frame the client will accept, in bytes.
+    ///
+    /// This is [MAX_FRAME_SIZE] unless changed with
+    /// [Self::set_max_frame_size()].
+    pub fn max_frame_size(&self) -> usize {
+        self.scanner.max_size()
+    }
+
+    /// Change the largest full frame the client will accept, for
+    /// firmware that sends frames larger than [MAX_FRAME_SIZE].
+    ///
+    /// This is capped at the buffer's
+    /// [capacity][ClientBuffer::capacity()], since a larger frame
+    /// could never fit. Returns the size actually used.
+    pub fn set_max_frame_size(&mut self, max_size: usize) -> usize {
+        let max_size = match self.buffer.capacity() {
+            Some(capacity) => max_size.min(capacity),
+            None => max_size,
+        };
+        self.scanner.set_max_size(max_size);
+        max_size
+    }
+
     /// Get the bounds of the frame found in the last parse, if any.
     pub fn found(&self) -> &Option<FoundFrame> {
         &self.found
     }
 
+    /// Get the on-wire bytes of the frame found in the last parse, if
+    /// any, for logging or relaying.
+    ///
+    /// Frames are deobfuscated in place when they are found, so this
+    /// obfuscates the contents again as it iterates. The result is
+    /// exactly the bytes that arrived, from frame start to frame end.
+    pub fn last_raw_frame(&self) -> Option<impl Iterator<Item = u8> + '_> {
+        let found = self.found.as_ref()?;
+        let data = self.buffer.data();
+        let head = data.slice(found.full_frame.start..found.frame_contents.start);
+        let contents = data.slice(found.frame_contents.clone());
+        let tail = data.slice(found.frame_contents.end..found.full_frame.end);
+
+        let mut key = Key::new();
+        Some(
+            head.iter_elements()
+                .chain(contents.iter_elements().map(move |b| key.apply(b)))
+                .chain(tail.iter_elements()),
+        )
+    }
+
+    /// Serialize a message into the exact bytes [Self::write()] would
+    /// send, without sending them.
+    #[cfg(feature = "alloc")]
+    pub fn reframe<M>(&self, msg: &M) -> alloc::vec::Vec<u8>
+    where
+        M: MessageSerialize,
+    {
+        let mut ser = serialize::SerializerVec::new();
+        match protocol::serialize(&self.out_crc, &mut ser, msg) {
+            Ok(()) => ser.done(),
+            Err(e) => match e {},
+        }
+    }
+
     /// Read from the port into the internal buffer, if needed, and
     /// find a frame. First half of [Self::read()].
     ///
@@ -368,6 +1170,21 @@ where
     where
         F: embedded_io::Read,
     {
+        if self.start_read() {
+            let amt = self.buffer.read(&mut self.port)?;
+            if amt == 0 {
+                // end of file is an error
+                return Err(ClientError::UnexpectedEof);
+            }
+        }
+
+        self.find_frame();
+        Ok(())
+    }
+
+    /// Get the buffer ready for a read. Returns [true] if more data
+    /// needs to be read from the port.
+    fn start_read(&mut self) -> bool {
         // clear any previously found frame
         self.found = None;
 
@@ -380,31 +1197,24 @@ where
         // if the buffer is full, even now, clear it and restart
         if self.buffer.is_full() {
             self.buffer.clear();
+            self.scanner.reset();
             self.needs_read = true;
         }
 
         // if we've cleared the buffer, or if the last parse found nothing,
         // we need to read more data
-        if self.needs_read {
-            let amt = self.buffer.read(&mut self.port)?;
-            if amt == 0 {
-                // end of file is an error
-                return Err(ClientError::UnexpectedEof);
-            }
-            self.needs_read = false;
-        }
+        self.needs_read
+    }
 
-        // attempt to find a frame
-        let (skip, found) = protocol::find_frame(self.buffer.data_mut());
+    /// Find a frame in the buffer, after any needed read.
+    fn find_frame(&mut self) {
+        // attempt to find a frame, scanning only new data
+        let (skip, found) = self.scanner.scan(self.buffer.data_mut());
         self.skip = skip;
         self.found = found;
 
-        if self.found.is_none() {
-            // we found no frames, so we need more data
-            self.needs_read = true;
-        }
-
-        Ok(())
+        // if we found no frames, we need more data
+        self.needs_read = self.found.is_none();
     }
 
     /// Parse from the internal buffer. Second half of [Self::read()].
@@ -470,4 +1280,870 @@ where
         self.port.flush()?;
         Ok(())
     }
+
+    /// Send a request, and wait for a reply of type `Rep`.
+    ///
+    /// Frames that are not a `Rep` are ignored. If no reply arrives
+    /// within `timeout`, as measured by `clock`, or a frame fails its
+    /// CRC check, the request is sent again, up to `retries` more
+    /// times.
+    ///
+    /// The clock is only checked between reads, so the port should
+    /// have a read timeout shorter than `timeout`. Port timeouts are
+    /// otherwise ignored. Only replies that are part of [Message] can
+    /// be recognized.
+    ///
+    /// A late reply to an earlier request looks the same as a reply
+    /// to this one, and is accepted.
+    pub fn request<'a, Req, Rep>(
+        &'a mut self,
+        clock: &impl Clock,
+        req: &Req,
+        timeout: core::time::Duration,
+        retries: usize,
+    ) -> Result<Rep, RequestError<F::Error>>
+    where
+        F: embedded_io::Read + embedded_io::Write,
+        Req: MessageSerialize,
+        Rep: MessageType + MessageParse<B::Slice<'a>>,
+    {
+        use embedded_io::Error;
+
+        let mut crc_err = false;
+        for _ in 0..=retries {
+            self.write(req)?;
+            let start = clock.now();
+            crc_err = false;
+
+            while clock.now().saturating_sub(start) < timeout {
+                match self.read_into_buffer() {
+                    Ok(()) => {}
+                    Err(ClientError::Io(e)) if e.kind() == embedded_io::ErrorKind::TimedOut => {
+                        continue;
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+
+                // check the type without tying up the buffer, so that
+                // unrelated frames can be skipped
+                let found = match self.parse::<Message<B::Slice<'_>>>() {
+                    ParseResult::Ok(_, m) => m.message_type() == Rep::TYPE,
+                    ParseResult::CrcErr(_, _) => {
+                        crc_err = true;
+                        break;
+                    }
+                    _ => false,
+                };
+
+                if found {
+                    return match self.parse::<Rep>() {
+                        ParseResult::Ok(_, m) => Ok(m),
+                        _ => Err(RequestError::BadReply(Rep::TYPE)),
+                    };
+                }
+            }
+        }
+
+        if crc_err {
+            Err(RequestError::CrcErr)
+        } else {
+            Err(RequestError::Timeout)
+        }
+    }
+}
+
+/// An async client for the UV-K5 serial protocol.
+///
+/// This wraps a [Client], and dereferences to it for everything that
+/// doesn't do IO. Only reads and writes are async.
+#[cfg(feature = "embedded-io-async")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AsyncClient<F, B, InC, OutC> {
+    inner: Client<F, B, InC, OutC>,
+}
+
+/// An async host-sided client.
+#[cfg(feature = "embedded-io-async")]
+pub type AsyncClientHost<F, B = ArrayBuffer> =
+    AsyncClient<F, B, crc::CrcConstantIgnore, crc::CrcXModem>;
+
+/// An async radio-sided client.
+#[cfg(feature = "embedded-io-async")]
+pub type AsyncClientRadio<F, B = ArrayBuffer> =
+    AsyncClient<F, B, crc::CrcXModem, crc::CrcConstantIgnore>;
+
+#[cfg(feature = "embedded-io-async")]
+impl<F, B> AsyncClientHost<F, B>
+where
+    B: ClientBuffer,
+{
+    /// Create a new async host client.
+    pub fn new(port: F) -> Self
+    where
+        B: Default,
+    {
+        ClientHost::new(port).into()
+    }
+
+    /// Create a new async host client with the provided internal buffer.
+    pub fn new_with(buffer: B, port: F) -> Self {
+        ClientHost::new_with(buffer, port).into()
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<F, B> AsyncClientRadio<F, B>
+where
+    B: ClientBuffer,
+{
+    /// Create a new async radio client.
+    pub fn new(port: F) -> Self
+    where
+        B: Default,
+    {
+        ClientRadio::new(port).into()
+    }
+
+    /// Create a new async radio client with the provided internal buffer.
+    pub fn new_with(buffer: B, port: F) -> Self {
+        ClientRadio::new_with(buffer, port).into()
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<F, B, InC, OutC> From<Client<F, B, InC, OutC>> for AsyncClient<F, B, InC, OutC> {
+    fn from(inner: Client<F, B, InC, OutC>) -> Self {
+        Self { inner }
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<F, B, InC, OutC> core::ops::Deref for AsyncClient<F, B, InC, OutC> {
+    type Target = Client<F, B, InC, OutC>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<F, B, InC, OutC> core::ops::DerefMut for AsyncClient<F, B, InC, OutC> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<F, B, InC, OutC> AsyncClient<F, B, InC, OutC>
+where
+    B: ClientBuffer,
+    InC: crc::CrcStyle,
+    OutC: crc::CrcStyle,
+{
+    /// Release the wrapped synchronous [Client].
+    pub fn into_inner(self) -> Client<F, B, InC, OutC> {
+        self.inner
+    }
+
+    /// Read from the port into the internal buffer, if needed, and
+    /// find a frame. First half of [Self::read()].
+    ///
+    /// See [Client::read_into_buffer()].
+    pub async fn read_into_buffer(&mut self) -> Result<(), ClientError<F::Error>>
+    where
+        B: ClientBufferAsync,
+        F: embedded_io_async::Read,
+    {
+        let inner = &mut self.inner;
+        if inner.start_read() {
+            let amt = inner.buffer.read_async(&mut inner.port).await?;
+            if amt == 0 {
+                // end of file is an error
+                return Err(ClientError::UnexpectedEof);
+            }
+        }
+
+        inner.find_frame();
+        Ok(())
+    }
+
+    /// Read from the port and attempt to parse a message.
+    pub async fn read<'a, M>(
+        &'a mut self,
+    ) -> Result<ParseResult<B::Slice<'a>, M>, ClientError<F::Error>>
+    where
+        M: MessageParse<B::Slice<'a>>,
+        B: ClientBufferAsync,
+        F: embedded_io_async::Read,
+    {
+        self.read_into_buffer().await?;
+        Ok(self.inner.parse())
+    }
+
+    /// Write a message to the port.
+    ///
+    /// Messages that do not fit in a [MAX_FRAME_SIZE] frame are not
+    /// sent, and return [ClientError::FrameTooLarge].
+    pub async fn write<M>(&mut self, msg: &M) -> Result<(), ClientError<F::Error>>
+    where
+        F: embedded_io_async::Write,
+        M: MessageSerialize,
+    {
+        // serialize is synchronous, so build the frame first
+        let mut frame = [0; MAX_FRAME_SIZE];
+        let mut ser = serialize::SerializerSlice::new(&mut frame);
+        protocol::serialize(&self.inner.out_crc, &mut ser, msg)
+            .map_err(|_| ClientError::FrameTooLarge)?;
+
+        self.inner.port.write_all(ser.done()).await?;
+        self.inner.port.flush().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "alloc")]
+mod test {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn frame_bytes<M>(msg: &M) -> Vec<u8>
+    where
+        M: MessageSerialize,
+    {
+        let mut ser = serialize::SerializerVec::new();
+        protocol::serialize(&crc::CrcXModem::new(), &mut ser, msg).unwrap();
+        ser.done()
+    }
+
+    fn check_buffer<B>(mut buffer: B)
+    where
+        B: ClientBuffer,
+        for<'a> B::Slice<'a>: Into<&'a [u8]>,
+    {
+        assert_eq!(buffer.data().into(), &[] as &[u8]);
+        assert_eq!(buffer.read(&mut &[1, 2, 3, 4, 5][..]), Ok(5));
+        assert_eq!(buffer.data().into(), &[1, 2, 3, 4, 5]);
+        buffer.skip(2);
+        assert_eq!(buffer.data().into(), &[3, 4, 5]);
+        assert_eq!(buffer.read(&mut &[6, 7][..]), Ok(2));
+        assert_eq!(buffer.data().into(), &[3, 4, 5, 6, 7]);
+        buffer.skip(5);
+        assert_eq!(buffer.data().into(), &[] as &[u8]);
+        assert_eq!(buffer.read(&mut &[8][..]), Ok(1));
+        buffer.clear();
+        assert_eq!(buffer.data().into(), &[] as &[u8]);
+        assert!(!buffer.is_full());
+    }
+
+    #[test]
+    fn array_buffer() {
+        check_buffer(ArrayBuffer::<0x10>::new());
+
+        let mut buffer = ArrayBuffer::<4>::new();
+        assert_eq!(buffer.read(&mut &[1, 2, 3, 4, 5][..]), Ok(4));
+        assert!(buffer.is_full());
+    }
+
+    #[cfg(feature = "heapless")]
+    #[test]
+    fn heapless_buffer() {
+        check_buffer(HeaplessBuffer::<0x10>::new());
+
+        // a read that exactly fills the buffer
+        let mut buffer = HeaplessBuffer::<4>::new();
+        assert_eq!(buffer.capacity(), Some(4));
+        assert_eq!(buffer.read(&mut &[1, 2, 3, 4][..]), Ok(4));
+        assert_eq!(buffer.data(), &[1, 2, 3, 4]);
+        assert!(buffer.is_full());
+        assert_eq!(buffer.read(&mut &[5][..]), Ok(0));
+
+        // skipping compacts, making room at the end
+        buffer.skip(3);
+        assert!(!buffer.is_full());
+        assert_eq!(buffer.data(), &[4]);
+        assert_eq!(buffer.read(&mut &[5, 6, 7, 8, 9][..]), Ok(3));
+        assert_eq!(buffer.data(), &[4, 5, 6, 7]);
+        assert!(buffer.is_full());
+
+        // skipping past the end empties it
+        buffer.skip(10);
+        assert_eq!(buffer.data(), &[] as &[u8]);
+    }
+
+    #[test]
+    fn vec_buffer() {
+        check_buffer(VecBuffer::new());
+        check_buffer(VecBuffer::with_cap(0x10));
+
+        let mut buffer = VecBuffer::with_cap(4);
+        assert_eq!(buffer.read(&mut &[1, 2, 3, 4, 5][..]), Ok(4));
+        assert_eq!(buffer.data(), &[1, 2, 3, 4]);
+        assert!(buffer.is_full());
+        assert_eq!(buffer.read(&mut &[5][..]), Ok(0));
+    }
+
+    #[test]
+    fn vec_buffer_grows() {
+        let data: Vec<u8> = (0..MAX_FRAME_SIZE * 3).map(|i| i as u8).collect();
+        let mut reader = &data[..];
+        let mut buffer = VecBuffer::new();
+        while !reader.is_empty() {
+            buffer.read(&mut reader).unwrap();
+        }
+        assert!(!buffer.is_full());
+        assert_eq!(buffer.data(), &data[..]);
+    }
+
+    #[test]
+    fn vec_buffer_client() {
+        let hello = radio::Hello {
+            session_id: 0x12345678,
+        };
+
+        // bury the frame behind more noise than fits in an ArrayBuffer
+        let mut data = vec![0; MAX_FRAME_SIZE * 2];
+        data.extend(frame_bytes(&hello));
+
+        let mut client = ClientRadio::new_with(VecBuffer::new(), &data[..]);
+        let m = loop {
+            if let ParseResult::Ok(_, m) = client.read::<radio::Hello>().unwrap() {
+                break m;
+            }
+        };
+        assert_eq!(m, hello);
+    }
+
+    #[test]
+    fn vec_buffer_large_frame() {
+        use crate::protocol::messages::custom::DebugOutput;
+
+        // a frame bigger than MAX_FRAME_SIZE takes several reads to
+        // arrive, and must not be lost to the buffer filling up
+        let data: Vec<u8> = (0..MAX_FRAME_SIZE * 2).map(|i| i as u8).collect();
+        let frame = frame_bytes(&DebugOutput {
+            defmt: false,
+            data: &data[..],
+        });
+        assert!(frame.len() > MAX_FRAME_SIZE * 2);
+
+        let mut client = ClientHost::new_with(VecBuffer::new(), &frame[..]);
+        client.set_max_frame_size(frame.len());
+        let mut reads = 0;
+        let m = loop {
+            reads += 1;
+            if let ParseResult::Ok(_, m) = client.read::<DebugOutput<&[u8]>>().unwrap() {
+                break m.data.to_vec();
+            }
+        };
+        assert!(reads > 2);
+        assert_eq!(m, data);
+    }
+
+    #[test]
+    fn max_frame_size() {
+        use crate::protocol::messages::custom::DebugOutput;
+
+        let data = vec![0x42; MAX_FRAME_SIZE];
+        let frame = frame_bytes(&DebugOutput {
+            defmt: false,
+            data: &data[..],
+        });
+        assert!(frame.len() > MAX_FRAME_SIZE);
+
+        // too big by default, and just over a custom cap
+        for max_size in [None, Some(frame.len() - 1)] {
+            let mut client = ClientHost::new_with(VecBuffer::new(), &frame[..]);
+            assert_eq!(client.max_frame_size(), MAX_FRAME_SIZE);
+            if let Some(max_size) = max_size {
+                assert_eq!(client.set_max_frame_size(max_size), max_size);
+            }
+            while client.read_into_buffer().is_ok() {
+                assert!(client.found().is_none());
+            }
+        }
+
+        // just under a custom cap
+        let mut client = ClientHost::new_with(VecBuffer::new(), &frame[..]);
+        assert_eq!(client.set_max_frame_size(frame.len()), frame.len());
+        let m = loop {
+            if let ParseResult::Ok(_, m) = client.read::<DebugOutput<&[u8]>>().unwrap() {
+                break m.data.to_vec();
+            }
+        };
+        assert_eq!(m, data);
+
+        // capped by the buffer
+        let mut client = ClientHost::<_, ArrayBuffer>::new(&frame[..]);
+        assert_eq!(client.set_max_frame_size(0x1000), MAX_FRAME_SIZE);
+        assert_eq!(client.max_frame_size(), MAX_FRAME_SIZE);
+    }
+
+    #[test]
+    fn raw_frame() {
+        let hello = radio::Hello {
+            session_id: 0x12345678,
+        };
+        let frame = frame_bytes(&hello);
+
+        let mut data = vec![0x55; 3];
+        data.extend(&frame);
+        let mut client = ClientRadio::<_>::new(&data[..]);
+        assert!(client.last_raw_frame().is_none());
+
+        let m = loop {
+            if let ParseResult::Ok(_, m) = client.read::<radio::Hello>().unwrap() {
+                break m;
+            }
+        };
+        assert_eq!(m, hello);
+
+        let raw: Vec<u8> = client.last_raw_frame().unwrap().collect();
+        assert_eq!(raw, frame);
+    }
+
+    #[test]
+    fn reframe() {
+        let hello = radio::Hello {
+            session_id: 0x12345678,
+        };
+
+        // a host client writes with the radio's incoming crc
+        let host = ClientHost::<&[u8]>::new(&[][..]);
+        let bytes = host.reframe(&hello);
+        assert_eq!(bytes, frame_bytes(&hello));
+
+        // and the radio reads it back
+        let mut radio = ClientRadio::<_>::new(&bytes[..]);
+        match radio.read::<radio::Hello>().unwrap() {
+            ParseResult::Ok(_, m) => assert_eq!(m, hello),
+            r => panic!("unexpected parse result: {:?}", r),
+        }
+        let raw: Vec<u8> = radio.last_raw_frame().unwrap().collect();
+        assert_eq!(raw, bytes);
+    }
+
+    /// A port with canned input, that times out once it runs dry.
+    struct SimPort {
+        input: Vec<u8>,
+        output: Vec<u8>,
+    }
+
+    impl embedded_io::ErrorType for SimPort {
+        type Error = embedded_io::ErrorKind;
+    }
+
+    impl embedded_io::Read for SimPort {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            if self.input.is_empty() {
+                return Err(embedded_io::ErrorKind::TimedOut);
+            }
+            let amt = buf.len().min(self.input.len());
+            buf[..amt].copy_from_slice(&self.input[..amt]);
+            self.input.drain(..amt);
+            Ok(amt)
+        }
+    }
+
+    impl embedded_io::Write for SimPort {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.output.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    // what a radio in bootloader mode sends, followed by noise
+    fn radio_output<M>(msg: &M) -> Vec<u8>
+    where
+        M: MessageSerialize,
+    {
+        let mut radio = ClientRadio::<Vec<u8>>::new(Vec::new());
+        radio.write(msg).unwrap();
+        let mut output = radio.free().3;
+        output.extend([0x55; 3]);
+        output
+    }
+
+    #[test]
+    fn bootloader_handshake() {
+        let ready = bootloader::BootloaderReady {
+            chip_id: bootloader::ChipId::Long([1, 2, 3, 4]),
+            version: crate::Version::new_from_str("2.00.06").unwrap(),
+        };
+        let mut input = vec![0x55; 3];
+        input.extend(radio_output(&ready));
+
+        let mut host = ClientHost::<_>::new(SimPort {
+            input,
+            output: Vec::new(),
+        });
+        let version = crate::Version::new_from_str("*.01.23").unwrap();
+        assert_eq!(host.bootloader_handshake(version.clone()), Ok(ready));
+
+        // the radio sees our reply
+        let output = host.free().3.output;
+        let mut radio = ClientRadio::<_>::new(&output[..]);
+        match radio.read::<bootloader::BootloaderReadyReply>().unwrap() {
+            ParseResult::Ok(_, m) => assert_eq!(m.version, version),
+            r => panic!("unexpected parse result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn identify() {
+        let ready = bootloader::BootloaderReady {
+            chip_id: bootloader::ChipId::Short([1, 2]),
+            version: crate::Version::new_from_str("2.00.06").unwrap(),
+        };
+        let mut host = ClientHost::<_>::new(SimPort {
+            input: radio_output(&ready),
+            output: Vec::new(),
+        });
+        assert_eq!(host.identify(), Ok(RadioIdentity::Bootloader(ready)));
+
+        // only the hello was sent, the bootloader gets no reply
+        let output = host.free().3.output;
+        let mut sim = ClientRadio::<_>::new(&output[..]);
+        assert!(matches!(
+            sim.read::<radio::Hello>().unwrap(),
+            ParseResult::Ok(_, _)
+        ));
+        assert_eq!(sim.read::<radio::Hello>(), Ok(ParseResult::None));
+        assert_eq!(sim.read::<radio::Hello>(), Err(ClientError::UnexpectedEof));
+
+        let reply = radio::HelloReply {
+            version: crate::Version::new_from_str("2.01.26").unwrap(),
+            has_custom_aes_key: false,
+            is_in_lock_screen: true,
+            _pad: Default::default(),
+            challenge: [0; 4],
+        };
+        let mut host = ClientHost::<_>::new(SimPort {
+            input: radio_output(&reply),
+            output: Vec::new(),
+        });
+        let id = host.identify().unwrap();
+        assert_eq!(id.mode(), RadioMode::Firmware);
+        assert_eq!(id, RadioIdentity::Firmware(reply));
+    }
+
+    #[test]
+    fn bootloader_handshake_errors() {
+        let version = crate::Version::new_from_str("*.01.23").unwrap();
+
+        let mut host = ClientHost::<_>::new(SimPort {
+            input: vec![0x55; 3],
+            output: Vec::new(),
+        });
+        assert_eq!(
+            host.bootloader_handshake(version.clone()),
+            Err(BootloaderHandshakeError::Timeout)
+        );
+
+        let mut host = ClientHost::<_>::new(SimPort {
+            input: radio_output(&radio::HelloReply {
+                version: version.clone(),
+                has_custom_aes_key: false,
+                is_in_lock_screen: false,
+                _pad: Default::default(),
+                challenge: [0; 4],
+            }),
+            output: Vec::new(),
+        });
+        assert_eq!(
+            host.bootloader_handshake(version),
+            Err(BootloaderHandshakeError::UnexpectedMessage(0x0515))
+        );
+        // nothing was sent
+        assert!(host.free().3.output.is_empty());
+    }
+
+    /// A radio that answers each `M` it is sent with whatever `reply`
+    /// returns for it, which may be nothing.
+    struct ReplyPort<M, F> {
+        port: SimPort,
+        reply: F,
+        _message: core::marker::PhantomData<M>,
+    }
+
+    impl<M, F> ReplyPort<M, F> {
+        fn new(reply: F) -> Self {
+            Self {
+                port: SimPort {
+                    input: Vec::new(),
+                    output: Vec::new(),
+                },
+                reply,
+                _message: core::marker::PhantomData,
+            }
+        }
+    }
+
+    impl<M, F> embedded_io::ErrorType for ReplyPort<M, F> {
+        type Error = embedded_io::ErrorKind;
+    }
+
+    impl<M, F> embedded_io::Read for ReplyPort<M, F>
+    where
+        M: for<'a> MessageParse<&'a [u8]>,
+        F: FnMut(M) -> Vec<u8>,
+    {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            let requests = core::mem::take(&mut self.port.output);
+            let mut radio = ClientRadio::<_>::new(&requests[..]);
+            while let Ok(r) = radio.read::<M>() {
+                if let ParseResult::Ok(_, m) = r {
+                    self.port.input.extend((self.reply)(m));
+                }
+            }
+            self.port.read(buf)
+        }
+    }
+
+    impl<M, F> embedded_io::Write for ReplyPort<M, F> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.port.write(buf)
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            self.port.flush()
+        }
+    }
+
+    /// A radio that answers EEPROM reads up to `max_block` bytes long,
+    /// noting the length of every read in `lens`. Larger reads are cut
+    /// short, or ignored if `ignore` is set.
+    fn clone_port(
+        max_block: usize,
+        ignore: bool,
+        lens: &mut Vec<u8>,
+    ) -> ReplyPort<radio::ReadEeprom, impl FnMut(radio::ReadEeprom) -> Vec<u8> + '_> {
+        let eeprom: Vec<u8> = (0..0x100).map(|i| i as u8).collect();
+        ReplyPort::new(move |m: radio::ReadEeprom| {
+            lens.push(m.len);
+            if ignore && m.len as usize > max_block {
+                return Vec::new();
+            }
+
+            let start = (m.address as usize).min(eeprom.len());
+            let end = (start + (m.len as usize).min(max_block)).min(eeprom.len());
+            radio_output(&radio::ReadEepromReply {
+                address: m.address,
+                len: (end - start) as u8,
+                _pad: Default::default(),
+                data: &eeprom[start..end],
+            })
+        })
+    }
+
+    #[test]
+    fn read_eeprom_adapts() {
+        for ignore in [false, true] {
+            let mut lens = Vec::new();
+            let mut host = ClientHost::<_>::new(clone_port(0x40, ignore, &mut lens));
+            let mut progress = Vec::new();
+            let (data, block) = host
+                .read_eeprom_range_adaptive(0x10, 0xe0, 0, 0x80, |n| progress.push(n))
+                .unwrap();
+            assert_eq!(block, 0x40);
+            assert_eq!(data, (0x10..0xf0).map(|i| i as u8).collect::<Vec<_>>());
+            assert_eq!(progress, [0x40, 0x80, 0xc0, 0xe0]);
+
+            drop(host);
+            if ignore {
+                assert_eq!(lens, [0x80, 0x40, 0x40, 0x40, 0x20]);
+            } else {
+                assert_eq!(lens, [0x80, 0x40, 0x40, 0x20]);
+            }
+        }
+    }
+
+    #[test]
+    fn read_eeprom_adaptive_end() {
+        // settled, so a short block is the end of the eeprom
+        let mut lens = Vec::new();
+        let mut host = ClientHost::<_>::new(clone_port(0x40, false, &mut lens));
+        let (data, block) = host
+            .read_eeprom_range_adaptive(0x80, 0x100, 0, 0x40, |_| {})
+            .unwrap();
+        assert_eq!((data.len(), block), (0x80, 0x40));
+
+        // not settled, so it takes an empty block to be sure
+        let mut lens = Vec::new();
+        let mut host = ClientHost::<_>::new(clone_port(0x80, false, &mut lens));
+        let data = host.read_eeprom_range(0xf0, 0x20, 0, |_| {}).unwrap();
+        assert_eq!(data.len(), 0x10);
+        drop(host);
+        assert_eq!(lens, [0x20, 0x10]);
+
+        // a radio that never answers still times out
+        let mut lens = Vec::new();
+        let mut host = ClientHost::<_>::new(clone_port(0, true, &mut lens));
+        assert_eq!(
+            host.read_eeprom_range_adaptive(0, 0x100, 0, 0x80, |_| {}),
+            Err(ReadEepromError::Client(ClientError::Io(
+                embedded_io::ErrorKind::TimedOut
+            )))
+        );
+        drop(host);
+        assert_eq!(lens, [0x80, 0x40, 0x20, 0x10, 8, 4, 2, 1]);
+    }
+
+    #[test]
+    fn read_eeprom_timeout() {
+        // without adapting, the first timeout is an error
+        let mut lens = Vec::new();
+        let mut host = ClientHost::<_>::new(clone_port(0x40, true, &mut lens));
+        assert_eq!(
+            host.read_eeprom_range(0, 0x100, 0, |_| {}),
+            Err(ReadEepromError::Client(ClientError::Io(
+                embedded_io::ErrorKind::TimedOut
+            )))
+        );
+        drop(host);
+        assert_eq!(lens, [0x80]);
+    }
+
+    #[test]
+    fn read_eeprom_long_reply() {
+        // a reply longer than the request is cut short
+        let eeprom: Vec<u8> = (0..8).collect();
+        let mut host = ClientHost::<_>::new(SimPort {
+            input: radio_output(&radio::ReadEepromReply {
+                address: 0,
+                len: 8,
+                _pad: Default::default(),
+                data: &eeprom[..],
+            }),
+            output: Vec::new(),
+        });
+        let mut progress = Vec::new();
+        let data = host
+            .read_eeprom_range(0, 4, 0, |n| progress.push(n))
+            .unwrap();
+        assert_eq!(data, [0, 1, 2, 3]);
+        assert_eq!(progress, [4]);
+    }
+
+    /// A clock that ticks forward a millisecond every time it is read.
+    struct TickClock(core::cell::Cell<core::time::Duration>);
+
+    impl Clock for TickClock {
+        fn now(&self) -> core::time::Duration {
+            let now = self.0.get();
+            self.0.set(now + core::time::Duration::from_millis(1));
+            now
+        }
+    }
+
+    /// A radio that answers hellos, after ignoring the first `skip`,
+    /// counting every hello in `hellos`. Every reply comes after an
+    /// unrelated message.
+    fn hello_port(
+        mut skip: usize,
+        hellos: &mut usize,
+    ) -> ReplyPort<radio::Hello, impl FnMut(radio::Hello) -> Vec<u8> + '_> {
+        ReplyPort::new(move |_: radio::Hello| {
+            *hellos += 1;
+            if skip > 0 {
+                skip -= 1;
+                return Vec::new();
+            }
+
+            let unrelated = bootloader::WriteFlashReply {
+                session_id: 0,
+                page: 0,
+                error: 0,
+            };
+            let mut output = radio_output(&unrelated);
+            output.extend(radio_output(&radio::HelloReply {
+                version: crate::Version::new_from_str("2.01.26").unwrap(),
+                has_custom_aes_key: false,
+                is_in_lock_screen: false,
+                _pad: Default::default(),
+                challenge: [0; 4],
+            }));
+            output
+        })
+    }
+
+    #[test]
+    fn request_retries() {
+        let hello = radio::Hello {
+            session_id: radio::HELLO_SESSION_ID,
+        };
+        let timeout = core::time::Duration::from_millis(10);
+        let clock = TickClock(Default::default());
+
+        // the first reply is dropped, and the retry is answered
+        let mut hellos = 0;
+        let mut host = ClientHost::<_>::new(hello_port(1, &mut hellos));
+        let reply: radio::HelloReply = host.request(&clock, &hello, timeout, 1).unwrap();
+        assert!(!reply.is_in_lock_screen);
+        drop(host);
+        assert_eq!(hellos, 2);
+
+        // an answer on the first try needs no retry
+        let mut hellos = 0;
+        let mut host = ClientHost::<_>::new(hello_port(0, &mut hellos));
+        let reply = host.request::<_, radio::HelloReply>(&clock, &hello, timeout, 1);
+        assert!(reply.is_ok());
+        drop(host);
+        assert_eq!(hellos, 1);
+
+        // out of retries
+        let mut hellos = 0;
+        let mut host = ClientHost::<_>::new(hello_port(2, &mut hellos));
+        assert_eq!(
+            host.request::<_, radio::HelloReply>(&clock, &hello, timeout, 1),
+            Err(RequestError::Timeout)
+        );
+        drop(host);
+        assert_eq!(hellos, 2);
+    }
+
+    #[cfg(all(feature = "std", feature = "embedded-io-async"))]
+    #[tokio::test]
+    async fn async_roundtrip() {
+        use embedded_io_adapters::tokio_1::FromTokio;
+
+        let (host, radio) = tokio::io::duplex(MAX_FRAME_SIZE);
+        let mut host = AsyncClientHost::<_>::new(FromTokio::new(host));
+        let mut radio = AsyncClientRadio::<_>::new(FromTokio::new(radio));
+
+        let hello = radio::Hello {
+            session_id: 0x12345678,
+        };
+        host.write(&hello).await.unwrap();
+        match radio.read::<radio::Hello>().await.unwrap() {
+            ParseResult::Ok(_, m) => assert_eq!(m, hello),
+            r => panic!("unexpected parse result: {:?}", r),
+        }
+    }
+
+    #[cfg(all(feature = "std", feature = "embedded-io-async"))]
+    #[tokio::test]
+    async fn async_write_too_large() {
+        use crate::protocol::messages::custom::DebugOutput;
+        use embedded_io_adapters::tokio_1::FromTokio;
+
+        let (host, _radio) = tokio::io::duplex(MAX_FRAME_SIZE);
+        let mut host = AsyncClientHost::<_>::new(FromTokio::new(host));
+
+        let data = vec![0x42; MAX_FRAME_SIZE];
+        let msg = DebugOutput {
+            defmt: false,
+            data: &data[..],
+        };
+        assert!(matches!(
+            host.write(&msg).await,
+            Err(ClientError::FrameTooLarge)
+        ));
+    }
 }
@@ -0,0 +1,37 @@
+/// A monotonic source of time, for timeouts kept by [Client][crate::Client]
+/// rather than by the port.
+pub trait Clock {
+    /// Time elapsed since some fixed point in the past.
+    fn now(&self) -> core::time::Duration;
+}
+
+/// A [Clock] using [std::time::Instant], counting from its creation.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StdClock {
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl StdClock {
+    /// Create a new clock, starting now.
+    pub fn new() -> Self {
+        Self {
+            start: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for StdClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+    fn now(&self) -> core::time::Duration {
+        self.start.elapsed()
+    }
+}
@@ -0,0 +1,1183 @@
+//! The stock UV-K5 firmware's EEPROM layout.
+//!
+//! Only the memory channels, the basic settings block, and the factory
+//! calibration are covered here. Everything else in a dump is left
+//! untouched by the `to_dump` functions, so a dump can be read, edited,
+//! and written back.
+
+use nom::IResult;
+
+use crate::protocol::parse::Parse;
+use crate::protocol::serialize::{Serializer, SerializerWrap};
+
+/// Size of the whole EEPROM.
+pub const EEPROM_SIZE: usize = 0x2000;
+
+/// Number of memory channels.
+pub const CHANNELS: usize = 200;
+
+/// Address of the channel settings, 0x10 bytes per channel.
+pub const CHANNEL_ADDRESS: usize = 0x0000;
+
+/// Address of the channel attributes, 1 byte per channel.
+pub const ATTRIBUTES_ADDRESS: usize = 0x0d60;
+
+/// Address of the settings block.
+pub const SETTINGS_ADDRESS: usize = 0x0e70;
+
+/// Address of the channel names, 0x10 bytes per channel.
+pub const NAMES_ADDRESS: usize = 0x0f50;
+
+/// Size of a channel entry, and of a channel name entry.
+pub const CHANNEL_LEN: usize = 0x10;
+
+/// Size of the settings block.
+pub const SETTINGS_LEN: usize = 0x10;
+
+/// Address of the factory calibration.
+pub const CALIBRATION_ADDRESS: usize = 0x1ed0;
+
+/// Size of the factory calibration: the transmit power table,
+/// followed directly by the battery calibration.
+pub const CALIBRATION_LEN: usize = CALIBRATION_BANDS * CALIBRATION_BAND_LEN + 12;
+
+/// Number of bands in the transmit power calibration.
+pub const CALIBRATION_BANDS: usize = 7;
+
+/// Size of a band's entry in the transmit power calibration.
+pub const CALIBRATION_BAND_LEN: usize = 0x10;
+
+/// Longest name the stock firmware will display.
+pub const NAME_MAX: usize = 10;
+
+/// CTCSS tones, in units of 0.1Hz, indexed by [Code::index].
+pub const CTCSS_TONES: [u16; 50] = [
+    670, 693, 719, 744, 770, 797, 825, 854, 885, 915, 948, 974, 1000, 1035, 1072, 1109, 1148, 1188,
+    1230, 1273, 1318, 1365, 1413, 1462, 1514, 1567, 1598, 1622, 1655, 1679, 1713, 1738, 1773, 1799,
+    1835, 1862, 1899, 1928, 1966, 1995, 2035, 2065, 2107, 2181, 2257, 2291, 2336, 2418, 2503, 2541,
+];
+
+/// An error produced reading or writing an EEPROM dump.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DumpError {
+    /// The dump is too short to contain this data.
+    TooShort,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DumpError {}
+
+impl core::fmt::Display for DumpError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::TooShort => write!(f, "eeprom dump is too short"),
+        }
+    }
+}
+
+// a helper macro for enums packed into a few bits, with unknown values
+macro_rules! bits_enum {
+    ($(#[$meta:meta])* $name:ident { $($(#[$vmeta:meta])* $variant:ident = $value:literal),* $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        pub enum $name {
+            $($(#[$vmeta])* $variant,)*
+            /// A value not understood by this library.
+            Unknown(u8),
+        }
+
+        impl $name {
+            pub const fn into_bits(self) -> u8 {
+                match self {
+                    $(Self::$variant => $value,)*
+                    Self::Unknown(v) => v,
+                }
+            }
+
+            pub const fn from_bits(v: u8) -> Self {
+                match v {
+                    $($value => Self::$variant,)*
+                    _ => Self::Unknown(v),
+                }
+            }
+        }
+    };
+}
+
+bits_enum!(
+    /// Type of a CTCSS/DCS code.
+    CodeType {
+        /// No code.
+        None = 0,
+        /// CTCSS tone.
+        Ctcss = 1,
+        /// DCS code.
+        Dcs = 2,
+        /// Inverted DCS code.
+        ReverseDcs = 3,
+    }
+);
+
+bits_enum!(
+    /// Direction of the transmit offset.
+    OffsetDirection {
+        /// Transmit on the receive frequency.
+        None = 0,
+        /// Transmit above the receive frequency.
+        Plus = 1,
+        /// Transmit below the receive frequency.
+        Minus = 2,
+    }
+);
+
+bits_enum!(
+    /// Channel modulation.
+    Modulation {
+        Fm = 0,
+        Am = 1,
+        Usb = 2,
+    }
+);
+
+bits_enum!(
+    /// Transmit power.
+    Power {
+        Low = 0,
+        Mid = 1,
+        High = 2,
+    }
+);
+
+/// Channel bandwidth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Bandwidth {
+    /// 25kHz.
+    Wide,
+    /// 12.5kHz.
+    Narrow,
+}
+
+impl Bandwidth {
+    pub const fn into_bits(self) -> u8 {
+        match self {
+            Self::Wide => 0,
+            Self::Narrow => 1,
+        }
+    }
+
+    pub const fn from_bits(v: u8) -> Self {
+        match v {
+            0 => Self::Wide,
+            _ => Self::Narrow,
+        }
+    }
+}
+
+/// A CTCSS/DCS code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Code {
+    /// What kind of code this is.
+    pub typ: CodeType,
+    /// Index into [CTCSS_TONES] for CTCSS, or into the DCS code table.
+    pub index: u8,
+}
+
+impl Code {
+    /// The CTCSS tone in units of 0.1Hz, if this is a valid CTCSS code.
+    pub fn ctcss_tone(&self) -> Option<u16> {
+        if self.typ == CodeType::Ctcss {
+            CTCSS_TONES.get(self.index as usize).copied()
+        } else {
+            None
+        }
+    }
+}
+
+/// An error produced encoding or decoding a [ChannelName].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ChannelNameError {
+    /// The name is longer than [NAME_MAX].
+    TooLong,
+    /// The name contains a byte outside of printable ASCII.
+    InvalidByte(u8),
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ChannelNameError {}
+
+impl core::fmt::Display for ChannelNameError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::TooLong => write!(f, "channel name is longer than {} bytes", NAME_MAX),
+            Self::InvalidByte(b) => write!(f, "channel name contains invalid byte 0x{:02x}", b),
+        }
+    }
+}
+
+/// A channel name, padded with `NUL`, `0xff`, or spaces.
+///
+/// The raw bytes are kept as-is, so names round-trip through a dump
+/// unchanged. Names are printable ASCII, the only characters the stock
+/// firmware's font can draw.
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ChannelName([u8; CHANNEL_LEN]);
+
+impl ChannelName {
+    pub const fn new_empty() -> Self {
+        Self([0; CHANNEL_LEN])
+    }
+
+    pub const fn new(data: [u8; CHANNEL_LEN]) -> Self {
+        Self(data)
+    }
+
+    /// Create a name from the raw field in EEPROM.
+    pub const fn from_bytes(data: &[u8; CHANNEL_LEN]) -> Self {
+        Self(*data)
+    }
+
+    /// Create a name from a string, padded with `NUL`. Returns [None]
+    /// if the name is longer than [NAME_MAX], or is not printable
+    /// ASCII. Use [str::parse()] to find out which.
+    pub fn new_from_str(name: &str) -> Option<Self> {
+        name.parse::<Self>().ok()
+    }
+
+    /// Is this byte allowed in a name?
+    pub const fn is_valid(b: u8) -> bool {
+        matches!(b, 0x20..=0x7e)
+    }
+
+    /// The name, up to the first `NUL` or `0xff` and without trailing
+    /// spaces. Fails if the name contains anything other than
+    /// printable ASCII.
+    pub fn as_str(&self) -> Result<&str, ChannelNameError> {
+        let name = self.trimmed();
+        if let Some(b) = name.iter().find(|b| !Self::is_valid(**b)) {
+            return Err(ChannelNameError::InvalidByte(*b));
+        }
+        // printable ASCII is always valid UTF-8
+        core::str::from_utf8(name).map_err(|_| ChannelNameError::InvalidByte(name[0]))
+    }
+
+    pub const fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The raw field to store in EEPROM.
+    pub const fn to_bytes(&self) -> [u8; CHANNEL_LEN] {
+        self.0
+    }
+
+    // the name up to the first NUL or 0xff, without trailing spaces
+    fn trimmed(&self) -> &[u8] {
+        let end = self
+            .0
+            .iter()
+            .position(|b| *b == 0x00 || *b == 0xff)
+            .unwrap_or(self.0.len());
+        let name = &self.0[..end];
+        let end = name.iter().rposition(|b| *b != b' ').map_or(0, |i| i + 1);
+        &name[..end]
+    }
+
+    pub fn parse<I>(input: I) -> IResult<I, Self>
+    where
+        I: Parse,
+    {
+        let (input, data) =
+            crate::protocol::messages::util::parse_array(nom::number::complete::u8)(input)?;
+        Ok((input, Self(data)))
+    }
+
+    pub fn serialize<S>(&self, ser: &mut S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        ser.write_bytes(&self.0)
+    }
+}
+
+/// Parses a name, padded with `NUL`. Fails if the name is longer than
+/// [NAME_MAX], or is not printable ASCII.
+impl core::str::FromStr for ChannelName {
+    type Err = ChannelNameError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let bytes = name.as_bytes();
+        if bytes.len() > NAME_MAX {
+            return Err(ChannelNameError::TooLong);
+        }
+        if let Some(b) = bytes.iter().find(|b| !Self::is_valid(**b)) {
+            return Err(ChannelNameError::InvalidByte(*b));
+        }
+
+        let mut data = [0; CHANNEL_LEN];
+        data[..bytes.len()].copy_from_slice(bytes);
+        Ok(Self(data))
+    }
+}
+
+impl Default for ChannelName {
+    fn default() -> Self {
+        Self::new_empty()
+    }
+}
+
+impl core::fmt::Debug for ChannelName {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        match self.as_str() {
+            Ok(s) => f.debug_tuple("ChannelName").field(&s).finish(),
+            Err(_) => f
+                .debug_tuple("ChannelName")
+                .field(&self.as_bytes())
+                .finish(),
+        }
+    }
+}
+
+/// Displays the name without padding, with any invalid bytes
+/// replaced by `\u{fffd}`.
+impl core::fmt::Display for ChannelName {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        use core::fmt::Write;
+        for b in self.trimmed() {
+            if Self::is_valid(*b) {
+                f.write_char(*b as char)?;
+            } else {
+                f.write_char(char::REPLACEMENT_CHARACTER)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for ChannelName {
+    fn format(&self, f: defmt::Formatter) {
+        match self.as_str() {
+            Ok(s) => defmt::write!(f, "ChannelName({})", s),
+            Err(_) => defmt::write!(f, "ChannelName({})", self.as_bytes()),
+        }
+    }
+}
+
+/// Per-channel attributes, stored apart from the channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ChannelAttributes {
+    /// Frequency band index, 0 through 6.
+    pub band: u8,
+    /// Unknown, possibly marks the channel as free.
+    pub unknown_b3: bool,
+    /// Compander mode, 0 is off.
+    pub compander: u8,
+    /// Channel is in scan list 2.
+    pub scanlist2: bool,
+    /// Channel is in scan list 1.
+    pub scanlist1: bool,
+}
+
+impl ChannelAttributes {
+    pub const fn into_bits(self) -> u8 {
+        (self.band & 0x7)
+            | ((self.unknown_b3 as u8) << 3)
+            | ((self.compander & 0x3) << 4)
+            | ((self.scanlist2 as u8) << 6)
+            | ((self.scanlist1 as u8) << 7)
+    }
+
+    pub const fn from_bits(v: u8) -> Self {
+        Self {
+            band: v & 0x7,
+            unknown_b3: v & (1 << 3) != 0,
+            compander: (v >> 4) & 0x3,
+            scanlist2: v & (1 << 6) != 0,
+            scanlist1: v & (1 << 7) != 0,
+        }
+    }
+}
+
+/// A memory channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Channel {
+    /// Receive frequency, in units of 10Hz.
+    pub frequency: u32,
+    /// Transmit offset, in units of 10Hz.
+    pub offset: u32,
+    /// Receive CTCSS/DCS code.
+    pub rx_code: Code,
+    /// Transmit CTCSS/DCS code.
+    pub tx_code: Code,
+    /// Transmit offset direction.
+    pub offset_direction: OffsetDirection,
+    /// Modulation.
+    pub modulation: Modulation,
+    /// Swap the receive and transmit frequencies.
+    pub reverse: bool,
+    /// Bandwidth.
+    pub bandwidth: Bandwidth,
+    /// Transmit power.
+    pub power: Power,
+    /// Don't transmit when the channel is busy.
+    pub busy_lock: bool,
+    /// Unknown high bits (5-7) of the flags byte, kept as-is.
+    pub unknown_flags: u8,
+    /// DTMF decoding enabled.
+    pub dtmf_decode: bool,
+    /// DTMF PTT ID mode, 0 is off.
+    pub ptt_id: u8,
+    /// Unknown high bits (4-7) of the DTMF byte, kept as-is.
+    pub unknown_dtmf: u8,
+    /// Index into the radio's frequency step table.
+    pub step: u8,
+    /// Scrambler setting, 0 is off.
+    pub scrambler: u8,
+    /// Channel name.
+    pub name: ChannelName,
+    /// Channel attributes.
+    pub attributes: ChannelAttributes,
+}
+
+impl Channel {
+    /// Byte offset and name of each field in the 0x10 byte channel
+    /// entry, in order. Fields packed into the same bytes share an
+    /// entry.
+    pub const FIELDS: [(usize, &'static str); 10] = [
+        (0x0, "frequency"),
+        (0x4, "offset"),
+        (0x8, "rx_code.index"),
+        (0x9, "tx_code.index"),
+        (0xa, "rx_code.typ/tx_code.typ"),
+        (0xb, "offset_direction/modulation"),
+        (0xc, "reverse/bandwidth/power/busy_lock/unknown_flags"),
+        (0xd, "dtmf_decode/ptt_id/unknown_dtmf"),
+        (0xe, "step"),
+        (0xf, "scrambler"),
+    ];
+
+    /// Parse the 0x10 byte channel entry, without name or attributes.
+    pub fn parse<I>(input: I) -> IResult<I, Self>
+    where
+        I: Parse,
+    {
+        use nom::number::complete::{le_u32, u8};
+
+        let (input, frequency) = le_u32(input)?;
+        let (input, offset) = le_u32(input)?;
+        let (input, rx_index) = u8(input)?;
+        let (input, tx_index) = u8(input)?;
+        let (input, codes) = u8(input)?;
+        let (input, modes) = u8(input)?;
+        let (input, flags) = u8(input)?;
+        let (input, dtmf) = u8(input)?;
+        let (input, step) = u8(input)?;
+        let (input, scrambler) = u8(input)?;
+
+        Ok((
+            input,
+            Self {
+                frequency,
+                offset,
+                rx_code: Code {
+                    typ: CodeType::from_bits(codes & 0xf),
+                    index: rx_index,
+                },
+                tx_code: Code {
+                    typ: CodeType::from_bits(codes >> 4),
+                    index: tx_index,
+                },
+                offset_direction: OffsetDirection::from_bits(modes & 0xf),
+                modulation: Modulation::from_bits(modes >> 4),
+                reverse: flags & (1 << 0) != 0,
+                bandwidth: Bandwidth::from_bits((flags >> 1) & 0x1),
+                power: Power::from_bits((flags >> 2) & 0x3),
+                busy_lock: flags & (1 << 4) != 0,
+                unknown_flags: flags & 0xe0,
+                dtmf_decode: dtmf & (1 << 0) != 0,
+                ptt_id: (dtmf >> 1) & 0x7,
+                unknown_dtmf: dtmf & 0xf0,
+                step,
+                scrambler,
+                name: ChannelName::default(),
+                attributes: ChannelAttributes::from_bits(0),
+            },
+        ))
+    }
+
+    /// Serialize the 0x10 byte channel entry, without name or attributes.
+    pub fn serialize<S>(&self, ser: &mut S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        ser.write_le_u32(self.frequency)?;
+        ser.write_le_u32(self.offset)?;
+        ser.write_u8(self.rx_code.index)?;
+        ser.write_u8(self.tx_code.index)?;
+        ser.write_u8((self.rx_code.typ.into_bits() & 0xf) | (self.tx_code.typ.into_bits() << 4))?;
+        ser.write_u8(
+            (self.offset_direction.into_bits() & 0xf) | (self.modulation.into_bits() << 4),
+        )?;
+        ser.write_u8(
+            (self.reverse as u8)
+                | (self.bandwidth.into_bits() << 1)
+                | ((self.power.into_bits() & 0x3) << 2)
+                | ((self.busy_lock as u8) << 4)
+                | (self.unknown_flags & 0xe0),
+        )?;
+        ser.write_u8(
+            (self.dtmf_decode as u8) | ((self.ptt_id & 0x7) << 1) | (self.unknown_dtmf & 0xf0),
+        )?;
+        ser.write_u8(self.step)?;
+        ser.write_u8(self.scrambler)
+    }
+}
+
+/// All the memory channels.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ChannelBank {
+    /// Memory channels. Empty channels are [None].
+    pub channels: [Option<Channel>; CHANNELS],
+}
+
+impl ChannelBank {
+    pub fn new() -> Self {
+        Self {
+            channels: [None; CHANNELS],
+        }
+    }
+
+    /// Read the channels out of an EEPROM dump.
+    ///
+    /// Channels with an erased frequency are empty.
+    pub fn from_dump(dump: &[u8]) -> Result<Self, DumpError> {
+        if dump.len() < NAMES_ADDRESS + CHANNELS * CHANNEL_LEN {
+            return Err(DumpError::TooShort);
+        }
+
+        let mut bank = Self::new();
+        for (i, slot) in bank.channels.iter_mut().enumerate() {
+            let entry = &dump[CHANNEL_ADDRESS + i * CHANNEL_LEN..];
+            let (_, mut channel) = Channel::parse(entry).map_err(|_| DumpError::TooShort)?;
+            if channel.frequency == 0xffffffff {
+                continue;
+            }
+
+            let name = &dump[NAMES_ADDRESS + i * CHANNEL_LEN..];
+            let (_, name) = ChannelName::parse(name).map_err(|_| DumpError::TooShort)?;
+            channel.name = name;
+            channel.attributes = ChannelAttributes::from_bits(dump[ATTRIBUTES_ADDRESS + i]);
+
+            *slot = Some(channel);
+        }
+
+        Ok(bank)
+    }
+
+    /// Write the channels into an EEPROM dump.
+    ///
+    /// Empty channels are erased to `0xff`.
+    pub fn to_dump(&self, dump: &mut [u8]) -> Result<(), DumpError> {
+        if dump.len() < NAMES_ADDRESS + CHANNELS * CHANNEL_LEN {
+            return Err(DumpError::TooShort);
+        }
+
+        for (i, slot) in self.channels.iter().enumerate() {
+            let entry = CHANNEL_ADDRESS + i * CHANNEL_LEN;
+            let name = NAMES_ADDRESS + i * CHANNEL_LEN;
+            let attributes = ATTRIBUTES_ADDRESS + i;
+
+            if let Some(channel) = slot {
+                let mut ser = SerializerWrap::new(&mut dump[entry..entry + CHANNEL_LEN]);
+                channel
+                    .serialize(&mut ser)
+                    .map_err(|_| DumpError::TooShort)?;
+                let mut ser = SerializerWrap::new(&mut dump[name..name + CHANNEL_LEN]);
+                channel
+                    .name
+                    .serialize(&mut ser)
+                    .map_err(|_| DumpError::TooShort)?;
+                dump[attributes] = channel.attributes.into_bits();
+            } else {
+                dump[entry..entry + CHANNEL_LEN].fill(0xff);
+                dump[name..name + CHANNEL_LEN].fill(0xff);
+                dump[attributes] = 0xff;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for ChannelBank {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The basic radio settings block.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Settings {
+    /// Channel used by the call key.
+    pub call_channel: u8,
+    /// Squelch level, 0 through 9.
+    pub squelch: u8,
+    /// Transmit timeout setting.
+    pub max_talk_time: u8,
+    /// Automatically scan the NOAA channels.
+    pub noaa_autoscan: bool,
+    /// Keypad is locked.
+    pub key_lock: bool,
+    /// VOX enabled.
+    pub vox_enabled: bool,
+    /// VOX level.
+    pub vox_level: u8,
+    /// Microphone gain.
+    pub mic_gain: u8,
+    /// Unknown.
+    pub unknown_8: u8,
+    /// How channels are displayed.
+    pub channel_display_mode: u8,
+    /// Cross-band mode.
+    pub crossband: u8,
+    /// Battery save setting.
+    pub battery_save: u8,
+    /// Dual watch mode.
+    pub dual_watch: u8,
+    /// Backlight timeout setting.
+    pub backlight: u8,
+    /// Tail tone elimination.
+    pub tail_tone_elimination: bool,
+    /// VFO mode allowed.
+    pub vfo_open: bool,
+}
+
+impl Settings {
+    /// Name of the field in each byte of the settings block.
+    pub const FIELDS: [&'static str; SETTINGS_LEN] = [
+        "call_channel",
+        "squelch",
+        "max_talk_time",
+        "noaa_autoscan",
+        "key_lock",
+        "vox_enabled",
+        "vox_level",
+        "mic_gain",
+        "unknown_8",
+        "channel_display_mode",
+        "crossband",
+        "battery_save",
+        "dual_watch",
+        "backlight",
+        "tail_tone_elimination",
+        "vfo_open",
+    ];
+
+    pub fn parse<I>(input: I) -> IResult<I, Self>
+    where
+        I: Parse,
+    {
+        use nom::number::complete::u8;
+
+        fn flag<I: Parse>(input: I) -> IResult<I, bool> {
+            nom::combinator::map(u8, |v| v != 0)(input)
+        }
+
+        let (input, call_channel) = u8(input)?;
+        let (input, squelch) = u8(input)?;
+        let (input, max_talk_time) = u8(input)?;
+        let (input, noaa_autoscan) = flag(input)?;
+        let (input, key_lock) = flag(input)?;
+        let (input, vox_enabled) = flag(input)?;
+        let (input, vox_level) = u8(input)?;
+        let (input, mic_gain) = u8(input)?;
+        let (input, unknown_8) = u8(input)?;
+        let (input, channel_display_mode) = u8(input)?;
+        let (input, crossband) = u8(input)?;
+        let (input, battery_save) = u8(input)?;
+        let (input, dual_watch) = u8(input)?;
+        let (input, backlight) = u8(input)?;
+        let (input, tail_tone_elimination) = flag(input)?;
+        let (input, vfo_open) = flag(input)?;
+
+        Ok((
+            input,
+            Self {
+                call_channel,
+                squelch,
+                max_talk_time,
+                noaa_autoscan,
+                key_lock,
+                vox_enabled,
+                vox_level,
+                mic_gain,
+                unknown_8,
+                channel_display_mode,
+                crossband,
+                battery_save,
+                dual_watch,
+                backlight,
+                tail_tone_elimination,
+                vfo_open,
+            },
+        ))
+    }
+
+    pub fn serialize<S>(&self, ser: &mut S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        ser.write_u8(self.call_channel)?;
+        ser.write_u8(self.squelch)?;
+        ser.write_u8(self.max_talk_time)?;
+        ser.write_u8(self.noaa_autoscan as u8)?;
+        ser.write_u8(self.key_lock as u8)?;
+        ser.write_u8(self.vox_enabled as u8)?;
+        ser.write_u8(self.vox_level)?;
+        ser.write_u8(self.mic_gain)?;
+        ser.write_u8(self.unknown_8)?;
+        ser.write_u8(self.channel_display_mode)?;
+        ser.write_u8(self.crossband)?;
+        ser.write_u8(self.battery_save)?;
+        ser.write_u8(self.dual_watch)?;
+        ser.write_u8(self.backlight)?;
+        ser.write_u8(self.tail_tone_elimination as u8)?;
+        ser.write_u8(self.vfo_open as u8)
+    }
+
+    /// Read the settings out of an EEPROM dump.
+    pub fn from_dump(dump: &[u8]) -> Result<Self, DumpError> {
+        let data = dump
+            .get(SETTINGS_ADDRESS..SETTINGS_ADDRESS + SETTINGS_LEN)
+            .ok_or(DumpError::TooShort)?;
+        let (_, settings) = Self::parse(data).map_err(|_| DumpError::TooShort)?;
+        Ok(settings)
+    }
+
+    /// Write the settings into an EEPROM dump.
+    pub fn to_dump(&self, dump: &mut [u8]) -> Result<(), DumpError> {
+        let data = dump
+            .get_mut(SETTINGS_ADDRESS..SETTINGS_ADDRESS + SETTINGS_LEN)
+            .ok_or(DumpError::TooShort)?;
+        let mut ser = SerializerWrap::new(data);
+        self.serialize(&mut ser).map_err(|_| DumpError::TooShort)
+    }
+}
+
+/// The factory calibration, written at the end of the EEPROM.
+///
+/// The transmit power table starts at [CALIBRATION_ADDRESS], with a
+/// [CALIBRATION_BAND_LEN] entry for each band. Each entry holds
+/// three bytes for each of low, mid, and high [Power], and those three
+/// bytes are the PA bias at the bottom, middle, and top of the band.
+/// The remaining bytes of each entry are unused.
+///
+/// The battery calibration follows at 0x1f40: six little-endian u16
+/// ADC readings of the battery divider, used as the battery level
+/// thresholds.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Calibration {
+    /// PA bias, indexed by band, then power, then position in band.
+    pub tx_power: [[[u8; 3]; 3]; CALIBRATION_BANDS],
+    /// Battery ADC readings at each battery level threshold.
+    /// [Self::BATTERY_REFERENCE] is the reading at 7.6V.
+    pub battery: [u16; 6],
+}
+
+impl Calibration {
+    /// Index of the battery threshold used as the voltage reference.
+    pub const BATTERY_REFERENCE: usize = 3;
+
+    pub fn parse<I>(input: I) -> IResult<I, Self>
+    where
+        I: Parse,
+    {
+        use nom::number::complete::{le_u16, u8};
+
+        let mut input = input;
+        let mut tx_power = [[[0; 3]; 3]; CALIBRATION_BANDS];
+        for band in tx_power.iter_mut() {
+            for power in band.iter_mut() {
+                for bias in power.iter_mut() {
+                    (input, *bias) = u8(input)?;
+                }
+            }
+            (input, _) = nom::bytes::complete::take(CALIBRATION_BAND_LEN - 9)(input)?;
+        }
+
+        let mut battery = [0; 6];
+        for threshold in battery.iter_mut() {
+            (input, *threshold) = le_u16(input)?;
+        }
+
+        Ok((input, Self { tx_power, battery }))
+    }
+
+    /// Read the calibration out of an EEPROM dump.
+    pub fn from_dump(dump: &[u8]) -> Result<Self, DumpError> {
+        let data = dump
+            .get(CALIBRATION_ADDRESS..CALIBRATION_ADDRESS + CALIBRATION_LEN)
+            .ok_or(DumpError::TooShort)?;
+        let (_, calibration) = Self::parse(data).map_err(|_| DumpError::TooShort)?;
+        Ok(calibration)
+    }
+
+    /// Convert a battery ADC reading to millivolts, the same way the
+    /// stock firmware does. Returns [None] if the calibration is
+    /// missing.
+    pub fn battery_millivolts(&self, adc: u16) -> Option<u32> {
+        let reference = self.battery[Self::BATTERY_REFERENCE];
+        if reference == 0 || reference == 0xffff {
+            return None;
+        }
+        Some(adc as u32 * 7600 / reference as u32)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "alloc")]
+mod test {
+    use alloc::borrow::ToOwned;
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    use quickcheck::{Arbitrary, Gen};
+    use quickcheck_macros::quickcheck;
+
+    impl Arbitrary for Code {
+        fn arbitrary(g: &mut Gen) -> Self {
+            Self {
+                typ: CodeType::from_bits(u8::arbitrary(g) & 0xf),
+                index: u8::arbitrary(g),
+            }
+        }
+    }
+
+    impl Arbitrary for ChannelName {
+        fn arbitrary(g: &mut Gen) -> Self {
+            Self::new([(); CHANNEL_LEN].map(|_| u8::arbitrary(g)))
+        }
+    }
+
+    impl Arbitrary for Channel {
+        fn arbitrary(g: &mut Gen) -> Self {
+            Self {
+                // erased frequency means empty channel
+                frequency: u32::arbitrary(g).min(0xfffffffe),
+                offset: u32::arbitrary(g),
+                rx_code: Code::arbitrary(g),
+                tx_code: Code::arbitrary(g),
+                offset_direction: OffsetDirection::from_bits(u8::arbitrary(g) & 0xf),
+                modulation: Modulation::from_bits(u8::arbitrary(g) & 0xf),
+                reverse: bool::arbitrary(g),
+                bandwidth: Bandwidth::from_bits(u8::arbitrary(g) & 0x1),
+                power: Power::from_bits(u8::arbitrary(g) & 0x3),
+                busy_lock: bool::arbitrary(g),
+                unknown_flags: u8::arbitrary(g) & 0xe0,
+                dtmf_decode: bool::arbitrary(g),
+                ptt_id: u8::arbitrary(g) & 0x7,
+                unknown_dtmf: u8::arbitrary(g) & 0xf0,
+                step: u8::arbitrary(g),
+                scrambler: u8::arbitrary(g),
+                name: ChannelName::arbitrary(g),
+                attributes: ChannelAttributes::from_bits(u8::arbitrary(g)),
+            }
+        }
+    }
+
+    impl Arbitrary for Settings {
+        fn arbitrary(g: &mut Gen) -> Self {
+            Self {
+                call_channel: u8::arbitrary(g),
+                squelch: u8::arbitrary(g),
+                max_talk_time: u8::arbitrary(g),
+                noaa_autoscan: bool::arbitrary(g),
+                key_lock: bool::arbitrary(g),
+                vox_enabled: bool::arbitrary(g),
+                vox_level: u8::arbitrary(g),
+                mic_gain: u8::arbitrary(g),
+                unknown_8: u8::arbitrary(g),
+                channel_display_mode: u8::arbitrary(g),
+                crossband: u8::arbitrary(g),
+                battery_save: u8::arbitrary(g),
+                dual_watch: u8::arbitrary(g),
+                backlight: u8::arbitrary(g),
+                tail_tone_elimination: bool::arbitrary(g),
+                vfo_open: bool::arbitrary(g),
+            }
+        }
+    }
+
+    #[quickcheck]
+    fn roundtrip_channel_bank(channels: Vec<(u8, Channel)>) -> bool {
+        let mut bank = ChannelBank::new();
+        for (i, channel) in channels {
+            bank.channels[i as usize % CHANNELS] = Some(channel);
+        }
+
+        let mut dump = vec![0; EEPROM_SIZE];
+        bank.to_dump(&mut dump).unwrap();
+        ChannelBank::from_dump(&dump) == Ok(bank)
+    }
+
+    #[quickcheck]
+    fn roundtrip_settings(settings: Settings) -> bool {
+        let mut dump = vec![0; EEPROM_SIZE];
+        settings.to_dump(&mut dump).unwrap();
+        Settings::from_dump(&dump) == Ok(settings)
+    }
+
+    fn known_dump() -> Vec<u8> {
+        let mut dump = vec![0xff; EEPROM_SIZE];
+
+        // channel 3: 146.520MHz -600kHz, 100.0Hz CTCSS, narrow, high power
+        dump[0x0030..0x0040].copy_from_slice(&[
+            0x60, 0x92, 0xdf, 0x00, 0x60, 0xea, 0x00, 0x00, 0x0c, 0x0c, 0x11, 0x02, 0x0a, 0x00,
+            0x05, 0x00,
+        ]);
+        dump[0x0d63] = 0xc2;
+        dump[0x0f80..0x0f90].copy_from_slice(b"CALL\0\0\0\0\0\0\0\0\0\0\0\0");
+
+        dump[0x0e70..0x0e80].copy_from_slice(&[
+            0x00, 0x03, 0x01, 0x00, 0x00, 0x01, 0x04, 0x02, 0x00, 0x01, 0x00, 0x04, 0x00, 0x03,
+            0x01, 0x01,
+        ]);
+
+        dump
+    }
+
+    #[test]
+    fn known_channel_bank() {
+        let dump = known_dump();
+        let bank = ChannelBank::from_dump(&dump).unwrap();
+
+        assert_eq!(bank.channels.iter().filter(|c| c.is_some()).count(), 1);
+        let channel = bank.channels[3].unwrap();
+        assert_eq!(channel.frequency, 14652000);
+        assert_eq!(channel.offset, 60000);
+        assert_eq!(channel.offset_direction, OffsetDirection::Minus);
+        assert_eq!(channel.modulation, Modulation::Fm);
+        assert_eq!(channel.rx_code.ctcss_tone(), Some(1000));
+        assert_eq!(channel.tx_code.ctcss_tone(), Some(1000));
+        assert_eq!(channel.bandwidth, Bandwidth::Narrow);
+        assert_eq!(channel.power, Power::High);
+        assert_eq!(channel.step, 5);
+        assert_eq!(channel.name.as_str(), Ok("CALL"));
+        assert!(channel.attributes.scanlist1);
+        assert!(channel.attributes.scanlist2);
+        assert_eq!(channel.attributes.band, 2);
+
+        let mut rewritten = vec![0xff; EEPROM_SIZE];
+        bank.to_dump(&mut rewritten).unwrap();
+        rewritten[0x0e70..0x0e80].copy_from_slice(&dump[0x0e70..0x0e80]);
+        assert_eq!(rewritten, dump);
+    }
+
+    #[test]
+    fn channel_unknown_bits() {
+        let mut entry = [0u8; CHANNEL_LEN];
+        entry[0x0c] = 0xe5;
+        entry[0x0d] = 0xf3;
+
+        let (_, channel) = Channel::parse(&entry[..]).unwrap();
+        assert!(channel.reverse);
+        assert_eq!(channel.power, Power::from_bits(1));
+        assert_eq!(channel.unknown_flags, 0xe0);
+        assert!(channel.dtmf_decode);
+        assert_eq!(channel.ptt_id, 1);
+        assert_eq!(channel.unknown_dtmf, 0xf0);
+
+        let mut rewritten = [0u8; CHANNEL_LEN];
+        channel
+            .serialize(&mut SerializerWrap::new(&mut rewritten[..]))
+            .unwrap();
+        assert_eq!(rewritten, entry);
+    }
+
+    #[test]
+    fn channel_fields() {
+        let zero = Channel::parse(&[0u8; CHANNEL_LEN][..]).unwrap().1;
+        let debug = format!("{:?}", zero);
+
+        assert_eq!(Channel::FIELDS[0].0, 0);
+        for (i, (offset, names)) in Channel::FIELDS.iter().enumerate() {
+            let end = Channel::FIELDS
+                .get(i + 1)
+                .map(|(o, _)| *o)
+                .unwrap_or(CHANNEL_LEN);
+            assert!(*offset < end);
+
+            // every name is a field, or a field of a field
+            for part in names.split(['/', '.']) {
+                assert!(debug.contains(&format!(" {}: ", part)), "{}", part);
+            }
+
+            // and every byte is parsed into something
+            for b in *offset..end {
+                let mut entry = [0u8; CHANNEL_LEN];
+                entry[b] = 0xff;
+                assert_ne!(Channel::parse(&entry[..]).unwrap().1, zero, "{:#x}", b);
+            }
+        }
+    }
+
+    #[test]
+    fn settings_fields() {
+        // split Debug output into "name: value" parts
+        fn fields(settings: &Settings) -> Vec<String> {
+            format!("{:?}", settings)
+                .split(['{', ','])
+                .skip(1)
+                .map(|f| f.trim_matches([' ', '}']).to_owned())
+                .collect()
+        }
+
+        let zero = fields(&Settings::parse(&[0u8; SETTINGS_LEN][..]).unwrap().1);
+        let names: Vec<&str> = zero.iter().map(|f| f.split(':').next().unwrap()).collect();
+        assert_eq!(names, Settings::FIELDS);
+
+        // byte i changes field i, and nothing else
+        for i in 0..SETTINGS_LEN {
+            let mut data = [0u8; SETTINGS_LEN];
+            data[i] = 1;
+            let changed = fields(&Settings::parse(&data[..]).unwrap().1);
+            for (j, (a, b)) in zero.iter().zip(changed.iter()).enumerate() {
+                assert_eq!(a == b, i != j, "{} {}", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn known_settings() {
+        let dump = known_dump();
+        let settings = Settings::from_dump(&dump).unwrap();
+
+        assert_eq!(settings.squelch, 3);
+        assert!(settings.vox_enabled);
+        assert_eq!(settings.vox_level, 4);
+        assert_eq!(settings.battery_save, 4);
+        assert!(settings.tail_tone_elimination);
+        assert!(settings.vfo_open);
+
+        let mut rewritten = dump.clone();
+        rewritten[0x0e70..0x0e80].fill(0);
+        settings.to_dump(&mut rewritten).unwrap();
+        assert_eq!(rewritten, dump);
+    }
+
+    #[test]
+    fn known_calibration() {
+        let mut dump = vec![0xff; EEPROM_SIZE];
+
+        // band 2 (VHF), as found on a typical radio
+        dump[0x1ef0..0x1f00].copy_from_slice(&[
+            0x32, 0x32, 0x32, 0x50, 0x50, 0x50, 0x8c, 0x8c, 0x8c, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0xff, 0xff,
+        ]);
+        dump[0x1f40..0x1f4c].copy_from_slice(&[
+            0xde, 0x04, 0xfa, 0x06, 0x45, 0x07, 0x5e, 0x07, 0xc5, 0x07, 0xfc, 0x08,
+        ]);
+
+        let calibration = Calibration::from_dump(&dump).unwrap();
+        assert_eq!(calibration.tx_power[0], [[0xff; 3]; 3]);
+        assert_eq!(calibration.tx_power[2], [[0x32; 3], [0x50; 3], [0x8c; 3]]);
+        assert_eq!(
+            calibration.battery,
+            [0x04de, 0x06fa, 0x0745, 0x075e, 0x07c5, 0x08fc]
+        );
+
+        // the reference reading is 7.6V
+        assert_eq!(calibration.battery_millivolts(0x075e), Some(7600));
+        assert_eq!(calibration.battery_millivolts(0x0645), Some(6467));
+
+        let mut erased = calibration.clone();
+        erased.battery = [0xffff; 6];
+        assert_eq!(erased.battery_millivolts(0x075e), None);
+    }
+
+    #[test]
+    fn channel_name_short() {
+        let name = ChannelName::new_from_str("CALL").unwrap();
+        assert_eq!(name.to_bytes(), *b"CALL\0\0\0\0\0\0\0\0\0\0\0\0");
+        assert_eq!(name.as_str(), Ok("CALL"));
+
+        // any mix of padding is trimmed, but inner spaces are kept
+        let name = ChannelName::from_bytes(b"2M CALL  \0\xff\xff\xff\xff\xff\xff");
+        assert_eq!(name.as_str(), Ok("2M CALL"));
+        assert_eq!(format!("{}", name), "2M CALL");
+
+        assert_eq!(ChannelName::new_empty().as_str(), Ok(""));
+        assert_eq!(
+            ChannelName::from_bytes(&[0xff; CHANNEL_LEN]).as_str(),
+            Ok("")
+        );
+    }
+
+    #[test]
+    fn channel_name_full() {
+        let name = ChannelName::new_from_str("0123456789").unwrap();
+        assert_eq!(name.as_str(), Ok("0123456789"));
+        assert_eq!(ChannelName::new_from_str("0123456789A"), None);
+        assert_eq!(
+            "0123456789A".parse::<ChannelName>(),
+            Err(ChannelNameError::TooLong)
+        );
+
+        // the whole field can be read, even past what the firmware shows
+        let name = ChannelName::from_bytes(b"0123456789ABCDEF");
+        assert_eq!(name.as_str(), Ok("0123456789ABCDEF"));
+        assert_eq!(&name.to_bytes(), b"0123456789ABCDEF");
+    }
+
+    #[test]
+    fn channel_name_invalid() {
+        assert_eq!(ChannelName::new_from_str("caf\u{e9}"), None);
+        assert_eq!(
+            "caf\u{e9}".parse::<ChannelName>(),
+            Err(ChannelNameError::InvalidByte(0xc3))
+        );
+        assert_eq!(
+            "A\tB".parse::<ChannelName>(),
+            Err(ChannelNameError::InvalidByte(b'\t'))
+        );
+
+        // invalid bytes are kept, for writing back unchanged
+        let name = ChannelName::from_bytes(b"AB\x01D\0\0\0\0\0\0\0\0\0\0\0\0");
+        assert_eq!(name.as_str(), Err(ChannelNameError::InvalidByte(0x01)));
+        assert_eq!(format!("{}", name), "AB\u{fffd}D");
+        assert_eq!(name.to_bytes()[2], 0x01);
+
+        // the name ends at the first NUL or 0xff, whatever follows
+        let name = ChannelName::from_bytes(b"AB\0D\0\0\0\0\0\0\0\0\0\0\0\0");
+        assert_eq!(name.as_str(), Ok("AB"));
+        assert_eq!(format!("{}", name), "AB");
+        let name = ChannelName::from_bytes(b"AB \xffD\x01\0\0\0\0\0\0\0\0\0\0");
+        assert_eq!(name.as_str(), Ok("AB"));
+    }
+
+    #[test]
+    fn too_short() {
+        assert_eq!(
+            ChannelBank::from_dump(&[0; 0x100]),
+            Err(DumpError::TooShort)
+        );
+        assert_eq!(Settings::from_dump(&[0; 0x100]), Err(DumpError::TooShort));
+        assert_eq!(
+            Calibration::from_dump(&[0; 0x1f00]),
+            Err(DumpError::TooShort)
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! Plain text encodings for binary fields, like the AES challenge,
+//! chip ID, and version bytes.
+//!
+//! Everything here is built on [core::fmt] and needs no allocation,
+//! so firmware can log the same text a host tool prints. Wrap a byte
+//! slice in [HexSlice] or [Base64Slice] and format it:
+//!
+//! ```
+//! use k5lib::encode::{Base64Slice, HexSlice};
+//!
+//! let challenge = [0x12345678u32, 0x9abcdef0, 0, 0xffffffff];
+//! let mut bytes = [0; 16];
+//! for (chunk, word) in bytes.chunks_exact_mut(4).zip(challenge) {
+//!     chunk.copy_from_slice(&word.to_le_bytes());
+//! }
+//!
+//! assert_eq!(
+//!     format!("{}", HexSlice(&bytes)),
+//!     "78563412f0debc9a00000000ffffffff"
+//! );
+//! assert_eq!(
+//!     format!("{}", Base64Slice(&bytes)),
+//!     "eFY0EvDevJoAAAAA/////w=="
+//! );
+//! ```
+//!
+//! Multi-byte words are not reordered, so encode them in wire order
+//! (little-endian) as above.
+
+/// Bytes shown as hex digits, two per byte and no separators.
+///
+/// [core::fmt::Display] and [core::fmt::LowerHex] use lowercase
+/// digits, and [core::fmt::UpperHex] uses uppercase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HexSlice<'a>(pub &'a [u8]);
+
+impl core::fmt::LowerHex for HexSlice<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        for b in self.0 {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::fmt::UpperHex for HexSlice<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        for b in self.0 {
+            write!(f, "{:02X}", b)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::fmt::Display for HexSlice<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        core::fmt::LowerHex::fmt(self, f)
+    }
+}
+
+/// The standard base64 alphabet, from RFC 4648.
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Bytes shown as standard base64 (RFC 4648), with `=` padding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Base64Slice<'a>(pub &'a [u8]);
+
+impl core::fmt::Display for Base64Slice<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        use core::fmt::Write;
+
+        for chunk in self.0.chunks(3) {
+            let mut group = [0; 3];
+            group[..chunk.len()].copy_from_slice(chunk);
+            let bits = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+
+            // n bytes fill n + 1 characters, the rest is padding
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    let index = (bits >> (18 - 6 * i)) & 0x3f;
+                    f.write_char(BASE64[index as usize] as char)?;
+                } else {
+                    f.write_char('=')?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "alloc")]
+mod test {
+    use alloc::format;
+
+    use super::*;
+
+    #[test]
+    fn hex() {
+        let data = [0x01, 0x23, 0xab, 0xff, 0x00];
+        assert_eq!(format!("{}", HexSlice(&data)), "0123abff00");
+        assert_eq!(format!("{:x}", HexSlice(&data)), "0123abff00");
+        assert_eq!(format!("{:X}", HexSlice(&data)), "0123ABFF00");
+        assert_eq!(format!("{}", HexSlice(&[])), "");
+    }
+
+    #[test]
+    fn base64() {
+        // test vectors from RFC 4648, covering every amount of padding
+        for (data, expected) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(format!("{}", Base64Slice(data.as_bytes())), expected);
+        }
+
+        // the top of the alphabet
+        assert_eq!(format!("{}", Base64Slice(&[0xfb, 0xff, 0xbf])), "+/+/");
+    }
+}
@@ -0,0 +1,181 @@
+//! The stock packed firmware image format.
+//!
+//! Packed images are what the bootloader expects to be flashed. They
+//! are a raw flash image with a [crate::Version] spliced in at [VERSION_LOC],
+//! obfuscated with a repeating xor key, followed by a little-endian
+//! xmodem CRC of everything before it.
+
+use crate::VERSION_LEN;
+
+#[cfg(feature = "alloc")]
+use crate::Version;
+
+/// Repeating xor key used to obfuscate packed firmware images.
+///
+/// Not to be confused with [crate::protocol::obfuscation::OBFUSCATION_KEY],
+/// which is used on the serial protocol.
+pub const OBFUSCATION: [u8; 128] = [
+    0x47, 0x22, 0xC0, 0x52, 0x5D, 0x57, 0x48, 0x94, 0xB1, 0x60, 0x60, 0xDB, 0x6F, 0xE3, 0x4C, 0x7C,
+    0xD8, 0x4A, 0xD6, 0x8B, 0x30, 0xEC, 0x25, 0xE0, 0x4C, 0xD9, 0x00, 0x7F, 0xBF, 0xE3, 0x54, 0x05,
+    0xE9, 0x3A, 0x97, 0x6B, 0xB0, 0x6E, 0x0C, 0xFB, 0xB1, 0x1A, 0xE2, 0xC9, 0xC1, 0x56, 0x47, 0xE9,
+    0xBA, 0xF1, 0x42, 0xB6, 0x67, 0x5F, 0x0F, 0x96, 0xF7, 0xC9, 0x3C, 0x84, 0x1B, 0x26, 0xE1, 0x4E,
+    0x3B, 0x6F, 0x66, 0xE6, 0xA0, 0x6A, 0xB0, 0xBF, 0xC6, 0xA5, 0x70, 0x3A, 0xBA, 0x18, 0x9E, 0x27,
+    0x1A, 0x53, 0x5B, 0x71, 0xB1, 0x94, 0x1E, 0x18, 0xF2, 0xD6, 0x81, 0x02, 0x22, 0xFD, 0x5A, 0x28,
+    0x91, 0xDB, 0xBA, 0x5D, 0x64, 0xC6, 0xFE, 0x86, 0x83, 0x9C, 0x50, 0x1C, 0x73, 0x03, 0x11, 0xD6,
+    0xAF, 0x30, 0xF4, 0x2C, 0x77, 0xB2, 0x7D, 0xBB, 0x3F, 0x29, 0x28, 0x57, 0x22, 0xD6, 0x92, 0x8B,
+];
+
+/// Location of the version in the unobfuscated image.
+pub const VERSION_LOC: usize = 0x2000;
+
+/// Smallest possible packed image.
+pub const PACKED_MIN_LEN: usize = VERSION_LOC + VERSION_LEN + 2;
+
+/// An error reading a packed firmware image.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FirmwareError {
+    /// The image is shorter than [PACKED_MIN_LEN].
+    NotEnoughData,
+    /// The CRC at the end of the image does not match.
+    BadChecksum,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FirmwareError {}
+
+impl core::fmt::Display for FirmwareError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::NotEnoughData => write!(
+                f,
+                "packed firmware must be at least {} bytes",
+                PACKED_MIN_LEN
+            ),
+            Self::BadChecksum => write!(f, "bad checksum on packed firmware"),
+        }
+    }
+}
+
+/// Obfuscate (or deobfuscate) data that starts `skip` bytes into an image.
+pub fn obfuscate_skip(data: &mut [u8], skip: usize) {
+    crate::protocol::obfuscation::xor_repeating(&OBFUSCATION, data, skip);
+}
+
+/// Obfuscate (or deobfuscate) a whole image.
+pub fn obfuscate(data: &mut [u8]) {
+    obfuscate_skip(data, 0);
+}
+
+fn checksum(data: &[u8]) -> u16 {
+    crc::Crc::<u16>::new(&crc::CRC_16_XMODEM).checksum(data)
+}
+
+/// Check the CRC on a packed image.
+pub fn check(packed: &[u8]) -> Result<(), FirmwareError> {
+    if packed.len() < PACKED_MIN_LEN {
+        return Err(FirmwareError::NotEnoughData);
+    }
+
+    // checksum is last two bytes, xmodem 16 bit, little-endian
+    let (body, provided) = packed.split_at(packed.len() - 2);
+    if u16::from_le_bytes([provided[0], provided[1]]) != checksum(body) {
+        return Err(FirmwareError::BadChecksum);
+    }
+
+    Ok(())
+}
+
+/// Unpack a packed image into a raw flash image and its version.
+#[cfg(feature = "alloc")]
+pub fn decrypt(packed: &[u8]) -> Result<(alloc::vec::Vec<u8>, Version), FirmwareError> {
+    check(packed)?;
+    decrypt_unchecked(packed)
+}
+
+/// Unpack a packed image without checking the CRC.
+#[cfg(feature = "alloc")]
+pub fn decrypt_unchecked(packed: &[u8]) -> Result<(alloc::vec::Vec<u8>, Version), FirmwareError> {
+    if packed.len() < PACKED_MIN_LEN {
+        return Err(FirmwareError::NotEnoughData);
+    }
+
+    // last two bytes are crc, ignore those
+    let mut raw = packed[..packed.len() - 2].to_vec();
+    obfuscate(&mut raw);
+
+    // splice out the version
+    let mut version = [0; VERSION_LEN];
+    version.copy_from_slice(&raw[VERSION_LOC..VERSION_LOC + VERSION_LEN]);
+    raw.drain(VERSION_LOC..VERSION_LOC + VERSION_LEN);
+
+    Ok((raw, Version::new(version)))
+}
+
+/// Pack a raw flash image with a version.
+///
+/// Images shorter than [VERSION_LOC] are padded with zeros.
+#[cfg(feature = "alloc")]
+pub fn encrypt(raw: &[u8], version: &Version) -> alloc::vec::Vec<u8> {
+    let mut packed = raw.to_vec();
+    if packed.len() < VERSION_LOC {
+        packed.resize(VERSION_LOC, 0);
+    }
+
+    packed.splice(VERSION_LOC..VERSION_LOC, version.iter().copied());
+    obfuscate(&mut packed);
+
+    let crc = checksum(&packed);
+    packed.extend(crc.to_le_bytes());
+    packed
+}
+
+#[cfg(test)]
+#[cfg(feature = "alloc")]
+mod test {
+    use alloc::vec::Vec;
+
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    fn sample() -> Vec<u8> {
+        (0..VERSION_LOC + 0x123).map(|i| (i * 7) as u8).collect()
+    }
+
+    #[test]
+    fn decrypt_encrypt() {
+        let raw = sample();
+        let version = Version::new_from_str("*0.1.0test").unwrap();
+
+        let packed = encrypt(&raw, &version);
+        assert_eq!(packed.len(), raw.len() + VERSION_LEN + 2);
+        assert_eq!(check(&packed), Ok(()));
+
+        let (unpacked, unversion) = decrypt(&packed).unwrap();
+        assert_eq!(unpacked, raw);
+        assert_eq!(unversion.as_str(), Ok("*0.1.0test"));
+        assert_eq!(encrypt(&unpacked, &unversion), packed);
+    }
+
+    #[quickcheck]
+    fn roundtrip(data: Vec<u8>, version: Vec<u8>) -> bool {
+        let version = &version[..version.len().min(VERSION_LEN - 1)];
+        let version = Version::new_from_bytes(version).unwrap();
+
+        let mut raw = sample();
+        raw.extend(data);
+        decrypt(&encrypt(&raw, &version)) == Ok((raw, version))
+    }
+
+    #[test]
+    fn bad_images() {
+        let mut packed = encrypt(&sample(), &Version::new_empty());
+        *packed.last_mut().unwrap() ^= 1;
+        assert_eq!(decrypt(&packed), Err(FirmwareError::BadChecksum));
+        assert_eq!(
+            decrypt(&packed[..PACKED_MIN_LEN - 1]),
+            Err(FirmwareError::NotEnoughData)
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Canonical hex dumps, as `offset  bytes  |ascii|` lines.
+//!
+//! Everything here is built on [core::fmt], so it works the same in
+//! `std` programs and in firmware writing to a UART. Each [Line] and
+//! [DedupLine] is [core::fmt::Display], or use [write_hexdump()] to
+//! write a whole dump to any [core::fmt::Write].
+
+/// Bytes shown on each line.
+pub const WIDTH: usize = 0x10;
+
+/// A single line of a hex dump.
+///
+/// A line with no data shows only the address, and is used to mark
+/// the end of a dump.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Line<'a, A = u16> {
+    address: A,
+    data: &'a [u8],
+}
+
+/// A line of a hex dump, or a marker for repeated lines.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DedupLine<'a, A = u16> {
+    /// A line of data.
+    Data(Line<'a, A>),
+    /// One or more lines identical to the line before, shown as `*`.
+    Duplicate,
+}
+
+impl<'a, A> Line<'a, A> {
+    /// Create a line starting at `address`, up to [WIDTH] bytes long.
+    pub fn new(address: A, data: &'a [u8]) -> Self {
+        Self { address, data }
+    }
+}
+
+/// Get the character for a byte, if it is printable ASCII.
+pub fn printable(chr: u8) -> Option<char> {
+    if (0x20..0x7f).contains(&chr) {
+        Some(chr as char)
+    } else {
+        None
+    }
+}
+
+/// Address types usable in a hex dump.
+pub trait FormatAddr: Sized {
+    /// Write the address, in hex.
+    fn format_addr(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result;
+
+    /// Convert from an offset into the data, if it fits.
+    fn from_usize(n: usize) -> Option<Self>;
+}
+
+impl FormatAddr for u16 {
+    fn format_addr(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:04x}", self)
+    }
+
+    fn from_usize(n: usize) -> Option<Self> {
+        n.try_into().ok()
+    }
+}
+
+impl<'a, A> core::fmt::Display for Line<'a, A>
+where
+    A: FormatAddr,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        self.address.format_addr(f)?;
+
+        if self.data.is_empty() {
+            return Ok(());
+        }
+
+        for i in 0..WIDTH {
+            if i % 8 == 0 {
+                write!(f, " ")?;
+            }
+            if i < self.data.len() {
+                write!(f, " {:02x}", self.data[i])?;
+            } else {
+                write!(f, "   ")?;
+            }
+        }
+
+        write!(f, "  |")?;
+
+        for b in self.data {
+            write!(f, "{}", printable(*b).unwrap_or('.'))?;
+        }
+
+        write!(f, "|")
+    }
+}
+
+impl<'a, A> core::fmt::Display for DedupLine<'a, A>
+where
+    A: FormatAddr,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::Data(line) => line.fmt(f),
+            Self::Duplicate => {
+                write!(f, "*")
+            }
+        }
+    }
+}
+
+/// An iterator over every line of a hex dump.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineIter<'a, A = u16> {
+    data: &'a [u8],
+    next: usize,
+    endline: bool,
+    _phantom: core::marker::PhantomData<A>,
+}
+
+impl<'a, A> LineIter<'a, A> {
+    /// Iterate over the lines for `data`, ending with an empty line.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            next: 0,
+            endline: false,
+            _phantom: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<'a, A> Iterator for LineIter<'a, A>
+where
+    A: FormatAddr,
+{
+    type Item = Line<'a, A>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.next;
+        if start >= self.data.len() {
+            if self.endline {
+                None
+            } else {
+                self.endline = true;
+                Some(Line {
+                    address: A::from_usize(self.next).expect("address too large"),
+                    data: &[],
+                })
+            }
+        } else {
+            let end = (start + WIDTH).min(self.data.len());
+            let part = &self.data[start..end];
+            self.next = end;
+            Some(Line {
+                address: A::from_usize(start).expect("address too large"),
+                data: part,
+            })
+        }
+    }
+}
+
+/// An iterator over the lines of a hex dump, with runs of identical
+/// lines collapsed into one [DedupLine::Duplicate].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DedupLineIter<'a, A = u16> {
+    inner: LineIter<'a, A>,
+    last: Option<&'a [u8]>,
+    in_duplicate: bool,
+}
+
+impl<'a, A> DedupLineIter<'a, A> {
+    /// Iterate over the deduplicated lines for `data`.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            inner: LineIter::new(data),
+            last: None,
+            in_duplicate: false,
+        }
+    }
+}
+
+impl<'a, A> Iterator for DedupLineIter<'a, A>
+where
+    A: FormatAddr,
+{
+    type Item = DedupLine<'a, A>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(line) = self.inner.next() {
+                if self.last == Some(line.data) {
+                    if !self.in_duplicate {
+                        self.in_duplicate = true;
+                        return Some(DedupLine::Duplicate);
+                    }
+                } else {
+                    self.last = Some(line.data);
+                    self.in_duplicate = false;
+                    return Some(DedupLine::Data(line));
+                }
+            } else {
+                return None;
+            }
+        }
+    }
+}
+
+/// Iterate over the deduplicated lines of a hex dump of `data`.
+pub fn hexdump_iter(data: &[u8]) -> DedupLineIter<'_> {
+    DedupLineIter::new(data)
+}
+
+/// Write a hex dump of `data`, with `prefix` before every line.
+pub fn write_hexdump<W>(w: &mut W, prefix: &str, data: &[u8]) -> core::fmt::Result
+where
+    W: core::fmt::Write + ?Sized,
+{
+    for line in hexdump_iter(data) {
+        writeln!(w, "{}{}", prefix, line)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg(feature = "alloc")]
+mod test {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn dump(prefix: &str, data: &[u8]) -> String {
+        let mut out = String::new();
+        write_hexdump(&mut out, prefix, data).unwrap();
+        out
+    }
+
+    #[test]
+    fn all_bytes() {
+        let data: Vec<u8> = (0..=255).collect();
+        let out = dump("", &data);
+        let lines: Vec<&str> = out.lines().collect();
+
+        assert_eq!(lines.len(), 17);
+        assert_eq!(
+            lines[0],
+            "0000  00 01 02 03 04 05 06 07  08 09 0a 0b 0c 0d 0e 0f  |................|"
+        );
+        assert_eq!(
+            lines[2],
+            "0020  20 21 22 23 24 25 26 27  28 29 2a 2b 2c 2d 2e 2f  | !\"#$%&'()*+,-./|"
+        );
+        assert_eq!(
+            lines[7],
+            "0070  70 71 72 73 74 75 76 77  78 79 7a 7b 7c 7d 7e 7f  |pqrstuvwxyz{|}~.|"
+        );
+        assert_eq!(
+            lines[15],
+            "00f0  f0 f1 f2 f3 f4 f5 f6 f7  f8 f9 fa fb fc fd fe ff  |................|"
+        );
+        assert_eq!(lines[16], "0100");
+    }
+
+    #[test]
+    fn partial_row() {
+        let out = dump(">> ", b"0123456789abcdefhi!");
+        assert_eq!(
+            out,
+            ">> 0000  30 31 32 33 34 35 36 37  38 39 61 62 63 64 65 66  |0123456789abcdef|\n\
+             >> 0010  68 69 21                                          |hi!|\n\
+             >> 0013\n"
+        );
+    }
+
+    #[test]
+    fn duplicates() {
+        let out = dump("", &[0xaa; 0x40]);
+        assert_eq!(
+            out,
+            "0000  aa aa aa aa aa aa aa aa  aa aa aa aa aa aa aa aa  |................|\n\
+             *\n\
+             0040\n"
+        );
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(dump("", &[]), "0000\n");
+    }
+}
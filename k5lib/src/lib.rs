@@ -6,6 +6,18 @@ extern crate alloc;
 mod client;
 pub use client::*;
 
+mod clock;
+pub use clock::*;
+
+pub mod eeprom;
+
+pub mod encode;
+
+pub mod firmware;
+
+#[cfg(feature = "hexdump")]
+pub mod hexdump;
+
 pub mod protocol;
 
 mod version;
@@ -158,6 +158,46 @@ impl<'a> CrcDigest for CrcXModemDigest<'a> {
     }
 }
 
+/// A [CrcStyle] that writes 16-bit XModem CRCs, but validates either
+/// a correct XModem CRC or a specific given value.
+///
+/// Stock firmware and bootloaders disagree on whether radio to host
+/// frames carry a real CRC. Most send `0xffff`, but some quirky
+/// bootloaders have been seen sending real CRCs or zeros. Use this
+/// to validate incoming frames from any of them without
+/// reconfiguring, see [crate::ClientHostAny].
+#[derive(Clone)]
+pub struct CrcAny {
+    xmodem: CrcXModem,
+    constant: u16,
+}
+
+impl CrcAny {
+    pub fn new(constant: u16) -> Self {
+        Self {
+            xmodem: CrcXModem::new(),
+            constant,
+        }
+    }
+
+    /// The constant value also accepted by this CRC.
+    pub fn constant(&self) -> u16 {
+        self.constant
+    }
+}
+
+impl CrcStyle for CrcAny {
+    type Digest<'a> = CrcXModemDigest<'a>;
+
+    fn digest(&self) -> Self::Digest<'_> {
+        self.xmodem.digest()
+    }
+
+    fn validate(&self, calculated: u16, provided: u16) -> bool {
+        calculated == provided || provided == self.constant
+    }
+}
+
 impl CrcStyle for crc::Crc<u16, crc::NoTable> {
     type Digest<'a> = crc::Digest<'a, u16, crc::NoTable>;
 
@@ -211,3 +251,51 @@ impl<'a> CrcDigest for crc::Digest<'a, u16, crc::Table<16>> {
         self.finalize()
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "alloc")]
+mod test {
+    use super::super::messages::radio::Hello;
+    use super::super::serialize::SerializerVec;
+    use super::super::{find_frame, parse, serialize, ParseResult};
+    use super::*;
+
+    fn frame_with<C>(crc: &C) -> alloc::vec::Vec<u8>
+    where
+        C: CrcStyle,
+    {
+        let mut ser = SerializerVec::new();
+        serialize(crc, &mut ser, &Hello { session_id: 0x1234 }).unwrap_or_else(|e| match e {});
+        ser.done()
+    }
+
+    fn validates<C>(crc: &C, mut frame: alloc::vec::Vec<u8>) -> bool
+    where
+        C: CrcStyle,
+    {
+        let (_, found) = find_frame(frame.as_mut_slice());
+        match parse::<_, _, Hello>(crc, frame.as_slice(), &found) {
+            ParseResult::Ok(_, m) => m.session_id == 0x1234,
+            ParseResult::CrcErr(_, _) => false,
+            _ => panic!("frame did not parse"),
+        }
+    }
+
+    #[test]
+    fn crc_any_xmodem() {
+        let frame = frame_with(&CrcXModem::new());
+        assert!(validates(&CrcAny::new(0x0000), frame));
+    }
+
+    #[test]
+    fn crc_any_constant() {
+        let frame = frame_with(&CrcConstant(0x0000));
+        assert!(validates(&CrcAny::new(0x0000), frame));
+    }
+
+    #[test]
+    fn crc_any_invalid() {
+        let frame = frame_with(&CrcConstant(0xcafe));
+        assert!(!validates(&CrcAny::new(0x0000), frame));
+    }
+}
@@ -7,12 +7,53 @@ use crate::protocol::serialize::{MessageSerialize, Serializer};
 
 use super::{util, MessageType};
 
+/// The chip ID reported in [BootloaderReady].
+///
+/// The stock 2.00.06 bootloader sends four words, for a 32 byte
+/// message body. Going by an old FIXME about other bootloaders using
+/// different packet formats, some may send only two words, for a 24
+/// byte body, but no such bootloader has been confirmed. The version
+/// field is always last, and always the same size, so either format
+/// is told apart by the length of the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChipId {
+    /// A two-word chip ID.
+    Short([u32; 2]),
+    /// A four-word chip ID, as sent by the stock bootloader.
+    Long([u32; 4]),
+}
+
+impl ChipId {
+    /// The words of the chip ID, in the order they were sent.
+    pub fn as_slice(&self) -> &[u32] {
+        match self {
+            ChipId::Short(id) => id,
+            ChipId::Long(id) => id,
+        }
+    }
+}
+
+impl From<[u32; 2]> for ChipId {
+    fn from(value: [u32; 2]) -> Self {
+        ChipId::Short(value)
+    }
+}
+
+impl From<[u32; 4]> for ChipId {
+    fn from(value: [u32; 4]) -> Self {
+        ChipId::Long(value)
+    }
+}
+
 /// 0x0518 Bootloader Ready, radio message (bootloader mode).
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BootloaderReady {
     /// Chip ID for the radio's CPU.
-    pub chip_id: [u32; 4],
+    pub chip_id: ChipId,
     /// Bootloader version.
     pub version: crate::Version,
 }
@@ -30,7 +71,7 @@ impl MessageSerialize for BootloaderReady {
     where
         S: Serializer,
     {
-        for v in self.chip_id.iter() {
+        for v in self.chip_id.as_slice() {
             ser.write_le_u32(*v)?;
         }
         ser.write_bytes(self.version.as_bytes())
@@ -42,17 +83,25 @@ where
     I: Parse,
 {
     fn parse_body(typ: u16) -> impl Parser<I, Self, Error<I>> {
-        move |input| {
+        move |input: I| {
             let input = if typ != Self::TYPE {
                 nom::combinator::fail::<_, (), _>(input)?.0
             } else {
                 input
             };
 
-            // FIXME some bootloaders have different packet formats
-            // I suspect they vary the chip_id field size, but...
-            // I don't have any examples, so I can't know.
-            let (input, chip_id) = util::parse_array(nom::number::complete::le_u32)(input)?;
+            // the version is always last, so whatever is left before it
+            // decides how long the chip id is
+            let (input, chip_id) = match input.input_len().checked_sub(crate::VERSION_LEN) {
+                Some(8) => {
+                    let (input, id) = util::parse_array(nom::number::complete::le_u32)(input)?;
+                    (input, ChipId::Short(id))
+                }
+                _ => {
+                    let (input, id) = util::parse_array(nom::number::complete::le_u32)(input)?;
+                    (input, ChipId::Long(id))
+                }
+            };
             let (input, version) = util::parse_version(input)?;
 
             Ok((input, BootloaderReady { chip_id, version }))
@@ -66,9 +115,40 @@ pub const WRITE_FLASH_SESSION_ID: u32 = 0x1d9f8d8a;
 /// Size of the data in a [WriteFlash] message.
 pub const WRITE_FLASH_LEN: usize = 0x100;
 
+/// An error produced building a [WriteFlash] message.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WriteFlashError {
+    /// The data is longer than [WRITE_FLASH_LEN]. Contains the length.
+    TooLong(usize),
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WriteFlashError {}
+
+impl core::fmt::Display for WriteFlashError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::TooLong(len) => write!(
+                f,
+                "flash data is 0x{:x} bytes, longer than 0x{:x}",
+                len, WRITE_FLASH_LEN
+            ),
+        }
+    }
+}
+
 /// 0x0519 Write Flash, host message (bootloader mode).
+///
+/// Use [WriteFlash::new()] or [WriteFlash::new_array()] to make sure
+/// the data is the right size.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "I: AsRef<[u8]>", deserialize = "I: FromIterator<u8>"))
+)]
 pub struct WriteFlash<I> {
     /// Session ID unique to this flash sequence. Use
     /// [WRITE_FLASH_SESSION_ID] if unsure. This must be the same
@@ -87,6 +167,9 @@ pub struct WriteFlash<I> {
     /// Alignment padding.
     pub _pad: util::Padding<2>,
     /// Data to write to flash. Must be 0x100 / [WRITE_FLASH_LEN] bytes!
+    ///
+    /// Anything else is padded with zeros or truncated when sent.
+    #[cfg_attr(feature = "serde", serde(with = "util::hex"))]
     pub data: I,
 }
 
@@ -94,6 +177,54 @@ impl<I> MessageType for WriteFlash<I> {
     const TYPE: u16 = 0x0519;
 }
 
+#[cfg(feature = "alloc")]
+impl WriteFlash<alloc::vec::Vec<u8>> {
+    /// Create a message writing `data` to `page`, padded with zeros
+    /// to [WRITE_FLASH_LEN]. Fails if `data` is too long.
+    pub fn new(
+        session_id: u32,
+        page: u16,
+        max_page: u16,
+        data: &[u8],
+    ) -> Result<Self, WriteFlashError> {
+        if data.len() > WRITE_FLASH_LEN {
+            return Err(WriteFlashError::TooLong(data.len()));
+        }
+
+        let mut padded = alloc::vec![0; WRITE_FLASH_LEN];
+        padded[..data.len()].copy_from_slice(data);
+        Ok(Self {
+            session_id,
+            page,
+            max_page,
+            len: data.len() as u16,
+            _pad: Default::default(),
+            data: padded,
+        })
+    }
+}
+
+impl WriteFlash<[u8; WRITE_FLASH_LEN]> {
+    /// Create a message writing a full page of `data` to `page`.
+    ///
+    /// Use [Self::borrow()] to get a message that can be sent.
+    pub fn new_array(
+        session_id: u32,
+        page: u16,
+        max_page: u16,
+        data: [u8; WRITE_FLASH_LEN],
+    ) -> Self {
+        Self {
+            session_id,
+            page,
+            max_page,
+            len: WRITE_FLASH_LEN as u16,
+            _pad: Default::default(),
+            data,
+        }
+    }
+}
+
 impl<I> WriteFlash<I> {
     pub fn map<F, J>(self, f: F) -> WriteFlash<J>
     where
@@ -157,10 +288,15 @@ where
         ser.write_le_u16(self.len)?;
         self._pad.serialize(ser)?;
 
-        // I don't like this assert, but this is better than
-        // sending a malformed packet to the bootloader. probably.
-        assert_eq!(self.data.input_len(), WRITE_FLASH_LEN);
-        ser.write_slice(&self.data)
+        // the bootloader expects exactly WRITE_FLASH_LEN bytes, so
+        // never send anything else, even if the data is the wrong size.
+        // new() and new_array() make sure it never is.
+        let len = self.data.input_len();
+        ser.write_slice(&self.data.slice(..len.min(WRITE_FLASH_LEN)))?;
+        for _ in len..WRITE_FLASH_LEN {
+            ser.write_u8(0)?;
+        }
+        Ok(())
     }
 }
 
@@ -206,6 +342,7 @@ where
 /// 0x051a Write Flash Reply, radio message (bootloader mode).
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WriteFlashReply {
     /// Session ID, matches the session id sent in the [WriteFlash] message.
     pub session_id: u32,
@@ -265,6 +402,7 @@ where
 /// 0x0530 Bootloader Ready Reply, host message (bootloader mode).
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BootloaderReadyReply {
     /// Incoming firmware version.
     pub version: crate::Version,
@@ -317,15 +455,25 @@ mod test {
     use quickcheck::{Arbitrary, Gen};
     use quickcheck_macros::quickcheck;
 
-    impl Arbitrary for BootloaderReady {
+    impl Arbitrary for ChipId {
         fn arbitrary(g: &mut Gen) -> Self {
-            Self {
-                chip_id: [
+            if bool::arbitrary(g) {
+                ChipId::Short([u32::arbitrary(g), u32::arbitrary(g)])
+            } else {
+                ChipId::Long([
                     u32::arbitrary(g),
                     u32::arbitrary(g),
                     u32::arbitrary(g),
                     u32::arbitrary(g),
-                ],
+                ])
+            }
+        }
+    }
+
+    impl Arbitrary for BootloaderReady {
+        fn arbitrary(g: &mut Gen) -> Self {
+            Self {
+                chip_id: ChipId::arbitrary(g),
                 version: crate::Version::arbitrary(g),
             }
         }
@@ -336,6 +484,45 @@ mod test {
         roundtrip(msg)
     }
 
+    #[test]
+    fn bootloader_ready_formats() {
+        let version = crate::Version::new_from_str("2.00.06").unwrap();
+        let mut version_bytes = Vec::new();
+        version_bytes.extend_from_slice(version.as_bytes());
+
+        // short, two-word chip id
+        let mut body = Vec::new();
+        body.extend_from_slice(&[0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00]);
+        body.extend_from_slice(&version_bytes);
+        let (rest, msg) = BootloaderReady::parse_body(BootloaderReady::TYPE)
+            .parse(&body[..])
+            .unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(msg.chip_id, ChipId::Short([1, 2]));
+        assert_eq!(msg.chip_id.as_slice(), &[1, 2]);
+        assert_eq!(msg.version, version);
+        assert!(roundtrip(msg));
+
+        // long, four-word chip id
+        let mut body = Vec::new();
+        for w in 1u32..=4 {
+            body.extend_from_slice(&w.to_le_bytes());
+        }
+        body.extend_from_slice(&version_bytes);
+        let (rest, msg) = BootloaderReady::parse_body(BootloaderReady::TYPE)
+            .parse(&body[..])
+            .unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(msg.chip_id, ChipId::Long([1, 2, 3, 4]));
+        assert_eq!(msg.version, version);
+        assert!(roundtrip(msg));
+
+        // too short for either
+        assert!(BootloaderReady::parse_body(BootloaderReady::TYPE)
+            .parse(&version_bytes[..])
+            .is_err());
+    }
+
     impl Arbitrary for WriteFlash<Vec<u8>> {
         fn arbitrary(g: &mut Gen) -> Self {
             let mut data = Vec::<u8>::arbitrary(g);
@@ -357,6 +544,33 @@ mod test {
         RoundTrip::new().run(&msg.borrow())
     }
 
+    #[test]
+    fn write_flash_new() {
+        // short data is padded
+        let msg = WriteFlash::new(WRITE_FLASH_SESSION_ID, 3, 10, &[1, 2, 3]).unwrap();
+        assert_eq!(msg.len, 3);
+        assert_eq!(msg.data.len(), WRITE_FLASH_LEN);
+        assert_eq!(&msg.data[..4], &[1, 2, 3, 0]);
+        assert!(msg.data[3..].iter().all(|b| *b == 0));
+        assert!(RoundTrip::new().run(&msg.borrow()));
+
+        // exact data is kept as-is
+        let data: Vec<u8> = (0..WRITE_FLASH_LEN).map(|i| i as u8).collect();
+        let msg = WriteFlash::new(WRITE_FLASH_SESSION_ID, 9, 10, &data).unwrap();
+        assert_eq!(msg.len as usize, WRITE_FLASH_LEN);
+        assert_eq!(msg.data, data);
+
+        let array = WriteFlash::new_array(WRITE_FLASH_SESSION_ID, 9, 10, [0; WRITE_FLASH_LEN]);
+        assert_eq!(array.len as usize, WRITE_FLASH_LEN);
+        assert!(RoundTrip::new().run(&array.borrow::<[u8]>()));
+
+        // long data is an error
+        assert_eq!(
+            WriteFlash::new(WRITE_FLASH_SESSION_ID, 0, 10, &[0; WRITE_FLASH_LEN + 1]),
+            Err(WriteFlashError::TooLong(WRITE_FLASH_LEN + 1))
+        );
+    }
+
     impl Arbitrary for WriteFlashReply {
         fn arbitrary(g: &mut Gen) -> Self {
             Self {
@@ -10,8 +10,14 @@ use super::MessageType;
 /// 0x8500 Debug Input, host message.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "I: AsRef<[u8]>", deserialize = "I: FromIterator<u8>"))
+)]
 pub struct DebugInput<I> {
     /// The input line.
+    #[cfg_attr(feature = "serde", serde(with = "super::util::hex"))]
     pub line: I,
 }
 
@@ -90,13 +96,55 @@ where
     }
 }
 
+/// Largest line a single [DebugInput] can carry and still fit in a
+/// [MAX_FRAME_SIZE][crate::protocol::MAX_FRAME_SIZE] frame.
+pub const DEBUG_INPUT_MAX: usize = 0x1f4;
+
+impl<'a> DebugInput<&'a [u8]> {
+    /// Split a line into messages of at most `max` bytes each.
+    ///
+    /// Splits are moved back so they don't land inside a UTF-8
+    /// character, unless the line isn't UTF-8 at all. An empty line
+    /// still produces one (empty) message. The radio sees each
+    /// message as a separate line, so `max` is usually
+    /// [DEBUG_INPUT_MAX] and longer lines are best avoided.
+    ///
+    /// Panics if `max` is less than 4.
+    pub fn split(line: &'a [u8], max: usize) -> impl Iterator<Item = Self> + 'a {
+        assert!(max >= 4, "DebugInput::split max must fit a UTF-8 character");
+
+        let mut rest = Some(line);
+        core::iter::from_fn(move || {
+            let line = rest.take()?;
+            if line.len() <= max {
+                return Some(DebugInput { line });
+            }
+
+            // back up over at most 3 continuation bytes
+            let end = (max - 3..=max)
+                .rev()
+                .find(|i| line[*i] & 0xc0 != 0x80)
+                .unwrap_or(max);
+            let (line, remaining) = line.split_at(end);
+            rest = Some(remaining);
+            Some(DebugInput { line })
+        })
+    }
+}
+
 /// 0x8501 Debug Output, radio message.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "I: AsRef<[u8]>", deserialize = "I: FromIterator<u8>"))
+)]
 pub struct DebugOutput<I> {
     /// True if this is a defmt frame (or part of one).
     pub defmt: bool,
     /// The output data.
+    #[cfg_attr(feature = "serde", serde(with = "super::util::hex"))]
     pub data: I,
 }
 
@@ -187,6 +235,264 @@ where
     }
 }
 
+/// Collects [DebugOutput] text into complete lines.
+///
+/// Output can be split across frames anywhere, even inside a UTF-8
+/// character, so any partial line is kept until the rest arrives.
+/// Lines may end in `\n`, `\r\n`, or a lone `\r`, and the endings are
+/// removed. Invalid UTF-8 is replaced with U+FFFD, and control
+/// characters other than tab are dropped.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct DebugConsole {
+    partial: alloc::vec::Vec<u8>,
+    // last byte was \r, so a \n right after it ends nothing
+    after_cr: bool,
+}
+
+#[cfg(feature = "alloc")]
+impl DebugConsole {
+    /// Create a new, empty console.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add raw output text, and return any lines it completes.
+    pub fn push(&mut self, data: &[u8]) -> alloc::vec::Vec<alloc::string::String> {
+        let mut lines = alloc::vec::Vec::new();
+        for &b in data {
+            let after_cr = core::mem::replace(&mut self.after_cr, b == b'\r');
+            match b {
+                b'\n' if after_cr => {}
+                b'\n' | b'\r' => lines.push(self.take()),
+                b'\t' => self.partial.push(b),
+                0x00..=0x1f | 0x7f => {}
+                _ => self.partial.push(b),
+            }
+        }
+        lines
+    }
+
+    /// Add a [DebugOutput] message, and return any lines it
+    /// completes. defmt output is not text, and is ignored.
+    pub fn push_output<I>(
+        &mut self,
+        output: &DebugOutput<I>,
+    ) -> alloc::vec::Vec<alloc::string::String>
+    where
+        I: AsRef<[u8]>,
+    {
+        if output.defmt {
+            return alloc::vec::Vec::new();
+        }
+        self.push(output.data.as_ref())
+    }
+
+    /// True if `data` carries on the output as UTF-8 text. A
+    /// character may be split across the end of the last output, and
+    /// the end of `data`.
+    pub fn is_text(&self, data: &[u8]) -> bool {
+        // find the start of the last character, which may be partial
+        let mut start = self.partial.len();
+        while start > 0 && self.partial.len() - start < 3 && self.partial[start - 1] & 0xc0 == 0x80
+        {
+            start -= 1;
+        }
+        let start = start.saturating_sub(1);
+
+        let mut joined = self.partial[start..].to_vec();
+        joined.extend_from_slice(data);
+        match core::str::from_utf8(&joined) {
+            Ok(_) => true,
+            // incomplete at the end is fine, the rest comes later
+            Err(e) => e.error_len().is_none(),
+        }
+    }
+
+    /// True if there is a partial line waiting for its end.
+    pub fn has_partial(&self) -> bool {
+        !self.partial.is_empty()
+    }
+
+    /// Take the partial line, if any, as if it had ended.
+    pub fn flush(&mut self) -> Option<alloc::string::String> {
+        self.has_partial().then(|| self.take())
+    }
+
+    fn take(&mut self) -> alloc::string::String {
+        let line = alloc::string::String::from_utf8_lossy(&self.partial).into_owned();
+        self.partial.clear();
+        line
+    }
+}
+
+/// Largest number of bytes a [ReadEepromExtReply] can carry and still
+/// fit in a [MAX_FRAME_SIZE][crate::protocol::MAX_FRAME_SIZE] frame.
+pub const READ_EEPROM_EXT_MAX: u16 = 0x1f0;
+
+/// 0x8502 Read EEPROM (extended), host message.
+///
+/// Like [ReadEeprom][super::radio::ReadEeprom], but with a 16-bit
+/// length, for firmware that can reply with larger blocks. Lengths
+/// are limited to [READ_EEPROM_EXT_MAX].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReadEepromExt {
+    /// Address to read.
+    pub address: u16,
+    /// Number of bytes to read from address.
+    pub len: u16,
+    /// Session ID, must match the one provided by initial
+    /// [Hello][super::radio::Hello].
+    pub session_id: u32,
+}
+
+impl MessageType for ReadEepromExt {
+    const TYPE: u16 = 0x8502;
+}
+
+impl MessageSerialize for ReadEepromExt {
+    fn message_type(&self) -> u16 {
+        Self::TYPE
+    }
+
+    fn message_body<S>(&self, ser: &mut S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        ser.write_le_u16(self.address)?;
+        ser.write_le_u16(self.len)?;
+        ser.write_le_u32(self.session_id)
+    }
+}
+
+impl<I> MessageParse<I> for ReadEepromExt
+where
+    I: Parse,
+{
+    fn parse_body(typ: u16) -> impl Parser<I, Self, Error<I>> {
+        move |input| {
+            let input = if typ != Self::TYPE {
+                nom::combinator::fail::<_, (), _>(input)?.0
+            } else {
+                input
+            };
+
+            let (input, address) = nom::number::complete::le_u16(input)?;
+            let (input, len) = nom::number::complete::le_u16(input)?;
+            let (input, session_id) = nom::number::complete::le_u32(input)?;
+            Ok((
+                input,
+                ReadEepromExt {
+                    address,
+                    len,
+                    session_id,
+                },
+            ))
+        }
+    }
+}
+
+/// 0x8503 Read EEPROM Reply (extended), radio message.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "I: AsRef<[u8]>", deserialize = "I: FromIterator<u8>"))
+)]
+pub struct ReadEepromExtReply<I> {
+    /// Address of data read.
+    pub address: u16,
+    /// Number of bytes of data read.
+    pub len: u16,
+    /// Data read from EEPROM.
+    #[cfg_attr(feature = "serde", serde(with = "super::util::hex"))]
+    pub data: I,
+}
+
+impl<I> MessageType for ReadEepromExtReply<I> {
+    const TYPE: u16 = 0x8503;
+}
+
+impl<I> ReadEepromExtReply<I> {
+    pub fn map<F, J>(self, f: F) -> ReadEepromExtReply<J>
+    where
+        F: FnOnce(I) -> J,
+    {
+        ReadEepromExtReply {
+            address: self.address,
+            len: self.len,
+            data: f(self.data),
+        }
+    }
+
+    pub fn map_ref<'a, F, J>(&'a self, f: F) -> ReadEepromExtReply<J>
+    where
+        F: FnOnce(&'a I) -> J,
+    {
+        ReadEepromExtReply {
+            address: self.address,
+            len: self.len,
+            data: f(&self.data),
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn to_owned(&self) -> ReadEepromExtReply<I::Owned>
+    where
+        I: alloc::borrow::ToOwned,
+    {
+        self.map_ref(I::to_owned)
+    }
+
+    pub fn borrow<Borrowed: ?Sized>(&self) -> ReadEepromExtReply<&Borrowed>
+    where
+        I: core::borrow::Borrow<Borrowed>,
+    {
+        self.map_ref(I::borrow)
+    }
+}
+
+impl<I> MessageSerialize for ReadEepromExtReply<I>
+where
+    I: Parse,
+{
+    fn message_type(&self) -> u16 {
+        Self::TYPE
+    }
+
+    fn message_body<S>(&self, ser: &mut S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        ser.write_le_u16(self.address)?;
+        ser.write_le_u16(self.len)?;
+        ser.write_slice(&self.data)
+    }
+}
+
+impl<I> MessageParse<I> for ReadEepromExtReply<I>
+where
+    I: Parse,
+{
+    fn parse_body(typ: u16) -> impl Parser<I, Self, Error<I>> {
+        move |input| {
+            let input = if typ != Self::TYPE {
+                nom::combinator::fail::<_, (), _>(input)?.0
+            } else {
+                input
+            };
+
+            let (input, address) = nom::number::complete::le_u16(input)?;
+            let (input, len) = nom::number::complete::le_u16(input)?;
+            let (input, data) = nom::bytes::complete::take(len as usize)(input)?;
+            Ok((input, ReadEepromExtReply { address, len, data }))
+        }
+    }
+}
+
 #[cfg(test)]
 #[cfg(feature = "alloc")]
 mod test {
@@ -224,4 +530,144 @@ mod test {
     fn roundtrip_debug_output(msg: DebugOutput<Vec<u8>>) -> bool {
         RoundTrip::new().run(&msg.borrow())
     }
+
+    #[test]
+    fn debug_input_split() {
+        let split = |line: &'static [u8], max| -> Vec<&[u8]> {
+            DebugInput::split(line, max).map(|m| m.line).collect()
+        };
+
+        assert_eq!(split(b"", 4), [b""]);
+        assert_eq!(split(b"hello", 8), [b"hello"]);
+        assert_eq!(split(b"hello", 4), [&b"hell"[..], b"o"]);
+
+        // never split inside a character
+        let line = "ab\u{00e9}\u{20ac}z".as_bytes();
+        let chunks = split(line, 4);
+        assert_eq!(chunks.concat(), line);
+        for chunk in chunks {
+            assert!(core::str::from_utf8(chunk).is_ok());
+            assert!(chunk.len() <= 4);
+        }
+
+        // unless there's no character boundary to be found
+        assert_eq!(split(&[0x80; 6], 4), [&[0x80; 4][..], &[0x80; 2]]);
+
+        let long = [b'x'; DEBUG_INPUT_MAX + 1];
+        for msg in DebugInput::split(&long, DEBUG_INPUT_MAX) {
+            assert!(RoundTrip::new().run(&msg));
+        }
+    }
+
+    #[test]
+    fn debug_console_is_text() {
+        let mut console = DebugConsole::new();
+        assert!(console.is_text(b"plain"));
+        assert!(console.is_text("caf\u{e9}".as_bytes()));
+        assert!(!console.is_text(b"\xff\xfe"));
+        assert!(!console.is_text(b"\x80"));
+
+        // a character split across two outputs
+        let cafe = "caf\u{e9}".as_bytes();
+        assert!(console.is_text(&cafe[..4]));
+        assert!(console.push(&cafe[..4]).is_empty());
+        assert!(console.is_text(&cafe[4..]));
+        assert!(!console.is_text(b"x"));
+        assert!(console.push(&cafe[4..]).is_empty());
+
+        // the last character is complete now
+        assert!(console.is_text(b"x"));
+        assert!(!console.is_text(&cafe[4..]));
+        assert_eq!(console.flush().as_deref(), Some("caf\u{e9}"));
+    }
+
+    #[test]
+    fn debug_console_split_frames() {
+        let mut console = DebugConsole::new();
+        let first = DebugOutput {
+            defmt: false,
+            data: &b"hello, wo"[..],
+        };
+        let second = DebugOutput {
+            defmt: false,
+            data: &b"rld\r"[..],
+        };
+        let third = DebugOutput {
+            defmt: false,
+            data: &b"\nsecond\rthird\n\npartial"[..],
+        };
+
+        assert!(console.push_output(&first).is_empty());
+        assert!(console.has_partial());
+        assert_eq!(console.push_output(&second), ["hello, world"]);
+        assert_eq!(console.push_output(&third), ["second", "third", ""]);
+        assert_eq!(console.flush().as_deref(), Some("partial"));
+        assert_eq!(console.flush(), None);
+
+        let defmt = DebugOutput {
+            defmt: true,
+            data: &b"not\ntext\n"[..],
+        };
+        assert!(console.push_output(&defmt).is_empty());
+        assert!(!console.has_partial());
+    }
+
+    #[test]
+    fn debug_console_utf8() {
+        let mut console = DebugConsole::new();
+
+        // a character split across frames comes out whole
+        let euro = "\u{20ac}".as_bytes();
+        assert!(console.push(&euro[..1]).is_empty());
+        assert_eq!(console.push(&[&euro[1..], b"\n"].concat()), ["\u{20ac}"]);
+
+        // invalid bytes are replaced, control characters dropped
+        assert_eq!(
+            console.push(b"a\xffb\x1b[0m\tc\x00\n"),
+            ["a\u{fffd}b[0m\tc"]
+        );
+    }
+
+    impl Arbitrary for ReadEepromExt {
+        fn arbitrary(g: &mut Gen) -> Self {
+            Self {
+                address: u16::arbitrary(g),
+                len: u16::arbitrary(g),
+                session_id: u32::arbitrary(g),
+            }
+        }
+    }
+
+    #[quickcheck]
+    fn roundtrip_read_eeprom_ext(msg: ReadEepromExt) -> bool {
+        roundtrip(msg)
+    }
+
+    impl Arbitrary for ReadEepromExtReply<Vec<u8>> {
+        fn arbitrary(g: &mut Gen) -> Self {
+            // make sure to test lengths past the stock 0xff limit
+            let len = u16::arbitrary(g) % (READ_EEPROM_EXT_MAX + 1);
+            let data: Vec<u8> = (0..len).map(|_| u8::arbitrary(g)).collect();
+            Self {
+                address: u16::arbitrary(g),
+                len,
+                data,
+            }
+        }
+    }
+
+    #[quickcheck]
+    fn roundtrip_read_eeprom_ext_reply(msg: ReadEepromExtReply<Vec<u8>>) -> bool {
+        RoundTrip::new().run(&msg.borrow())
+    }
+
+    #[test]
+    fn read_eeprom_ext_reply_max() {
+        let msg = ReadEepromExtReply {
+            address: 0x1000,
+            len: READ_EEPROM_EXT_MAX,
+            data: alloc::vec![0x5a; READ_EEPROM_EXT_MAX as usize],
+        };
+        assert!(RoundTrip::new().run(&msg.borrow()));
+    }
 }
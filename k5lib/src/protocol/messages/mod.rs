@@ -15,9 +15,30 @@ pub trait MessageType {
     const TYPE: u16;
 }
 
+// the message type of a message, without needing MessageSerialize
+fn type_of<M: MessageType>(_msg: &M) -> u16 {
+    M::TYPE
+}
+
+/// Which way a message travels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    /// From the host computer to the radio, a [HostMessage].
+    HostToRadio,
+    /// From the radio to the host computer, a [RadioMessage].
+    RadioToHost,
+}
+
 /// Any kind of message, either a [HostMessage] or a [RadioMessage].
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "I: AsRef<[u8]>", deserialize = "I: FromIterator<u8>"))
+)]
 pub enum Message<I> {
     Host(HostMessage<I>),
     Radio(RadioMessage<I>),
@@ -58,6 +79,33 @@ impl<I> Message<I> {
     {
         self.map_ref(I::borrow)
     }
+
+    /// Which way this message travels.
+    pub fn direction(&self) -> Direction {
+        match self {
+            Self::Host(m) => m.direction(),
+            Self::Radio(m) => m.direction(),
+        }
+    }
+
+    /// A human-readable name for this message, for logs and UIs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Host(m) => m.name(),
+            Self::Radio(m) => m.name(),
+        }
+    }
+
+    /// The message type for this message.
+    ///
+    /// This is the same as [MessageSerialize::message_type()], but
+    /// works for any `I`.
+    pub fn message_type(&self) -> u16 {
+        match self {
+            Self::Host(m) => m.message_type(),
+            Self::Radio(m) => m.message_type(),
+        }
+    }
 }
 
 impl<I> MessageSerialize for Message<I>
@@ -94,9 +142,67 @@ where
     }
 }
 
+/// Either a built-in message `M`, or a message `E` defined elsewhere.
+///
+/// This lets firmware and tools add their own message types without
+/// changing this crate. Parsing tries `M` first, and only hands
+/// unrecognized types to `E`, so built-in messages always win. For
+/// example, `Extended<HostMessage<I>, MyMessage>` parses everything
+/// [HostMessage] does, plus `MyMessage`. More than one extension can
+/// be added by nesting.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Extended<M, E> {
+    Builtin(M),
+    Extension(E),
+}
+
+impl<M, E> MessageSerialize for Extended<M, E>
+where
+    M: MessageSerialize,
+    E: MessageSerialize,
+{
+    fn message_type(&self) -> u16 {
+        match self {
+            Self::Builtin(m) => m.message_type(),
+            Self::Extension(m) => m.message_type(),
+        }
+    }
+
+    fn message_body<S>(&self, ser: &mut S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Builtin(m) => m.message_body(ser),
+            Self::Extension(m) => m.message_body(ser),
+        }
+    }
+}
+
+impl<I, M, E> MessageParse<I> for Extended<M, E>
+where
+    I: Parse,
+    M: MessageParse<I>,
+    E: MessageParse<I>,
+{
+    fn parse_body(typ: u16) -> impl Parser<I, Self, Error<I>> {
+        nom::branch::alt((
+            nom::combinator::map(M::parse_body(typ), Extended::Builtin),
+            nom::combinator::map(E::parse_body(typ), Extended::Extension),
+        ))
+    }
+}
+
 /// Messages sent from the host computer to the radio.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "I: AsRef<[u8]>", deserialize = "I: FromIterator<u8>"))
+)]
 pub enum HostMessage<I> {
     /// 0x0514 Hello
     Hello(radio::Hello),
@@ -104,11 +210,17 @@ pub enum HostMessage<I> {
     WriteFlash(bootloader::WriteFlash<I>),
     /// 0x051b Read EEPROM
     ReadEeprom(radio::ReadEeprom),
+    /// 0x051d Write EEPROM
+    WriteEeprom(radio::WriteEeprom<I>),
+    /// 0x052d Write Challenge
+    WriteChallenge(radio::WriteChallenge),
     /// 0x0530 Bootloader Ready Reply (bootloader mode)
     BootloaderReadyReply(bootloader::BootloaderReadyReply),
 
     /// 0x8500 Debug Input (custom)
     DebugInput(custom::DebugInput<I>),
+    /// 0x8502 Read EEPROM, extended (custom)
+    ReadEepromExt(custom::ReadEepromExt),
 }
 
 impl<I> HostMessage<I> {
@@ -120,9 +232,12 @@ impl<I> HostMessage<I> {
             Self::Hello(o) => HostMessage::Hello(o),
             Self::WriteFlash(o) => HostMessage::WriteFlash(o.map(f)),
             Self::ReadEeprom(o) => HostMessage::ReadEeprom(o),
+            Self::WriteEeprom(o) => HostMessage::WriteEeprom(o.map(f)),
+            Self::WriteChallenge(o) => HostMessage::WriteChallenge(o),
             Self::BootloaderReadyReply(o) => HostMessage::BootloaderReadyReply(o),
 
             Self::DebugInput(o) => HostMessage::DebugInput(o.map(f)),
+            Self::ReadEepromExt(o) => HostMessage::ReadEepromExt(o),
         }
     }
 
@@ -134,9 +249,12 @@ impl<I> HostMessage<I> {
             Self::Hello(o) => HostMessage::Hello(o.clone()),
             Self::WriteFlash(o) => HostMessage::WriteFlash(o.map_ref(f)),
             Self::ReadEeprom(o) => HostMessage::ReadEeprom(o.clone()),
+            Self::WriteEeprom(o) => HostMessage::WriteEeprom(o.map_ref(f)),
+            Self::WriteChallenge(o) => HostMessage::WriteChallenge(o.clone()),
             Self::BootloaderReadyReply(o) => HostMessage::BootloaderReadyReply(o.clone()),
 
             Self::DebugInput(o) => HostMessage::DebugInput(o.map_ref(f)),
+            Self::ReadEepromExt(o) => HostMessage::ReadEepromExt(o.clone()),
         }
     }
 
@@ -154,6 +272,44 @@ impl<I> HostMessage<I> {
     {
         self.map_ref(I::borrow)
     }
+
+    /// Which way this message travels, always [Direction::HostToRadio].
+    pub fn direction(&self) -> Direction {
+        Direction::HostToRadio
+    }
+
+    /// A human-readable name for this message, for logs and UIs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Hello(_) => "Hello",
+            Self::WriteFlash(_) => "Write Flash",
+            Self::ReadEeprom(_) => "Read EEPROM",
+            Self::WriteEeprom(_) => "Write EEPROM",
+            Self::WriteChallenge(_) => "Write Challenge",
+            Self::BootloaderReadyReply(_) => "Bootloader Ready Reply",
+
+            Self::DebugInput(_) => "Debug Input",
+            Self::ReadEepromExt(_) => "Read EEPROM (extended)",
+        }
+    }
+
+    /// The message type for this message.
+    ///
+    /// This is the same as [MessageSerialize::message_type()], but
+    /// works for any `I`.
+    pub fn message_type(&self) -> u16 {
+        match self {
+            Self::Hello(m) => type_of(m),
+            Self::WriteFlash(m) => type_of(m),
+            Self::ReadEeprom(m) => type_of(m),
+            Self::WriteEeprom(m) => type_of(m),
+            Self::WriteChallenge(m) => type_of(m),
+            Self::BootloaderReadyReply(m) => type_of(m),
+
+            Self::DebugInput(m) => type_of(m),
+            Self::ReadEepromExt(m) => type_of(m),
+        }
+    }
 }
 
 impl<I> MessageSerialize for HostMessage<I>
@@ -165,9 +321,12 @@ where
             Self::Hello(m) => m.message_type(),
             Self::WriteFlash(m) => m.message_type(),
             Self::ReadEeprom(m) => m.message_type(),
+            Self::WriteEeprom(m) => m.message_type(),
+            Self::WriteChallenge(m) => m.message_type(),
             Self::BootloaderReadyReply(m) => m.message_type(),
 
             Self::DebugInput(m) => m.message_type(),
+            Self::ReadEepromExt(m) => m.message_type(),
         }
     }
 
@@ -179,9 +338,12 @@ where
             Self::Hello(m) => m.message_body(ser),
             Self::WriteFlash(m) => m.message_body(ser),
             Self::ReadEeprom(m) => m.message_body(ser),
+            Self::WriteEeprom(m) => m.message_body(ser),
+            Self::WriteChallenge(m) => m.message_body(ser),
             Self::BootloaderReadyReply(m) => m.message_body(ser),
 
             Self::DebugInput(m) => m.message_body(ser),
+            Self::ReadEepromExt(m) => m.message_body(ser),
         }
     }
 }
@@ -199,6 +361,12 @@ where
             radio::ReadEeprom::TYPE => radio::ReadEeprom::parse_body(typ)
                 .map(Self::ReadEeprom)
                 .parse(input),
+            radio::WriteEeprom::<()>::TYPE => radio::WriteEeprom::parse_body(typ)
+                .map(Self::WriteEeprom)
+                .parse(input),
+            radio::WriteChallenge::TYPE => radio::WriteChallenge::parse_body(typ)
+                .map(Self::WriteChallenge)
+                .parse(input),
             bootloader::BootloaderReadyReply::TYPE => {
                 bootloader::BootloaderReadyReply::parse_body(typ)
                     .map(Self::BootloaderReadyReply)
@@ -208,6 +376,9 @@ where
             custom::DebugInput::<()>::TYPE => custom::DebugInput::parse_body(typ)
                 .map(Self::DebugInput)
                 .parse(input),
+            custom::ReadEepromExt::TYPE => custom::ReadEepromExt::parse_body(typ)
+                .map(Self::ReadEepromExt)
+                .parse(input),
 
             // we don't recognize the message type
             _ => nom::combinator::fail(input),
@@ -218,6 +389,11 @@ where
 /// Messages sent from the radio to the host computer.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "I: AsRef<[u8]>", deserialize = "I: FromIterator<u8>"))
+)]
 pub enum RadioMessage<I> {
     /// 0x0515 HelloReply
     HelloReply(radio::HelloReply),
@@ -227,9 +403,15 @@ pub enum RadioMessage<I> {
     WriteFlashReply(bootloader::WriteFlashReply),
     /// 0x51c Read EEPROM Reply
     ReadEepromReply(radio::ReadEepromReply<I>),
+    /// 0x051e Write EEPROM Reply
+    WriteEepromReply(radio::WriteEepromReply),
+    /// 0x052e Challenge Reply
+    ChallengeReply(radio::ChallengeReply),
 
     /// 0x8501 Debug Output (custom)
     DebugOutput(custom::DebugOutput<I>),
+    /// 0x8503 Read EEPROM Reply, extended (custom)
+    ReadEepromExtReply(custom::ReadEepromExtReply<I>),
 }
 
 impl<I> RadioMessage<I> {
@@ -242,8 +424,11 @@ impl<I> RadioMessage<I> {
             Self::BootloaderReady(o) => RadioMessage::BootloaderReady(o),
             Self::WriteFlashReply(o) => RadioMessage::WriteFlashReply(o),
             Self::ReadEepromReply(o) => RadioMessage::ReadEepromReply(o.map(f)),
+            Self::WriteEepromReply(o) => RadioMessage::WriteEepromReply(o),
+            Self::ChallengeReply(o) => RadioMessage::ChallengeReply(o),
 
             Self::DebugOutput(o) => RadioMessage::DebugOutput(o.map(f)),
+            Self::ReadEepromExtReply(o) => RadioMessage::ReadEepromExtReply(o.map(f)),
         }
     }
 
@@ -256,8 +441,11 @@ impl<I> RadioMessage<I> {
             Self::BootloaderReady(o) => RadioMessage::BootloaderReady(o.clone()),
             Self::WriteFlashReply(o) => RadioMessage::WriteFlashReply(o.clone()),
             Self::ReadEepromReply(o) => RadioMessage::ReadEepromReply(o.map_ref(f)),
+            Self::WriteEepromReply(o) => RadioMessage::WriteEepromReply(o.clone()),
+            Self::ChallengeReply(o) => RadioMessage::ChallengeReply(o.clone()),
 
             Self::DebugOutput(o) => RadioMessage::DebugOutput(o.map_ref(f)),
+            Self::ReadEepromExtReply(o) => RadioMessage::ReadEepromExtReply(o.map_ref(f)),
         }
     }
 
@@ -275,6 +463,44 @@ impl<I> RadioMessage<I> {
     {
         self.map_ref(I::borrow)
     }
+
+    /// Which way this message travels, always [Direction::RadioToHost].
+    pub fn direction(&self) -> Direction {
+        Direction::RadioToHost
+    }
+
+    /// A human-readable name for this message, for logs and UIs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::HelloReply(_) => "Hello Reply",
+            Self::BootloaderReady(_) => "Bootloader Ready",
+            Self::WriteFlashReply(_) => "Write Flash Reply",
+            Self::ReadEepromReply(_) => "Read EEPROM Reply",
+            Self::WriteEepromReply(_) => "Write EEPROM Reply",
+            Self::ChallengeReply(_) => "Challenge Reply",
+
+            Self::DebugOutput(_) => "Debug Output",
+            Self::ReadEepromExtReply(_) => "Read EEPROM Reply (extended)",
+        }
+    }
+
+    /// The message type for this message.
+    ///
+    /// This is the same as [MessageSerialize::message_type()], but
+    /// works for any `I`.
+    pub fn message_type(&self) -> u16 {
+        match self {
+            Self::HelloReply(m) => type_of(m),
+            Self::BootloaderReady(m) => type_of(m),
+            Self::WriteFlashReply(m) => type_of(m),
+            Self::ReadEepromReply(m) => type_of(m),
+            Self::WriteEepromReply(m) => type_of(m),
+            Self::ChallengeReply(m) => type_of(m),
+
+            Self::DebugOutput(m) => type_of(m),
+            Self::ReadEepromExtReply(m) => type_of(m),
+        }
+    }
 }
 
 impl<I> MessageSerialize for RadioMessage<I>
@@ -287,8 +513,11 @@ where
             Self::BootloaderReady(m) => m.message_type(),
             Self::WriteFlashReply(m) => m.message_type(),
             Self::ReadEepromReply(m) => m.message_type(),
+            Self::WriteEepromReply(m) => m.message_type(),
+            Self::ChallengeReply(m) => m.message_type(),
 
             Self::DebugOutput(m) => m.message_type(),
+            Self::ReadEepromExtReply(m) => m.message_type(),
         }
     }
 
@@ -301,8 +530,11 @@ where
             Self::BootloaderReady(m) => m.message_body(ser),
             Self::WriteFlashReply(m) => m.message_body(ser),
             Self::ReadEepromReply(m) => m.message_body(ser),
+            Self::WriteEepromReply(m) => m.message_body(ser),
+            Self::ChallengeReply(m) => m.message_body(ser),
 
             Self::DebugOutput(m) => m.message_body(ser),
+            Self::ReadEepromExtReply(m) => m.message_body(ser),
         }
     }
 }
@@ -325,10 +557,19 @@ where
             radio::ReadEepromReply::<()>::TYPE => radio::ReadEepromReply::parse_body(typ)
                 .map(Self::ReadEepromReply)
                 .parse(input),
+            radio::WriteEepromReply::TYPE => radio::WriteEepromReply::parse_body(typ)
+                .map(Self::WriteEepromReply)
+                .parse(input),
+            radio::ChallengeReply::TYPE => radio::ChallengeReply::parse_body(typ)
+                .map(Self::ChallengeReply)
+                .parse(input),
 
             custom::DebugOutput::<()>::TYPE => custom::DebugOutput::parse_body(typ)
                 .map(Self::DebugOutput)
                 .parse(input),
+            custom::ReadEepromExtReply::<()>::TYPE => custom::ReadEepromExtReply::parse_body(typ)
+                .map(Self::ReadEepromExtReply)
+                .parse(input),
 
             // we don't recognize the message type
             _ => nom::combinator::fail(input),
@@ -394,4 +635,173 @@ mod test {
             }
         }
     }
+
+    // a made-up message, to test Extended
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Ping(u8);
+
+    impl MessageType for Ping {
+        const TYPE: u16 = 0x85ff;
+    }
+
+    impl MessageSerialize for Ping {
+        fn message_type(&self) -> u16 {
+            Self::TYPE
+        }
+
+        fn message_body<S>(&self, ser: &mut S) -> Result<(), S::Error>
+        where
+            S: Serializer,
+        {
+            ser.write_u8(self.0)
+        }
+    }
+
+    impl<I> MessageParse<I> for Ping
+    where
+        I: Parse,
+    {
+        fn parse_body(typ: u16) -> impl Parser<I, Self, Error<I>> {
+            move |input| {
+                let input = if typ != Self::TYPE {
+                    nom::combinator::fail::<_, (), _>(input)?.0
+                } else {
+                    input
+                };
+
+                let (input, value) = nom::number::complete::u8(input)?;
+                Ok((input, Ping(value)))
+            }
+        }
+    }
+
+    #[test]
+    fn extended() {
+        type Ext<'a> = Extended<HostMessage<&'a [u8]>, Ping>;
+
+        let mut rt = RoundTrip::new();
+        let ping: Option<Ext> = rt.ser(&Ping(42)).de();
+        assert_eq!(ping, Some(Extended::Extension(Ping(42))));
+
+        // built-in messages still parse as before
+        let hello = radio::Hello { session_id: 0x1234 };
+        let mut rt = RoundTrip::new();
+        let parsed: Option<Ext> = rt.ser(&hello).de();
+        assert_eq!(parsed, Some(Extended::Builtin(HostMessage::Hello(hello))));
+
+        // but the extension is unknown without Extended
+        let mut rt = RoundTrip::new();
+        let plain: Option<HostMessage<&[u8]>> = rt.ser(&Ping(42)).de();
+        assert_eq!(plain, None);
+    }
+
+    #[test]
+    fn names_and_directions() {
+        let data: &[u8] = &[];
+        let host: [HostMessage<&[u8]>; 8] = [
+            HostMessage::Hello(radio::Hello { session_id: 0 }),
+            HostMessage::WriteFlash(bootloader::WriteFlash {
+                session_id: 0,
+                page: 0,
+                max_page: 1,
+                len: 0,
+                _pad: Default::default(),
+                data,
+            }),
+            HostMessage::ReadEeprom(radio::ReadEeprom {
+                address: 0,
+                len: 0,
+                _pad: Default::default(),
+                session_id: 0,
+            }),
+            HostMessage::WriteEeprom(radio::WriteEeprom {
+                address: 0,
+                len: 0,
+                allow_password: false,
+                session_id: 0,
+                data,
+            }),
+            HostMessage::WriteChallenge(radio::WriteChallenge { response: [0; 4] }),
+            HostMessage::BootloaderReadyReply(bootloader::BootloaderReadyReply {
+                version: Default::default(),
+            }),
+            HostMessage::DebugInput(custom::DebugInput { line: data }),
+            HostMessage::ReadEepromExt(custom::ReadEepromExt {
+                address: 0,
+                len: 0,
+                session_id: 0,
+            }),
+        ];
+        let radio: [RadioMessage<&[u8]>; 8] = [
+            RadioMessage::HelloReply(radio::HelloReply {
+                version: Default::default(),
+                has_custom_aes_key: false,
+                is_in_lock_screen: false,
+                _pad: Default::default(),
+                challenge: [0; 4],
+            }),
+            RadioMessage::BootloaderReady(bootloader::BootloaderReady {
+                chip_id: bootloader::ChipId::Long([0; 4]),
+                version: Default::default(),
+            }),
+            RadioMessage::WriteFlashReply(bootloader::WriteFlashReply {
+                session_id: 0,
+                page: 0,
+                error: 0,
+            }),
+            RadioMessage::ReadEepromReply(radio::ReadEepromReply {
+                address: 0,
+                len: 0,
+                _pad: Default::default(),
+                data,
+            }),
+            RadioMessage::WriteEepromReply(radio::WriteEepromReply { address: 0 }),
+            RadioMessage::ChallengeReply(radio::ChallengeReply {
+                is_locked: false,
+                _pad: Default::default(),
+            }),
+            RadioMessage::DebugOutput(custom::DebugOutput { defmt: false, data }),
+            RadioMessage::ReadEepromExtReply(custom::ReadEepromExtReply {
+                address: 0,
+                len: 0,
+                data,
+            }),
+        ];
+
+        let host_names = [
+            (0x0514, "Hello"),
+            (0x0519, "Write Flash"),
+            (0x051b, "Read EEPROM"),
+            (0x051d, "Write EEPROM"),
+            (0x052d, "Write Challenge"),
+            (0x0530, "Bootloader Ready Reply"),
+            (0x8500, "Debug Input"),
+            (0x8502, "Read EEPROM (extended)"),
+        ];
+        for (msg, (typ, name)) in host.into_iter().zip(host_names) {
+            let msg = Message::Host(msg);
+            assert_eq!(msg.direction(), Direction::HostToRadio);
+            assert_eq!(msg.name(), name);
+            assert_eq!(msg.message_type(), typ);
+            assert_eq!(MessageSerialize::message_type(&msg), typ);
+        }
+
+        let radio_names = [
+            (0x0515, "Hello Reply"),
+            (0x0518, "Bootloader Ready"),
+            (0x051a, "Write Flash Reply"),
+            (0x051c, "Read EEPROM Reply"),
+            (0x051e, "Write EEPROM Reply"),
+            (0x052e, "Challenge Reply"),
+            (0x8501, "Debug Output"),
+            (0x8503, "Read EEPROM Reply (extended)"),
+        ];
+        for (msg, (typ, name)) in radio.into_iter().zip(radio_names) {
+            let msg = Message::Radio(msg);
+            assert_eq!(msg.direction(), Direction::RadioToHost);
+            assert_eq!(msg.name(), name);
+            assert_eq!(msg.message_type(), typ);
+            assert_eq!(MessageSerialize::message_type(&msg), typ);
+        }
+    }
 }
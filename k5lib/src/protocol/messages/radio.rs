@@ -13,6 +13,7 @@ pub const HELLO_SESSION_ID: u32 = 0x6457396a;
 /// 0x0514 Hello, host message.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Hello {
     /// Session ID on all host messages. All further messages must use
     /// this same ID or they will be ignored.
@@ -62,6 +63,7 @@ where
 /// 0x0515 HelloReply, radio message.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HelloReply {
     /// Version provided by the radio.
     /// Assume UTF-8, or at least, ASCII, padded by zeros.
@@ -144,6 +146,7 @@ where
 /// 0x051b Read EEPROM, host message.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReadEeprom {
     /// Address to read.
     pub address: u16,
@@ -207,6 +210,11 @@ where
 /// 0x051c Read Eeprom Reply, radio message.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "I: AsRef<[u8]>", deserialize = "I: FromIterator<u8>"))
+)]
 pub struct ReadEepromReply<I> {
     /// Address of data read.
     pub address: u16,
@@ -215,6 +223,7 @@ pub struct ReadEepromReply<I> {
     /// Alignment padding.
     pub _pad: util::Padding<1>,
     /// Data read from EEPROM.
+    #[cfg_attr(feature = "serde", serde(with = "util::hex"))]
     pub data: I,
 }
 
@@ -311,6 +320,273 @@ where
     }
 }
 
+/// 0x051d Write EEPROM, host message.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "I: AsRef<[u8]>", deserialize = "I: FromIterator<u8>"))
+)]
+pub struct WriteEeprom<I> {
+    /// Address to write.
+    pub address: u16,
+    /// Number of bytes to write to address. The stock firmware
+    /// writes in blocks of 8 bytes, so this should be a multiple of 8.
+    pub len: u8,
+    /// Allow writing to the password area of the EEPROM.
+    pub allow_password: bool,
+    /// Session ID, must match the one provided by initial [Hello].
+    pub session_id: u32,
+    /// Data to write to EEPROM.
+    #[cfg_attr(feature = "serde", serde(with = "util::hex"))]
+    pub data: I,
+}
+
+impl<I> MessageType for WriteEeprom<I> {
+    const TYPE: u16 = 0x051d;
+}
+
+impl<I> WriteEeprom<I> {
+    pub fn map<F, J>(self, f: F) -> WriteEeprom<J>
+    where
+        F: FnOnce(I) -> J,
+    {
+        WriteEeprom {
+            address: self.address,
+            len: self.len,
+            allow_password: self.allow_password,
+            session_id: self.session_id,
+            data: f(self.data),
+        }
+    }
+
+    pub fn map_ref<'a, F, J>(&'a self, f: F) -> WriteEeprom<J>
+    where
+        F: FnOnce(&'a I) -> J,
+    {
+        WriteEeprom {
+            address: self.address,
+            len: self.len,
+            allow_password: self.allow_password,
+            session_id: self.session_id,
+            data: f(&self.data),
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn to_owned(&self) -> WriteEeprom<I::Owned>
+    where
+        I: alloc::borrow::ToOwned,
+    {
+        self.map_ref(I::to_owned)
+    }
+
+    pub fn borrow<Borrowed: ?Sized>(&self) -> WriteEeprom<&Borrowed>
+    where
+        I: core::borrow::Borrow<Borrowed>,
+    {
+        self.map_ref(I::borrow)
+    }
+}
+
+impl<I> MessageSerialize for WriteEeprom<I>
+where
+    I: Parse,
+{
+    fn message_type(&self) -> u16 {
+        Self::TYPE
+    }
+
+    fn message_body<S>(&self, ser: &mut S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        ser.write_le_u16(self.address)?;
+        ser.write_u8(self.len)?;
+        ser.write_u8(self.allow_password as u8)?;
+        ser.write_le_u32(self.session_id)?;
+        ser.write_slice(&self.data)
+    }
+}
+
+impl<I> MessageParse<I> for WriteEeprom<I>
+where
+    I: Parse,
+{
+    fn parse_body(typ: u16) -> impl Parser<I, Self, Error<I>> {
+        move |input| {
+            let input = if typ != Self::TYPE {
+                nom::combinator::fail::<_, (), _>(input)?.0
+            } else {
+                input
+            };
+
+            let (input, address) = nom::number::complete::le_u16(input)?;
+            let (input, len) = nom::number::complete::u8(input)?;
+
+            let (input, allow_password) = nom::number::complete::u8(input)?;
+            let allow_password = allow_password > 0;
+
+            let (input, session_id) = nom::number::complete::le_u32(input)?;
+            let (input, data) = nom::bytes::complete::take(len as usize)(input)?;
+            Ok((
+                input,
+                WriteEeprom {
+                    address,
+                    len,
+                    allow_password,
+                    session_id,
+                    data,
+                },
+            ))
+        }
+    }
+}
+
+/// 0x051e Write EEPROM Reply, radio message.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WriteEepromReply {
+    /// Address of data written.
+    pub address: u16,
+}
+
+impl MessageType for WriteEepromReply {
+    const TYPE: u16 = 0x051e;
+}
+
+impl MessageSerialize for WriteEepromReply {
+    fn message_type(&self) -> u16 {
+        Self::TYPE
+    }
+
+    fn message_body<S>(&self, ser: &mut S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        ser.write_le_u16(self.address)
+    }
+}
+
+impl<I> MessageParse<I> for WriteEepromReply
+where
+    I: Parse,
+{
+    fn parse_body(typ: u16) -> impl Parser<I, Self, Error<I>> {
+        move |input| {
+            let input = if typ != Self::TYPE {
+                nom::combinator::fail::<_, (), _>(input)?.0
+            } else {
+                input
+            };
+
+            let (input, address) = nom::number::complete::le_u16(input)?;
+            Ok((input, WriteEepromReply { address }))
+        }
+    }
+}
+
+/// 0x052d Write Challenge, host message.
+///
+/// This answers the AES challenge provided in [HelloReply].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WriteChallenge {
+    /// Response to the challenge in [HelloReply::challenge].
+    pub response: [u32; 4],
+}
+
+impl MessageType for WriteChallenge {
+    const TYPE: u16 = 0x052d;
+}
+
+impl MessageSerialize for WriteChallenge {
+    fn message_type(&self) -> u16 {
+        Self::TYPE
+    }
+
+    fn message_body<S>(&self, ser: &mut S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        for r in self.response.iter() {
+            ser.write_le_u32(*r)?;
+        }
+        Ok(())
+    }
+}
+
+impl<I> MessageParse<I> for WriteChallenge
+where
+    I: Parse,
+{
+    fn parse_body(typ: u16) -> impl Parser<I, Self, Error<I>> {
+        move |input| {
+            let input = if typ != Self::TYPE {
+                nom::combinator::fail::<_, (), _>(input)?.0
+            } else {
+                input
+            };
+
+            let (input, response) = util::parse_array(nom::number::complete::le_u32)(input)?;
+            Ok((input, WriteChallenge { response }))
+        }
+    }
+}
+
+/// 0x052e Challenge Reply, radio message.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChallengeReply {
+    /// Radio is still locked, the response was not accepted.
+    pub is_locked: bool,
+    /// Alignment padding.
+    pub _pad: util::Padding<3>,
+}
+
+impl MessageType for ChallengeReply {
+    const TYPE: u16 = 0x052e;
+}
+
+impl MessageSerialize for ChallengeReply {
+    fn message_type(&self) -> u16 {
+        Self::TYPE
+    }
+
+    fn message_body<S>(&self, ser: &mut S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        ser.write_u8(self.is_locked as u8)?;
+        self._pad.serialize(ser)
+    }
+}
+
+impl<I> MessageParse<I> for ChallengeReply
+where
+    I: Parse,
+{
+    fn parse_body(typ: u16) -> impl Parser<I, Self, Error<I>> {
+        move |input| {
+            let input = if typ != Self::TYPE {
+                nom::combinator::fail::<_, (), _>(input)?.0
+            } else {
+                input
+            };
+
+            let (input, is_locked) = nom::number::complete::u8(input)?;
+            let is_locked = is_locked > 0;
+
+            let (input, _pad) = util::Padding::parse(input)?;
+            Ok((input, ChallengeReply { is_locked, _pad }))
+        }
+    }
+}
+
 #[cfg(test)]
 #[cfg(feature = "alloc")]
 mod test {
@@ -390,4 +666,99 @@ mod test {
     fn roundtrip_read_eeprom_reply(msg: ReadEepromReply<Vec<u8>>) -> bool {
         RoundTrip::new().run(&msg.borrow())
     }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_read_eeprom_reply() {
+        let msg = ReadEepromReply {
+            address: 0x0e70,
+            len: 4,
+            _pad: util::Padding::new(),
+            data: alloc::vec![0xde, 0xad, 0xbe, 0xef],
+        };
+
+        let json = serde_json::to_string(&msg).unwrap();
+        assert_eq!(
+            json,
+            r#"{"address":3696,"len":4,"_pad":"00","data":"deadbeef"}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ReadEepromReply<Vec<u8>>>(&json).unwrap(),
+            msg
+        );
+
+        // hex must be whole bytes, and padding must be the right size
+        assert!(serde_json::from_str::<ReadEepromReply<Vec<u8>>>(
+            r#"{"address":0,"len":0,"_pad":"00","data":"abc"}"#
+        )
+        .is_err());
+        assert!(serde_json::from_str::<ReadEepromReply<Vec<u8>>>(
+            r#"{"address":0,"len":0,"_pad":"0000","data":""}"#
+        )
+        .is_err());
+    }
+
+    impl Arbitrary for WriteEeprom<Vec<u8>> {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let mut data = Vec::<u8>::arbitrary(g);
+            data.truncate(0xff);
+            Self {
+                address: u16::arbitrary(g),
+                len: data.len() as u8,
+                allow_password: bool::arbitrary(g),
+                session_id: u32::arbitrary(g),
+                data,
+            }
+        }
+    }
+
+    #[quickcheck]
+    fn roundtrip_write_eeprom(msg: WriteEeprom<Vec<u8>>) -> bool {
+        RoundTrip::new().run(&msg.borrow())
+    }
+
+    impl Arbitrary for WriteEepromReply {
+        fn arbitrary(g: &mut Gen) -> Self {
+            Self {
+                address: u16::arbitrary(g),
+            }
+        }
+    }
+
+    #[quickcheck]
+    fn roundtrip_write_eeprom_reply(msg: WriteEepromReply) -> bool {
+        roundtrip(msg)
+    }
+
+    impl Arbitrary for WriteChallenge {
+        fn arbitrary(g: &mut Gen) -> Self {
+            Self {
+                response: [
+                    u32::arbitrary(g),
+                    u32::arbitrary(g),
+                    u32::arbitrary(g),
+                    u32::arbitrary(g),
+                ],
+            }
+        }
+    }
+
+    #[quickcheck]
+    fn roundtrip_write_challenge(msg: WriteChallenge) -> bool {
+        roundtrip(msg)
+    }
+
+    impl Arbitrary for ChallengeReply {
+        fn arbitrary(g: &mut Gen) -> Self {
+            Self {
+                is_locked: bool::arbitrary(g),
+                _pad: util::Padding::arbitrary(g),
+            }
+        }
+    }
+
+    #[quickcheck]
+    fn roundtrip_challenge_reply(msg: ChallengeReply) -> bool {
+        roundtrip(msg)
+    }
 }
@@ -92,23 +92,111 @@ impl<const LEN: usize> defmt::Format for Padding<LEN> {
     }
 }
 
+#[cfg(feature = "serde")]
+impl<const LEN: usize> serde::Serialize for Padding<LEN> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        hex::serialize(&self.0, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const LEN: usize> serde::Deserialize<'de> for Padding<LEN> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        // collect into an array, or None if the length is wrong
+        struct Fill<const LEN: usize>(Option<[u8; LEN]>);
+
+        impl<const LEN: usize> FromIterator<u8> for Fill<LEN> {
+            fn from_iter<T: IntoIterator<Item = u8>>(iter: T) -> Self {
+                let mut data = [0; LEN];
+                let mut len = 0;
+                for b in iter {
+                    if len >= LEN {
+                        return Self(None);
+                    }
+                    data[len] = b;
+                    len += 1;
+                }
+                Self((len == LEN).then_some(data))
+            }
+        }
+
+        let Fill(data) = hex::deserialize(deserializer)?;
+        data.map(Self)
+            .ok_or_else(|| serde::de::Error::custom(format_args!("expected {} bytes", LEN)))
+    }
+}
+
+/// Serialize byte data as a hex string, for use with `#[serde(with)]`.
+///
+/// This serializes anything that is `AsRef<[u8]>`, and deserializes
+/// into anything that is `FromIterator<u8>`, like `Vec<u8>`. Borrowed
+/// slices can only be serialized.
+#[cfg(feature = "serde")]
+pub mod hex {
+    use crate::encode::HexSlice;
+
+    pub fn serialize<S, I>(data: &I, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+        I: AsRef<[u8]> + ?Sized,
+    {
+        serializer.collect_str(&HexSlice(data.as_ref()))
+    }
+
+    pub fn deserialize<'de, D, I>(deserializer: D) -> Result<I, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+        I: FromIterator<u8>,
+    {
+        deserializer.deserialize_str(HexVisitor(core::marker::PhantomData))
+    }
+
+    struct HexVisitor<I>(core::marker::PhantomData<I>);
+
+    impl<'de, I> serde::de::Visitor<'de> for HexVisitor<I>
+    where
+        I: FromIterator<u8>,
+    {
+        type Value = I;
+
+        fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            write!(f, "a hex string")
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            if !v.len().is_multiple_of(2) {
+                return Err(E::invalid_length(v.len(), &self));
+            }
+
+            v.as_bytes()
+                .chunks(2)
+                .map(|pair| {
+                    core::str::from_utf8(pair)
+                        .ok()
+                        .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                        .ok_or_else(|| E::invalid_value(serde::de::Unexpected::Str(v), &self))
+                })
+                .collect()
+        }
+    }
+}
+
 #[cfg(test)]
 #[cfg(feature = "alloc")]
 mod test {
-    use alloc::vec::Vec;
-
     use quickcheck::{Arbitrary, Gen};
 
     use super::*;
 
-    impl Arbitrary for crate::Version {
-        fn arbitrary(g: &mut Gen) -> Self {
-            let mut version = Vec::<u8>::arbitrary(g);
-            version.truncate(crate::VERSION_LEN - 1); // null terminator
-            crate::Version::new_from_bytes(&version).unwrap()
-        }
-    }
-
     impl<const LEN: usize> Arbitrary for Padding<LEN> {
         fn arbitrary(g: &mut Gen) -> Self {
             let mut data = [0; LEN];
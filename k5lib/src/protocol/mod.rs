@@ -23,7 +23,7 @@ pub mod parse;
 pub use parse::{MessageParse, Parse, ParseMut, ParseResult};
 
 pub mod messages;
-pub use messages::{HostMessage, Message, MessageType, RadioMessage};
+pub use messages::{Extended, HostMessage, Message, MessageType, RadioMessage};
 
 pub mod serialize;
 pub use serialize::{MessageSerialize, Serializer};
@@ -41,6 +41,15 @@ where
     parse::find_frame(input)
 }
 
+/// Find a frame, like [find_frame()], but accepting frames up to
+/// `max_size` bytes instead of [MAX_FRAME_SIZE].
+pub fn find_frame_with<I>(input: I, max_size: usize) -> (usize, Option<parse::FoundFrame>)
+where
+    I: ParseMut,
+{
+    parse::find_frame_with(input, max_size)
+}
+
 /// Parse an entire frame containing a message, checking the CRC. If
 /// the frame doesn't parse as this message, or the CRC fails, it will
 /// return that error.
@@ -55,6 +64,19 @@ where
     M::parse_frame(&crc, input, found)
 }
 
+/// Parse every frame in a buffer, in order, deobfuscating in place.
+///
+/// This repeatedly calls [find_frame()] and [parse()] until the
+/// buffer is used up. CRC and parse errors are returned as items, and
+/// do not stop the iteration. See [parse::parse_all()].
+pub fn parse_all<'a, C, M>(crc: C, input: &'a mut [u8]) -> parse::ParseAll<'a, C, M>
+where
+    C: crc::CrcStyle,
+    M: MessageParse<&'a [u8]>,
+{
+    parse::parse_all(crc, input)
+}
+
 /// Serialize a message into a full frame, with obfuscation, CRC, and
 /// start/end markers.
 pub fn serialize<C, S, M>(crc: &C, serializer: &mut S, message: &M) -> Result<(), S::Error>
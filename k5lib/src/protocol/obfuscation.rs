@@ -1,7 +1,39 @@
-pub const OBFUSCATION: [u8; 16] = [
+/// Repeating xor key used to obfuscate frames on the serial protocol.
+///
+/// Not to be confused with [crate::firmware::OBFUSCATION], which is
+/// used on packed firmware images.
+pub const OBFUSCATION_KEY: [u8; 16] = [
     0x16, 0x6c, 0x14, 0xe6, 0x2e, 0x91, 0x0d, 0x40, 0x21, 0x35, 0xd5, 0x40, 0x13, 0x03, 0xe9, 0x80,
 ];
 
+/// The old name for [OBFUSCATION_KEY].
+#[deprecated = "renamed to OBFUSCATION_KEY"]
+pub const OBFUSCATION: [u8; 16] = OBFUSCATION_KEY;
+
+/// Xor `data` with a repeating `key`, as if `data` started `offset`
+/// bytes into the stream.
+///
+/// Xor is its own inverse, so this both obfuscates and deobfuscates.
+/// Panics if `key` is empty.
+pub fn xor_repeating(key: &[u8], data: &mut [u8], offset: usize) {
+    let mut i = offset % key.len();
+    for x in data.iter_mut() {
+        *x ^= key[i];
+        i += 1;
+        if i >= key.len() {
+            i = 0;
+        }
+    }
+}
+
+/// Obfuscate (or deobfuscate) `data` with [OBFUSCATION_KEY], as if it
+/// started `offset` bytes into a frame body.
+///
+/// This is the stateless version of [Key] and [Obfuscator].
+pub fn xor_block(data: &mut [u8], offset: usize) {
+    xor_repeating(&OBFUSCATION_KEY, data, offset)
+}
+
 /// Infinite deobfuscation key iterator.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -15,9 +47,9 @@ impl Key {
     }
 
     pub fn get(&mut self) -> u8 {
-        let v = OBFUSCATION[self.index];
+        let v = OBFUSCATION_KEY[self.index];
         self.index += 1;
-        if self.index >= OBFUSCATION.len() {
+        if self.index >= OBFUSCATION_KEY.len() {
             self.index = 0;
         }
         v
@@ -28,7 +60,7 @@ impl Key {
     }
 
     pub fn advance(&mut self, num: usize) {
-        self.index = (self.index + num) % OBFUSCATION.len();
+        self.index = (self.index + num) % OBFUSCATION_KEY.len();
     }
 }
 
@@ -50,3 +82,123 @@ impl Iterator for Key {
 }
 
 impl core::iter::FusedIterator for Key {}
+
+/// Obfuscate (or deobfuscate) a whole buffer in one go.
+pub fn obfuscate(buf: &mut [u8]) {
+    Obfuscator::new().apply_to(buf)
+}
+
+/// Streaming obfuscator, for data that arrives in several chunks.
+///
+/// Obfuscation is a plain xor, so this also deobfuscates.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Obfuscator {
+    key: Key,
+}
+
+impl Obfuscator {
+    pub fn new() -> Self {
+        Self { key: Key::new() }
+    }
+
+    /// Obfuscate the next chunk of data in place.
+    pub fn apply_to(&mut self, buf: &mut [u8]) {
+        xor_block(buf, self.key.index);
+        self.key.advance(buf.len());
+    }
+
+    /// Obfuscate the next chunk of data as it is iterated.
+    pub fn obfuscate_iter<I>(&mut self, iter: I) -> ObfuscateIter<'_, I::IntoIter>
+    where
+        I: IntoIterator<Item = u8>,
+    {
+        ObfuscateIter {
+            key: &mut self.key,
+            inner: iter.into_iter(),
+        }
+    }
+}
+
+/// Iterator adapter returned by [Obfuscator::obfuscate_iter()].
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ObfuscateIter<'a, I> {
+    key: &'a mut Key,
+    inner: I,
+}
+
+impl<'a, I> Iterator for ObfuscateIter<'a, I>
+where
+    I: Iterator<Item = u8>,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|b| self.key.apply(b))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "alloc")]
+mod test {
+    use alloc::vec::Vec;
+
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    #[test]
+    fn xor_block_matches_key() {
+        let data: Vec<u8> = (0..OBFUSCATION_KEY.len() * 3 + 5)
+            .map(|i| i as u8)
+            .collect();
+
+        for offset in [0, 7, OBFUSCATION_KEY.len() + 3] {
+            let mut key = Key::new();
+            key.advance(offset);
+            let expected: Vec<u8> = data.iter().map(|b| key.apply(*b)).collect();
+
+            let mut block = data.clone();
+            xor_block(&mut block, offset);
+            assert_eq!(block, expected, "offset {}", offset);
+
+            // and back again
+            xor_block(&mut block, offset);
+            assert_eq!(block, data);
+        }
+    }
+
+    #[quickcheck]
+    fn obfuscate_chunked(data: Vec<u8>, split: usize) -> bool {
+        let split = if data.is_empty() {
+            0
+        } else {
+            split % data.len()
+        };
+
+        let mut obfuscated = data.clone();
+        let (a, b) = obfuscated.split_at_mut(split);
+        let mut obfuscator = Obfuscator::new();
+        obfuscator.apply_to(a);
+        obfuscator.apply_to(b);
+
+        let mut single = data.clone();
+        obfuscate(&mut single);
+        if single != obfuscated {
+            return false;
+        }
+
+        let mut deobfuscator = Obfuscator::new();
+        let mut deobfuscated: Vec<u8> = deobfuscator
+            .obfuscate_iter(obfuscated[..split].iter().copied())
+            .collect();
+        deobfuscated.extend(deobfuscator.obfuscate_iter(obfuscated[split..].iter().copied()));
+
+        deobfuscated == data
+    }
+}
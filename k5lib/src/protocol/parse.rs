@@ -1,3 +1,4 @@
+use core::marker::PhantomData;
 use core::ops::Range;
 use nom::error::Error;
 use nom::IResult;
@@ -165,6 +166,34 @@ impl<'a> Matcher<'a> {
     }
 }
 
+/// Parse a little-endian u16, written by [Serializer::write_le_u16()].
+///
+/// [Serializer::write_le_u16()]: super::serialize::Serializer::write_le_u16()
+pub fn le_u16<I: Parse>(input: I) -> IResult<I, u16> {
+    nom::number::complete::le_u16(input)
+}
+
+/// Parse a little-endian u32, written by [Serializer::write_le_u32()].
+///
+/// [Serializer::write_le_u32()]: super::serialize::Serializer::write_le_u32()
+pub fn le_u32<I: Parse>(input: I) -> IResult<I, u32> {
+    nom::number::complete::le_u32(input)
+}
+
+/// Parse a big-endian u16, written by [Serializer::write_be_u16()].
+///
+/// [Serializer::write_be_u16()]: super::serialize::Serializer::write_be_u16()
+pub fn be_u16<I: Parse>(input: I) -> IResult<I, u16> {
+    nom::number::complete::be_u16(input)
+}
+
+/// Parse a big-endian u32, written by [Serializer::write_be_u32()].
+///
+/// [Serializer::write_be_u32()]: super::serialize::Serializer::write_be_u32()
+pub fn be_u32<I: Parse>(input: I) -> IResult<I, u32> {
+    nom::number::complete::be_u32(input)
+}
+
 /// Helper to grab a le u16 out of an enumerated byte iterator.
 fn read_le_u16(iter: &mut impl Iterator<Item = (usize, u8)>) -> Option<u16> {
     Some((iter.next()?.1 as u16) | ((iter.next()?.1 as u16) << 8))
@@ -185,94 +214,217 @@ pub struct FoundFrame {
 ///
 /// If a frame is found, return the range for the full frame, and a
 /// range for the deobfuscated contents.
+///
+/// Frames larger than [MAX_FRAME_SIZE] are treated as false
+/// frames. To use a different limit, see [find_frame_with()].
+///
+/// This scans the whole input every time. To scan data as it
+/// arrives, use a [FrameScanner].
 #[allow(clippy::type_complexity)]
 pub fn find_frame<I>(input: I) -> (usize, Option<FoundFrame>)
 where
     I: ParseMut,
 {
-    let mut bytes = input.iter().enumerate();
-
-    // loop until we stop advancing or find a frame
-    loop {
-        // search for the FRAME_START
-        let start = match Matcher::new(&FRAME_START).search(&mut bytes) {
-            MatchResult::Matched(range) => range,
-            MatchResult::NotMatched => {
-                // there is no FRAME_START, anywhere
-                return (input.len(), None);
-            }
-            MatchResult::Incomplete(i) => {
-                // there might be a FRAME_START here later
-                return (i, None);
-            }
-        };
+    FrameScanner::new().scan(input)
+}
 
-        // now there is a little-endian u16 length
-        let Some(length) = read_le_u16(&mut bytes) else {
-            // not enough data yet, consume up to FRAME_START
-            return (start.start, None);
-        };
+/// Find a frame, and deobfuscate the contents, treating frames larger
+/// than `max_size` bytes as false frames. See [find_frame()].
+#[allow(clippy::type_complexity)]
+pub fn find_frame_with<I>(input: I, max_size: usize) -> (usize, Option<FoundFrame>)
+where
+    I: ParseMut,
+{
+    FrameScanner::with_max_size(max_size).scan(input)
+}
 
-        // make sure our length makes sense
-        // FRAME_START + u16 len + body + u16 crc + FRAME_END
-        if length as usize > MAX_FRAME_SIZE - FRAME_START.len() - FRAME_END.len() - 2 - 2 {
-            // this is too big, so this is a false frame
-            // Skip 1 past FRAME_START and try again.
-            bytes = input.iter().enumerate();
-            bytes.nth(start.start + 1);
-            continue;
-        }
+/// An incremental version of [find_frame()], for input that arrives a
+/// little at a time.
+///
+/// The scanner remembers how far it got between calls to
+/// [Self::scan()], so each byte is only searched once. In exchange,
+/// the input given to each call must be the input from the last call,
+/// with the consumed bytes removed from the front and any new bytes
+/// added to the end. If the input changes any other way, call
+/// [Self::reset()] first.
+///
+/// For the same input, [Self::scan()] returns exactly what
+/// [find_frame()] would.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FrameScanner {
+    /// Where to continue searching for FRAME_START.
+    pos: usize,
+    /// Partial FRAME_START search state.
+    matcher: Matcher<'static>,
+    /// Start of a found FRAME_START, waiting for the rest of the frame.
+    start: Option<usize>,
+    /// Largest full frame to accept.
+    max_size: usize,
+}
 
-        // keep track of where we are now
-        let body_start = start.end + 2;
+impl Default for FrameScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameScanner {
+    /// Create a new scanner, with no input seen yet.
+    pub fn new() -> Self {
+        Self::with_max_size(MAX_FRAME_SIZE)
+    }
 
-        // now there is length bytes, then a 2-byte crc (which we skip for now)
-        if bytes.nth(length as usize).is_none() || bytes.next().is_none() {
-            // not enough data yet, consume up to FRAME_START
-            return (start.start, None);
+    /// Create a new scanner, accepting frames up to `max_size` bytes
+    /// instead of [MAX_FRAME_SIZE].
+    pub fn with_max_size(max_size: usize) -> Self {
+        Self {
+            pos: 0,
+            matcher: Matcher::new(&FRAME_START),
+            start: None,
+            max_size,
         }
+    }
+
+    /// Largest full frame this scanner accepts, in bytes.
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Change the largest full frame this scanner accepts.
+    ///
+    /// This applies to frames not yet found, including one that has
+    /// started arriving.
+    pub fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+    }
 
-        // ok, where are we now
-        let crc_end = body_start + length as usize + 2;
+    /// Forget all previously scanned input.
+    pub fn reset(&mut self) {
+        *self = Self::with_max_size(self.max_size);
+    }
 
-        // search for FRAME_END
-        let end = match Matcher::new(&FRAME_END).match_(crc_end, &mut bytes) {
-            MatchResult::Matched(range) => range,
-            MatchResult::NotMatched => {
-                // FRAME_END should be here but is not. This is a false start.
+    /// Find a frame, and deobfuscate the contents. See [find_frame()].
+    #[allow(clippy::type_complexity)]
+    pub fn scan<I>(&mut self, input: I) -> (usize, Option<FoundFrame>)
+    where
+        I: ParseMut,
+    {
+        // loop until we stop advancing or find a frame
+        loop {
+            let start = match self.start {
+                Some(start) => start,
+                None => {
+                    // search for the FRAME_START, starting where we left off
+                    let mut bytes = input.iter().enumerate().skip(self.pos);
+                    let result = self.matcher.search(&mut bytes);
+                    self.pos = input.len();
+                    match result {
+                        MatchResult::Matched(range) => {
+                            self.matcher = Matcher::new(&FRAME_START);
+                            self.start = Some(range.start);
+                            range.start
+                        }
+                        MatchResult::NotMatched => {
+                            // there is no FRAME_START, anywhere
+                            return self.consume(input.len());
+                        }
+                        MatchResult::Incomplete(i) => {
+                            // there might be a FRAME_START here later
+                            return self.consume(i);
+                        }
+                    }
+                }
+            };
+
+            let mut bytes = input.iter().enumerate().skip(start + FRAME_START.len());
+
+            // now there is a little-endian u16 length
+            let Some(length) = read_le_u16(&mut bytes) else {
+                // not enough data yet, consume up to FRAME_START
+                return self.consume(start);
+            };
+
+            // make sure our length makes sense
+            // FRAME_START + u16 len + body + u16 crc + FRAME_END
+            let fits = self
+                .max_size
+                .checked_sub(FRAME_START.len() + FRAME_END.len() + 2 + 2)
+                .is_some_and(|max_length| length as usize <= max_length);
+            if !fits {
+                // this is too big, so this is a false frame
                 // Skip 1 past FRAME_START and try again.
-                bytes = input.iter().enumerate();
-                bytes.nth(start.start + 1);
+                self.restart(start);
                 continue;
             }
-            MatchResult::Incomplete(_) => {
+
+            // keep track of where we are now
+            let body_start = start + FRAME_START.len() + 2;
+
+            // now there is length bytes, then a 2-byte crc (which we skip for now)
+            if bytes.nth(length as usize).is_none() || bytes.next().is_none() {
                 // not enough data yet, consume up to FRAME_START
-                return (start.start, None);
+                return self.consume(start);
             }
-        };
 
-        // it would be neat to be able to do the CRC and parse, and then
-        // if that fails, skip 1 past FRAME_START like above.
-        // however, these steps need deobfuscated data
-        // and lifetimes make it hard to re-obfuscate on failure
-
-        // so, best effort:
-        // there are about 6 bytes all with exactly the values they need.
-        // this looks like a frame. it's a frame.
-
-        // we have a frame from start.start to end.end
-        // the body + crc is inside body_start to crc_end
-        drop(bytes);
-        let body_range = body_start..crc_end;
-        let mut frame_body = input.slice(body_range.clone());
-        frame_body.deobfuscate(&mut Key::new());
-        return (
-            end.end,
-            Some(FoundFrame {
-                full_frame: start.start..end.end,
-                frame_contents: body_range,
-            }),
-        );
+            // ok, where are we now
+            let crc_end = body_start + length as usize + 2;
+
+            // search for FRAME_END
+            let end = match Matcher::new(&FRAME_END).match_(crc_end, &mut bytes) {
+                MatchResult::Matched(range) => range,
+                MatchResult::NotMatched => {
+                    // FRAME_END should be here but is not. This is a false start.
+                    // Skip 1 past FRAME_START and try again.
+                    self.restart(start);
+                    continue;
+                }
+                MatchResult::Incomplete(_) => {
+                    // not enough data yet, consume up to FRAME_START
+                    return self.consume(start);
+                }
+            };
+
+            // it would be neat to be able to do the CRC and parse, and then
+            // if that fails, skip 1 past FRAME_START like above.
+            // however, these steps need deobfuscated data
+            // and lifetimes make it hard to re-obfuscate on failure
+
+            // so, best effort:
+            // there are about 6 bytes all with exactly the values they need.
+            // this looks like a frame. it's a frame.
+
+            // we have a frame from start to end.end
+            // the body + crc is inside body_start to crc_end
+            drop(bytes);
+            self.reset();
+            let body_range = body_start..crc_end;
+            let mut frame_body = input.slice(body_range.clone());
+            frame_body.deobfuscate(&mut Key::new());
+            return (
+                end.end,
+                Some(FoundFrame {
+                    full_frame: start..end.end,
+                    frame_contents: body_range,
+                }),
+            );
+        }
+    }
+
+    /// Go back to searching for FRAME_START, just past a false start.
+    fn restart(&mut self, start: usize) {
+        self.pos = start + FRAME_START.len();
+        self.matcher = Matcher::new(&FRAME_START);
+        self.start = None;
+    }
+
+    /// Shift our state to account for the caller removing `amount`
+    /// bytes from the front of the input.
+    fn consume(&mut self, amount: usize) -> (usize, Option<FoundFrame>) {
+        self.pos -= amount;
+        self.matcher.start = self.matcher.start.map(|s| s - amount);
+        self.start = self.start.map(|s| s - amount);
+        (amount, None)
     }
 }
 
@@ -333,18 +485,85 @@ impl<I, O, E> ParseResult<I, O, E> {
             Self::None => ParseResult::None,
         }
     }
+
+    /// Move the frame range later by `amount`.
+    fn offset(self, amount: usize) -> Self {
+        let shift = |r: Range<usize>| r.start + amount..r.end + amount;
+        match self {
+            Self::Ok(r, o) => Self::Ok(shift(r), o),
+            Self::ParseErr(r, frame, err) => Self::ParseErr(shift(r), frame, err),
+            Self::CrcErr(r, frame) => Self::CrcErr(shift(r), frame),
+            Self::None => Self::None,
+        }
+    }
+}
+
+impl<I, O, E> ParseResult<I, O, E>
+where
+    I: Parse,
+{
+    /// For [Self::CrcErr], recompute the calculated and provided CRC
+    /// from the frame body. Returns [None] for any other result.
+    pub fn crc_error<C>(&self, crc: C) -> Option<CrcError>
+    where
+        C: CrcStyle,
+    {
+        match self {
+            Self::CrcErr(_, body) => check_crc_detailed(crc, body.clone()).err(),
+            _ => None,
+        }
+    }
+}
+
+/// Why a CRC check failed, from [check_crc_detailed()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CrcError {
+    /// The frame body is too short to hold a CRC.
+    Missing,
+    /// The CRC at the end of the frame body was wrong.
+    Mismatch { calculated: u16, provided: u16 },
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CrcError {}
+
+impl core::fmt::Display for CrcError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Missing => write!(f, "frame too short for a CRC"),
+            Self::Mismatch {
+                calculated,
+                provided,
+            } => write!(
+                f,
+                "bad CRC: expected 0x{:04x}, got 0x{:04x}",
+                calculated, provided
+            ),
+        }
+    }
 }
 
 /// Check the little-endian u16 CRC at the end of a frame body.
 ///
 /// Return the body (without CRC) on success.
 pub fn check_crc<C, I>(crc: C, input: I) -> Option<I>
+where
+    C: CrcStyle,
+    I: Parse,
+{
+    check_crc_detailed(crc, input).ok()
+}
+
+/// Check the little-endian u16 CRC at the end of a frame body, like
+/// [check_crc()], but report why the check failed.
+pub fn check_crc_detailed<C, I>(crc: C, input: I) -> Result<I, CrcError>
 where
     C: CrcStyle,
     I: Parse,
 {
     if input.input_len() < 2 {
-        return None;
+        return Err(CrcError::Missing);
     }
 
     let (suffix, prefix) = input.take_split(input.input_len() - 2);
@@ -355,14 +574,14 @@ where
 
     let calculated = digest.finalize();
 
-    if let Some(provided) = read_le_u16(&mut suffix.iter_indices()) {
-        if crc.validate(calculated, provided) {
-            Some(input.slice(0..input.input_len() - 2))
-        } else {
-            None
-        }
+    let provided = read_le_u16(&mut suffix.iter_indices()).ok_or(CrcError::Missing)?;
+    if crc.validate(calculated, provided) {
+        Ok(input.slice(0..input.input_len() - 2))
     } else {
-        None
+        Err(CrcError::Mismatch {
+            calculated,
+            provided,
+        })
     }
 }
 
@@ -470,12 +689,80 @@ where
     }
 }
 
+/// An iterator over every frame in a buffer. See [parse_all()].
+#[derive(Debug)]
+pub struct ParseAll<'a, C, M> {
+    crc: C,
+    input: &'a mut [u8],
+    offset: usize,
+    _message: PhantomData<fn() -> M>,
+}
+
+/// Parse every frame in a buffer, deobfuscating each in place.
+///
+/// Frames that fail the CRC check or do not parse as `M` are still
+/// returned, as [ParseResult::CrcErr] and [ParseResult::ParseErr],
+/// and iteration continues after them. Data between frames is
+/// skipped, so [ParseResult::None] is never returned. All ranges are
+/// relative to the start of the buffer.
+///
+/// An incomplete frame at the end of the buffer is ignored.
+pub fn parse_all<'a, C, M>(crc: C, input: &'a mut [u8]) -> ParseAll<'a, C, M>
+where
+    C: CrcStyle,
+    M: MessageParse<&'a [u8]>,
+{
+    ParseAll {
+        crc,
+        input,
+        offset: 0,
+        _message: PhantomData,
+    }
+}
+
+impl<'a, C, M> Iterator for ParseAll<'a, C, M>
+where
+    C: CrcStyle,
+    M: MessageParse<&'a [u8]>,
+{
+    type Item = ParseResult<&'a [u8], M>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.input.is_empty() {
+            let (consumed, found) = find_frame(&mut *self.input);
+            if consumed == 0 {
+                // only an incomplete frame left
+                self.input = &mut [];
+                break;
+            }
+
+            // split off the consumed part, so results can borrow it
+            let (frame, rest) = core::mem::take(&mut self.input).split_at_mut(consumed);
+            self.input = rest;
+            let offset = self.offset;
+            self.offset += consumed;
+
+            let frame: &'a [u8] = frame;
+            match M::parse_frame(&self.crc, frame, &found) {
+                ParseResult::None => continue,
+                res => return Some(res.offset(offset)),
+            }
+        }
+
+        None
+    }
+}
+
 #[cfg(test)]
 #[cfg(feature = "alloc")]
 mod test {
     use alloc::borrow::ToOwned;
+    use alloc::string::ToString;
 
     use super::super::crc::CrcConstant;
+    use super::super::messages::radio::Hello;
+    use super::super::serialize;
+    use super::super::serialize::SerializerVec;
     use super::*;
 
     fn found(full: Range<usize>) -> FoundFrame {
@@ -560,12 +847,102 @@ mod test {
         assert_eq!(find_frame(frame.as_mut()), (10, None));
     }
 
+    #[test]
+    fn find_frame_custom_max() {
+        // 0x10 byte body, 0x18 byte frame
+        let mut frame = [0; 0x18];
+        frame[..4].copy_from_slice(b"\xab\xcd\x10\x00");
+        frame[0x14..].copy_from_slice(b"\x00\x00\xdc\xba");
+        assert_eq!(
+            find_frame_with(frame.to_owned().as_mut(), 0x18),
+            (0x18, Some(found(0..0x18)))
+        );
+        assert_eq!(
+            find_frame_with(frame.to_owned().as_mut(), 0x17),
+            (0x18, None)
+        );
+
+        // larger than MAX_FRAME_SIZE
+        let mut frame = alloc::vec![0; MAX_FRAME_SIZE + 1];
+        frame[..4].copy_from_slice(b"\xab\xcd\xf9\x01");
+        frame[MAX_FRAME_SIZE - 3..].copy_from_slice(b"\x00\x00\xdc\xba");
+        assert_eq!(find_frame(frame.clone().as_mut_slice()).1, None);
+        assert_eq!(
+            find_frame_with(frame.as_mut_slice(), MAX_FRAME_SIZE + 1),
+            (MAX_FRAME_SIZE + 1, Some(found(0..MAX_FRAME_SIZE + 1)))
+        );
+
+        // a cap too small for any frame rejects everything
+        let mut frame = b"\xab\xcd\x00\x00\x00\x00\xdc\xba".to_owned();
+        assert_eq!(find_frame_with(frame.as_mut(), 4), (8, None));
+    }
+
     #[test]
     fn find_frame_bad_end() {
         let mut frame = b"abc\xab\xcd\x01\x00\x70\x03\x7b\xdc\xbbafter".to_owned();
         assert_eq!(find_frame(frame.as_mut()), (17, None));
     }
 
+    // feed data to a scanner one byte at a time, like a slow UART,
+    // checking each step against find_frame() on the same buffer
+    fn scan_trickle(data: &[u8]) -> alloc::vec::Vec<alloc::vec::Vec<u8>> {
+        let mut scanner = FrameScanner::new();
+        let mut buffer = alloc::vec::Vec::new();
+        let mut frames = alloc::vec::Vec::new();
+        for b in data.iter() {
+            buffer.push(*b);
+            loop {
+                let mut batch = buffer.clone();
+                let expected = find_frame(batch.as_mut_slice());
+                let result = scanner.scan(buffer.as_mut_slice());
+                assert_eq!(result, expected);
+                assert_eq!(buffer, batch);
+
+                let (consumed, found) = result;
+                if let Some(found) = found {
+                    frames.push(buffer[found.frame_contents].to_vec());
+                }
+                buffer.drain(..consumed);
+
+                // a found frame may be followed by more data already here
+                if consumed == 0 || buffer.is_empty() {
+                    break;
+                }
+            }
+        }
+        frames
+    }
+
+    #[test]
+    fn scan_trickle_frames() {
+        let frames = scan_trickle(
+            b"abc\xab\xcd\x01\x00\x70\x03\x7b\xdc\xbaafter\xab\xcd\x01\x00\x70\x03\x7b\xdc\xba",
+        );
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0], b"foo");
+        assert_eq!(frames[0], frames[1]);
+    }
+
+    #[test]
+    fn scan_trickle_false_starts() {
+        // bad length, then bad end, then a real frame
+        let frames = scan_trickle(
+            b"\xab\xcd\x00\x02foo\xab\xcd\x01\x00\x70\x03\x7b\xdc\xbbxyz\xab\xcd\x01\x00\x70\x03\x7b\xdc\xba",
+        );
+        assert_eq!(frames, [b"foo"]);
+    }
+
+    #[test]
+    fn scan_split_reads() {
+        let mut scanner = FrameScanner::new();
+        let mut data = b"abc\xab\xcd\x01\x00\x70\x03\x7b\xdc\xbaafter".to_owned();
+
+        assert_eq!(scanner.scan(&mut data[..5]), (3, None));
+        assert_eq!(scanner.scan(&mut data[3..9]), (0, None));
+        assert_eq!(scanner.scan(&mut data[3..]), (9, Some(found(0..9))));
+        assert_eq!(scanner, FrameScanner::new());
+    }
+
     #[test]
     fn frame_empty() {
         let mut data = b"".to_owned();
@@ -724,8 +1101,96 @@ mod test {
             nom::bytes::complete::tag(b"foo".as_ref()),
         );
         assert_eq!(
-            (skip, res),
-            (14, ParseResult::CrcErr(3..14, b"foo\xfe\xc0".as_ref()))
-        )
+            (skip, &res),
+            (14, &ParseResult::CrcErr(3..14, b"foo\xfe\xc0".as_ref()))
+        );
+
+        let mismatch = CrcError::Mismatch {
+            calculated: 0xcafe,
+            provided: 0xc0fe,
+        };
+        assert_eq!(res.crc_error(CrcConstant(0xcafe)), Some(mismatch));
+        assert_eq!(mismatch.to_string(), "bad CRC: expected 0xcafe, got 0xc0fe");
+    }
+
+    #[test]
+    fn crc_error_detail() {
+        let body = b"foo\xfe\xca".as_ref();
+        assert_eq!(
+            check_crc_detailed(CrcConstant(0xcafe), body),
+            Ok(b"foo".as_ref())
+        );
+        assert_eq!(
+            check_crc_detailed(CrcConstant(0xcafe), b"\xfe".as_ref()),
+            Err(CrcError::Missing)
+        );
+
+        let other: ParseResult<_, ()> = ParseResult::ParseErr(
+            0..1,
+            body,
+            Error {
+                input: body,
+                code: nom::error::ErrorKind::Tag,
+            },
+        );
+        assert_eq!(other.crc_error(CrcConstant(0xcafe)), None);
+    }
+
+    fn hello_frame(crc: impl CrcStyle, session_id: u32) -> alloc::vec::Vec<u8> {
+        let mut ser = SerializerVec::new();
+        serialize(&crc, &mut ser, &Hello { session_id }).unwrap_or_else(|e| match e {});
+        ser.done()
+    }
+
+    #[test]
+    fn parse_all_frames() {
+        let mut data = b"abc".to_vec();
+        data.extend(hello_frame(CrcConstant(0xcafe), 1));
+        let second = data.len();
+        data.extend(hello_frame(CrcConstant(0xcafe), 2));
+        let bad_crc = data.len();
+        data.extend(b"\xab\xcdjunk");
+        data.extend(hello_frame(CrcConstant(0x1234), 3));
+        let end = data.len();
+        data.extend(b"after\xab\xcd");
+
+        let results: alloc::vec::Vec<_> = parse_all::<_, Hello>(CrcConstant(0xcafe), &mut data)
+            .map(|r| (r.range().cloned(), r.ok().map(|m| m.session_id)))
+            .collect();
+        assert_eq!(
+            results,
+            [
+                (Some(3..second), Some(1)),
+                (Some(second..bad_crc), Some(2)),
+                (Some(bad_crc + 6..end), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_all_errors() {
+        let mut data = b"\xab\xcd\x03\x00\x70\x03\x7b\x18\xe4\xdc\xba".to_vec();
+        data.extend(hello_frame(CrcConstant(0x1234), 1));
+        let end = data.len();
+
+        let mut results = parse_all::<_, Hello>(CrcConstant(0xcafe), &mut data);
+        assert!(matches!(
+            results.next(),
+            Some(ParseResult::ParseErr(r, b"foo", _)) if r == (0..11)
+        ));
+        assert!(matches!(
+            results.next(),
+            Some(ParseResult::CrcErr(r, _)) if r == (11..end)
+        ));
+        assert_eq!(results.next(), None);
+    }
+
+    #[test]
+    fn parse_all_empty() {
+        let mut data = b"".to_owned();
+        assert_eq!(
+            parse_all::<_, Hello>(CrcConstant(0xcafe), &mut data).next(),
+            None
+        );
     }
 }
@@ -57,6 +57,19 @@ pub trait Serializer {
     fn write_le_i32(&mut self, val: i32) -> Result<(), Self::Error> {
         self.write_le_u32(val as u32)
     }
+
+    fn write_be_u16(&mut self, val: u16) -> Result<(), Self::Error> {
+        self.write_bytes_mut(&mut [(val >> 8) as u8, (val & 0xff) as u8])
+    }
+
+    fn write_be_u32(&mut self, val: u32) -> Result<(), Self::Error> {
+        self.write_bytes_mut(&mut [
+            ((val >> 24) & 0xff) as u8,
+            ((val >> 16) & 0xff) as u8,
+            ((val >> 8) & 0xff) as u8,
+            (val & 0xff) as u8,
+        ])
+    }
 }
 
 impl<S> Serializer for &mut S
@@ -99,6 +112,14 @@ where
     fn write_le_i32(&mut self, val: i32) -> Result<(), Self::Error> {
         (*self).write_le_i32(val)
     }
+
+    fn write_be_u16(&mut self, val: u16) -> Result<(), Self::Error> {
+        (*self).write_be_u16(val)
+    }
+
+    fn write_be_u32(&mut self, val: u32) -> Result<(), Self::Error> {
+        (*self).write_be_u32(val)
+    }
 }
 
 /// Wrap an [embedded_io::Write] to become a [Serializer].
@@ -146,6 +167,84 @@ where
     }
 }
 
+/// An error type for [SerializerSlice].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SerializerSliceError {
+    /// The buffer is too small for everything written to it.
+    BufferFull,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SerializerSliceError {}
+
+impl core::fmt::Display for SerializerSliceError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::BufferFull => write!(f, "buffer full"),
+        }
+    }
+}
+
+/// Wrap a `&mut [u8]` to become a [Serializer], without needing alloc.
+///
+/// Writes that do not fit fail with
+/// [BufferFull][SerializerSliceError::BufferFull], and leave the
+/// buffer as it was. This derefs to the part of the buffer written
+/// so far.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SerializerSlice<'a> {
+    inner: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> SerializerSlice<'a> {
+    pub fn new(inner: &'a mut [u8]) -> Self {
+        Self { inner, len: 0 }
+    }
+
+    /// The number of bytes still free in the buffer.
+    pub fn remaining(&self) -> usize {
+        self.inner.len() - self.len
+    }
+
+    /// Return the written part of the buffer.
+    pub fn done(self) -> &'a mut [u8] {
+        &mut self.inner[..self.len]
+    }
+}
+
+impl<'a> core::ops::Deref for SerializerSlice<'a> {
+    type Target = [u8];
+    fn deref(&self) -> &Self::Target {
+        &self.inner[..self.len]
+    }
+}
+
+impl<'a> core::ops::DerefMut for SerializerSlice<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner[..self.len]
+    }
+}
+
+impl<'a> Serializer for SerializerSlice<'a> {
+    type Error = SerializerSliceError;
+
+    fn write_u8(&mut self, val: u8) -> Result<(), Self::Error> {
+        self.write_bytes(&[val])
+    }
+
+    fn write_bytes(&mut self, val: &[u8]) -> Result<(), Self::Error> {
+        if val.len() > self.remaining() {
+            return Err(SerializerSliceError::BufferFull);
+        }
+        self.inner[self.len..self.len + val.len()].copy_from_slice(val);
+        self.len += val.len();
+        Ok(())
+    }
+}
+
 /// Wrap a [`Vec<u8>`] to become a [Serializer].
 #[cfg(feature = "alloc")]
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -276,6 +375,16 @@ impl Serializer for SerializerLength {
         self.len += 4;
         Ok(())
     }
+
+    fn write_be_u16(&mut self, _val: u16) -> Result<(), Self::Error> {
+        self.len += 2;
+        Ok(())
+    }
+
+    fn write_be_u32(&mut self, _val: u32) -> Result<(), Self::Error> {
+        self.len += 4;
+        Ok(())
+    }
 }
 
 /// A [Serializer] that also computes a CRC on the side.
@@ -469,3 +578,106 @@ pub trait MessageSerialize {
         ser.write_bytes(&super::FRAME_END)
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "alloc")]
+mod test {
+    use super::super::crc::CrcXModem;
+    use super::super::messages::radio::{Hello, ReadEeprom, HELLO_SESSION_ID};
+    use super::*;
+
+    fn check_slice<M>(msg: &M)
+    where
+        M: MessageSerialize,
+    {
+        let mut vec = SerializerVec::new();
+        msg.frame(&CrcXModem::new(), &mut vec)
+            .unwrap_or_else(|e| match e {});
+        let expected = vec.done();
+
+        // exactly the right size
+        let mut buf = [0; 64];
+        let mut ser = SerializerSlice::new(&mut buf[..expected.len()]);
+        msg.frame(&CrcXModem::new(), &mut ser).unwrap();
+        assert_eq!(ser.remaining(), 0);
+        assert_eq!(ser.done(), &expected[..]);
+
+        // with room to spare
+        let mut ser = SerializerSlice::new(&mut buf);
+        msg.frame(&CrcXModem::new(), &mut ser).unwrap();
+        assert_eq!(&ser[..], &expected[..]);
+
+        // one byte short
+        let mut ser = SerializerSlice::new(&mut buf[..expected.len() - 1]);
+        assert_eq!(
+            msg.frame(&CrcXModem::new(), &mut ser),
+            Err(SerializerSliceError::BufferFull)
+        );
+        assert_eq!(&ser[..], &expected[..ser.len()]);
+    }
+
+    #[test]
+    fn slice_hello() {
+        check_slice(&Hello {
+            session_id: HELLO_SESSION_ID,
+        });
+    }
+
+    #[test]
+    fn slice_read_eeprom() {
+        check_slice(&ReadEeprom {
+            address: 0x0e70,
+            len: 0x10,
+            _pad: Default::default(),
+            session_id: HELLO_SESSION_ID,
+        });
+    }
+
+    #[test]
+    fn slice_full() {
+        let mut buf = [0; 3];
+        let mut ser = SerializerSlice::new(&mut buf);
+        assert_eq!(ser.write_le_u16(0x1234), Ok(()));
+        assert_eq!(
+            ser.write_le_u16(0x5678),
+            Err(SerializerSliceError::BufferFull)
+        );
+        assert_eq!(ser.write_u8(0x9a), Ok(()));
+        assert_eq!(ser.write_u8(0xbc), Err(SerializerSliceError::BufferFull));
+        assert_eq!(ser.done(), &[0x34, 0x12, 0x9a]);
+    }
+
+    #[test]
+    fn endianness() {
+        use super::super::parse::{be_u16, be_u32, le_u16, le_u32};
+
+        let mut ser = SerializerVec::new();
+        ser.write_le_u16(0x1234).unwrap_or_else(|e| match e {});
+        ser.write_be_u16(0x1234).unwrap_or_else(|e| match e {});
+        ser.write_le_u32(0x12345678).unwrap_or_else(|e| match e {});
+        ser.write_be_u32(0x12345678).unwrap_or_else(|e| match e {});
+        let bytes = ser.done();
+        assert_eq!(
+            bytes,
+            [0x34, 0x12, 0x12, 0x34, 0x78, 0x56, 0x34, 0x12, 0x12, 0x34, 0x56, 0x78]
+        );
+
+        let input = &bytes[..];
+        let (input, le16) = le_u16(input).unwrap();
+        let (input, be16) = be_u16(input).unwrap();
+        let (input, le32) = le_u32(input).unwrap();
+        let (input, be32) = be_u32(input).unwrap();
+        assert!(input.is_empty());
+        assert_eq!((le16, be16), (0x1234, 0x1234));
+        assert_eq!((le32, be32), (0x12345678, 0x12345678));
+
+        // reading with the wrong endianness swaps the bytes
+        assert_eq!(be_u16(&bytes[..2]).unwrap().1, 0x3412);
+        assert_eq!(le_u32(&bytes[8..]).unwrap().1, 0x78563412);
+
+        let mut len = SerializerLength::new();
+        len.write_be_u16(0).unwrap_or_else(|e| match e {});
+        len.write_be_u32(0).unwrap_or_else(|e| match e {});
+        assert_eq!(len.len(), 6);
+    }
+}
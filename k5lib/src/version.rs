@@ -1,7 +1,13 @@
 /// Max size of version string, including terminating `NUL`.
 pub const VERSION_LEN: usize = 16;
 
-#[derive(Clone, Eq, Hash, Ord, PartialEq, PartialOrd, Default)]
+/// A firmware version string, as stored in firmware images and sent
+/// by the radio.
+///
+/// Versions with leading `x.y.z` numbers order by those numbers,
+/// ignoring any leading `*`, and come before all versions without
+/// them. Versions without numbers order by their raw bytes.
+#[derive(Clone, Eq, Hash, PartialEq, Default)]
 pub struct Version([u8; VERSION_LEN]);
 
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -59,13 +65,57 @@ impl Version {
     }
 
     pub fn as_str(&self) -> Result<&str, core::str::Utf8Error> {
-        // unwrap: always at least one element
+        core::str::from_utf8(self.as_trimmed_bytes())
+    }
+
+    /// The version without padding, or the leading `*` that marks
+    /// custom firmware. [None] if it is not valid UTF-8.
+    pub fn as_trimmed_str(&self) -> Option<&str> {
+        let s = self.as_str().ok()?;
+        Some(s.strip_prefix('*').unwrap_or(s))
+    }
+
+    /// The version bytes, up to the first `NUL`.
+    pub fn as_trimmed_bytes(&self) -> &[u8] {
         let mut zero = 0;
-        while self.0[zero] > 0 && zero < self.0.len() {
+        while zero < self.0.len() && self.0[zero] > 0 {
             zero += 1;
         }
-        let zero_terminated = &self.0[..zero];
-        core::str::from_utf8(zero_terminated)
+        &self.0[..zero]
+    }
+
+    /// Parse a leading `x.y.z` or `x.y` from the trimmed version.
+    ///
+    /// Returns the numbers, with a missing `z` as 0, and the rest of
+    /// the version after them.
+    pub fn numbers(&self) -> Option<([u32; 3], &str)> {
+        let mut rest = self.as_trimmed_str()?;
+        let mut numbers = [0; 3];
+        let mut found = 0;
+        while found < numbers.len() {
+            if found > 0 {
+                rest = rest.strip_prefix('.')?;
+            }
+
+            let digits = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let Ok(n) = rest[..digits].parse() else {
+                break;
+            };
+            numbers[found] = n;
+            found += 1;
+            rest = &rest[digits..];
+
+            if !rest.starts_with('.') {
+                break;
+            }
+        }
+
+        if found < 2 {
+            return None;
+        }
+        Some((numbers, rest))
     }
 
     pub const fn as_c_str(&self) -> Result<&core::ffi::CStr, core::ffi::FromBytesUntilNulError> {
@@ -86,6 +136,41 @@ impl core::fmt::Debug for Version {
     }
 }
 
+impl core::fmt::Display for Version {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self.as_str() {
+            Ok(s) => write!(f, "{}", s),
+            Err(_) => {
+                for b in self.as_trimmed_bytes() {
+                    write!(f, "{}", core::ascii::escape_default(*b))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        // numeric versions all come first, so this stays transitive
+        let numbers = match (self.numbers(), other.numbers()) {
+            (Some((a, a_rest)), Some((b, b_rest))) => a.cmp(&b).then_with(|| a_rest.cmp(b_rest)),
+            (Some(_), None) => core::cmp::Ordering::Less,
+            (None, Some(_)) => core::cmp::Ordering::Greater,
+            (None, None) => core::cmp::Ordering::Equal,
+        };
+
+        // stay consistent with Eq by falling back to the raw bytes
+        numbers.then_with(|| self.0.cmp(&other.0))
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 #[cfg(feature = "defmt")]
 impl defmt::Format for Version {
     fn format(&self, f: defmt::Formatter) {
@@ -96,9 +181,184 @@ impl defmt::Format for Version {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Version {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self.as_str() {
+            Ok(s) => serializer.serialize_str(s),
+            Err(_) => serializer.serialize_bytes(self.as_trimmed_bytes()),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Version {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct VersionVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for VersionVisitor {
+            type Value = Version;
+
+            fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                write!(f, "a version string or bytes")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Version::new_from_str(v).map_err(E::custom)
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Version::new_from_bytes(v).map_err(E::custom)
+            }
+
+            // formats without bytes, like JSON, write them as a list
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::SeqAccess<'de>,
+            {
+                let mut data = [0; VERSION_LEN];
+                let mut len = 0;
+                while let Some(b) = seq.next_element()? {
+                    if len >= VERSION_LEN - 1 {
+                        return Err(serde::de::Error::custom(VersionError::TooLong));
+                    }
+                    data[len] = b;
+                    len += 1;
+                }
+                Version::new_from_bytes(&data[..len]).map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_any(VersionVisitor)
+    }
+}
+
 impl core::ops::Deref for Version {
     type Target = [u8];
     fn deref(&self) -> &Self::Target {
         self.as_bytes()
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "alloc")]
+mod test {
+    extern crate std;
+    use std::format;
+    use std::vec::Vec;
+
+    use quickcheck::{Arbitrary, Gen};
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    impl Arbitrary for Version {
+        fn arbitrary(g: &mut Gen) -> Self {
+            // mostly numeric versions, to exercise both kinds
+            if bool::arbitrary(g) {
+                let star = if bool::arbitrary(g) { "*" } else { "" };
+                let n = |g: &mut Gen| u8::arbitrary(g) % 12;
+                let rest = *g.choose(&["", "rc", "test"]).unwrap();
+                let s = if bool::arbitrary(g) {
+                    format!("{}{}.{}.{}{}", star, n(g), n(g), n(g), rest)
+                } else {
+                    format!("{}{}.{}{}", star, n(g), n(g), rest)
+                };
+                Version::new_from_str(&s).unwrap()
+            } else {
+                let bytes: Vec<u8> = Vec::arbitrary(g);
+                Version::new_from_bytes(&bytes[..bytes.len().min(VERSION_LEN - 1)]).unwrap()
+            }
+        }
+    }
+
+    fn v(s: &str) -> Version {
+        Version::new_from_str(s).unwrap()
+    }
+
+    #[test]
+    fn firmware_versions() {
+        let version = v("*0.1.0test");
+        assert_eq!(version.as_str(), Ok("*0.1.0test"));
+        assert_eq!(version.as_trimmed_str(), Some("0.1.0test"));
+        assert_eq!(version.numbers(), Some(([0, 1, 0], "test")));
+
+        assert_eq!(v("2.01.26").numbers(), Some(([2, 1, 26], "")));
+        assert_eq!(v("*2.01").numbers(), Some(([2, 1, 0], "")));
+        assert_eq!(v("k5_2.01.26").numbers(), None);
+        assert_eq!(v("*").as_trimmed_str(), Some(""));
+    }
+
+    #[test]
+    fn padding() {
+        assert_eq!(Version::new_empty().as_str(), Ok(""));
+        assert_eq!(v("abc").as_bytes()[3..], [0; VERSION_LEN - 3]);
+
+        // bytes after a NUL are dropped
+        assert_eq!(Version::new_from_bytes(b"ab\0cd").unwrap(), v("ab"));
+
+        // room is always left for a NUL
+        assert!(Version::new_from_bytes(&[b'a'; VERSION_LEN - 1]).is_ok());
+        assert_eq!(
+            Version::new_from_bytes(&[b'a'; VERSION_LEN]),
+            Err(VersionError::TooLong)
+        );
+
+        // ...except when built directly, with no NUL at all
+        let full = Version::new([b'a'; VERSION_LEN]);
+        assert_eq!(full.as_trimmed_bytes().len(), VERSION_LEN);
+    }
+
+    #[test]
+    fn display() {
+        extern crate std;
+        use std::string::ToString;
+
+        assert_eq!(v("*0.1.0test").to_string(), "*0.1.0test");
+        let bytes = Version::new_from_bytes(b"a\xffb").unwrap();
+        assert_eq!(bytes.to_string(), "a\\xffb");
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(v("2.01.26") > v("2.01.9"));
+        assert!(v("*0.10.0") > v("0.9.0"));
+        assert!(v("1.2.3") < v("1.2.3rc"));
+        assert!(v("2.0") < v("2.0.1"));
+
+        // no pattern falls back to bytes, after all numeric versions
+        assert!(v("beta") > v("alpha"));
+        assert!(v("beta") > v("1.0.0"));
+        assert!(v("0") > v("9.9.9"));
+        assert!(v("*0.1") < v("+"));
+
+        // consistent with Eq
+        assert_eq!(v("1.2.3").cmp(&v("1.2.3")), core::cmp::Ordering::Equal);
+        assert_ne!(v("*1.2.3").cmp(&v("1.2.3")), core::cmp::Ordering::Equal);
+    }
+    #[quickcheck]
+    fn ordering_transitive(a: Version, b: Version, c: Version) -> bool {
+        let transitive = |x: &Version, y: &Version, z: &Version| !(x <= y && y <= z) || x <= z;
+        transitive(&a, &b, &c)
+            && transitive(&c, &b, &a)
+            && transitive(&b, &a, &c)
+            && transitive(&a, &c, &b)
+    }
+
+    #[quickcheck]
+    fn ordering_consistent(a: Version, b: Version) -> bool {
+        (a.cmp(&b) == core::cmp::Ordering::Equal) == (a == b) && a.cmp(&b) == b.cmp(&a).reverse()
+    }
+}
@@ -140,7 +140,7 @@ pub fn read_firmware_from(
     match format {
         BinaryFormat::Raw => Ok((
             UnpackedFirmware::new_cloned(data),
-            BinaryInfo::from_image(BinaryFormat::Raw, data, None)?,
+            BinaryInfo::from_image(BinaryFormat::Raw, data, version)?,
         )),
         BinaryFormat::Packed => {
             let packed = PackedFirmware::new_cloned(data)?;
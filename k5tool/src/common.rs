@@ -70,6 +70,17 @@ impl std::io::Write for SerialPort {
     }
 }
 
+impl SerialPort {
+    /// Change how long a read waits for data before timing out.
+    pub fn set_read_timeout(&mut self, timeout: std::time::Duration) -> anyhow::Result<()> {
+        match self {
+            Self::Serial(port) => port.get_mut().set_timeout(timeout)?,
+            Self::Tcp(port) => port.get_ref().set_read_timeout(Some(timeout))?,
+        }
+        Ok(())
+    }
+}
+
 impl SerialPortArgs {
     pub fn open(&self) -> anyhow::Result<SerialPort> {
         let timeout = std::time::Duration::from_secs(self.timeout);
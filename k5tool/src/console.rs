@@ -2,10 +2,21 @@ use std::io::{Read, Write};
 
 use rustyline::{error::ReadlineError, DefaultEditor, ExternalPrinter};
 
-use k5lib::protocol::messages::custom::DebugInput;
-use k5lib::protocol::messages::{Message, RadioMessage};
+use k5lib::encode::HexSlice;
+use k5lib::protocol::messages::custom::{DebugConsole, DebugInput, DEBUG_INPUT_MAX};
+use k5lib::protocol::messages::{Direction, Message, RadioMessage};
 use k5lib::protocol::ParseResult;
 
+/// How long --watch waits before reopening a port that dropped.
+const RECONNECT_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// How much of a message --watch prints, in characters.
+const WATCH_SUMMARY_MAX: usize = 80;
+
+/// How long to wait for more output before printing a partial line,
+/// or, with --script, deciding a command has finished.
+const QUIET: std::time::Duration = std::time::Duration::from_millis(100);
+
 #[derive(clap::Args, Debug)]
 pub struct ConsoleOpts {
     #[command(flatten)]
@@ -15,17 +26,302 @@ pub struct ConsoleOpts {
 
     #[arg(short, long)]
     elf: Option<String>,
+
+    /// Only watch the port, printing every frame in either direction
+    /// on one line, with a timestamp. Nothing is ever sent, and the
+    /// port is reopened if it drops.
+    #[arg(long)]
+    watch: bool,
+    /// With --watch, also print the raw bytes of each frame.
+    #[arg(long, requires = "watch")]
+    hex: bool,
+
+    /// Send each line of this file as a command, instead of reading
+    /// from a prompt, and print a transcript of the output.
+    #[arg(long, conflicts_with = "watch")]
+    script: Option<String>,
+    /// With --script, write the transcript to this file, not stdout.
+    #[arg(long, requires = "script")]
+    transcript: Option<String>,
+    /// With --script, write the transcript as JSON, one object per line.
+    #[arg(long, requires = "script")]
+    json: bool,
+    /// With --script, how long each command may take to answer, in
+    /// milliseconds.
+    #[arg(long, requires = "script", default_value_t = 2000)]
+    command_timeout: u64,
+    /// With --script, stop at the first command that gets no answer.
+    #[arg(long, requires = "script")]
+    fail_fast: bool,
 }
 
 impl crate::ToolRun for ConsoleOpts {
     fn run(&self) -> anyhow::Result<()> {
-        let port = self.port.open()?;
+        if self.watch {
+            return self.watch();
+        }
+        if let Some(ref script) = self.script {
+            return self.script(script);
+        }
+
+        let mut port = self.port.open()?;
+        port.set_read_timeout(QUIET)?;
         let mut client = self.debug.wrap_host(k5lib::ClientHost::new_std(port))?;
         let mut console = Console::new(&mut client, self.elf.as_ref().map(|s| s.as_str()));
         console.run()
     }
 }
 
+impl ConsoleOpts {
+    fn watch(&self) -> anyhow::Result<()> {
+        use std::io::IsTerminal;
+
+        let color = std::io::stdout().is_terminal();
+        let start = std::time::Instant::now();
+        let mut port = self.port.open()?;
+        loop {
+            eprintln!("*** watching, ^C to stop");
+            // host frames have a real crc, radio frames a constant one
+            let mut client = k5lib::ClientHostAny::<_>::new_any_std(0xffff, port);
+            let err = loop {
+                let line = match client.read_any() {
+                    Ok(ParseResult::None) => continue,
+                    Ok(res) => watch_line(start.elapsed(), &res, color),
+                    Err(k5lib::ClientError::Io(ref io))
+                        if matches!(
+                            io.kind(),
+                            std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock,
+                        ) =>
+                    {
+                        // a quiet port is fine
+                        continue;
+                    }
+                    Err(e) => break e,
+                };
+
+                println!("{}", line);
+                if self.hex {
+                    if let Some(raw) = client.last_raw_frame() {
+                        let raw: Vec<u8> = raw.collect();
+                        print!("{}", crate::hexdump::hexdump_format(&raw));
+                    }
+                }
+            };
+
+            eprintln!("!!! {}, reconnecting", err);
+            port = loop {
+                std::thread::sleep(RECONNECT_DELAY);
+                if let Ok(port) = self.port.open() {
+                    break port;
+                }
+            };
+        }
+    }
+}
+
+/// One event in a --script transcript.
+#[derive(serde::Serialize, Debug, Clone, PartialEq)]
+struct TranscriptLine<'a> {
+    /// Seconds since the script started.
+    time: f64,
+    #[serde(flatten)]
+    event: TranscriptEvent<'a>,
+}
+
+#[derive(serde::Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum TranscriptEvent<'a> {
+    /// A command sent to the radio.
+    Input(&'a str),
+    /// A line of output from the radio.
+    Output(&'a str),
+    /// The command that got no output in time.
+    Timeout(&'a str),
+}
+
+impl TranscriptLine<'_> {
+    /// Format this event as one transcript line, without a newline.
+    fn format(&self, json: bool) -> anyhow::Result<String> {
+        if json {
+            return Ok(serde_json::to_string(self)?);
+        }
+
+        let (arrow, text) = match self.event {
+            TranscriptEvent::Input(s) => (">", s),
+            TranscriptEvent::Output(s) => ("<", s),
+            TranscriptEvent::Timeout(_) => ("!!", "timed out"),
+        };
+        Ok(format!("[{:10.3}] {} {}", self.time, arrow, text))
+    }
+}
+
+/// Where --script writes its transcript.
+struct Transcript {
+    out: Box<dyn Write>,
+    json: bool,
+    start: std::time::Instant,
+}
+
+impl Transcript {
+    fn record(&mut self, event: TranscriptEvent) -> anyhow::Result<()> {
+        let line = TranscriptLine {
+            time: self.start.elapsed().as_secs_f64(),
+            event,
+        };
+        writeln!(self.out, "{}", line.format(self.json)?)?;
+        Ok(())
+    }
+}
+
+impl ConsoleOpts {
+    fn script(&self, path: &str) -> anyhow::Result<()> {
+        let script = std::fs::read_to_string(path)?;
+        for (i, command) in script.lines().enumerate() {
+            check_input(command).map_err(|e| anyhow::anyhow!("{}:{}: {}", path, i + 1, e))?;
+        }
+
+        let mut port = self.port.open()?;
+        port.set_read_timeout(QUIET)?;
+        let mut client = self.debug.wrap_host(k5lib::ClientHost::new_std(port))?;
+
+        let out: Box<dyn Write> = match self.transcript {
+            Some(ref path) => Box::new(std::io::BufWriter::new(std::fs::File::create(path)?)),
+            None => Box::new(std::io::stdout()),
+        };
+        let mut transcript = Transcript {
+            out,
+            json: self.json,
+            start: std::time::Instant::now(),
+        };
+
+        let timeout = std::time::Duration::from_millis(self.command_timeout);
+        let mut text = DebugConsole::new();
+        let mut failed = 0;
+        for command in script.lines().filter(|l| !l.is_empty()) {
+            transcript.record(TranscriptEvent::Input(command))?;
+            client.write(&DebugInput {
+                line: command.as_bytes(),
+            })?;
+
+            if !script_wait(&mut client, &mut text, &mut transcript, timeout)? {
+                transcript.record(TranscriptEvent::Timeout(command))?;
+                failed += 1;
+                if self.fail_fast {
+                    break;
+                }
+            }
+        }
+
+        transcript.out.flush()?;
+        if failed > 0 {
+            anyhow::bail!("{} command(s) got no output", failed);
+        }
+        Ok(())
+    }
+}
+
+/// Record output for one --script command, until the port goes quiet
+/// after some output, or `timeout` passes. Returns whether any output
+/// arrived.
+fn script_wait<F>(
+    client: &mut crate::debug::DebugClientHost<F>,
+    text: &mut DebugConsole,
+    transcript: &mut Transcript,
+    timeout: std::time::Duration,
+) -> anyhow::Result<bool>
+where
+    F: Read + Write,
+{
+    let deadline = std::time::Instant::now() + timeout;
+    let mut got_output = false;
+    loop {
+        let quiet = match client.read_radio() {
+            Ok(ParseResult::Ok(_, RadioMessage::DebugOutput(output))) => {
+                got_output = true;
+                if output.defmt {
+                    let line = format!("defmt {}", HexSlice(output.data));
+                    transcript.record(TranscriptEvent::Output(&line))?;
+                } else {
+                    for line in text.push_output(&output) {
+                        transcript.record(TranscriptEvent::Output(&line))?;
+                    }
+                }
+                false
+            }
+            // anything else is not part of the conversation
+            Ok(_) => false,
+            Err(k5lib::ClientError::Io(ref io))
+                if matches!(
+                    io.kind(),
+                    std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock,
+                ) =>
+            {
+                true
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        if (quiet && got_output) || std::time::Instant::now() >= deadline {
+            break;
+        }
+    }
+
+    if let Some(line) = text.flush() {
+        transcript.record(TranscriptEvent::Output(&line))?;
+    }
+    Ok(got_output)
+}
+
+/// Check that a line fits in one [DebugInput]. The radio treats each
+/// message as a whole line, so longer lines can't be split up.
+fn check_input(line: &str) -> Result<(), String> {
+    if line.len() > DEBUG_INPUT_MAX {
+        Err(format!(
+            "line is {} bytes, longer than the {} that fit in a message",
+            line.len(),
+            DEBUG_INPUT_MAX
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Format one --watch line: a timestamp, a direction arrow, and a
+/// summary of the frame, colored by direction.
+fn watch_line<I>(
+    elapsed: std::time::Duration,
+    res: &ParseResult<I, Message<I>>,
+    color: bool,
+) -> String
+where
+    I: std::fmt::Debug,
+{
+    let (ansi, arrow, summary) = match res {
+        ParseResult::Ok(_, m) => {
+            let (ansi, arrow) = match m.direction() {
+                Direction::HostToRadio => ("36", "->"),
+                Direction::RadioToHost => ("33", "<-"),
+            };
+            let mut summary = format!("{} (0x{:04x}) {:?}", m.name(), m.message_type(), m);
+            if let Some((i, _)) = summary.char_indices().nth(WATCH_SUMMARY_MAX) {
+                summary.truncate(i);
+                summary.push_str("...");
+            }
+            (ansi, arrow, summary)
+        }
+        ParseResult::ParseErr(_, _, e) => ("31", "!!", format!("parse error: {:?}", e)),
+        ParseResult::CrcErr(_, _) => ("31", "!!", "crc error".to_owned()),
+        ParseResult::None => ("0", "  ", String::new()),
+    };
+
+    let line = format!("[{:10.3}] {} {}", elapsed.as_secs_f64(), arrow, summary);
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", ansi, line)
+    } else {
+        line
+    }
+}
+
 pub trait ConsoleTryClone: Sized {
     fn try_clone(&self) -> Option<Self>;
 }
@@ -152,6 +448,7 @@ where
             let mut write_client = k5lib::ClientHostStd::<_, k5lib::ArrayBuffer>::new_std(writer);
 
             let reader = s.spawn(move || -> anyhow::Result<()> {
+                let mut text = DebugConsole::new();
                 loop {
                     match self.client.read_and_get_extra::<Message<&[u8]>>() {
                         Ok((msg, extra)) => {
@@ -178,19 +475,24 @@ where
                                         } else {
                                             printer.print(format!("{:?}\n", m))?;
                                         }
+                                    } else if text.is_text(output.data) {
+                                        for line in text.push_output(output) {
+                                            printer.print(line + "\n")?;
+                                        }
                                     } else {
-                                        match std::str::from_utf8(output.data) {
-                                            Ok(s) => printer.print(s.to_owned())?,
-                                            Err(_) => {
-                                                let dump = "\n".to_owned()
-                                                    + &crate::hexdump::hexdump_format(output.data)
-                                                    + "\n";
-                                                printer.print(dump)?;
-                                            }
+                                        if let Some(line) = text.flush() {
+                                            printer.print(line + "\n")?;
                                         }
+                                        let dump = "\n".to_owned()
+                                            + &crate::hexdump::hexdump_format(output.data)
+                                            + "\n";
+                                        printer.print(dump)?;
                                     }
                                 }
                                 ParseResult::Ok(_, ref m) => {
+                                    if let Some(line) = text.flush() {
+                                        printer.print(line + "\n")?;
+                                    }
                                     printer.print(format!("{:?}\n", m))?;
                                 }
                                 ParseResult::ParseErr(_, _, ref e) => {
@@ -214,7 +516,11 @@ where
                             };
 
                             if timed_out {
-                                // time-outs are ok
+                                // time-outs are ok, and mean the radio
+                                // is done talking for now
+                                if let Some(line) = text.flush() {
+                                    printer.print(line + "\n")?;
+                                }
                                 continue;
                             } else {
                                 // other errors are not
@@ -237,9 +543,12 @@ where
                 match rl.readline("> ") {
                     Ok(line) => {
                         rl.add_history_entry(&line)?;
-                        write_client.write(&DebugInput {
-                            line: line.as_bytes(),
-                        })?;
+                        match check_input(&line) {
+                            Ok(()) => write_client.write(&DebugInput {
+                                line: line.as_bytes(),
+                            })?,
+                            Err(e) => eprintln!("!!! {}", e),
+                        }
                     }
 
                     Err(ReadlineError::Eof) | Err(ReadlineError::Interrupted) => {
@@ -252,3 +561,65 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use k5lib::protocol::messages::radio::{Hello, HELLO_SESSION_ID};
+    use k5lib::protocol::messages::HostMessage;
+
+    use super::*;
+
+    #[test]
+    fn watch_lines() {
+        let elapsed = std::time::Duration::from_millis(1_500);
+        let hello = Message::<&[u8]>::Host(HostMessage::Hello(Hello {
+            session_id: HELLO_SESSION_ID,
+        }));
+
+        let line = watch_line(elapsed, &ParseResult::Ok(0..0, hello), false);
+        assert!(line.starts_with("[     1.500] -> Hello (0x0514) "));
+        assert!(line.chars().count() <= 13 + 3 + WATCH_SUMMARY_MAX + 3);
+
+        let res = ParseResult::<&[u8], Message<&[u8]>>::CrcErr(0..0, &[]);
+        assert_eq!(
+            watch_line(elapsed, &res, true),
+            "\x1b[31m[     1.500] !! crc error\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn input_too_long() {
+        assert_eq!(check_input(""), Ok(()));
+        assert_eq!(check_input(&"x".repeat(DEBUG_INPUT_MAX)), Ok(()));
+        assert!(check_input(&"x".repeat(DEBUG_INPUT_MAX + 1)).is_err());
+    }
+
+    #[test]
+    fn transcript_lines() {
+        let line = |time, event| TranscriptLine { time, event };
+
+        let input = line(0.25, TranscriptEvent::Input("help"));
+        assert_eq!(input.format(false).unwrap(), "[     0.250] > help");
+        assert_eq!(
+            input.format(true).unwrap(),
+            r#"{"time":0.25,"input":"help"}"#
+        );
+
+        let output = line(1.5, TranscriptEvent::Output("ok \"quoted\""));
+        assert_eq!(
+            output.format(false).unwrap(),
+            "[     1.500] < ok \"quoted\""
+        );
+        assert_eq!(
+            output.format(true).unwrap(),
+            r#"{"time":1.5,"output":"ok \"quoted\""}"#
+        );
+
+        let timeout = line(3.0, TranscriptEvent::Timeout("help"));
+        assert_eq!(timeout.format(false).unwrap(), "[     3.000] !! timed out");
+        assert_eq!(
+            timeout.format(true).unwrap(),
+            r#"{"time":3.0,"timeout":"help"}"#
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use k5lib::protocol::crc;
+use k5lib::protocol::messages::bootloader::{BootloaderReady, BootloaderReadyReply};
 use k5lib::protocol::serialize::{Serializer, SerializerWrap};
 use k5lib::protocol::{
     parse, serialize, HostMessage, Message, MessageParse, MessageSerialize, ParseResult,
@@ -204,6 +205,26 @@ where
         self.read()
     }
 
+    /// Wait for the bootloader, and reply with the incoming firmware
+    /// version. This mirrors
+    /// [k5lib::ClientHost::bootloader_handshake()], with debug output.
+    pub fn bootloader_handshake(
+        &mut self,
+        version: k5lib::Version,
+    ) -> Result<BootloaderReady, k5lib::BootloaderHandshakeError<std::io::Error>>
+    where
+        F: std::io::Read + std::io::Write,
+    {
+        let ready = loop {
+            if let Some(ready) = k5lib::bootloader_handshake_step(self.read_radio()) {
+                break ready?;
+            }
+        };
+
+        self.write(&BootloaderReadyReply { version })?;
+        Ok(ready)
+    }
+
     /// Write a message to the port.
     pub fn write<M>(&mut self, msg: &M) -> Result<(), k5lib::ClientError<std::io::Error>>
     where
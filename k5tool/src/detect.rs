@@ -0,0 +1,24 @@
+use k5lib::{DetectModeError, RadioMode};
+
+#[derive(clap::Args, Debug)]
+pub struct DetectOpts {
+    #[command(flatten)]
+    port: crate::common::SerialPortArgs,
+}
+
+impl crate::ToolRun for DetectOpts {
+    fn run(&self) -> anyhow::Result<()> {
+        let mut client = k5lib::ClientHostStd::<_>::new_std(self.port.open()?);
+
+        match client.detect_mode() {
+            Ok(RadioMode::Bootloader) => eprintln!("Radio is in bootloader mode, ready to flash."),
+            Ok(RadioMode::Firmware) => eprintln!("Radio is running firmware."),
+            Err(DetectModeError::Timeout) => {
+                anyhow::bail!("No response from radio. Is it connected and turned on?")
+            }
+            Err(e) => anyhow::bail!(e),
+        }
+
+        Ok(())
+    }
+}
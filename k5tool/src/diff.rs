@@ -0,0 +1,213 @@
+use std::fmt::Write;
+
+use crate::hexdump::{Line, WIDTH};
+
+#[derive(clap::Args, Debug)]
+pub struct DiffOpts {
+    old: String,
+    new: String,
+    /// Unchanged rows to show around each changed row.
+    #[arg(short, long, default_value_t = 0)]
+    context: usize,
+    /// Only list the changed bytes, without rows.
+    #[arg(long, conflicts_with = "context")]
+    only_changed: bool,
+    /// Name the changed fields, using the stock EEPROM layout.
+    #[arg(long)]
+    eeprom: bool,
+}
+
+impl crate::ToolRun for DiffOpts {
+    fn run(&self) -> anyhow::Result<()> {
+        let old = std::fs::read(&self.old)?;
+        let new = std::fs::read(&self.new)?;
+
+        if old.len().max(new.len()) > u16::MAX as usize + 1 {
+            anyhow::bail!("Inputs must be 64KiB or smaller.");
+        }
+
+        if old == new {
+            eprintln!("Files are identical.");
+            return Ok(());
+        }
+
+        println!("--- {}", self.old);
+        println!("+++ {}", self.new);
+        print!("{}", self.format(&old, &new));
+        Ok(())
+    }
+}
+
+impl DiffOpts {
+    fn format(&self, old: &[u8], new: &[u8]) -> String {
+        let rows = old.len().max(new.len()).div_ceil(WIDTH);
+        fn row(data: &[u8], r: usize) -> &[u8] {
+            let start = (r * WIDTH).min(data.len());
+            let end = ((r + 1) * WIDTH).min(data.len());
+            &data[start..end]
+        }
+
+        let changed: Vec<bool> = (0..rows).map(|r| row(old, r) != row(new, r)).collect();
+        let context = if self.only_changed { 0 } else { self.context };
+        let shown = |r: usize| {
+            let start = r.saturating_sub(context);
+            let end = (r + context + 1).min(rows);
+            changed[start..end].iter().any(|c| *c)
+        };
+
+        let mut out = String::new();
+        let mut last = None;
+        for r in (0..rows).filter(|r| shown(*r)) {
+            let address = (r * WIDTH) as u16;
+
+            // separate rows that aren't next to each other
+            if !self.only_changed && last.is_some_and(|l| l + 1 != r) {
+                writeln!(out, "--").unwrap();
+            }
+            last = Some(r);
+
+            if !changed[r] {
+                writeln!(out, " {}", Line::new(address, row(old, r))).unwrap();
+                continue;
+            }
+
+            if !self.only_changed {
+                writeln!(out, "-{}", Line::new(address, row(old, r))).unwrap();
+                writeln!(out, "+{}", Line::new(address, row(new, r))).unwrap();
+            }
+
+            for i in r * WIDTH..(r + 1) * WIDTH {
+                let (o, n) = (old.get(i), new.get(i));
+                if o == n {
+                    continue;
+                }
+
+                let byte = |b: Option<&u8>| b.map(|b| format!("{:02x}", b)).unwrap_or("--".into());
+                write!(out, "    {:04x}: {} -> {}", i, byte(o), byte(n)).unwrap();
+                if let Some(field) = self.eeprom.then(|| eeprom_field(i)).flatten() {
+                    write!(out, "  {}", field).unwrap();
+                }
+                writeln!(out).unwrap();
+            }
+        }
+
+        out
+    }
+}
+
+/// Name the field at an address in the stock EEPROM layout, if known.
+fn eeprom_field(address: usize) -> Option<String> {
+    use k5lib::eeprom::*;
+
+    // index and offset into a table of entries
+    let entry = |base: usize, len: usize| {
+        let offset = address.checked_sub(base)?;
+        (offset < CHANNELS * len).then_some((offset / len, offset % len))
+    };
+
+    if let Some((i, offset)) = entry(CHANNEL_ADDRESS, CHANNEL_LEN) {
+        let (_, field) = Channel::FIELDS.iter().rev().find(|(o, _)| *o <= offset)?;
+        Some(format!("channels[{}].{}", i, field))
+    } else if let Some((i, _)) = entry(ATTRIBUTES_ADDRESS, 1) {
+        Some(format!("channels[{}].attributes", i))
+    } else if let Some((i, _)) = entry(NAMES_ADDRESS, CHANNEL_LEN) {
+        Some(format!("channels[{}].name", i))
+    } else {
+        let offset = address.checked_sub(SETTINGS_ADDRESS)?;
+        let field = Settings::FIELDS.get(offset)?;
+        Some(format!("settings.{}", field))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn opts(context: usize, only_changed: bool, eeprom: bool) -> DiffOpts {
+        DiffOpts {
+            old: String::new(),
+            new: String::new(),
+            context,
+            only_changed,
+            eeprom,
+        }
+    }
+
+    // two 0x40 byte images, differing in rows 1 and 3
+    fn images() -> (Vec<u8>, Vec<u8>) {
+        let old = vec![0xff; 0x40];
+        let mut new = old.clone();
+        new[0x12] = 0x01;
+        new[0x13] = 0x02;
+        new[0x3f] = 0x00;
+        (old, new)
+    }
+
+    #[test]
+    fn rows() {
+        let (old, new) = images();
+        let ff = Line::new(0x0010u16, &old[0x10..0x20]).to_string();
+        let mut changed = old[0x10..0x20].to_vec();
+        changed[2] = 0x01;
+        changed[3] = 0x02;
+        let changed = Line::new(0x0010u16, &changed).to_string();
+
+        let out = opts(0, false, false).format(&old, &new);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], format!("-{}", ff));
+        assert_eq!(lines[1], format!("+{}", changed));
+        assert_eq!(lines[2], "    0012: ff -> 01");
+        assert_eq!(lines[3], "    0013: ff -> 02");
+        assert_eq!(lines[4], "--");
+        assert!(lines[5].starts_with("-0030"));
+        assert!(lines[6].starts_with("+0030"));
+        assert_eq!(lines[7], "    003f: ff -> 00");
+        assert_eq!(lines.len(), 8);
+    }
+
+    #[test]
+    fn context() {
+        let (old, new) = images();
+        let out = opts(1, false, false).format(&old, &new);
+        let lines: Vec<&str> = out.lines().collect();
+
+        // every row is within one of a change, so no separators
+        assert!(lines[0].starts_with(" 0000"));
+        assert!(lines[1].starts_with("-0010"));
+        assert!(lines[5].starts_with(" 0020"));
+        assert!(lines[6].starts_with("-0030"));
+        assert!(!lines.contains(&"--"));
+    }
+
+    #[test]
+    fn only_changed() {
+        let (old, mut new) = images();
+        new.truncate(0x3e);
+        let out = opts(0, true, false).format(&old, &new);
+        assert_eq!(
+            out,
+            "    0012: ff -> 01\n    0013: ff -> 02\n    003e: ff -> --\n    003f: ff -> --\n"
+        );
+    }
+
+    #[test]
+    fn eeprom_annotations() {
+        let old = vec![0xff; k5lib::eeprom::EEPROM_SIZE];
+        let mut new = old.clone();
+        new[0x0014] = 0; // channel 1 offset
+        new[0x0d62] = 0; // channel 2 attributes
+        new[0x0e71] = 0; // squelch
+        new[0x0f55] = 0; // channel 0 name
+
+        let out = opts(0, true, true).format(&old, &new);
+        assert_eq!(
+            out,
+            "    0014: ff -> 00  channels[1].offset\n\
+             \x20   0d62: ff -> 00  channels[2].attributes\n\
+             \x20   0e71: ff -> 00  settings.squelch\n\
+             \x20   0f55: ff -> 00  channels[0].name\n"
+        );
+
+        assert_eq!(eeprom_field(0x1f00), None);
+    }
+}
@@ -1,8 +1,7 @@
 use std::io::{Read, Write};
 
 use k5lib::protocol::messages::bootloader::{
-    BootloaderReady, BootloaderReadyReply, WriteFlash, WriteFlashReply, WRITE_FLASH_LEN,
-    WRITE_FLASH_SESSION_ID,
+    WriteFlash, WriteFlashReply, WRITE_FLASH_LEN, WRITE_FLASH_SESSION_ID,
 };
 use k5lib::Version;
 
@@ -32,6 +31,24 @@ pub struct FlashOpts {
 
     #[arg(short, long)]
     attach: bool,
+
+    /// Start writing at this page, instead of from the beginning.
+    ///
+    /// Use this to pick up after a flash that failed partway, using
+    /// the page suggested by the failure message. This skips the
+    /// bootloader handshake, which has already happened, so it only
+    /// works if the radio is still in the bootloader from the failed
+    /// attempt and has not been restarted. Nothing checks that the
+    /// earlier pages hold the same image. Resuming with a different
+    /// image, or into a bootloader that has restarted, leaves a
+    /// partially written image that will not boot until it is flashed
+    /// again from the beginning.
+    #[arg(long, default_value_t = 0)]
+    resume_from: usize,
+    /// Retry a page this many times if the bootloader rejects it, or
+    /// does not confirm it, before giving up.
+    #[arg(long, default_value_t = 0)]
+    retries: usize,
 }
 
 impl crate::ToolRun for FlashOpts {
@@ -116,17 +133,66 @@ where
             assert!(max_page <= BOOTLOADER_START_PAGE);
         }
 
-        // wait for a bootloader ready message
-        let m = loop {
-            if let Some(m) = self.client.read::<BootloaderReady>()?.ok() {
-                break m;
-            }
-        };
+        let start_page = self.opts.resume_from;
+        if start_page >= max_page {
+            anyhow::bail!(
+                "Cannot resume from page {}, image only has {} pages.",
+                start_page,
+                max_page
+            );
+        }
 
+        if start_page == 0 {
+            self.handshake()?;
+        } else {
+            // the bootloader stops sending ready messages after the
+            // first write, so there is nothing to wait for here
+            eprintln!();
+            eprintln!(
+                "Resuming at page {} of {}, assuming earlier pages are written.",
+                start_page, max_page
+            );
+            crate::common::confirm("Continue flashing?", self.opts.yes)?;
+        }
+
+        let bar = crate::common::upload_bar((max_page * WRITE_FLASH_LEN) as u64);
+        bar.set_position((start_page * WRITE_FLASH_LEN) as u64);
+
+        let res = self.write_pages(start_page..max_page, max_page, |end| {
+            bar.set_position(end as u64)
+        });
+        if let Err(e) = res {
+            bar.abandon();
+            return Err(e);
+        }
+
+        bar.finish();
+
+        if self.opts.attach {
+            let elf = (self.info.format == crate::binformat::BinaryFormat::Elf)
+                .then_some(self.opts.firmware.as_str());
+            let mut console = crate::console::Console::new(&mut self.client, elf);
+            console.run()?;
+        }
+
+        Ok(())
+    }
+
+    /// Wait for the bootloader, and send it our version.
+    fn handshake(&mut self) -> anyhow::Result<()> {
         // make sure the user thinks any info printed is ok
         eprintln!();
         crate::common::confirm("Continue flashing?", self.opts.yes)?;
 
+        // ok, let's do this
+        let m = match self.client.bootloader_handshake(self.version.clone()) {
+            Ok(m) => m,
+            Err(k5lib::BootloaderHandshakeError::Timeout) => {
+                anyhow::bail!("No bootloader found. Is the radio in flash mode?")
+            }
+            Err(e) => anyhow::bail!(e),
+        };
+
         // report info
         eprint!("Connected to bootloader, version: ");
         if let Ok(v) = m.version.as_str() {
@@ -135,17 +201,21 @@ where
             eprintln!("{:x?}", m.version.as_bytes());
         }
 
-        // ok, let's do this
-        self.client.write(&BootloaderReadyReply {
-            version: self.version.clone(),
-        })?;
-
-        let bar = crate::common::upload_bar((max_page * WRITE_FLASH_LEN) as u64);
-
-        // we need a buffer in case one page is partially full, to add 0's
-        let mut chunk = [0; WRITE_FLASH_LEN];
+        Ok(())
+    }
 
-        for page in 0..max_page {
+    /// Write a range of pages, calling `progress` with the end of the
+    /// data written after each confirmed page.
+    fn write_pages(
+        &mut self,
+        pages: std::ops::Range<usize>,
+        max_page: usize,
+        mut progress: impl FnMut(usize),
+    ) -> anyhow::Result<()> {
+        // last page the bootloader confirmed, for resuming
+        let mut last_acked = pages.start.checked_sub(1);
+
+        for page in pages {
             // paranoia
             if !self.opts.ignore.contains(&crate::flash_lint::Ignores::Size) {
                 assert!(page < BOOTLOADER_START_PAGE);
@@ -154,49 +224,90 @@ where
             // figure out the extents of this page
             let start = page * WRITE_FLASH_LEN;
             let end = self.data.len().min(start + WRITE_FLASH_LEN);
-            let chunkend = end - start;
-
-            // copy this page into chunk, zero what's left over
-            chunk[..chunkend].copy_from_slice(&self.data[start..end]);
-            chunk[chunkend..].fill(0);
-
-            // write this chunk
-            self.client.write(&WriteFlash {
-                session_id: self.session_id,
-                page: page as u16,
-                max_page: max_page as u16,
-                len: chunkend as u16,
-                _pad: Default::default(),
-                data: &chunk[..],
-            })?;
-
-            // wait for a confirmation
-            let m = loop {
-                if let Some(m) = self.client.read::<WriteFlashReply>()?.ok() {
-                    break m;
-                }
-            };
 
-            if m.error > 0 {
-                anyhow::bail!("Bootloader reported error.");
+            // this zeroes what's left over in the last page
+            let msg = WriteFlash::new(
+                self.session_id,
+                page as u16,
+                max_page as u16,
+                &self.data[start..end],
+            )?;
+
+            let mut attempt = 0;
+            loop {
+                match self.write_page(&msg.borrow()) {
+                    Ok(()) => break,
+                    Err(e) if attempt < self.opts.retries => {
+                        attempt += 1;
+                        eprintln!(
+                            "Page {}: {}, retrying ({}/{}).",
+                            page, e, attempt, self.opts.retries
+                        );
+                    }
+                    Err(e) => {
+                        let resume = match last_acked {
+                            Some(p) => format!(
+                                "Last confirmed page was {}, use --resume-from {} to continue.",
+                                p,
+                                p + 1
+                            ),
+                            None => "No pages were confirmed.".to_owned(),
+                        };
+                        anyhow::bail!("Page {}: {}. {}", page, e, resume);
+                    }
+                }
             }
 
-            if m.session_id != self.session_id || m.page != page as u16 {
-                anyhow::bail!("Bootloader did not confirm write.");
+            last_acked = Some(page);
+            progress(end);
+        }
+
+        Ok(())
+    }
+
+    /// Write a single page, and wait for the bootloader to confirm it.
+    fn write_page(&mut self, msg: &WriteFlash<&[u8]>) -> anyhow::Result<()> {
+        self.client.write(msg)?;
+
+        // wait for a confirmation
+        let m = loop {
+            match self.client.read::<WriteFlashReply>() {
+                Ok(res) => {
+                    if let Some(m) = res.ok() {
+                        break m;
+                    }
+                }
+                Err(k5lib::ClientError::Io(e))
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+                    ) =>
+                {
+                    anyhow::bail!("bootloader did not reply");
+                }
+                Err(e) => return Err(e.into()),
             }
+        };
 
-            bar.set_position(end as u64);
+        if m.error > 0 {
+            anyhow::bail!("{}", write_flash_error(m.error));
         }
 
-        bar.finish();
-
-        if self.opts.attach {
-            let elf = (self.info.format == crate::binformat::BinaryFormat::Elf)
-                .then(|| self.opts.firmware.as_str());
-            let mut console = crate::console::Console::new(&mut self.client, elf);
-            console.run()?;
+        if m.session_id != self.session_id || m.page != msg.page {
+            anyhow::bail!("bootloader did not confirm write");
         }
 
         Ok(())
     }
 }
+
+/// Describe a non-zero error from a [WriteFlashReply].
+fn write_flash_error(error: u16) -> String {
+    // the bootloader doesn't say why, but these are the usual causes
+    format!(
+        "bootloader rejected the page with error {}, it may be out of \
+         sequence, or the bootloader may not have accepted the firmware \
+         version",
+        error
+    )
+}
@@ -0,0 +1,291 @@
+//! Malformed frames, for checking how the protocol clients recover.
+//!
+//! Every case is built around one valid message, and most end with an
+//! intact copy of it, so a client that recovers properly always parses
+//! that last frame. The cases are based on the edge cases in the
+//! `k5lib` parser tests:
+//!
+//!  * **valid**: a well-formed frame, for comparison.
+//!  * **garbage**: non-frame bytes before a frame.
+//!  * **bad length**: a frame start, with a length longer than any
+//!    frame can be.
+//!  * **bad end**: a frame with the wrong end marker.
+//!  * **bad crc**: a frame with a corrupted CRC. Host clients ignore
+//!    the CRC on radio frames, so this only fails on radio clients.
+//!  * **short body**: a message body shorter than its header says.
+//!  * **long body**: a message body longer than its header says.
+//!  * **unknown type**: a message type no client knows.
+//!  * **empty**: a frame with nothing in it, not even a message type.
+//!  * **start in body**: a frame start, with a length that runs into
+//!    the start of the next frame.
+//!  * **truncated frame**: a frame cut short, immediately followed by
+//!    a whole frame.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use k5lib::protocol::crc::{CrcConstantIgnore, CrcDigest, CrcStyle, CrcXModem};
+use k5lib::protocol::messages::{bootloader, radio};
+use k5lib::protocol::obfuscation::xor_block;
+use k5lib::protocol::serialize::SerializerVec;
+use k5lib::protocol::{MessageSerialize, ParseResult, FRAME_END, FRAME_START};
+
+/// Cases are fed to clients from memory.
+type Port = std::io::Cursor<Vec<u8>>;
+
+/// What a client reports for each frame it reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Ok,
+    CrcErr,
+    ParseErr,
+}
+
+/// A malformed input, and what a client should report reading it.
+#[derive(Debug, Clone)]
+pub struct Case {
+    pub name: &'static str,
+    pub data: Vec<u8>,
+    pub expected: Vec<Outcome>,
+}
+
+/// Build a frame around raw contents, with the given CRC value.
+fn frame(contents: &[u8], crc: u16) -> Vec<u8> {
+    let mut body = contents.to_vec();
+    body.extend(crc.to_le_bytes());
+    xor_block(&mut body, 0);
+
+    let mut frame = FRAME_START.to_vec();
+    frame.extend((contents.len() as u16).to_le_bytes());
+    frame.extend(body);
+    frame.extend(FRAME_END);
+    frame
+}
+
+/// Build a frame around raw contents, with a correct CRC.
+fn frame_crc(crc: &impl CrcStyle, contents: &[u8]) -> Vec<u8> {
+    let mut digest = crc.digest();
+    digest.update(contents);
+    frame(contents, digest.finalize())
+}
+
+/// Generate every malformed case around `msg`, framed with `crc`.
+///
+/// Set `crc_checked` if the client reading these checks the CRC.
+pub fn cases(msg: &impl MessageSerialize, crc: &impl CrcStyle, crc_checked: bool) -> Vec<Case> {
+    let mut ser = SerializerVec::new();
+    msg.frame_body(&mut ser).unwrap_or_else(|e| match e {});
+    let contents = ser.done();
+    let good = frame_crc(crc, &contents);
+
+    let then_good = |mut data: Vec<u8>| {
+        data.extend(&good);
+        data
+    };
+
+    let mut cases = vec![
+        Case {
+            name: "valid",
+            data: good.clone(),
+            expected: vec![Outcome::Ok],
+        },
+        Case {
+            name: "garbage",
+            data: then_good(b"junk\x00\xff".to_vec()),
+            expected: vec![Outcome::Ok],
+        },
+        Case {
+            name: "bad length",
+            data: then_good([&FRAME_START[..], &b"\xff\xffjunk"[..]].concat()),
+            expected: vec![Outcome::Ok],
+        },
+    ];
+
+    let mut bad_end = good.clone();
+    let last = bad_end.len() - 1;
+    bad_end[last] ^= 0xff;
+    cases.push(Case {
+        name: "bad end",
+        data: then_good(bad_end),
+        expected: vec![Outcome::Ok],
+    });
+
+    let mut digest = crc.digest();
+    digest.update(&contents);
+    let bad_crc = frame(&contents, !digest.finalize());
+    cases.push(Case {
+        name: "bad crc",
+        data: then_good(bad_crc),
+        expected: if crc_checked {
+            vec![Outcome::CrcErr, Outcome::Ok]
+        } else {
+            vec![Outcome::Ok, Outcome::Ok]
+        },
+    });
+
+    let short = frame_crc(crc, &contents[..contents.len() - 1]);
+    cases.push(Case {
+        name: "short body",
+        data: then_good(short),
+        expected: vec![Outcome::ParseErr, Outcome::Ok],
+    });
+
+    // message parsers ignore anything after the fields they know about
+    let long = frame_crc(crc, &[&contents[..], &[0x00][..]].concat());
+    cases.push(Case {
+        name: "long body",
+        data: then_good(long),
+        expected: vec![Outcome::Ok, Outcome::Ok],
+    });
+
+    let mut unknown = contents.clone();
+    unknown[..2].copy_from_slice(&0xffffu16.to_le_bytes());
+    cases.push(Case {
+        name: "unknown type",
+        data: then_good(frame_crc(crc, &unknown)),
+        expected: vec![Outcome::ParseErr, Outcome::Ok],
+    });
+
+    cases.push(Case {
+        name: "empty",
+        data: then_good(frame_crc(crc, &[])),
+        expected: vec![Outcome::ParseErr, Outcome::Ok],
+    });
+
+    // length 4, so the end marker should be where the next frame starts
+    cases.push(Case {
+        name: "start in body",
+        data: then_good([&FRAME_START[..], &b"\x04\x00junk!!"[..]].concat()),
+        expected: vec![Outcome::Ok],
+    });
+
+    cases.push(Case {
+        name: "truncated frame",
+        data: then_good(good[..good.len() - 3].to_vec()),
+        expected: vec![Outcome::Ok],
+    });
+
+    cases
+}
+
+/// Sort a parse result into an [Outcome], or [None] if no frame
+/// was found.
+fn outcome<I, O>(result: ParseResult<I, O>) -> Option<Outcome> {
+    match result {
+        ParseResult::Ok(_, _) => Some(Outcome::Ok),
+        ParseResult::CrcErr(_, _) => Some(Outcome::CrcErr),
+        ParseResult::ParseErr(_, _, _) => Some(Outcome::ParseErr),
+        ParseResult::None => None,
+    }
+}
+
+/// Read every frame in `client` with `read`, until the input runs out.
+fn outcomes<C>(
+    client: &mut C,
+    mut read: impl FnMut(&mut C) -> Result<Option<Outcome>, k5lib::ClientError<std::io::Error>>,
+) -> Result<Vec<Outcome>, k5lib::ClientError<std::io::Error>> {
+    let mut outcomes = Vec::new();
+    loop {
+        match read(client) {
+            Ok(Some(outcome)) => outcomes.push(outcome),
+            Ok(None) => {}
+            Err(k5lib::ClientError::UnexpectedEof) => return Ok(outcomes),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// The result of running one [Case] through a client.
+#[derive(Debug)]
+pub struct Report {
+    pub client: &'static str,
+    pub case: Case,
+    /// The outcomes seen, or a description of what went wrong instead.
+    pub result: Result<Vec<Outcome>, String>,
+}
+
+impl Report {
+    /// Did the client report exactly what was expected?
+    pub fn passed(&self) -> bool {
+        self.result.as_ref() == Ok(&self.case.expected)
+    }
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let status = if self.passed() { "pass" } else { "FAIL" };
+        write!(f, "{} {:<6} {:<16}", status, self.client, self.case.name)?;
+        match &self.result {
+            Ok(outcomes) if self.passed() => write!(f, "{:?}", outcomes),
+            Ok(outcomes) => write!(f, "{:?}, expected {:?}", outcomes, self.case.expected),
+            Err(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Run one case through a fresh client, catching any panic.
+fn run_case<C>(
+    client: &'static str,
+    case: Case,
+    new: impl FnOnce(Port) -> C,
+    read: impl FnMut(&mut C) -> Result<Option<Outcome>, k5lib::ClientError<std::io::Error>>,
+) -> Report {
+    let port = Port::new(case.data.clone());
+    let result = match catch_unwind(AssertUnwindSafe(|| outcomes(&mut new(port), read))) {
+        Ok(Ok(outcomes)) => Ok(outcomes),
+        Ok(Err(e)) => Err(format!("client error: {}", e)),
+        Err(_) => Err("panicked".to_owned()),
+    };
+    Report {
+        client,
+        case,
+        result,
+    }
+}
+
+/// Run every case through both a radio and a host client.
+///
+/// The radio client reads host messages with a checked CRC, and the
+/// host client reads radio messages with the CRC ignored, just like
+/// the real thing.
+pub fn run_all() -> Vec<Report> {
+    let mut reports = Vec::new();
+
+    let hello = radio::Hello {
+        session_id: radio::HELLO_SESSION_ID,
+    };
+    for case in cases(&hello, &CrcXModem::new(), true) {
+        reports.push(run_case(
+            "radio",
+            case,
+            k5lib::ClientRadioStd::<Port>::new_std,
+            |c| c.read_host().map(outcome),
+        ));
+    }
+
+    let ready = bootloader::BootloaderReady {
+        chip_id: bootloader::ChipId::Long([0x01234567, 0x89abcdef, 0xfedcba98, 0x76543210]),
+        version: k5lib::Version::new_from_str("k5sim").unwrap(),
+    };
+    for case in cases(&ready, &CrcConstantIgnore(0xffff), false) {
+        reports.push(run_case(
+            "host",
+            case,
+            k5lib::ClientHostStd::<Port>::new_std,
+            |c| c.read_radio().map(outcome),
+        ));
+    }
+
+    reports
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn all_cases_pass() {
+        for report in run_all() {
+            assert!(report.passed(), "{}", report);
+        }
+    }
+}
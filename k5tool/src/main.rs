@@ -2,17 +2,22 @@ pub mod binformat;
 pub mod common;
 pub mod debug;
 pub mod flash_lint;
+pub mod fuzz;
 pub mod hexdump;
 pub mod packed;
 
 pub mod console;
+mod detect;
+mod diff;
 mod flash;
 mod flash_info;
 mod pack;
 mod parsedump;
+mod radio_info;
 mod read_eeprom;
 mod simulate;
 mod unpack;
+mod write_eeprom;
 
 trait ToolRun {
     fn run(&self) -> anyhow::Result<()> {
@@ -30,14 +35,18 @@ struct ToolOptions {
 #[derive(clap::Subcommand, Debug)]
 enum ToolCommand {
     Console(console::ConsoleOpts),
+    Detect(detect::DetectOpts),
+    Diff(diff::DiffOpts),
     Flash(flash::FlashOpts),
     FlashInfo(flash_info::FlashInfoOpts),
     ListPorts(ListPortsOpts),
     Pack(pack::PackOpts),
     ParseDump(parsedump::ParseDumpOpts),
+    RadioInfo(radio_info::RadioInfoOpts),
     ReadEeprom(read_eeprom::ReadEepromOpts),
     Simulate(simulate::SimulateOpts),
     Unpack(unpack::UnpackOpts),
+    WriteEeprom(write_eeprom::WriteEepromOpts),
 }
 
 impl ToolRun for ToolCommand {
@@ -45,14 +54,18 @@ impl ToolRun for ToolCommand {
         use ToolCommand::*;
         match self {
             Console(o) => o.run(),
+            Detect(o) => o.run(),
+            Diff(o) => o.run(),
             Flash(o) => o.run(),
             FlashInfo(o) => o.run(),
             ListPorts(o) => o.run(),
             Pack(o) => o.run(),
             ParseDump(o) => o.run(),
+            RadioInfo(o) => o.run(),
             ReadEeprom(o) => o.run(),
             Simulate(o) => o.run(),
             Unpack(o) => o.run(),
+            WriteEeprom(o) => o.run(),
         }
     }
 }
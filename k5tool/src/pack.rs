@@ -9,6 +9,16 @@ pub struct PackOpts {
     // file will silently produce garbage. if the user wants raw, they can say
     #[arg(long, value_enum, default_value = "elf")]
     format: crate::binformat::BinaryFormat,
+    /// Output an encrypted stock image. This is the default, use
+    /// --encrypt=false to write a raw flash image instead.
+    #[arg(
+        long,
+        default_value_t = true,
+        default_missing_value = "true",
+        num_args = 0..=1,
+        action = clap::ArgAction::Set,
+    )]
+    encrypt: bool,
 }
 
 impl crate::ToolRun for PackOpts {
@@ -22,6 +32,12 @@ impl crate::ToolRun for PackOpts {
         let (unpacked, info) =
             crate::binformat::read_firmware(&self.firmware, self.format, version)?;
 
+        if !self.encrypt {
+            info.report();
+            std::fs::write(&self.packed, &unpacked[..])?;
+            return Ok(());
+        }
+
         let version = info.version.clone().ok_or(anyhow::anyhow!(
             "image has no version, use --version to provide one"
         ))?;
@@ -1,28 +1,225 @@
+use std::io::Write;
+
+use k5lib::encode::HexSlice;
+use k5lib::protocol::messages::Direction;
+use k5lib::protocol::serialize::SerializerVec;
+use k5lib::protocol::{self, crc, Message, MessageSerialize, ParseResult};
+
+/// Output formats for parsedump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, clap::ValueEnum)]
+pub enum DumpFormat {
+    /// Human-readable messages, as printed by --debug.
+    Text,
+    /// One JSON object per frame, per line.
+    Json,
+    /// One CSV row per frame, with a hex body column.
+    Csv,
+}
+
 #[derive(clap::Args, Debug)]
 pub struct ParseDumpOpts {
     dumpfile: String,
+    #[arg(long, value_enum, default_value = "text")]
+    format: DumpFormat,
     #[command(flatten)]
     debug: crate::debug::DebugClientArgs,
 }
 
+/// Result of the CRC check on a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CrcStatus {
+    Ok,
+    Error,
+}
+
+/// A single frame in a dump, decoded as far as possible.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Record {
+    /// Start of the frame, as a byte offset into the dump file.
+    pub start: usize,
+    /// End of the frame, exclusive.
+    pub end: usize,
+    #[serde(serialize_with = "serialize_direction")]
+    pub direction: Direction,
+    pub crc: CrcStatus,
+    /// Message type, if the message parsed.
+    #[serde(rename = "type")]
+    pub message_type: Option<u16>,
+    /// Message body, or the raw frame body if the message did not parse.
+    #[serde(serialize_with = "serialize_hex")]
+    pub body: Vec<u8>,
+    /// The decoded message, if it parsed.
+    pub message: Option<Message<Vec<u8>>>,
+}
+
+fn serialize_hex<S>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.collect_str(&HexSlice(data))
+}
+
+fn serialize_direction<S>(direction: &Direction, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_str(direction_str(*direction))
+}
+
+fn direction_str(direction: Direction) -> &'static str {
+    match direction {
+        Direction::HostToRadio => "host-to-radio",
+        Direction::RadioToHost => "radio-to-host",
+    }
+}
+
+impl Record {
+    fn new<'a, M>(
+        offset: usize,
+        direction: Direction,
+        res: ParseResult<&'a [u8], M>,
+        f: impl FnOnce(M) -> Message<&'a [u8]>,
+    ) -> Option<Self> {
+        let (range, crc, body, message) = match res {
+            ParseResult::Ok(range, m) => {
+                let m = f(m);
+                let mut ser = SerializerVec::new();
+                m.message_body(&mut ser).unwrap_or_else(|e| match e {});
+                (range, CrcStatus::Ok, ser.done(), Some(m))
+            }
+            ParseResult::ParseErr(range, body, _) => (range, CrcStatus::Ok, body.to_vec(), None),
+            ParseResult::CrcErr(range, body) => (range, CrcStatus::Error, body.to_vec(), None),
+            ParseResult::None => return None,
+        };
+
+        Some(Self {
+            start: offset + range.start,
+            end: offset + range.end,
+            // frames that did not parse only have the dump to go on
+            direction: message.as_ref().map_or(direction, |m| m.direction()),
+            crc,
+            message_type: message.as_ref().map(|m| m.message_type()),
+            body,
+            message: message.map(|m| m.map(|b| b.to_vec())),
+        })
+    }
+
+    /// The name of the message, if it parsed.
+    fn message_name(&self) -> Option<&'static str> {
+        self.message.as_ref().map(|m| m.name())
+    }
+}
+
+/// Iterate over the frames in a dump, with their offset in the dump
+/// and the direction byte.
+fn frames(rawdata: &[u8]) -> impl Iterator<Item = (usize, u8, &[u8])> {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        let raw = &rawdata[offset..];
+        if raw.len() < 3 {
+            return None;
+        }
+        let dir = raw[0];
+        let len = ((raw[1] as u16) | ((raw[2] as u16) << 8)) as usize;
+        let frameraw = &raw[3..(3 + len).min(raw.len())];
+
+        let start = offset + 3;
+        offset = start + frameraw.len();
+        Some((start, dir, frameraw))
+    })
+}
+
+/// Decode every frame in a dump.
+pub fn records(rawdata: &[u8]) -> anyhow::Result<Vec<Record>> {
+    let mut records = Vec::new();
+    for (offset, dir, frameraw) in frames(rawdata) {
+        // parsing deobfuscates in place
+        let mut frameraw = frameraw.to_vec();
+
+        // see the text output below for why these are flipped
+        if dir == crate::debug::ClientDirection::Host.flip() as u8 {
+            // the radio sends a constant CRC, which the host ignores
+            let crc = crc::CrcConstantIgnore(0xffff);
+            for res in protocol::parse_all(crc, &mut frameraw) {
+                records.extend(Record::new(
+                    offset,
+                    Direction::RadioToHost,
+                    res,
+                    Message::Radio,
+                ));
+            }
+        } else if dir == crate::debug::ClientDirection::Radio.flip() as u8 {
+            let crc = crc::CrcXModem::new();
+            for res in protocol::parse_all(crc, &mut frameraw) {
+                records.extend(Record::new(
+                    offset,
+                    Direction::HostToRadio,
+                    res,
+                    Message::Host,
+                ));
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Write records as JSON, one object per line.
+pub fn write_json<W: Write>(mut out: W, records: &[Record]) -> anyhow::Result<()> {
+    for record in records {
+        serde_json::to_writer(&mut out, record)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Write records as CSV, with a header row.
+pub fn write_csv<W: Write>(mut out: W, records: &[Record]) -> anyhow::Result<()> {
+    writeln!(out, "start,end,direction,crc,type,name,body")?;
+    for record in records {
+        let crc = match record.crc {
+            CrcStatus::Ok => "ok",
+            CrcStatus::Error => "error",
+        };
+        let typ = record
+            .message_type
+            .map(|t| format!("0x{:04x}", t))
+            .unwrap_or_default();
+
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{}",
+            record.start,
+            record.end,
+            direction_str(record.direction),
+            crc,
+            typ,
+            record.message_name().unwrap_or_default(),
+            HexSlice(&record.body),
+        )?;
+    }
+    Ok(())
+}
+
 impl crate::ToolRun for ParseDumpOpts {
     fn run(&self) -> anyhow::Result<()> {
+        let rawdata = std::fs::read(&self.dumpfile)?;
+
+        match self.format {
+            DumpFormat::Text => self.run_text(&rawdata),
+            DumpFormat::Json => write_json(std::io::stdout().lock(), &records(&rawdata)?),
+            DumpFormat::Csv => write_csv(std::io::stdout().lock(), &records(&rawdata)?),
+        }
+    }
+}
+
+impl ParseDumpOpts {
+    fn run_text(&self, rawdata: &[u8]) -> anyhow::Result<()> {
         // command only makes sense *at all* for debug >= 1
         let mut debug = self.debug.clone();
         debug.debug = debug.debug.max(1);
 
-        let rawdata = std::fs::read(&self.dumpfile)?;
-        let mut raw = &rawdata[..];
-
-        loop {
-            if raw.len() < 3 {
-                break;
-            }
-            let dir = raw[0];
-            let len = ((raw[1] as u16) | ((raw[2] as u16) << 8)) as usize;
-            let frameraw = &raw[3..3 + len];
-            raw = &raw[3 + len..];
-
+        for (_, dir, frameraw) in frames(rawdata) {
             // direction is the type this client *writes*, so to read, we flip
             if dir == crate::debug::ClientDirection::Host.flip() as u8 {
                 // radio -> computer, so act like host
@@ -39,3 +236,155 @@ impl crate::ToolRun for ParseDumpOpts {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use k5lib::protocol::crc::{CrcConstant, CrcXModem};
+    use k5lib::protocol::messages::radio::{Hello, HelloReply, HELLO_SESSION_ID};
+    use k5lib::protocol::serialize::MessageSerialize;
+
+    use super::*;
+
+    fn push_frame<M: MessageSerialize>(
+        dump: &mut Vec<u8>,
+        dir: crate::debug::ClientDirection,
+        crc: &impl k5lib::protocol::crc::CrcStyle,
+        msg: &M,
+    ) {
+        let mut ser = SerializerVec::new();
+        msg.frame(crc, &mut ser).unwrap_or_else(|e| match e {});
+        let raw = ser.done();
+        dump.push(dir as u8);
+        dump.extend((raw.len() as u16).to_le_bytes());
+        dump.extend(raw);
+    }
+
+    fn capture() -> Vec<u8> {
+        let mut dump = Vec::new();
+        push_frame(
+            &mut dump,
+            crate::debug::ClientDirection::Host,
+            &CrcXModem::new(),
+            &Hello {
+                session_id: HELLO_SESSION_ID,
+            },
+        );
+        push_frame(
+            &mut dump,
+            crate::debug::ClientDirection::Radio,
+            &CrcConstant(0xffff),
+            &HelloReply {
+                version: k5lib::Version::new_from_str("2.01.26").unwrap(),
+                has_custom_aes_key: false,
+                is_in_lock_screen: false,
+                _pad: Default::default(),
+                challenge: [1, 2, 3, 4],
+            },
+        );
+        // host frame with a bad CRC
+        push_frame(
+            &mut dump,
+            crate::debug::ClientDirection::Host,
+            &CrcConstant(0x1234),
+            &Hello { session_id: 0 },
+        );
+        dump
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let dump = capture();
+        let records = records(&dump).unwrap();
+        let mut out = Vec::new();
+        write_json(&mut out, &records).unwrap();
+
+        let lines: Vec<serde_json::Value> = std::str::from_utf8(&out)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+
+        for line in lines.iter() {
+            let mut keys: Vec<&str> = line
+                .as_object()
+                .unwrap()
+                .keys()
+                .map(|k| k.as_str())
+                .collect();
+            keys.sort();
+            assert_eq!(
+                keys,
+                [
+                    "body",
+                    "crc",
+                    "direction",
+                    "end",
+                    "message",
+                    "start",
+                    "type"
+                ]
+            );
+        }
+
+        // first frame starts after the 3-byte dump header
+        assert_eq!(lines[0]["start"], 3);
+        assert_eq!(lines[0]["end"], 3 + (dump[1] as u64));
+        assert_eq!(lines[0]["direction"], "host-to-radio");
+        assert_eq!(lines[0]["crc"], "ok");
+        assert_eq!(lines[0]["type"], 0x0514);
+        assert_eq!(lines[0]["body"], "6a395764");
+        assert_eq!(
+            lines[0]["message"]["Host"]["Hello"]["session_id"],
+            HELLO_SESSION_ID
+        );
+
+        assert_eq!(lines[1]["direction"], "radio-to-host");
+        assert_eq!(
+            lines[1]["message"]["Radio"]["HelloReply"]["version"],
+            "2.01.26"
+        );
+
+        assert_eq!(lines[2]["crc"], "error");
+        assert!(lines[2]["message"].is_null());
+        assert!(lines[2]["type"].is_null());
+    }
+
+    #[test]
+    fn csv() {
+        let records = records(&capture()).unwrap();
+        let mut out = Vec::new();
+        write_csv(&mut out, &records).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "start,end,direction,crc,type,name,body");
+        assert!(lines[1].ends_with(",host-to-radio,ok,0x0514,Hello,6a395764"));
+        assert!(lines[2].contains(",radio-to-host,ok,0x0515,Hello Reply,"));
+        assert!(lines[3].contains(",host-to-radio,error,,,"));
+    }
+
+    #[test]
+    fn several_frames_per_read() {
+        let mut raw = b"junk".to_vec();
+        for session_id in [1, 2] {
+            let mut ser = SerializerVec::new();
+            Hello { session_id }
+                .frame(&CrcXModem::new(), &mut ser)
+                .unwrap_or_else(|e| match e {});
+            raw.extend(ser.done());
+        }
+
+        let mut dump = vec![crate::debug::ClientDirection::Host as u8];
+        dump.extend((raw.len() as u16).to_le_bytes());
+        dump.extend(&raw);
+
+        let records = records(&dump).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].start, 3 + 4);
+        assert_eq!(records[0].end, records[1].start);
+        assert_eq!(records[1].end, dump.len());
+        assert!(records.iter().all(|r| r.crc == CrcStatus::Ok));
+    }
+}
@@ -0,0 +1,174 @@
+use k5lib::protocol::messages::{bootloader, radio};
+use k5lib::{RadioIdentity, RadioMode, Version};
+
+#[derive(clap::Args, Debug)]
+pub struct RadioInfoOpts {
+    /// Print the radio's details as JSON.
+    #[arg(long)]
+    json: bool,
+    #[command(flatten)]
+    port: crate::common::SerialPortArgs,
+}
+
+impl crate::ToolRun for RadioInfoOpts {
+    fn run(&self) -> anyhow::Result<()> {
+        let mut client = k5lib::ClientHostStd::<_>::new_std(self.port.open()?);
+        let info = match client.identify() {
+            Ok(id) => FlashInfo::from(id),
+            Err(k5lib::DetectModeError::Timeout) => {
+                anyhow::bail!("No response from radio. Is it connected and turned on?")
+            }
+            Err(e) => anyhow::bail!(e),
+        };
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&info)?);
+        } else {
+            print!("{}", info);
+        }
+        Ok(())
+    }
+}
+
+/// A snapshot of a radio's identity, taken before flashing, as
+/// printed by `radio-info`.
+///
+/// The bootloader only reports its chip ID and version, and the
+/// firmware only reports its version and lock status, so which fields
+/// are present depends on the mode the radio is in. Neither reports
+/// the flash size, and neither can read NVR over the serial protocol.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct FlashInfo {
+    pub mode: Mode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chip_id: Option<Vec<u32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bootloader_version: Option<Version>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub firmware_version: Option<Version>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lock_screen: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_aes_key: Option<bool>,
+}
+
+/// The mode a radio was in, for [FlashInfo].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Mode {
+    Bootloader,
+    Firmware,
+}
+
+impl From<RadioMode> for Mode {
+    fn from(value: RadioMode) -> Self {
+        match value {
+            RadioMode::Bootloader => Mode::Bootloader,
+            RadioMode::Firmware => Mode::Firmware,
+        }
+    }
+}
+
+impl From<&bootloader::BootloaderReady> for FlashInfo {
+    fn from(ready: &bootloader::BootloaderReady) -> Self {
+        Self {
+            mode: Mode::Bootloader,
+            chip_id: Some(ready.chip_id.as_slice().to_vec()),
+            bootloader_version: Some(ready.version.clone()),
+            firmware_version: None,
+            lock_screen: None,
+            custom_aes_key: None,
+        }
+    }
+}
+
+impl From<&radio::HelloReply> for FlashInfo {
+    fn from(reply: &radio::HelloReply) -> Self {
+        Self {
+            mode: Mode::Firmware,
+            chip_id: None,
+            bootloader_version: None,
+            firmware_version: Some(reply.version.clone()),
+            lock_screen: Some(reply.is_in_lock_screen),
+            custom_aes_key: Some(reply.has_custom_aes_key),
+        }
+    }
+}
+
+impl From<RadioIdentity> for FlashInfo {
+    fn from(id: RadioIdentity) -> Self {
+        match id {
+            RadioIdentity::Bootloader(ref m) => m.into(),
+            RadioIdentity::Firmware(ref m) => m.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for FlashInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fn yes_no(v: bool) -> &'static str {
+            if v {
+                "yes"
+            } else {
+                "no"
+            }
+        }
+
+        let mode = match self.mode {
+            Mode::Bootloader => "bootloader",
+            Mode::Firmware => "firmware",
+        };
+        writeln!(f, "Mode:               {}", mode)?;
+        if let Some(ref chip_id) = self.chip_id {
+            write!(f, "Chip ID:           ")?;
+            for word in chip_id {
+                write!(f, " {:08x}", word)?;
+            }
+            writeln!(f)?;
+        }
+        if let Some(ref v) = self.bootloader_version {
+            writeln!(f, "Bootloader version: {}", v)?;
+        }
+        if let Some(ref v) = self.firmware_version {
+            writeln!(f, "Firmware version:   {}", v)?;
+        }
+        if let Some(v) = self.lock_screen {
+            writeln!(f, "Lock screen:        {}", yes_no(v))?;
+        }
+        if let Some(v) = self.custom_aes_key {
+            writeln!(f, "Custom AES key:     {}", yes_no(v))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_bootloader_ready() {
+        let ready = bootloader::BootloaderReady {
+            chip_id: bootloader::ChipId::Long([0x01234567, 0x89abcdef, 0xfedcba98, 0x76543210]),
+            version: Version::new_from_str("2.00.06").unwrap(),
+        };
+        let info = FlashInfo::from(RadioIdentity::Bootloader(ready));
+        assert_eq!(Mode::from(RadioMode::Bootloader), info.mode);
+
+        assert_eq!(
+            info.to_string(),
+            "Mode:               bootloader\n\
+             Chip ID:            01234567 89abcdef fedcba98 76543210\n\
+             Bootloader version: 2.00.06\n"
+        );
+
+        assert_eq!(
+            serde_json::to_value(&info).unwrap(),
+            serde_json::json!({
+                "mode": "bootloader",
+                "chip_id": [0x01234567, 0x89abcdef_u32, 0xfedcba98_u32, 0x76543210],
+                "bootloader_version": "2.00.06",
+            })
+        );
+    }
+}
@@ -1,8 +1,13 @@
 use std::io::{Read, Write};
 
+use k5lib::protocol::crc::CrcConstantIgnore;
 use k5lib::protocol::messages::{bootloader, radio};
 use k5lib::protocol::{HostMessage, ParseResult};
 
+/// EEPROM range holding the radio password. The stock firmware refuses
+/// writes here while in the lock screen, unless asked to allow them.
+const PASSWORD_AREA: std::ops::Range<usize> = 0x0e98..0x0ea0;
+
 #[derive(clap::Args, Debug)]
 pub struct SimulateOpts {
     #[arg(default_value = "localhost:8855")]
@@ -14,6 +19,26 @@ pub struct SimulateOpts {
     #[arg(short, long)]
     boot: bool,
 
+    /// Instead of simulating a radio, check the protocol clients
+    /// against malformed frames, then send the same frames to every
+    /// client that connects. The frames are built around a
+    /// BootloaderReady with --boot, or a HelloReply otherwise.
+    #[arg(long)]
+    fuzz: bool,
+
+    /// Report a custom AES key in the HelloReply.
+    #[arg(long)]
+    custom_aes_key: bool,
+    /// Report being in the lock screen in the HelloReply.
+    #[arg(long)]
+    lock_screen: bool,
+    /// AES challenge to send in the HelloReply.
+    #[arg(long, num_args = 4, default_values_t = [0, 0, 0, 0])]
+    challenge: Vec<u32>,
+
+    /// EEPROM image to serve, and to write back to on disconnect.
+    #[arg(long, conflicts_with_all = ["initial_eeprom", "dump_eeprom"])]
+    eeprom: Option<String>,
     #[arg(long)]
     initial_eeprom: Option<String>,
     #[arg(long)]
@@ -31,8 +56,18 @@ pub struct SimulateOpts {
 
 impl crate::ToolRun for SimulateOpts {
     fn run(&self) -> anyhow::Result<()> {
+        if self.fuzz {
+            fuzz()?;
+            return self.serve_fuzz();
+        }
+
         let mut eeprom = if let Some(ref initial_eeprom_path) = self.initial_eeprom {
             std::fs::read(initial_eeprom_path)?
+        } else if let Some(eeprom_path) = self.eeprom.as_ref().filter(|p| {
+            // a missing image starts blank, and is created on disconnect
+            std::path::Path::new(p).exists()
+        }) {
+            std::fs::read(eeprom_path)?
         } else {
             vec![0; self.eeprom_size]
         };
@@ -63,6 +98,9 @@ impl crate::ToolRun for SimulateOpts {
             let client = k5lib::ClientRadio::new_std(stream);
             let client = self.debug.wrap_radio(client)?;
             if let Err(e) = Simulator::new(client, self, &mut eeprom, &mut flash).simulate() {
+                // save whatever we have, even if this is a real error
+                self.persist(&eeprom, &flash)?;
+
                 match e.downcast_ref::<k5lib::ClientError<std::io::Error>>() {
                     // an expected error, at disconnect
                     Some(k5lib::ClientError::UnexpectedEof) => {}
@@ -79,17 +117,99 @@ impl crate::ToolRun for SimulateOpts {
 
                 // if we make it here, it's an expected error
                 println!("Disconnected from {}.", addr);
+            }
+        }
+    }
+}
 
-                if let Some(ref eeprom_path) = self.dump_eeprom {
-                    std::fs::write(eeprom_path, &eeprom)?;
-                }
+/// Write each case to `out` in turn, logging what the client should
+/// report for it.
+fn send_fuzz<W: Write>(out: &mut W, cases: &[crate::fuzz::Case]) -> std::io::Result<()> {
+    for case in cases {
+        println!("Sending {}, expecting {:?}.", case.name, case.expected);
+        out.write_all(&case.data)?;
+        out.flush()?;
+    }
+    Ok(())
+}
 
-                if let Some(ref flash_path) = self.dump_flash {
-                    std::fs::write(flash_path, &flash)?;
-                }
+/// Run every [crate::fuzz] case, and fail if any are mishandled.
+fn fuzz() -> anyhow::Result<()> {
+    let reports = crate::fuzz::run_all();
+    for report in reports.iter() {
+        println!("{}", report);
+    }
+
+    let failed = reports.iter().filter(|r| !r.passed()).count();
+    if failed > 0 {
+        anyhow::bail!("{} of {} cases failed", failed, reports.len());
+    }
+    println!("All {} cases passed.", reports.len());
+    Ok(())
+}
+
+impl SimulateOpts {
+    fn boot_ready(&self) -> anyhow::Result<bootloader::BootloaderReady> {
+        Ok(bootloader::BootloaderReady {
+            chip_id: bootloader::ChipId::Long([0x01234567, 0x89abcdef, 0xfedcba98, 0x76543210]),
+            version: k5lib::Version::new_from_str(&self.version)?,
+        })
+    }
+
+    fn hello_reply(&self) -> anyhow::Result<radio::HelloReply> {
+        let mut challenge = [0; 4];
+        challenge.copy_from_slice(&self.challenge);
+        Ok(radio::HelloReply {
+            version: k5lib::Version::new_from_str(&self.version)?,
+            has_custom_aes_key: self.custom_aes_key,
+            is_in_lock_screen: self.lock_screen,
+            _pad: Default::default(),
+            challenge,
+        })
+    }
+
+    /// Every [crate::fuzz] case around the first message this radio
+    /// would send to a host.
+    fn fuzz_cases(&self) -> anyhow::Result<Vec<crate::fuzz::Case>> {
+        // hosts ignore the CRC on radio frames
+        let crc = CrcConstantIgnore(0xffff);
+        Ok(if self.boot {
+            crate::fuzz::cases(&self.boot_ready()?, &crc, false)
+        } else {
+            crate::fuzz::cases(&self.hello_reply()?, &crc, false)
+        })
+    }
+
+    /// Send every fuzz case to each client that connects, then hang up.
+    fn serve_fuzz(&self) -> anyhow::Result<()> {
+        let cases = self.fuzz_cases()?;
+
+        let listener = std::net::TcpListener::bind(&self.bind)?;
+        println!("Listening on {}.", self.bind);
+
+        loop {
+            let (mut stream, addr) = listener.accept()?;
+            println!("Connected to {}.", addr);
+
+            match send_fuzz(&mut stream, &cases) {
+                Ok(()) => println!("Sent {} cases to {}.", cases.len(), addr),
+                // the client may well give up on us part way
+                Err(e) => println!("Disconnected from {}: {}", addr, e),
             }
         }
     }
+
+    fn persist(&self, eeprom: &[u8], flash: &[u8]) -> anyhow::Result<()> {
+        if let Some(eeprom_path) = self.eeprom.as_ref().or(self.dump_eeprom.as_ref()) {
+            std::fs::write(eeprom_path, eeprom)?;
+        }
+
+        if let Some(ref flash_path) = self.dump_flash {
+            std::fs::write(flash_path, flash)?;
+        }
+
+        Ok(())
+    }
 }
 
 struct Simulator<'a, F> {
@@ -134,10 +254,7 @@ where
     }
 
     fn send_boot_ready(&mut self) -> anyhow::Result<()> {
-        self.client.write(&bootloader::BootloaderReady {
-            chip_id: [0x01234567, 0x89abcdef, 0xfedcba98, 0x76543210],
-            version: k5lib::Version::new_from_str(&self.opts.version)?,
-        })?;
+        self.client.write(&self.opts.boot_ready()?)?;
         Ok(())
     }
 
@@ -195,13 +312,7 @@ where
         match msg {
             HostMessage::Hello(m) => {
                 self.session_id = Some(m.session_id);
-                self.client.write(&radio::HelloReply {
-                    version: k5lib::Version::new_from_str(&self.opts.version)?,
-                    has_custom_aes_key: false,
-                    is_in_lock_screen: false,
-                    _pad: Default::default(),
-                    challenge: [0; 4],
-                })?;
+                self.client.write(&self.opts.hello_reply()?)?;
             }
 
             HostMessage::ReadEeprom(m) => {
@@ -228,6 +339,26 @@ where
                 }
             }
 
+            HostMessage::WriteEeprom(m) if Some(m.session_id) == self.session_id => {
+                // sleep a bit, eeprom writes are slow
+                std::thread::sleep(std::time::Duration::from_millis(100));
+
+                let protect = self.opts.lock_screen && !m.allow_password;
+                for (i, b) in m.data.iter().enumerate() {
+                    let addr = m.address as usize + i;
+                    if addr >= self.eeprom.len() {
+                        break;
+                    }
+                    if protect && PASSWORD_AREA.contains(&addr) {
+                        continue;
+                    }
+                    self.eeprom[addr] = *b;
+                }
+
+                self.client
+                    .write(&radio::WriteEepromReply { address: m.address })?;
+            }
+
             _ => {}
         }
         Ok(())
@@ -306,3 +437,225 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    // one end of an in-memory, bidirectional pipe
+    struct Pipe {
+        tx: mpsc::Sender<Vec<u8>>,
+        rx: mpsc::Receiver<Vec<u8>>,
+        pending: Vec<u8>,
+    }
+
+    fn pipe() -> (Pipe, Pipe) {
+        let (atx, brx) = mpsc::channel();
+        let (btx, arx) = mpsc::channel();
+        let a = Pipe {
+            tx: atx,
+            rx: arx,
+            pending: Vec::new(),
+        };
+        let b = Pipe {
+            tx: btx,
+            rx: brx,
+            pending: Vec::new(),
+        };
+        (a, b)
+    }
+
+    impl Read for Pipe {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.pending.is_empty() {
+                match self.rx.recv() {
+                    Ok(data) => self.pending = data,
+                    // other end hung up
+                    Err(_) => return Ok(0),
+                }
+            }
+            let amt = buf.len().min(self.pending.len());
+            buf[..amt].copy_from_slice(&self.pending[..amt]);
+            self.pending.drain(..amt);
+            Ok(amt)
+        }
+    }
+
+    impl Write for Pipe {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.tx
+                .send(buf.to_vec())
+                .map_err(|_| std::io::ErrorKind::BrokenPipe)?;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(clap::Parser)]
+    struct Args {
+        #[command(flatten)]
+        opts: SimulateOpts,
+    }
+
+    fn opts(args: &[&str]) -> SimulateOpts {
+        use clap::Parser;
+        Args::parse_from(std::iter::once("simulate").chain(args.iter().copied())).opts
+    }
+
+    // run a simulator against host, until host hangs up
+    fn simulate(
+        opts: &SimulateOpts,
+        eeprom: &mut [u8],
+        host: impl FnOnce(&mut k5lib::ClientHostStd<Pipe>) + Send,
+    ) {
+        let (host_end, radio_end) = pipe();
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                let mut client = k5lib::ClientHost::new_std(host_end);
+                host(&mut client);
+            });
+
+            let client = opts
+                .debug
+                .wrap_radio(k5lib::ClientRadio::new_std(radio_end))
+                .unwrap();
+            let e = Simulator::new(client, opts, eeprom, &mut [])
+                .simulate()
+                .unwrap_err();
+            assert!(matches!(
+                e.downcast_ref(),
+                Some(k5lib::ClientError::<std::io::Error>::UnexpectedEof)
+            ));
+        });
+    }
+
+    fn hello(client: &mut k5lib::ClientHostStd<Pipe>, session_id: u32) -> radio::HelloReply {
+        client.write(&radio::Hello { session_id }).unwrap();
+        loop {
+            if let Some(m) = client.read::<radio::HelloReply>().unwrap().ok() {
+                break m;
+            }
+        }
+    }
+
+    fn write_eeprom(
+        client: &mut k5lib::ClientHostStd<Pipe>,
+        session_id: u32,
+        address: u16,
+        data: &[u8],
+    ) {
+        client
+            .write(&radio::WriteEeprom {
+                address,
+                len: data.len() as u8,
+                allow_password: false,
+                session_id,
+                data,
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn fuzz_over_connection() {
+        for args in [&["--fuzz"][..], &["--fuzz", "--boot"][..]] {
+            let opts = opts(args);
+            let cases = opts.fuzz_cases().unwrap();
+            let mut sent = Vec::new();
+            send_fuzz(&mut sent, &cases).unwrap();
+
+            // a host on the other end sees every good frame
+            let mut client = k5lib::ClientHostStd::<_>::new_std(std::io::Cursor::new(sent));
+            let mut parsed = 0;
+            loop {
+                match client.read_radio() {
+                    Ok(ParseResult::Ok(_, _)) => parsed += 1,
+                    Ok(_) => {}
+                    Err(k5lib::ClientError::UnexpectedEof) => break,
+                    Err(e) => panic!("{}", e),
+                }
+            }
+            let expected = cases
+                .iter()
+                .flat_map(|c| c.expected.iter())
+                .filter(|o| **o == crate::fuzz::Outcome::Ok)
+                .count();
+            assert_eq!(parsed, expected);
+        }
+    }
+
+    #[test]
+    fn hello_reply() {
+        let opts = opts(&[
+            "--version",
+            "2.01.26",
+            "--lock-screen",
+            "--challenge",
+            "1",
+            "2",
+            "3",
+            "4",
+        ]);
+        simulate(&opts, &mut [0; 0x100], |client| {
+            let reply = hello(client, radio::HELLO_SESSION_ID);
+            assert_eq!(reply.version.as_str(), Ok("2.01.26"));
+            assert!(!reply.has_custom_aes_key);
+            assert!(reply.is_in_lock_screen);
+            assert_eq!(reply.challenge, [1, 2, 3, 4]);
+        });
+    }
+
+    #[test]
+    fn write_read_eeprom() {
+        let opts = opts(&[]);
+        let mut eeprom = vec![0xff; 0x100];
+        simulate(&opts, &mut eeprom, |client| {
+            let session_id = radio::HELLO_SESSION_ID;
+            hello(client, session_id);
+
+            write_eeprom(client, session_id, 0x10, &[1, 2, 3, 4, 5, 6, 7, 8]);
+            let reply = loop {
+                if let Some(m) = client.read::<radio::WriteEepromReply>().unwrap().ok() {
+                    break m;
+                }
+            };
+            assert_eq!(reply.address, 0x10);
+
+            // writes with the wrong session are ignored
+            write_eeprom(client, session_id + 1, 0x18, &[0; 8]);
+
+            let data = client
+                .read_eeprom_range(0x08, 0x18, session_id, |_| {})
+                .unwrap();
+            assert_eq!(data[..8], [0xff; 8]);
+            assert_eq!(data[8..16], [1, 2, 3, 4, 5, 6, 7, 8]);
+            assert_eq!(data[16..], [0xff; 8]);
+        });
+
+        assert_eq!(eeprom[0x10..0x18], [1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn password_area() {
+        let opts = opts(&["--lock-screen"]);
+        let mut eeprom = vec![0xff; 0x2000];
+        simulate(&opts, &mut eeprom, |client| {
+            let session_id = radio::HELLO_SESSION_ID;
+            hello(client, session_id);
+            write_eeprom(client, session_id, 0x0e90, &[0; 0x10]);
+            while client
+                .read::<radio::WriteEepromReply>()
+                .unwrap()
+                .ok()
+                .is_none()
+            {}
+        });
+
+        assert_eq!(eeprom[0x0e90..0x0e98], [0; 8]);
+        assert_eq!(eeprom[0x0e98..0x0ea0], [0xff; 8]);
+    }
+}
@@ -5,11 +5,20 @@ pub struct UnpackOpts {
 
     #[arg(long, value_enum, default_value = "auto")]
     format: crate::binformat::BinaryFormat,
+    /// Input is an encrypted stock image. Same as --format packed.
+    #[arg(long, conflicts_with = "format")]
+    decrypt: bool,
 }
 
 impl crate::ToolRun for UnpackOpts {
     fn run(&self) -> anyhow::Result<()> {
-        let (unpacked, info) = crate::binformat::read_firmware(&self.firmware, self.format, None)?;
+        let format = if self.decrypt {
+            crate::binformat::BinaryFormat::Packed
+        } else {
+            self.format
+        };
+
+        let (unpacked, info) = crate::binformat::read_firmware(&self.firmware, format, None)?;
 
         info.report();
         std::fs::write(&self.unpacked, &unpacked[..])?;
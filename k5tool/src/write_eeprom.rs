@@ -0,0 +1,116 @@
+use std::io::{Read, Write};
+
+use k5lib::protocol::messages::radio::{
+    Hello, HelloReply, WriteEeprom, WriteEepromReply, HELLO_SESSION_ID,
+};
+
+// must be a multiple of 8, the radio writes in 8-byte blocks
+const CHUNK_SIZE: usize = 0x80;
+
+#[derive(clap::Args, Debug)]
+pub struct WriteEepromOpts {
+    input: String,
+    #[command(flatten)]
+    port: crate::common::SerialPortArgs,
+    #[command(flatten)]
+    debug: crate::debug::DebugClientArgs,
+    #[arg(long, default_value_t = crate::common::EEPROM_MAX)]
+    eeprom_size: usize,
+    /// Allow writing the password area of the EEPROM.
+    #[arg(long)]
+    allow_password: bool,
+    #[arg(short, long)]
+    yes: bool,
+}
+
+impl crate::ToolRun for WriteEepromOpts {
+    fn run(&self) -> anyhow::Result<()> {
+        let data = std::fs::read(&self.input)?;
+        if data.len() > self.eeprom_size {
+            anyhow::bail!(
+                "Input is too large: 0x{:x} bytes, EEPROM is 0x{:x} bytes",
+                data.len(),
+                self.eeprom_size
+            );
+        }
+        if data.len() > u16::MAX as usize + 1 {
+            anyhow::bail!("Input is too large: EEPROM addresses are only 16 bits");
+        }
+        if data.len() % 8 != 0 {
+            anyhow::bail!("Input size must be a multiple of 8 bytes");
+        }
+
+        self.send_hello(self.port.open()?, &data)
+    }
+}
+
+impl WriteEepromOpts {
+    fn send_hello<F>(&self, port: F, data: &[u8]) -> anyhow::Result<()>
+    where
+        F: Read + Write,
+    {
+        let session_id = HELLO_SESSION_ID;
+        let mut client = self.debug.wrap_host(k5lib::ClientHost::new_std(port))?;
+
+        client.write(&Hello { session_id })?;
+        let m = loop {
+            if let Some(m) = client.read::<HelloReply>()?.ok() {
+                break m;
+            }
+        };
+
+        if let Ok(ver) = m.version.as_str() {
+            eprintln!("Connected to version: {}", ver);
+        } else {
+            eprintln!("Connected to version: {:x?}", m.version.as_bytes());
+        }
+
+        eprintln!();
+        crate::common::confirm("Overwrite radio EEPROM?", self.yes)?;
+
+        self.write_eeprom(client, data)
+    }
+
+    fn write_eeprom<F>(
+        &self,
+        mut client: crate::debug::DebugClientHost<F>,
+        data: &[u8],
+    ) -> anyhow::Result<()>
+    where
+        F: Read + Write,
+    {
+        let bar = crate::common::upload_bar(data.len() as u64);
+        bar.set_position(0);
+
+        let session_id = HELLO_SESSION_ID;
+        // usize, so the end of a full 0x10000 byte EEPROM doesn't overflow
+        let mut address: usize = 0;
+        for chunk in data.chunks(CHUNK_SIZE) {
+            // run() checks data fits in 16-bit addresses
+            let chunk_address = address as u16;
+            client.write(&WriteEeprom {
+                address: chunk_address,
+                len: chunk.len() as u8,
+                allow_password: self.allow_password,
+                session_id,
+                data: chunk,
+            })?;
+            let m = loop {
+                if let Some(m) = client.read::<WriteEepromReply>()?.ok() {
+                    break m;
+                }
+            };
+
+            if chunk_address != m.address {
+                anyhow::bail!("Reply had different address!");
+            }
+
+            address += chunk.len();
+            bar.set_position(address as u64);
+        }
+
+        bar.finish();
+
+        Ok(())
+    }
+}